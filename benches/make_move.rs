@@ -0,0 +1,54 @@
+//! Throughput of a trained player's moves, and of the table lookup behind each of them, hashed
+//! by position as the table used to be and indexed by the position's code as it is now
+//!
+//! Run with `cargo bench --bench make_move`. To compare two versions of the player, run it
+//! with `-- --save-baseline before` on the first and `-- --baseline before` on the second.
+use std::collections::HashMap;
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
+use tictacrs::agents::annealing::scheduled_player;
+use tictacrs::game::states::{encode_state, reachable_states, STATE_CODES};
+use tictacrs::prelude::*;
+
+/// Positions with X to move
+fn x_to_move() -> Vec<[Piece; 9]> {
+    reachable_states().into_iter()
+        .filter(|state| Board::from_compact_state(*state).ok().and_then(|board| board.turn()) == Some(PlayerPiece::X))
+        .collect()
+}
+
+fn make_move(c: &mut Criterion) {
+    let mut player_x = scheduled_player(PlayerPiece::X);
+    let mut player_o = scheduled_player(PlayerPiece::O);
+    Trainer::transitions(&mut player_x, &mut player_o, 5000)
+        .unwrap_or_else(|_| panic!("the players use the same piece"))
+        .for_each(drop);
+    // Frozen, so the table stays the same from one iteration to the next
+    player_x.set_learning(Learning::Frozen);
+    let positions = x_to_move();
+    c.bench_function("make_move", |b| b.iter(|| {
+        for state in &positions {
+            black_box(player_x.make_move(black_box(state)).ok());
+        }
+    }));
+}
+
+fn table_lookup(c: &mut Criterion) {
+    let states = reachable_states();
+    let hashed: HashMap<[Piece; 9], f64> = states.iter().map(|state| (*state, 0.5)).collect();
+    let mut indexed: Vec<Option<f64>> = vec![None; STATE_CODES];
+    for state in &states {
+        indexed[encode_state(state) as usize] = Some(0.5);
+    }
+    let mut group = c.benchmark_group("table_lookup");
+    group.bench_function("hashed", |b| b.iter(|| {
+        states.iter().filter_map(|state| hashed.get(black_box(state))).sum::<f64>()
+    }));
+    group.bench_function("indexed", |b| b.iter(|| {
+        states.iter().filter_map(|state| indexed[encode_state(black_box(state)) as usize]).sum::<f64>()
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, make_move, table_lookup);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+//! Use a player inside a game loop of your own, here against a scripted opponent which
+//! always takes the first empty square, printing the player's reasoning for each move
+//!
+//! Run with `cargo run --example custom_loop`
+use tictacrs::prelude::*;
+
+fn main() {
+    // Any player works here, such as one read with Player::load, a fresh one
+    // with a fixed seed just keeps the example reproducible
+    let mut computer = Player::builder(PlayerPiece::O)
+        .seed(42)
+        .build()
+        .unwrap_or_else(|_| panic!("invalid configuration"));
+    let mut board = Board::new();
+    while let Some(piece) = board.turn() {
+        if piece == computer.get_player_piece() {
+            let state = board.get_compact_state();
+            let chosen = computer.make_move_on_board(&board)
+                .unwrap_or_else(|_| panic!("the computer asked to move out of turn"));
+            println!("{}", computer.explain_move(&state, chosen));
+            board.player_move(&Player::to_human_move(&chosen), &piece.to_string())
+                .unwrap_or_else(|_| panic!("the computer made an illegal move"));
+        } else {
+            let square = Coord::all()
+                .find(|coord| board.get_compact_state()[coord.to_index()] == Piece::Empty)
+                .expect("the game isn't over, so a square is free");
+            println!("Scripted player {} plays {}", piece, square);
+            board.player_move(&square.to_string(), &piece.to_string())
+                .unwrap_or_else(|_| panic!("the scripted player made an illegal move"));
+        }
+    }
+    println!("{}", board);
+    match board.result() {
+        Some(GameResult::Win(winner)) => { println!("{} wins", winner) }
+        Some(GameResult::Draw) => { println!("It's a draw") }
+        None => { println!("The game was left unfinished") }
+    }
+}
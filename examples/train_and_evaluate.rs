@@ -0,0 +1,48 @@
+//! Train a pair of players against each other in memory, then see how the trained X player
+//! does against an opponent which moves at random
+//!
+//! Run with `cargo run --example train_and_evaluate`
+use tictacrs::agents::annealing::scheduled_player;
+use tictacrs::agents::random::RandomAgent;
+use tictacrs::prelude::*;
+
+fn main() {
+    let mut player_x = scheduled_player(PlayerPiece::X);
+    let mut player_o = scheduled_player(PlayerPiece::O);
+    // Train without saving anything, keeping count of the results
+    let mut results = [0u32; 3];
+    let transitions = Trainer::transitions(&mut player_x, &mut player_o, 5000)
+        .unwrap_or_else(|_| panic!("the players use the same piece"));
+    for result in transitions.filter_map(|transition| transition.result) {
+        results[outcome_index(result.for_piece(PlayerPiece::X))] += 1;
+    }
+    println!("Training: X won {}, lost {}, drew {}", results[0], results[1], results[2]);
+    println!("X learned the values of {} positions", player_x.state_count());
+
+    // Evaluate the trained player greedily, without it learning any more
+    player_x.set_learning(Learning::Frozen);
+    let mut random_o = RandomAgent::from_entropy(PlayerPiece::O);
+    let mut results = [0u32; 3];
+    for _ in 0..500 {
+        let mut board = Board::new();
+        while let Some(piece) = board.turn() {
+            let mover: &mut dyn Agent = if piece == PlayerPiece::X { &mut player_x } else { &mut random_o };
+            let chosen = mover.choose_move(&board.get_compact_state())
+                .unwrap_or_else(|_| panic!("the player asked to move out of turn"));
+            board.player_move(&Player::to_human_move(&chosen), &piece.to_string())
+                .unwrap_or_else(|_| panic!("the player made an illegal move"));
+        }
+        let result = board.result().expect("the game only stops once it's over");
+        results[outcome_index(result.for_piece(PlayerPiece::X))] += 1;
+    }
+    println!("Against random moves: X won {}, lost {}, drew {}", results[0], results[1], results[2]);
+}
+
+/// Position of an outcome in the won, lost, drawn counts
+fn outcome_index(outcome: GameOutcomeFor) -> usize {
+    match outcome {
+        GameOutcomeFor::Win => { 0 }
+        GameOutcomeFor::Loss => { 1 }
+        GameOutcomeFor::Draw => { 2 }
+    }
+}
@@ -0,0 +1,169 @@
+//! The interface every opponent implements, so a learned [`Player`] and any other way of
+//! choosing moves can be played against each other and in the same game loops
+
+use std::error::Error;
+use std::fmt;
+use crate::agents::players::{MoveDecision, Player, PlayerError};
+use crate::game::board::{MoveLegality, Piece, PlayerPiece};
+use crate::game::result::GameOutcomeFor;
+
+/// Something which plays one side of a game
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// /// Always takes the first empty square
+/// struct FirstEmpty(PlayerPiece);
+///
+/// impl Agent for FirstEmpty {
+///     fn piece(&self) -> PlayerPiece {
+///         self.0
+///     }
+///
+///     fn choose_move(&mut self, state: &[Piece; 9]) -> Result<[u8; 2], AgentError> {
+///         match state.iter().position(|piece| *piece == Piece::Empty) {
+///             Some(idx) => { Ok([idx as u8 / 3, idx as u8 % 3]) }
+///             None => { Err(AgentError::GameAlreadyOver) }
+///         }
+///     }
+/// }
+///
+/// let mut agent = FirstEmpty(PlayerPiece::X);
+/// assert_eq!(agent.choose_move(&[Piece::Empty; 9]), Ok([0, 0]));
+/// ```
+pub trait Agent {
+    /// The piece the agent plays
+    fn piece(&self) -> PlayerPiece;
+
+    /// Get ready for a new game, called before the agent's first move of each game
+    fn start_game(&mut self) {}
+
+    /// Choose a move, as row and column, from the given position. Only called when it's the
+    /// agent's turn.
+    fn choose_move(&mut self, state: &[Piece; 9]) -> Result<[u8; 2], AgentError>;
+
+    /// The move the agent thinks is best from the given position, never exploring and without
+    /// changing the agent. By default agents can't choose without changing, so this fails.
+    fn greedy_move(&self, _state: &[Piece; 9]) -> Result<[u8; 2], AgentError> {
+        Err(AgentError::NoMove("the agent can't choose a move without changing".to_string()))
+    }
+
+    /// Learn from a finished game, given its final position and how it went for the agent
+    fn observe_result(&mut self, _final_state: &[Piece; 9], _outcome: GameOutcomeFor) {}
+
+    /// The agent's last move and how it was chosen, if it keeps track
+    fn last_decision(&self) -> Option<MoveDecision> {
+        None
+    }
+
+    /// Whether the agent's last move was exploratory rather than the best it knew of
+    fn last_move_was_exploratory(&self) -> bool {
+        self.last_decision().is_some_and(|decision| decision.exploratory)
+    }
+}
+
+/// Errors returned when an agent can't choose a move
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AgentError {
+    /// The game is already over
+    GameAlreadyOver,
+    /// It isn't the agent's turn
+    NotMyTurn,
+    /// The agent failed to choose a move for some other reason
+    NoMove(String),
+    /// The agent chose the given (row, col), which can't be played for the given reason
+    IllegalMove([u8; 2], MoveLegality),
+}
+
+impl fmt::Display for AgentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgentError::GameAlreadyOver => { write!(f, "the game is already over") }
+            AgentError::NotMyTurn => { write!(f, "it isn't the agent's turn") }
+            AgentError::NoMove(reason) => { write!(f, "no move was chosen: {}", reason) }
+            AgentError::IllegalMove([row, col], legality) => {
+                write!(f, "the agent chose row {}, column {}, ", row, col)?;
+                match legality {
+                    MoveLegality::Legal => { write!(f, "which is legal") }
+                    MoveLegality::Occupied(piece) => { write!(f, "which already holds {}", piece) }
+                    MoveLegality::OutOfBounds => { write!(f, "which is off the board") }
+                    MoveLegality::GameOver(_) => { write!(f, "after the game was over") }
+                }
+            }
+        }
+    }
+}
+
+impl Error for AgentError {}
+
+impl From<PlayerError> for AgentError {
+    fn from(e: PlayerError) -> AgentError {
+        match e {
+            PlayerError::GameAlreadyOver => { AgentError::GameAlreadyOver }
+            PlayerError::NotMyTurn => { AgentError::NotMyTurn }
+            e => { AgentError::NoMove(e.to_string()) }
+        }
+    }
+}
+
+impl Agent for Player {
+    fn piece(&self) -> PlayerPiece {
+        self.get_player_piece()
+    }
+
+    fn start_game(&mut self) {
+        self.start_new_game();
+    }
+
+    /// The player trusts that it's its turn, so this also works for boards which started
+    /// with handicap pieces
+    fn choose_move(&mut self, state: &[Piece; 9]) -> Result<[u8; 2], AgentError> {
+        Ok(self.make_move_unchecked(state)?)
+    }
+
+    /// The best move as [`Player::best_move`] chooses it, so ties are settled by the player's
+    /// tie break policy
+    fn greedy_move(&self, state: &[Piece; 9]) -> Result<[u8; 2], AgentError> {
+        self.check_position(state)?;
+        Ok(self.best_move(state).expect("The player can move from a checked position"))
+    }
+
+    /// The player works out the reward from the final position itself, see
+    /// [`Player::observe_terminal`], then applies any updates held back until the end of the game
+    fn observe_result(&mut self, final_state: &[Piece; 9], _outcome: GameOutcomeFor) {
+        self.observe_terminal(final_state);
+        self.end_episode();
+    }
+
+    fn last_decision(&self) -> Option<MoveDecision> {
+        Player::last_decision(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::result::GameResult;
+
+    #[test]
+    fn test_player_agent() {
+        let mut player = Player::builder(PlayerPiece::O).seed(3).build().unwrap();
+        let agent: &mut dyn Agent = &mut player;
+        assert_eq!(agent.piece(), PlayerPiece::O);
+        agent.start_game();
+        let mut state = [Piece::Empty; 9];
+        state[4] = Piece::X;
+        let chosen = agent.choose_move(&state).unwrap();
+        assert_eq!(state[(chosen[0] * 3 + chosen[1]) as usize], Piece::Empty);
+        assert_eq!(agent.choose_move(&[Piece::X; 9]), Err(AgentError::GameAlreadyOver));
+        agent.observe_result(&[Piece::X; 9], GameResult::Win(PlayerPiece::X).for_piece(PlayerPiece::O));
+    }
+
+    #[test]
+    fn test_from_player_error() {
+        assert_eq!(AgentError::from(PlayerError::NotMyTurn), AgentError::NotMyTurn);
+        assert_eq!(AgentError::from(PlayerError::BadMagic),
+                   AgentError::NoMove(PlayerError::BadMagic.to_string()));
+    }
+}
@@ -0,0 +1,113 @@
+//! The schedules the tictacrs binary trains and plays with, which lower the learning and
+//! exploration rates as a player gains experience
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::agents::players::Player;
+use crate::game::board::PlayerPiece;
+
+/// Learning rate the schedule starts from
+pub const INITIAL_LEARNING_RATE: f64 = 0.75;
+/// Exploration rate the schedule starts from
+pub const INITIAL_EXPLORATION_RATE: f64 = 0.2;
+
+/// Schedule for the learning rate
+pub const LEARNING_SCHEDULE: AnnealingSchedule = AnnealingSchedule::StepDecay { drop_rate: 0.99, step_size: 20 };
+/// Schedule for the exploration rate
+pub const EXPLORATION_SCHEDULE: AnnealingSchedule = AnnealingSchedule::StepDecay { drop_rate: 0.9, step_size: 10 };
+
+/// How a rate changes as a player gains experience, saved along with the player
+///
+/// ```
+/// use tictacrs::agents::annealing::AnnealingSchedule;
+///
+/// let schedule = AnnealingSchedule::LinearDecay { floor: 0.1, steps: 100 };
+/// assert_eq!(schedule.evaluate(0.5, 50), 0.3);
+/// assert_eq!(schedule.evaluate(0.5, 1000), 0.1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default, BorshSerialize, BorshDeserialize)]
+pub enum AnnealingSchedule {
+    /// The rate stays at its initial value
+    #[default]
+    Constant,
+    /// The rate is multiplied by `drop_rate` every `step_size` iterations
+    StepDecay { drop_rate: f64, step_size: u32 },
+    /// The rate is multiplied by `rate` every iteration
+    ExponentialDecay { rate: f64 },
+    /// The rate falls in a straight line to `floor` over `steps` iterations, then stays there
+    LinearDecay { floor: f64, steps: u32 },
+}
+
+impl AnnealingSchedule {
+    /// The rate after the given number of iterations, starting from the initial rate
+    pub fn evaluate(&self, initial_rate: f64, iteration: u32) -> f64 {
+        match *self {
+            AnnealingSchedule::Constant => { initial_rate }
+            AnnealingSchedule::StepDecay { drop_rate, step_size } => {
+                initial_rate * drop_rate.powi((iteration / step_size.max(1)) as i32)
+            }
+            AnnealingSchedule::ExponentialDecay { rate } => { initial_rate * rate.powi(iteration as i32) }
+            AnnealingSchedule::LinearDecay { floor, steps } => {
+                if iteration >= steps {
+                    floor
+                } else {
+                    initial_rate + (floor - initial_rate) * iteration as f64 / steps as f64
+                }
+            }
+        }
+    }
+}
+
+/// Function used for calculating the learning rate, [`LEARNING_SCHEDULE`] as a function
+pub fn learning_rate_function(initial_rate: f64, iteration: u32) -> f64 {
+    LEARNING_SCHEDULE.evaluate(initial_rate, iteration)
+}
+
+/// Function used for calculating the exploration rate, [`EXPLORATION_SCHEDULE`] as a function
+pub fn exploration_rate_function(initial_rate: f64, iteration: u32) -> f64 {
+    EXPLORATION_SCHEDULE.evaluate(initial_rate, iteration)
+}
+
+/// A new, untrained player using these schedules
+///
+/// ```
+/// use tictacrs::prelude::*;
+/// use tictacrs::agents::annealing::{scheduled_player, INITIAL_LEARNING_RATE};
+///
+/// let player = scheduled_player(PlayerPiece::O);
+/// assert_eq!(player.get_learning_rate(), INITIAL_LEARNING_RATE);
+/// ```
+pub fn scheduled_player(piece: PlayerPiece) -> Player {
+    Player::new(piece, INITIAL_LEARNING_RATE, INITIAL_EXPLORATION_RATE, LEARNING_SCHEDULE, EXPLORATION_SCHEDULE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_decay() {
+        // The rates stay put within a step, then drop by a fixed factor each step
+        assert_eq!(learning_rate_function(0.75, 19), 0.75);
+        assert_eq!(learning_rate_function(0.75, 20), 0.75 * 0.99);
+        assert_eq!(learning_rate_function(0.75, 45), 0.75 * 0.99 * 0.99);
+        assert_eq!(exploration_rate_function(0.2, 9), 0.2);
+        assert_eq!(exploration_rate_function(0.2, 30), 0.2 * 0.9f64.powi(3));
+        let mut player = scheduled_player(PlayerPiece::X);
+        player.update_iteration(100);
+        assert_eq!(player.get_exploration_rate(), exploration_rate_function(INITIAL_EXPLORATION_RATE, 100));
+    }
+
+    #[test]
+    fn test_schedules() {
+        assert_eq!(AnnealingSchedule::Constant.evaluate(0.4, 1000), 0.4);
+        assert_eq!(AnnealingSchedule::ExponentialDecay { rate: 0.5 }.evaluate(0.4, 3), 0.05);
+        let linear = AnnealingSchedule::LinearDecay { floor: 0.2, steps: 4 };
+        assert_eq!([0, 1, 4, 5].map(|iteration| linear.evaluate(0.6, iteration)), [0.6, 0.5, 0.2, 0.2]);
+        // No steps at all goes straight to the floor, and a zero step size doesn't divide by zero
+        assert_eq!(AnnealingSchedule::LinearDecay { floor: 0.2, steps: 0 }.evaluate(0.6, 0), 0.2);
+        assert_eq!(AnnealingSchedule::StepDecay { drop_rate: 0.5, step_size: 0 }.evaluate(0.4, 2), 0.1);
+        for schedule in [AnnealingSchedule::Constant, LEARNING_SCHEDULE, linear] {
+            assert_eq!(borsh::from_slice::<AnnealingSchedule>(&borsh::to_vec(&schedule).unwrap()).unwrap(), schedule);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Checking a player never loses from the start of a game, whatever its opponent does, by
+//! following its greedy moves against every possible reply
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use crate::agents::players::Player;
+use crate::formatting::count;
+use crate::game::board::{Board, Piece, PlayerPiece};
+use crate::game::coords::Coord;
+use crate::game::result::GameResult;
+
+/// How each position searched was first reached: the position before and the move made from
+/// it, None for the empty board
+type ReachedFrom = HashMap<[Piece; 9], Option<([Piece; 9], PlayerPiece, Coord)>>;
+
+/// What [`certify`] found
+#[derive(Debug, Clone, PartialEq)]
+pub struct Certificate {
+    /// The piece the certified player uses
+    pub piece: PlayerPiece,
+    /// Number of distinct positions reached
+    pub positions: usize,
+    /// One of the shortest games the player loses, as the piece moved and where, or None if
+    /// it never loses
+    pub counterexample: Option<Vec<(PlayerPiece, Coord)>>,
+}
+
+impl Certificate {
+    /// Whether the player never loses
+    pub fn is_certified(&self) -> bool {
+        self.counterexample.is_none()
+    }
+}
+
+impl fmt::Display for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.counterexample {
+            None => {
+                write!(f, "{} never loses: all {} positions its moves can reach end in a win or draw",
+                       self.piece, count(self.positions as u64))
+            }
+            Some(ref moves) => {
+                let line: Vec<String> = moves.iter().map(|(piece, coord)| format!("{} {}", piece, coord)).collect();
+                write!(f, "{} can lose in {} moves: {} ({} wins)", self.piece, moves.len(), line.join(", "),
+                       self.piece.opponent())
+            }
+        }
+    }
+}
+
+/// Check whether the player can lose a game from the empty board, when it always makes its
+/// best move and its opponent may make any move. Where equally good moves are chosen between
+/// randomly, every one of them is followed. Positions are searched in order of the number of
+/// moves made, so a counterexample is one of the shortest.
+///
+/// ```
+/// use tictacrs::agents::certify::certify;
+/// use tictacrs::prelude::*;
+///
+/// // An untrained player moves anywhere, so it can lose
+/// let player = Player::builder(PlayerPiece::X).build().unwrap();
+/// assert!(!certify(&player).is_certified());
+/// ```
+pub fn certify(player: &Player) -> Certificate {
+    let piece = player.get_player_piece();
+    let start = Board::new();
+    let mut reached_from: ReachedFrom = HashMap::from([(start.get_compact_state(), None)]);
+    let mut unvisited = VecDeque::from([start]);
+    while let Some(board) = unvisited.pop_front() {
+        let state = board.get_compact_state();
+        let mover = match board.turn() {
+            Some(mover) => { mover }
+            None => {
+                if board.result() == Some(GameResult::Win(piece.opponent())) {
+                    let counterexample = Some(line_to(&reached_from, state));
+                    return Certificate { piece, positions: reached_from.len(), counterexample };
+                }
+                continue;
+            }
+        };
+        let moves = if mover == piece {
+            policy_moves(player, &state)
+        } else {
+            Coord::all().filter(|coord| state[coord.to_index()] == Piece::Empty).collect()
+        };
+        for coord in moves {
+            let next = board.try_apply_all(&[(coord.row(), coord.col(), mover.into())])
+                .expect("Only empty squares are played");
+            // Positions reached again by moves in another order were already searched
+            if let Entry::Vacant(entry) = reached_from.entry(next.get_compact_state()) {
+                entry.insert(Some((state, mover, coord)));
+                unvisited.push_back(next);
+            }
+        }
+    }
+    Certificate { piece, positions: reached_from.len(), counterexample: None }
+}
+
+/// The moves the player might make from a position when not exploring: its best move, or all
+/// of the equally good ones if it chooses between them randomly
+fn policy_moves(player: &Player, state: &[Piece; 9]) -> Vec<Coord> {
+    let tied = player.greedy_moves(state);
+    let chosen = match player.settle_tie(&tied) {
+        Some(chosen) => { vec![chosen] }
+        None => { tied }
+    };
+    chosen.iter().filter_map(|chosen| Coord::new(chosen[0], chosen[1])).collect()
+}
+
+/// The moves leading from the empty board to a position
+fn line_to(reached_from: &ReachedFrom, mut state: [Piece; 9]) -> Vec<(PlayerPiece, Coord)> {
+    let mut moves = Vec::new();
+    while let Some(Some((previous, piece, coord))) = reached_from.get(&state) {
+        moves.push((*piece, *coord));
+        state = *previous;
+    }
+    moves.reverse();
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::annealing::AnnealingSchedule;
+    use crate::agents::players::{SaveState, TieBreak};
+
+    /// The best result the piece can force from a position, 1 for a win, 0.5 for a draw and
+    /// 0 for a loss, recording the value of every position reached
+    fn minimax(board: &Board, piece: PlayerPiece, values: &mut HashMap<[Piece; 9], f64>) -> f64 {
+        let state = board.get_compact_state();
+        if let Some(value) = values.get(&state) {
+            return *value;
+        }
+        let value = match board.turn() {
+            None => {
+                match board.result() {
+                    Some(GameResult::Win(winner)) if winner == piece => { 1. }
+                    Some(GameResult::Win(_)) => { 0. }
+                    _ => { 0.5 }
+                }
+            }
+            Some(mover) => {
+                let outcomes = Coord::all().filter(|coord| state[coord.to_index()] == Piece::Empty).map(|coord| {
+                    let next = board.try_apply_all(&[(coord.row(), coord.col(), mover.into())]).unwrap();
+                    minimax(&next, piece, values)
+                });
+                if mover == piece { outcomes.fold(0., f64::max) } else { outcomes.fold(1., f64::min) }
+            }
+        };
+        values.insert(state, value);
+        value
+    }
+
+    /// A player whose table holds the value of perfect play from every position
+    fn perfect_player(piece: PlayerPiece) -> Player {
+        let mut values = HashMap::new();
+        minimax(&Board::new(), piece, &mut values);
+        let save_state = SaveState {
+            piece,
+            state_space: values.into_iter().collect(),
+            initial_learning_rate: 0.,
+            initial_exploration_rate: 0.,
+            iteration: 0,
+            games_played: 0,
+            symmetric: false,
+            learning_schedule: AnnealingSchedule::Constant,
+            exploration_schedule: AnnealingSchedule::Constant,
+        };
+        Player::from_bytes(&borsh::to_vec(&save_state).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_untrained_player_loses() {
+        for (piece, shortest) in [(PlayerPiece::X, 6), (PlayerPiece::O, 5)] {
+            let player = Player::builder(piece).build().unwrap();
+            let certificate = certify(&player);
+            assert!(!certificate.is_certified());
+            let moves = certificate.counterexample.clone().unwrap();
+            assert_eq!(moves.len(), shortest);
+            // The counterexample replays as a legal game the player loses
+            let mut board = Board::new();
+            for (piece, coord) in &moves {
+                assert_eq!(board.turn(), Some(*piece));
+                board.player_move(&coord.to_string(), &piece.to_string()).unwrap();
+            }
+            assert_eq!(board.result(), Some(GameResult::Win(piece.opponent())));
+            assert!(certificate.to_string().starts_with(&format!("{} can lose in {} moves: X ", piece, shortest)));
+        }
+    }
+
+    #[test]
+    fn test_perfect_player_never_loses() {
+        for piece in [PlayerPiece::X, PlayerPiece::O] {
+            let mut player = perfect_player(piece);
+            let certificate = certify(&player);
+            assert!(certificate.is_certified(), "{}", certificate);
+            assert!(certificate.to_string().starts_with(&format!("{} never loses: all ", piece)));
+            // Following a single one of the best moves reaches fewer positions
+            player.set_tie_break(TieBreak::FirstIndex);
+            let narrowed = certify(&player);
+            assert!(narrowed.is_certified());
+            assert!(narrowed.positions < certificate.positions);
+        }
+    }
+}
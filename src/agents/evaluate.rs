@@ -0,0 +1,229 @@
+//! Measure how well an agent plays, by counting its results over many games against another
+
+use std::fmt;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use crate::agents::agent::Agent;
+use crate::agents::minimax::MinimaxAgent;
+use crate::agents::players::{Learning, Player};
+use crate::agents::random::RandomAgent;
+use crate::agents::trainer::{Trainer, TrainerError};
+use crate::formatting::{count, percent};
+use crate::game::board::PlayerPiece;
+use crate::game::result::GameOutcomeFor;
+
+/// How an agent's games went, see [`evaluate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Evaluation {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Evaluation {
+    /// Number of games played
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+/// Play `games` games between the agent and the opponent, whichever plays X moving first, and
+/// count how they went for the agent. The agents learn from the games as they would from any
+/// others, so freeze a player (see [`Learning::Frozen`](crate::agents::players::Learning)) to
+/// measure it as it stands.
+///
+/// ```
+/// use tictacrs::agents::evaluate::evaluate;
+/// use tictacrs::agents::random::RandomAgent;
+/// use tictacrs::prelude::*;
+///
+/// let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+/// player_x.set_learning(Learning::Frozen);
+/// let evaluation = evaluate(&mut player_x, &mut RandomAgent::new(PlayerPiece::O, 2), 100).unwrap();
+/// assert_eq!(evaluation.games(), 100);
+/// ```
+pub fn evaluate(agent: &mut dyn Agent, opponent: &mut dyn Agent, games: u32) -> Result<Evaluation, TrainerError> {
+    let piece = agent.piece();
+    let mut evaluation = Evaluation::default();
+    for _ in 0..games {
+        let record = if piece == PlayerPiece::X {
+            Trainer::play_game(agent, opponent, false)?
+        } else {
+            Trainer::play_game(opponent, agent, false)?
+        };
+        match record.result.for_piece(piece) {
+            GameOutcomeFor::Win => { evaluation.wins += 1 }
+            GameOutcomeFor::Draw => { evaluation.draws += 1 }
+            GameOutcomeFor::Loss => { evaluation.losses += 1 }
+        }
+    }
+    Ok(evaluation)
+}
+
+/// The opponents a trained player can be measured against, see [`evaluate_baseline`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Baseline {
+    /// Moves at random, see [`RandomAgent`]
+    Random,
+    /// Plays perfectly, see [`MinimaxAgent`]
+    Minimax,
+    /// The trained player for the other piece
+    SelfPlay,
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Baseline::Random => { write!(f, "random") }
+            Baseline::Minimax => { write!(f, "minimax") }
+            Baseline::SelfPlay => { write!(f, "self") }
+        }
+    }
+}
+
+/// How a trained player did against a baseline, see [`evaluate_baseline`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BaselineReport {
+    /// The piece of the player measured
+    pub piece: PlayerPiece,
+    pub baseline: Baseline,
+    pub evaluation: Evaluation,
+}
+
+impl BaselineReport {
+    /// Fraction of the games played which ended this many times
+    fn share(&self, games: u32) -> f64 {
+        games as f64 / self.evaluation.games().max(1) as f64
+    }
+}
+
+impl fmt::Display for BaselineReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let evaluation = &self.evaluation;
+        write!(f, "{} against {}: won {}, drew {} and lost {} of {} games", self.piece, self.baseline,
+               percent(self.share(evaluation.wins)), percent(self.share(evaluation.draws)),
+               percent(self.share(evaluation.losses)), count(evaluation.games() as u64))
+    }
+}
+
+/// Written with the shares of wins, draws and losses alongside the counts, for scripts
+/// tracking how well training goes
+impl Serialize for BaselineReport {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let evaluation = &self.evaluation;
+        let mut report = serializer.serialize_struct("BaselineReport", 9)?;
+        report.serialize_field("piece", &self.piece.to_string())?;
+        report.serialize_field("opponent", &self.baseline.to_string())?;
+        report.serialize_field("games", &evaluation.games())?;
+        report.serialize_field("wins", &evaluation.wins)?;
+        report.serialize_field("draws", &evaluation.draws)?;
+        report.serialize_field("losses", &evaluation.losses)?;
+        report.serialize_field("win_rate", &self.share(evaluation.wins))?;
+        report.serialize_field("draw_rate", &self.share(evaluation.draws))?;
+        report.serialize_field("loss_rate", &self.share(evaluation.losses))?;
+        report.end()
+    }
+}
+
+/// Play `games` games between a trained player and a baseline opponent, and report how they
+/// went for the player. The player is frozen (see [`Learning::Frozen`]) so it makes its best
+/// moves, and its learning mode is restored afterwards. Against [`Baseline::SelfPlay`] the
+/// opponent is `counterpart`, the trained player for the other piece, frozen in the same way;
+/// without one this fails with [`TrainerError::InvalidPlayers`]. The random and perfect
+/// opponents make their random choices from `seed`.
+///
+/// ```
+/// use tictacrs::agents::evaluate::{evaluate_baseline, Baseline};
+/// use tictacrs::prelude::*;
+///
+/// let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+/// let report = evaluate_baseline(&mut player_x, Baseline::Minimax, None, 10, 2).unwrap();
+/// // Nobody beats perfect play
+/// assert_eq!(report.evaluation.wins, 0);
+/// ```
+pub fn evaluate_baseline(player: &mut Player, baseline: Baseline, counterpart: Option<&mut Player>,
+                         games: u32, seed: u64) -> Result<BaselineReport, TrainerError> {
+    let piece = player.get_player_piece();
+    let learning = player.get_learning();
+    player.set_learning(Learning::Frozen);
+    let evaluation = match (baseline, counterpart) {
+        (Baseline::Random, _) => {
+            evaluate(player, &mut RandomAgent::new(piece.opponent(), seed), games)
+        }
+        (Baseline::Minimax, _) => {
+            evaluate(player, &mut MinimaxAgent::with_random_ties(piece.opponent(), seed), games)
+        }
+        (Baseline::SelfPlay, Some(counterpart)) => {
+            let counterpart_learning = counterpart.get_learning();
+            counterpart.set_learning(Learning::Frozen);
+            let evaluation = evaluate(player, counterpart, games);
+            counterpart.set_learning(counterpart_learning);
+            evaluation
+        }
+        (Baseline::SelfPlay, None) => { Err(TrainerError::InvalidPlayers) }
+    };
+    player.set_learning(learning);
+    Ok(BaselineReport { piece, baseline, evaluation: evaluation? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        // Random play is reproducible when seeded
+        let first = evaluate(&mut RandomAgent::new(PlayerPiece::O, 1), &mut RandomAgent::new(PlayerPiece::X, 2),
+                             200).unwrap();
+        let second = evaluate(&mut RandomAgent::new(PlayerPiece::O, 1), &mut RandomAgent::new(PlayerPiece::X, 2),
+                              200).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.games(), 200);
+        // X, moving first, wins more often between random players
+        assert!(first.losses > first.wins);
+        assert!(matches!(evaluate(&mut RandomAgent::new(PlayerPiece::O, 1),
+                                  &mut RandomAgent::new(PlayerPiece::O, 2), 1),
+                         Err(TrainerError::InvalidPlayers)));
+    }
+
+    #[test]
+    fn test_training_beats_random() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(3).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(4).build().unwrap();
+        Trainer::transitions(&mut player_x, &mut player_o, 5000).unwrap().for_each(drop);
+        player_x.set_learning(Learning::Frozen);
+        let untrained = evaluate(&mut Player::builder(PlayerPiece::X).seed(3).build().unwrap(),
+                                 &mut RandomAgent::new(PlayerPiece::O, 5), 1000).unwrap();
+        let trained = evaluate(&mut player_x, &mut RandomAgent::new(PlayerPiece::O, 5), 1000).unwrap();
+        assert!(trained.wins > untrained.wins);
+        assert!(trained.losses < untrained.losses);
+    }
+
+    #[test]
+    fn test_evaluate_baseline() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(6).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(7).build().unwrap();
+        Trainer::transitions(&mut player_x, &mut player_o, 3000).unwrap().for_each(drop);
+        let states = player_o.state_count();
+        let mut copy = player_o.clone();
+        let random = evaluate_baseline(&mut player_o, Baseline::Random, None, 50, 1).unwrap();
+        assert_eq!((random.piece, random.baseline, random.evaluation.games()), (PlayerPiece::O, Baseline::Random, 50));
+        assert_eq!(random, evaluate_baseline(&mut copy, Baseline::Random, None, 50, 1).unwrap());
+        let perfect = evaluate_baseline(&mut player_o, Baseline::Minimax, None, 20, 1).unwrap();
+        assert_eq!(perfect.evaluation.wins, 0);
+        // Against itself the result mirrors the other player's
+        let own = evaluate_baseline(&mut player_o, Baseline::SelfPlay, Some(&mut player_x), 10, 1).unwrap();
+        let mirrored = evaluate_baseline(&mut player_x, Baseline::SelfPlay, Some(&mut player_o), 10, 1).unwrap();
+        assert_eq!((own.evaluation.wins, own.evaluation.losses), (mirrored.evaluation.losses, mirrored.evaluation.wins));
+        assert_eq!(evaluate_baseline(&mut player_o, Baseline::SelfPlay, None, 10, 1), Err(TrainerError::InvalidPlayers));
+        // Nothing is learned, and learning is restored
+        assert_eq!(player_o.state_count(), states);
+        assert_eq!(player_o.get_learning(), Learning::default());
+        let report = BaselineReport { piece: PlayerPiece::X, baseline: Baseline::SelfPlay,
+                                      evaluation: Evaluation { wins: 3, draws: 1, losses: 0 } };
+        assert_eq!(report.to_string(), "X against self: won 75.0%, drew 25.0% and lost 0.0% of 4 games");
+        let json = serde_json::to_value(report).unwrap();
+        assert_eq!(json["opponent"], "self");
+        assert_eq!(json["games"], 4);
+        assert_eq!(json["win_rate"], 0.75);
+    }
+}
@@ -0,0 +1,174 @@
+//! Checks on the spread of the values in a player's table, to catch training runs which have
+//! stopped learning anything useful
+use std::fmt;
+use crate::agents::players::Player;
+use crate::formatting::percent;
+
+/// The values of a player's table, sorted, for percentiles and health checks. Finished
+/// positions are never stored, so only the values the player actually learns are included.
+///
+/// ```
+/// use tictacrs::agents::health::ValueDistribution;
+///
+/// let distribution = ValueDistribution::from_values([0.9, 0.1, 0.5, 0.3]);
+/// assert_eq!(distribution.percentile(50.), Some(0.3));
+/// assert_eq!(distribution.percentile(100.), Some(0.9));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueDistribution {
+    values: Vec<f64>,
+}
+
+impl ValueDistribution {
+    /// Distribution of the values in a player's table, including its overlay if it has one
+    pub fn of_player(player: &Player) -> ValueDistribution {
+        Self::from_values(player.iter_states().map(|(_, value)| value))
+    }
+
+    pub fn from_values<I: IntoIterator<Item = f64>>(values: I) -> ValueDistribution {
+        let mut values: Vec<f64> = values.into_iter().collect();
+        values.sort_by(f64::total_cmp);
+        ValueDistribution { values }
+    }
+
+    /// Number of values
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The smallest value which at least the given percentage (0 to 100) of the values are no
+    /// larger than, or None if there are no values
+    pub fn percentile(&self, percentile: f64) -> Option<f64> {
+        if self.values.is_empty() {
+            return None;
+        }
+        let rank = (percentile.clamp(0., 100.) / 100. * self.values.len() as f64).ceil() as usize;
+        Some(self.values[rank.clamp(1, self.values.len()) - 1])
+    }
+
+    /// Fraction of the values within epsilon of the target, 0 if there are no values
+    pub fn fraction_near(&self, target: f64, epsilon: f64) -> f64 {
+        self.fraction_where(|value| (value - target).abs() <= epsilon)
+    }
+
+    fn fraction_where<F: Fn(f64) -> bool>(&self, predicate: F) -> f64 {
+        if self.values.is_empty() {
+            return 0.;
+        }
+        let matching = self.values.iter().filter(|value| predicate(**value)).count();
+        matching as f64 / self.values.len() as f64
+    }
+
+    /// Whether too many of the values are stuck at the ends of the range, or at the value
+    /// new states start from
+    pub fn health(&self, thresholds: &HealthThresholds) -> TableHealth {
+        let saturated = self.fraction_where(|value| {
+            value <= thresholds.epsilon || value >= 1. - thresholds.epsilon
+        });
+        let unlearned = self.fraction_near(0.5, thresholds.epsilon);
+        if saturated > thresholds.max_fraction {
+            TableHealth::Saturated(saturated)
+        } else if unlearned > thresholds.max_fraction {
+            TableHealth::NotLearning(unlearned)
+        } else {
+            TableHealth::Healthy
+        }
+    }
+}
+
+impl fmt::Display for ValueDistribution {
+    /// The quartiles and extremes of the values
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.values.is_empty() {
+            return write!(f, "no values");
+        }
+        let quantiles: Vec<String> = [0., 25., 50., 75., 100.].iter()
+            .map(|p| format!("{:.2}", self.percentile(*p).unwrap()))
+            .collect();
+        write!(f, "min {} | p25 {} | median {} | p75 {} | max {}", quantiles[0], quantiles[1],
+               quantiles[2], quantiles[3], quantiles[4])
+    }
+}
+
+/// When [`ValueDistribution::health`] flags a table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthThresholds {
+    /// How close to 0, 0.5 or 1 a value has to be to count as stuck there
+    pub epsilon: f64,
+    /// Fraction of the values which can be stuck before the table is flagged
+    pub max_fraction: f64,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        HealthThresholds { epsilon: 0.01, max_fraction: 0.9 }
+    }
+}
+
+/// Result of checking a table with [`ValueDistribution::health`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableHealth {
+    Healthy,
+    /// The given fraction of the values are at 0 or 1, usually from a learning rate which is
+    /// too high
+    Saturated(f64),
+    /// The given fraction of the values are still at 0.5, where new states start, usually
+    /// from a learning rate which is too low
+    NotLearning(f64),
+}
+
+impl fmt::Display for TableHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableHealth::Healthy => { write!(f, "healthy") }
+            TableHealth::Saturated(fraction) => {
+                write!(f, "saturated, {} of values are at 0 or 1", percent(*fraction))
+            }
+            TableHealth::NotLearning(fraction) => {
+                write!(f, "not learning, {} of values are still at 0.5", percent(*fraction))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let distribution = ValueDistribution::from_values((1..=10).map(|v| v as f64 / 10.));
+        assert_eq!(distribution.len(), 10);
+        assert_eq!(distribution.percentile(0.), Some(0.1));
+        assert_eq!(distribution.percentile(25.), Some(0.3));
+        assert_eq!(distribution.percentile(90.), Some(0.9));
+        assert_eq!(distribution.percentile(150.), Some(1.));
+        assert_eq!(distribution.to_string(), "min 0.10 | p25 0.30 | median 0.50 | p75 0.80 | max 1.00");
+        assert_eq!(ValueDistribution::from_values([]).percentile(50.), None);
+    }
+
+    #[test]
+    fn test_health() {
+        let thresholds = HealthThresholds::default();
+        // Nearly everything pushed to the ends of the range
+        let saturated = ValueDistribution::from_values(
+            (0..100).map(|i| if i < 95 { (i % 2) as f64 } else { 0.3 }));
+        assert_eq!(saturated.health(&thresholds), TableHealth::Saturated(0.95));
+        // Nearly everything where it started
+        let unlearned = ValueDistribution::from_values(
+            (0..100).map(|i| if i < 95 { 0.5 + (i % 3) as f64 * 0.004 } else { 0.8 }));
+        assert_eq!(unlearned.health(&thresholds), TableHealth::NotLearning(0.95));
+        assert_eq!(unlearned.health(&thresholds).to_string(),
+                   "not learning, 95.0% of values are still at 0.5");
+        // Spread out, or too few stuck to flag
+        let spread = ValueDistribution::from_values((0..100).map(|i| i as f64 / 99.));
+        assert_eq!(spread.health(&thresholds), TableHealth::Healthy);
+        let strict = HealthThresholds { epsilon: 0.01, max_fraction: 0.98 };
+        assert_eq!(saturated.health(&strict), TableHealth::Healthy);
+        assert_eq!(ValueDistribution::from_values([]).health(&thresholds), TableHealth::Healthy);
+    }
+}
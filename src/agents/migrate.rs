@@ -0,0 +1,172 @@
+//! Bringing player saves written by older versions up to the current format. The version in a
+//! save's header ([`crate::meta::SAVE_FORMAT_VERSION`]) is bumped whenever the layout of a
+//! save changes, and decoding handles every layout it supports. Changes to what a save
+//! contains are numbered separately, as revisions, which the header doesn't record, so a
+//! save's revision is worked out from what it contains, and each migration in the chain
+//! takes a save from one revision to the next.
+use std::fmt;
+#[cfg(feature = "persistence")]
+use std::fs;
+#[cfg(feature = "persistence")]
+use std::path::Path;
+#[cfg(feature = "persistence")]
+use crate::agents::players::save_atomically;
+use crate::agents::players::{Player, PlayerError, SaveState};
+use crate::agents::save_file;
+use crate::formatting::count;
+
+/// Revision of the saves written now
+pub const CURRENT_SAVE_REVISION: u32 = 2;
+/// Revision of the saves which stored finished positions alongside the learned ones
+pub const FINISHED_POSITIONS_REVISION: u32 = 1;
+
+/// One step in the chain of migrations
+pub(crate) struct Migration {
+    /// The revision the migration starts from, it produces the next one
+    pub(crate) from_revision: u32,
+    /// What the migration changes, shown after the number of items changed
+    pub(crate) description: &'static str,
+    /// Whether a save can only have been written at this revision
+    pub(crate) detect: fn(&SaveState) -> bool,
+    /// Make the change, returning the number of items changed
+    pub(crate) apply: fn(&mut SaveState) -> usize,
+}
+
+/// Every migration, oldest first
+pub(crate) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from_revision: FINISHED_POSITIONS_REVISION,
+        description: "finished positions dropped",
+        detect: |save_state| save_state.state_space.keys().any(|state| Player::is_terminal(&state)),
+        apply: |save_state| Player::drop_terminal_states(&mut save_state.state_space),
+    },
+];
+
+/// A migration which was applied to a save
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    pub from_revision: u32,
+    /// Number of items the migration changed
+    pub count: usize,
+    pub description: &'static str,
+}
+
+impl fmt::Display for AppliedMigration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "revision {} to {}: {} {}", self.from_revision, self.from_revision + 1,
+               count(self.count as u64), self.description)
+    }
+}
+
+/// The revision a save was detected as, and the migrations applied to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_revision: u32,
+    pub changes: Vec<AppliedMigration>,
+}
+
+impl MigrationReport {
+    /// Whether the save was already in the current format
+    pub fn is_noop(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_noop() {
+            return write!(f, "already in the current format (revision {}), nothing to do",
+                          CURRENT_SAVE_REVISION);
+        }
+        write!(f, "migrated from revision {} to {}", self.from_revision, CURRENT_SAVE_REVISION)?;
+        for change in &self.changes {
+            write!(f, "\n  {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// The revision a save was written at: the oldest revision whose migration detects it, or
+/// the current revision if none do
+pub(crate) fn detect_revision(save_state: &SaveState) -> u32 {
+    MIGRATIONS.iter()
+        .find(|migration| (migration.detect)(save_state))
+        .map_or(CURRENT_SAVE_REVISION, |migration| migration.from_revision)
+}
+
+/// Bring a save up to the current format, applying each migration from its detected revision
+/// on. Applying them again changes nothing.
+pub(crate) fn apply_migrations(save_state: &mut SaveState) -> MigrationReport {
+    let from_revision = detect_revision(save_state);
+    let changes = MIGRATIONS.iter()
+        .filter(|migration| migration.from_revision >= from_revision)
+        .map(|migration| AppliedMigration {
+            from_revision: migration.from_revision,
+            count: (migration.apply)(save_state),
+            description: migration.description,
+        })
+        .collect();
+    MigrationReport { from_revision, changes }
+}
+
+/// Bring the bytes of a save up to the current format, returning the migrated bytes. Saves
+/// from before the save header are given one.
+pub fn migrate_bytes(bytes: &[u8]) -> Result<(Vec<u8>, MigrationReport), PlayerError> {
+    let mut save_state = save_file::decode(bytes)?;
+    let report = apply_migrations(&mut save_state);
+    Ok((save_file::encode(&save_state)?, report))
+}
+
+/// Bring a save file up to the current format, writing the result to `output` (which may be
+/// the input). The result is saved the same way as players are (see
+/// [`Player::save_player_state`]), so a failure never leaves a partly written save. Nothing is
+/// written if the input is already current (header included) and is also the output.
+#[cfg(feature = "persistence")]
+pub fn migrate_file<P: AsRef<Path>, Q: AsRef<Path>>(input: P, output: Q) -> Result<MigrationReport, PlayerError> {
+    let bytes = match fs::read(&input) {
+        Ok(bytes) => { bytes }
+        Err(e) => { return Err(PlayerError::invalid_file(input, e)) }
+    };
+    let (migrated, report) = migrate_bytes(&bytes)?;
+    if migrated == bytes && input.as_ref() == output.as_ref() {
+        return Ok(report);
+    }
+    save_atomically(output.as_ref(), &migrated)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::annealing::AnnealingSchedule;
+    use crate::game::board::{Piece, PlayerPiece};
+
+    #[test]
+    fn test_apply_migrations() {
+        let mut won = [Piece::Empty; 9];
+        won[..3].copy_from_slice(&[Piece::X; 3]);
+        won[3..5].copy_from_slice(&[Piece::O; 2]);
+        let mut save_state = SaveState {
+            piece: PlayerPiece::X,
+            state_space: [([Piece::Empty; 9], 0.5), (won, 1.)].into_iter().collect(),
+            initial_learning_rate: 0.5,
+            initial_exploration_rate: 0.1,
+            iteration: 4,
+            games_played: 0,
+            symmetric: false,
+            learning_schedule: AnnealingSchedule::Constant,
+            exploration_schedule: AnnealingSchedule::Constant,
+        };
+        assert_eq!(detect_revision(&save_state), FINISHED_POSITIONS_REVISION);
+        let report = apply_migrations(&mut save_state);
+        assert_eq!(report.changes, vec![AppliedMigration {
+            from_revision: 1, count: 1, description: "finished positions dropped" }]);
+        assert_eq!(report.to_string(), "migrated from revision 1 to 2\n  revision 1 to 2: 1 finished positions dropped");
+        assert_eq!(save_state.state_space.len(), 1);
+        assert_eq!(detect_revision(&save_state), CURRENT_SAVE_REVISION);
+        // Migrating again changes nothing
+        let report = apply_migrations(&mut save_state);
+        assert!(report.is_noop());
+        assert_eq!(report.to_string(), "already in the current format (revision 2), nothing to do");
+    }
+}
@@ -0,0 +1,185 @@
+//! An opponent which plays perfectly, by searching the whole game tree from each position
+
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use crate::agents::agent::{Agent, AgentError};
+use crate::game::board::{Piece, PlayerPiece};
+use crate::game::rules;
+use crate::game::states::{encode_state, STATE_CODES};
+
+/// Always makes the game-theoretic best move, so it never loses. Of the moves which win it
+/// takes the quickest, and of those which lose the slowest, so it always completes a line
+/// when it can and blocks one when it has to.
+///
+/// ```
+/// use tictacrs::agents::minimax::MinimaxAgent;
+/// use tictacrs::prelude::*;
+///
+/// // X threatens the top row, which O has to block
+/// let mut state = [Piece::Empty; 9];
+/// state[0] = Piece::X;
+/// state[1] = Piece::X;
+/// state[4] = Piece::O;
+/// let mut agent = MinimaxAgent::new(PlayerPiece::O);
+/// assert_eq!(agent.choose_move(&state), Ok([0, 2]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MinimaxAgent {
+    piece: PlayerPiece,
+    /// Score of each position searched for the player to move in it, indexed by the
+    /// position's code and then the mover
+    scores: Vec<Option<i8>>,
+    /// Chooses between equally good moves, None to take the one on the lowest square
+    generator: Option<SmallRng>,
+}
+
+impl MinimaxAgent {
+    /// A perfect agent playing the given piece, which takes the lowest square of equally
+    /// good moves, so always plays the same game against the same moves
+    pub fn new(piece: PlayerPiece) -> MinimaxAgent {
+        MinimaxAgent { piece, scores: vec![None; STATE_CODES * 2], generator: None }
+    }
+
+    /// A perfect agent playing the given piece, which chooses between equally good moves at
+    /// random, the same way whenever it's created with the same seed
+    pub fn with_random_ties(piece: PlayerPiece, seed: u64) -> MinimaxAgent {
+        MinimaxAgent { generator: Some(SmallRng::seed_from_u64(seed)), ..MinimaxAgent::new(piece) }
+    }
+
+    /// The best score the mover can force from the position: 0 for a draw, positive for a
+    /// win and negative for a loss, further from 0 the sooner the game ends
+    fn score(&mut self, state: &[Piece; 9], mover: PlayerPiece) -> i8 {
+        let slot = encode_state(state) as usize * 2 + if mover == PlayerPiece::X { 0 } else { 1 };
+        if let Some(score) = self.scores[slot] {
+            return score;
+        }
+        let empty = state.iter().filter(|piece| **piece == Piece::Empty).count() as i8;
+        let score = if rules::winner(state).is_some() {
+            // The other player's last move completed a line
+            -(1 + empty)
+        } else {
+            (0..9).filter(|idx| state[*idx] == Piece::Empty)
+                .map(|idx| -self.score(&Self::after(state, idx, mover), mover.opponent()))
+                .max()
+                .unwrap_or(0)
+        };
+        self.scores[slot] = Some(score);
+        score
+    }
+
+    /// The position after the mover plays on the square
+    fn after(state: &[Piece; 9], square: usize, mover: PlayerPiece) -> [Piece; 9] {
+        let mut next = *state;
+        next[square] = mover.into();
+        next
+    }
+}
+
+impl Agent for MinimaxAgent {
+    fn piece(&self) -> PlayerPiece {
+        self.piece
+    }
+
+    fn choose_move(&mut self, state: &[Piece; 9]) -> Result<[u8; 2], AgentError> {
+        if rules::winner(state).is_some() || rules::is_full(state) {
+            return Err(AgentError::GameAlreadyOver);
+        }
+        let scored: Vec<(usize, i8)> = (0..9).filter(|idx| state[*idx] == Piece::Empty)
+            .map(|idx| (idx, -self.score(&Self::after(state, idx, self.piece), self.piece.opponent())))
+            .collect();
+        let best = scored.iter().map(|(_, score)| *score).max().expect("There is an empty square");
+        let tied: Vec<usize> = scored.iter().filter(|(_, score)| *score == best).map(|(idx, _)| *idx).collect();
+        let chosen = match self.generator.as_mut() {
+            Some(generator) => { *tied.choose(generator).expect("The best move is tied with itself") }
+            None => { tied[0] }
+        };
+        Ok([chosen as u8 / 3, chosen as u8 % 3])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::random::RandomAgent;
+    use crate::game::board::Board;
+    use crate::game::result::GameResult;
+    use crate::game::states::reachable_states;
+    use crate::game::threats::winning_squares;
+
+    /// The piece whose turn it is in a position reached from the empty board
+    fn to_move(state: &[Piece; 9]) -> PlayerPiece {
+        let x_count = state.iter().filter(|piece| **piece == Piece::X).count();
+        let o_count = state.iter().filter(|piece| **piece == Piece::O).count();
+        if x_count == o_count { PlayerPiece::X } else { PlayerPiece::O }
+    }
+
+    /// Play a game out from the empty board, returning its result
+    fn play(x: &mut dyn Agent, o: &mut dyn Agent) -> GameResult {
+        let mut board = Board::new();
+        while let Some(piece) = board.turn() {
+            let mover: &mut dyn Agent = if piece == PlayerPiece::X { &mut *x } else { &mut *o };
+            let chosen = mover.choose_move(&board.get_compact_state()).unwrap();
+            board.make_auto_player_move(chosen[0], chosen[1], piece);
+        }
+        board.result().unwrap()
+    }
+
+    #[test]
+    fn test_never_loses_to_random() {
+        for piece in [PlayerPiece::X, PlayerPiece::O] {
+            let mut perfect = MinimaxAgent::with_random_ties(piece, 1);
+            let mut random = RandomAgent::new(piece.opponent(), 2);
+            for _ in 0..500 {
+                let result = if piece == PlayerPiece::X {
+                    play(&mut perfect, &mut random)
+                } else {
+                    play(&mut random, &mut perfect)
+                };
+                assert_ne!(result, GameResult::Win(piece.opponent()));
+            }
+        }
+        // Between perfect players every game is a draw
+        let mut x = MinimaxAgent::with_random_ties(PlayerPiece::X, 3);
+        let mut o = MinimaxAgent::with_random_ties(PlayerPiece::O, 4);
+        for _ in 0..50 {
+            assert_eq!(play(&mut x, &mut o), GameResult::Draw);
+        }
+    }
+
+    #[test]
+    fn test_wins_and_blocks() {
+        let mut agents = [MinimaxAgent::new(PlayerPiece::X), MinimaxAgent::new(PlayerPiece::O)];
+        for state in reachable_states() {
+            if rules::winner(&state).is_some() || rules::is_full(&state) {
+                continue;
+            }
+            let mover = to_move(&state);
+            let agent = &mut agents[if mover == PlayerPiece::X { 0 } else { 1 }];
+            let [row, col] = agent.choose_move(&state).unwrap();
+            let chosen = (row * 3 + col) as usize;
+            let wins = winning_squares(&state, mover.into());
+            let threats = winning_squares(&state, mover.opponent().into());
+            if !wins.is_empty() {
+                assert!(wins.contains(&chosen), "didn't win in {:?}", state);
+            } else if threats.len() == 1 {
+                // Against a fork every move loses just as quickly, so only single threats are
+                // sure to be blocked
+                assert_eq!(threats, vec![chosen], "didn't block in {:?}", state);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ties() {
+        // Every opening move draws, so the deterministic agent takes the first square
+        let mut first = MinimaxAgent::new(PlayerPiece::X);
+        assert_eq!(first.choose_move(&[Piece::Empty; 9]), Ok([0, 0]));
+        let mut random = MinimaxAgent::with_random_ties(PlayerPiece::X, 5);
+        let mut again = MinimaxAgent::with_random_ties(PlayerPiece::X, 5);
+        let openings: Vec<[u8; 2]> = (0..20).map(|_| random.choose_move(&[Piece::Empty; 9]).unwrap()).collect();
+        assert!(openings.iter().any(|opening| *opening != [0, 0]));
+        assert!(openings.iter().all(|opening| again.choose_move(&[Piece::Empty; 9]) == Ok(*opening)));
+        assert_eq!(first.choose_move(&[Piece::X; 9]), Err(AgentError::GameAlreadyOver));
+    }
+}
@@ -1,2 +1,19 @@
+pub mod agent;
+pub mod annealing;
+pub mod certify;
+#[cfg(feature = "train")]
+pub mod evaluate;
+pub mod health;
+pub mod migrate;
+pub mod minimax;
 pub mod players;
-pub mod trainer;
\ No newline at end of file
+pub mod random;
+pub(crate) mod save_file;
+pub mod session;
+#[cfg(feature = "train")]
+pub mod simulate;
+pub(crate) mod state_table;
+#[cfg(feature = "persistence")]
+pub mod snapshots;
+#[cfg(feature = "train")]
+pub mod trainer;
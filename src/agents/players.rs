@@ -1,13 +1,34 @@
-use crate::game::board::Piece;
+use crate::agents::annealing::{AnnealingSchedule, EXPLORATION_SCHEDULE, LEARNING_SCHEDULE};
+use crate::agents::{migrate, save_file};
+use crate::agents::state_table::StateTable;
+use crate::game::board::{Board, Piece, PlayerPiece};
+use crate::game::coords::Coord;
+use crate::game::rules::{self, GameState};
+use crate::game::states::{reachable_states, symmetric_images};
+use crate::game::symmetry::canonical_form;
+use crate::game::threats::{describe_line, is_fork, lines_completed};
+use crate::formatting::{count, percent};
+use crate::meta::compact_board_char;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::panic::Location;
+use std::str::FromStr;
 use borsh::{BorshDeserialize, BorshSerialize};
 use rand::distributions::Standard;
 use rand::rngs::SmallRng;
 use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
-use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::hash::{DefaultHasher, Hash, Hasher};
+#[cfg(feature = "persistence")]
 use std::fs::File;
+#[cfg(feature = "persistence")]
 use std::io::{BufReader, BufWriter, Write};
+#[cfg(feature = "persistence")]
 use std::path::Path;
+use std::path::PathBuf;
 /*
 Description of the player:
 - Has a table of numbers, one for each possible state of the game. Each
@@ -27,33 +48,439 @@ Description of the player:
  */
 
 /// Struct representing the "savable" part of the player
-#[derive(BorshSerialize, BorshDeserialize)]
-struct SaveState {
+#[derive(BorshSerialize, Clone, Debug, PartialEq)]
+pub(crate) struct SaveState {
     /// Which piece the player uses
-    piece: Piece,
+    pub(crate) piece: PlayerPiece,
     /// The states and probability of winning from each (modification of this is how learning occurs)
-    state_space: HashMap<[Piece; 9], f64>,
+    pub(crate) state_space: StateTable,
     /// How fast the probabilities of winning from a position are updated
-    initial_learning_rate: f64,
+    pub(crate) initial_learning_rate: f64,
     /// How often a less than optimum choice is made
-    initial_exploration_rate: f64,
+    pub(crate) initial_exploration_rate: f64,
     /// Number of games played (used to taper the learning rate)
-    iteration: u32,
+    pub(crate) iteration: u32,
+    /// Number of games played against people, saves from before it was counted read as 0
+    pub(crate) games_played: u32,
+    /// Whether the table holds one value for each position and all its rotations and
+    /// reflections, stored under their canonical form (saves from before this read as false)
+    pub(crate) symmetric: bool,
+    /// How the learning rate is annealed, saves from before schedules were saved read as the
+    /// schedules the tictacrs binary has always trained with
+    pub(crate) learning_schedule: AnnealingSchedule,
+    /// How the exploration rate is annealed
+    pub(crate) exploration_schedule: AnnealingSchedule,
 }
 
+impl BorshDeserialize for SaveState {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<SaveState> {
+        Ok(SaveState {
+            piece: PlayerPiece::deserialize_reader(reader)?,
+            state_space: StateTable::deserialize_reader(reader)?,
+            initial_learning_rate: f64::deserialize_reader(reader)?,
+            initial_exploration_rate: f64::deserialize_reader(reader)?,
+            iteration: u32::deserialize_reader(reader)?,
+            games_played: read_trailing(reader)?.unwrap_or(0),
+            symmetric: read_trailing(reader)?.unwrap_or(false),
+            learning_schedule: read_trailing(reader)?.unwrap_or(LEARNING_SCHEDULE),
+            exploration_schedule: read_trailing(reader)?.unwrap_or(EXPLORATION_SCHEDULE),
+        })
+    }
+}
+
+/// A field which older saves end without, None if the input has already ended
+fn read_trailing<T: BorshDeserialize, R: std::io::Read>(reader: &mut R) -> std::io::Result<Option<T>> {
+    let mut first = [0u8; 1];
+    loop {
+        match reader.read(&mut first) {
+            Ok(0) => { return Ok(None) }
+            Ok(_) => { break }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => { continue }
+            Err(e) => { return Err(e) }
+        }
+    }
+    T::deserialize_reader(&mut std::io::Read::chain(&first[..], reader)).map(Some)
+}
+
+/// Write a file by way of a temporary file beside it (`<file_path>.tmp`), which is flushed and
+/// synced to disk before being renamed over the destination, so the destination always holds
+/// either its old contents or all of the new ones. Renaming replaces an existing file on
+/// Windows as well as Unix.
+#[cfg(feature = "persistence")]
+pub(crate) fn save_atomically(file_path: &Path, bytes: &[u8]) -> Result<(), PlayerError> {
+    let mut temporary = file_path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let file = match File::create(&temporary) {
+        Ok(f) => { f }
+        Err(e) => { return Err(PlayerError::invalid_file(file_path, e)) }
+    };
+    if let Err(e) = write_flushed(file, bytes).and_then(|file| file.sync_all()) {
+        _ = std::fs::remove_file(&temporary);
+        return Err(PlayerError::UnableToSave(e));
+    }
+    if let Err(e) = std::fs::rename(&temporary, file_path) {
+        _ = std::fs::remove_file(&temporary);
+        return Err(PlayerError::invalid_file(file_path, e));
+    }
+    Ok(())
+}
+
+/// Write all the bytes through a buffer and flush it, returning the writer, or the first error
+/// including one from the final flush
+#[cfg(feature = "persistence")]
+fn write_flushed<W: Write>(writer: W, bytes: &[u8]) -> io::Result<W> {
+    let mut buffered = BufWriter::new(writer);
+    buffered.write_all(bytes)?;
+    buffered.into_inner().map_err(|e| e.into_error())
+}
+
+/// A function annealing a rate, which takes the initial rate and the iteration and returns the
+/// rate to use. Closures can capture parameters, e.g. a decay rate read from a configuration.
+pub type AnnealingFn = Arc<dyn Fn(f64, u32) -> f64 + Send + Sync>;
 
 /// Struct representing the computer "Player"
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// let mut player = Player::new(PlayerPiece::X, 0.75, 0.0, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+/// let board = Board::new();
+/// let [row, col] = player.make_move(&board.get_compact_state()).unwrap();
+/// assert!(row < 3 && col < 3);
+/// ```
+#[derive(Clone)]
 pub struct Player {
     /// The savable state of the player
     save_state: SaveState,
-    /// Function to update the learning rate over time, takes in the current learning rate
-    /// and the iteration and returns a new learning rate
-    learning_annealing_function: fn(f64, u32) -> f64,
-    /// Function to update the exploration rate over time, takes in the current exploration rate
-    /// and the iteration, and returns a new exploration rate
-    exploration_annealing_function: fn(f64, u32) -> f64,
+    /// Function annealing the learning rate in place of the saved schedule, if any (functions
+    /// can't be saved)
+    learning_annealing_function: Option<AnnealingFn>,
+    /// Function annealing the exploration rate in place of the saved schedule, if any
+    exploration_annealing_function: Option<AnnealingFn>,
     /// Random number generator used by the player to make decisions
     generator: SmallRng,
+    /// How to choose between moves with equal value
+    tie_break: TieBreak,
+    /// Whether (and how) the player learns while it plays
+    learning: Learning,
+    /// Optional overlay table, when present all learning is written here instead of into the
+    /// base state space, and lookups check it before the base state space
+    overlay: Option<StateTable>,
+    /// The player's last move, and how it was chosen
+    last_decision: Option<MoveDecision>,
+    /// Factors scaling the exploration rate by how far into the game the player is
+    ply_exploration: PlyExploration,
+    /// When the player's value updates are applied
+    update_mode: UpdateMode,
+    /// Updates recorded during the current episode as (state, target, rate), waiting to be
+    /// applied by [`Player::end_episode`]
+    pending_updates: Vec<([Piece; 9], f64, f64)>,
+    /// Fraction of each update also applied to the symmetric images of the state already in
+    /// the table, if propagating updates to them
+    symmetric_propagation: Option<f64>,
+    /// Which states the player may add to its table and update while learning
+    learn_scope: LearnScope,
+    /// Positions the player has moved from or to during the current episode, the only ones
+    /// its updates should touch
+    trajectory: HashSet<[Piece; 9]>,
+    /// Value updates so far which broke one of the learning invariants
+    violations: LearningViolations,
+    /// The position the player's last move in the current game reached, whose value its next
+    /// greedy move (or the end of the game) backs up
+    last_afterstate: Option<[Piece; 9]>,
+    /// Value of a drawn game
+    draw_reward: f64,
+    /// Number of finished positions dropped from the saves the player was read from
+    terminal_states_dropped: usize,
+}
+
+/// Struct representing the "savable" part of a player's overlay
+#[cfg(feature = "persistence")]
+#[derive(BorshSerialize, BorshDeserialize)]
+struct OverlaySaveState {
+    /// Which piece the player the overlay was learned by uses
+    piece: PlayerPiece,
+    /// The states whose values differ from the base state space
+    state_space: StateTable,
+}
+
+/// Policy used to choose between several moves with the same (best) value
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum TieBreak {
+    /// Choose uniformly at random using the player's random number generator
+    #[default]
+    Random,
+    /// Choose the tied move with the lowest square index (reading order a1, a2, ..., c3)
+    FirstIndex,
+    /// Choose the tied move with the highest square index
+    LastIndex,
+    /// Prefer the center, then the corners, then the edges (ties within a group go to the
+    /// lowest square index)
+    PreferCenterThenCorners,
+}
+
+/// Whether a player updates its values as it plays, see [`Player::set_learning`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Learning {
+    /// Learn, exploring according to the annealed exploration schedule (as in training)
+    #[default]
+    Scheduled,
+    /// Learn, but explore at the given fixed rate instead of following the schedule
+    FixedExploration(f64),
+    /// Don't change any values, and never explore
+    Frozen,
+}
+
+/// Factors scaling a player's exploration rate by how far into the game it is, one for each
+/// ply from 0 (the first move) to 8 (the last), see [`Player::set_ply_exploration`]. The ply is
+/// the number of pieces on the board being moved on.
+///
+/// ```
+/// use tictacrs::agents::players::PlyExploration;
+///
+/// let factors: PlyExploration = "1,1,0.8,0.8,0.5,0.5,0.2,0.2,0".parse().unwrap();
+/// assert_eq!(factors.factor(2), 0.8);
+/// assert_eq!(factors.to_string(), "1,1,0.8,0.8,0.5,0.5,0.2,0.2,0");
+/// assert!("1,1,1".parse::<PlyExploration>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlyExploration([f64; 9]);
+
+impl PlyExploration {
+    /// Factors for each ply, each between 0 and 1
+    pub fn new(factors: [f64; 9]) -> Result<PlyExploration, PlayerConfigError> {
+        if factors.iter().all(|factor| (0. ..=1.).contains(factor)) {
+            Ok(PlyExploration(factors))
+        } else {
+            Err(PlayerConfigError::InvalidPlyExploration)
+        }
+    }
+
+    /// Factor for the given ply
+    pub fn factor(&self, ply: usize) -> f64 {
+        self.0[ply.min(8)]
+    }
+
+    /// The factor for each ply
+    pub fn factors(&self) -> [f64; 9] {
+        self.0
+    }
+}
+
+/// Explores at the full rate throughout the game
+impl Default for PlyExploration {
+    fn default() -> PlyExploration {
+        PlyExploration([1.; 9])
+    }
+}
+
+impl fmt::Display for PlyExploration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let factors: Vec<String> = self.0.iter().map(|factor| factor.to_string()).collect();
+        write!(f, "{}", factors.join(","))
+    }
+}
+
+impl FromStr for PlyExploration {
+    type Err = PlayerConfigError;
+
+    /// Parse nine comma separated factors, such as "1,1,0.8,0.8,0.5,0.5,0.2,0.2,0"
+    fn from_str(s: &str) -> Result<PlyExploration, PlayerConfigError> {
+        let factors: Vec<f64> = s.split(',')
+            .map(|factor| factor.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| PlayerConfigError::InvalidPlyExploration)?;
+        let factors: [f64; 9] = factors.try_into().map_err(|_| PlayerConfigError::InvalidPlyExploration)?;
+        PlyExploration::new(factors)
+    }
+}
+
+/// When a player applies the updates to its values, see [`Player::set_update_mode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpdateMode {
+    /// Update each value immediately, as the player moves
+    #[default]
+    Online,
+    /// Record the updates during a game, and apply them all at once against the values from
+    /// before the game when [`Player::end_episode`] is called
+    EndOfEpisode,
+}
+
+/// Which states a player may add to its table and update while learning, see
+/// [`Player::set_learn_scope`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LearnScope {
+    /// Add and update any state the player looks at
+    #[default]
+    All,
+    /// Only add and update the states on the player's own trajectory, the positions its moves
+    /// reach. The other positions it considers are looked up without being added to the table.
+    OwnLinesOnly,
+    /// Only apply the updates from finished games: the result backed up into the position
+    /// reached by the player's last move, and the position reached by the move before when
+    /// the last move ends the game
+    TerminalOnly,
+}
+
+impl fmt::Display for LearnScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LearnScope::All => { write!(f, "all") }
+            LearnScope::OwnLinesOnly => { write!(f, "own-lines-only") }
+            LearnScope::TerminalOnly => { write!(f, "terminal-only") }
+        }
+    }
+}
+
+/// Number of value updates which broke each of the learning invariants, see
+/// [`Player::learning_violations`]. With the strict-learning feature the player panics on
+/// the first one instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LearningViolations {
+    /// Values written outside of 0 to 1
+    pub out_of_range: usize,
+    /// Finished positions written with a value other than their fixed one (1 for a win, 0
+    /// otherwise)
+    pub terminal_changed: usize,
+    /// Updates to positions the player hasn't moved from or to during the episode
+    pub off_trajectory: usize,
+}
+
+impl LearningViolations {
+    /// Total number of violations of any kind
+    pub fn total(&self) -> usize {
+        self.out_of_range + self.terminal_changed + self.off_trajectory
+    }
+}
+
+impl fmt::Display for LearningViolations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} suspicious value updates: {} out of range, {} to finished positions, {} off \
+                   the trajectory", count(self.total() as u64), count(self.out_of_range as u64),
+               count(self.terminal_changed as u64), count(self.off_trajectory as u64))
+    }
+}
+
+/// Summary of the states removed from a player's table by [`Player::compact`]
+#[derive(Debug, PartialEq)]
+pub struct CompactReport {
+    /// Number of states in the table before compacting
+    pub states_before: usize,
+    /// Number of states removed from the table
+    pub states_removed: usize,
+}
+
+impl fmt::Display for CompactReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "removed {} of {} states", count(self.states_removed as u64),
+               count(self.states_before as u64))?;
+        if self.states_before > 0 {
+            write!(f, " ({})", percent(self.states_removed as f64 / self.states_before as f64))?;
+        }
+        Ok(())
+    }
+}
+
+/// The main reason a move was chosen, see [`Player::explain_move`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveReason {
+    /// The move completes a line, winning the game
+    Win,
+    /// The move stops the opponent from completing a line
+    Block,
+    /// The move creates two winning threats at once
+    Fork,
+    /// The move was chosen based on the learned values alone
+    Value,
+}
+
+/// Where a player's value for a position comes from, see [`MoveDecision::knowledge`] and
+/// [`MoveExplanation::knowledge`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Knowledge {
+    /// The position is in the player's table, so its value comes from earlier games (even if
+    /// it has been trained back to the default)
+    Learned,
+    /// The position isn't in the player's table, so it has the default value for unseen
+    /// positions
+    DefaultValue,
+    /// The game is over in the position, so its value is known without learning
+    Terminal,
+}
+
+/// A move a player chose, and how it chose it, see [`Player::decide_move`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveDecision {
+    /// The row and column moved to
+    pub position: [u8; 2],
+    /// Whether the move was exploratory, rather than the best the player knew of
+    pub exploratory: bool,
+    /// The player's value of the position the move reached
+    pub value: f64,
+    /// Where that value comes from, as the table stood before the move was chosen (considering
+    /// moves can add them to the table)
+    pub knowledge: Knowledge,
+}
+
+impl MoveDecision {
+    /// The row and column moved to, as returned by [`Player::make_move`]
+    pub fn position(&self) -> [u8; 2] {
+        self.position
+    }
+}
+
+/// Explanation of why a player chose a move, whose Display is one friendly sentence
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveExplanation {
+    /// The move that was chosen
+    pub chosen: [u8; 2],
+    /// The main reason for choosing it
+    pub reason: MoveReason,
+    /// The line completed or blocked by the move, if any
+    pub line: Option<[usize; 3]>,
+    /// Learned value of the chosen move
+    pub value: f64,
+    /// Highest learned value among all the possible moves
+    pub best_value: f64,
+    /// Number of possible moves
+    pub options: usize,
+    /// Where the value of the chosen move comes from
+    pub knowledge: Knowledge,
+}
+
+impl fmt::Display for MoveExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chosen = Player::to_human_move(&self.chosen);
+        let line = self.line.map(|l| describe_line(&l)).unwrap_or_default();
+        match self.reason {
+            MoveReason::Win => {
+                write!(f, "I played {} because it completes {}, winning the game", chosen, line)
+            }
+            MoveReason::Block => {
+                write!(f, "I played {} because it blocks your three-in-a-row on {}", chosen, line)
+            }
+            MoveReason::Fork => {
+                write!(f, "I played {} because it makes two threats at once, you can't block both",
+                       chosen)
+            }
+            MoveReason::Value if self.knowledge == Knowledge::DefaultValue && self.value >= self.best_value => {
+                write!(f, "I played {} but I haven't seen this position before, so I'm guessing among {} options",
+                       chosen, self.options)
+            }
+            MoveReason::Value if self.knowledge == Knowledge::DefaultValue => {
+                write!(f, "I played {} to explore, I haven't seen this position before, so I'm guessing (the best of {} options was {:.2})",
+                       chosen, self.options, self.best_value)
+            }
+            MoveReason::Value if self.value >= self.best_value => {
+                write!(f, "I played {} because it had the highest learned value {:.2} among {} options",
+                       chosen, self.value, self.options)
+            }
+            MoveReason::Value => {
+                write!(f, "I played {} to explore, its learned value is {:.2} but the best of {} options was {:.2}",
+                       chosen, self.value, self.options, self.best_value)
+            }
+        }
+    }
 }
 
 struct PotentialMoves {
@@ -63,27 +490,299 @@ struct PotentialMoves {
     probabilities: Vec<f64>,
 }
 
-impl Player {
-    /// Create a new player
-    pub fn new(piece: Piece, initial_learning_rate: f64, initial_exploration_rate: f64,
-               learning_annealing_function: fn(f64, u32) -> f64,
-               exploration_annealing_function: fn(f64, u32) -> f64, ) -> Player {
+/// Builder for a [`Player`], created with [`Player::builder`]
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// let player = Player::builder(PlayerPiece::O)
+///     .learning_rate(0.5)
+///     .exploration_rate(0.1)
+///     .exploration_schedule(AnnealingSchedule::StepDecay { drop_rate: 0.9, step_size: 10 })
+///     .tie_break(TieBreak::FirstIndex)
+///     .seed(42)
+///     .build()
+///     .unwrap();
+/// assert_eq!(player.get_player_piece(), PlayerPiece::O);
+/// ```
+#[derive(Clone)]
+pub struct PlayerBuilder {
+    piece: PlayerPiece,
+    initial_learning_rate: f64,
+    initial_exploration_rate: f64,
+    learning_schedule: AnnealingSchedule,
+    exploration_schedule: AnnealingSchedule,
+    learning_annealing_function: Option<AnnealingFn>,
+    exploration_annealing_function: Option<AnnealingFn>,
+    tie_break: TieBreak,
+    learning: Learning,
+    ply_exploration: PlyExploration,
+    update_mode: UpdateMode,
+    symmetric_propagation: Option<f64>,
+    symmetric: bool,
+    learn_scope: LearnScope,
+    draw_reward: f64,
+    seed: Option<u64>,
+}
+
+impl PlayerBuilder {
+    /// Set the initial learning rate (default 0.75), must be between 0 and 1
+    pub fn learning_rate(mut self, rate: f64) -> PlayerBuilder {
+        self.initial_learning_rate = rate;
+        self
+    }
+
+    /// Set the initial exploration rate (default 0.2), must be between 0 and 1
+    pub fn exploration_rate(mut self, rate: f64) -> PlayerBuilder {
+        self.initial_exploration_rate = rate;
+        self
+    }
+
+    /// Set the schedule annealing the learning rate, which is saved with the player (default
+    /// keeps the rate constant)
+    pub fn learning_schedule(mut self, schedule: AnnealingSchedule) -> PlayerBuilder {
+        self.learning_schedule = schedule;
+        self.learning_annealing_function = None;
+        self
+    }
+
+    /// Set the schedule annealing the exploration rate, which is saved with the player
+    /// (default keeps the rate constant)
+    pub fn exploration_schedule(mut self, schedule: AnnealingSchedule) -> PlayerBuilder {
+        self.exploration_schedule = schedule;
+        self.exploration_annealing_function = None;
+        self
+    }
+
+    /// Anneal the learning rate with a function of the initial rate and the iteration instead
+    /// of a schedule. Functions can't be saved, so a player read back from a save keeps its
+    /// rate constant.
+    pub fn learning_function(mut self, function: impl Fn(f64, u32) -> f64 + Send + Sync + 'static) -> PlayerBuilder {
+        self.learning_schedule = AnnealingSchedule::Constant;
+        self.learning_annealing_function = Some(Arc::new(function));
+        self
+    }
+
+    /// Anneal the exploration rate with a function instead of a schedule, see
+    /// [`PlayerBuilder::learning_function`]
+    pub fn exploration_function(mut self, function: impl Fn(f64, u32) -> f64 + Send + Sync + 'static) -> PlayerBuilder {
+        self.exploration_schedule = AnnealingSchedule::Constant;
+        self.exploration_annealing_function = Some(Arc::new(function));
+        self
+    }
+
+    /// Set how ties between equally valued moves are broken (default random)
+    pub fn tie_break(mut self, tie_break: TieBreak) -> PlayerBuilder {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// Set whether the player learns as it plays (default follows the schedules)
+    pub fn learning(mut self, learning: Learning) -> PlayerBuilder {
+        self.learning = learning;
+        self
+    }
+
+    /// Scale the exploration rate by how far into the game the player is (default explores
+    /// at the full rate throughout)
+    pub fn ply_exploration(mut self, ply_exploration: PlyExploration) -> PlayerBuilder {
+        self.ply_exploration = ply_exploration;
+        self
+    }
+
+    /// Set when the player's value updates are applied (default online)
+    pub fn update_mode(mut self, update_mode: UpdateMode) -> PlayerBuilder {
+        self.update_mode = update_mode;
+        self
+    }
+
+    /// Also apply the given fraction of each update to the symmetric images of the state which
+    /// are already in the table (default off), must be between 0 and 1
+    pub fn symmetric_propagation(mut self, fraction: f64) -> PlayerBuilder {
+        self.symmetric_propagation = Some(fraction);
+        self
+    }
+
+    /// Store one value for each position and all its rotations and reflections (default off),
+    /// so learning about a position teaches the player about all of them. Saved with the
+    /// player, and can't be combined with [`PlayerBuilder::symmetric_propagation`].
+    pub fn symmetric(mut self, symmetric: bool) -> PlayerBuilder {
+        self.symmetric = symmetric;
+        self
+    }
+
+    /// Set which states the player may add to its table and update (default all)
+    pub fn learn_scope(mut self, learn_scope: LearnScope) -> PlayerBuilder {
+        self.learn_scope = learn_scope;
+        self
+    }
+
+    /// Set the value of a drawn game, between a loss at 0 and a win at 1 (default 0)
+    pub fn draw_reward(mut self, reward: f64) -> PlayerBuilder {
+        self.draw_reward = reward;
+        self
+    }
+
+    /// Seed the player's random number generator, making its decisions reproducible
+    /// (default seeds from system entropy)
+    pub fn seed(mut self, seed: u64) -> PlayerBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Validate the configuration and create the player
+    pub fn build(self) -> Result<Player, PlayerConfigError> {
+        if !(0. ..=1.).contains(&self.initial_learning_rate) {
+            return Err(PlayerConfigError::InvalidLearningRate);
+        }
+        if !(0. ..=1.).contains(&self.initial_exploration_rate) {
+            return Err(PlayerConfigError::InvalidExplorationRate);
+        }
+        if let Learning::FixedExploration(rate) = self.learning {
+            if !(0. ..=1.).contains(&rate) {
+                return Err(PlayerConfigError::InvalidExplorationRate);
+            }
+        }
+        if let Some(fraction) = self.symmetric_propagation {
+            if !(0. ..=1.).contains(&fraction) {
+                return Err(PlayerConfigError::InvalidSymmetricPropagation);
+            }
+            if self.symmetric {
+                return Err(PlayerConfigError::PropagationWhileSymmetric);
+            }
+        }
+        if !(0. ..=1.).contains(&self.draw_reward) {
+            return Err(PlayerConfigError::InvalidDrawReward);
+        }
+        Ok(self.build_unchecked())
+    }
+
+    /// Create the player without validating the configuration
+    fn build_unchecked(self) -> Player {
         Player {
             save_state: SaveState {
-                piece,
-                state_space: HashMap::new(),
-                initial_learning_rate,
-                initial_exploration_rate,
+                piece: self.piece,
+                state_space: StateTable::new(),
+                initial_learning_rate: self.initial_learning_rate,
+                initial_exploration_rate: self.initial_exploration_rate,
                 iteration: 0,
+                games_played: 0,
+                symmetric: self.symmetric,
+                learning_schedule: self.learning_schedule,
+                exploration_schedule: self.exploration_schedule,
             },
-            learning_annealing_function,
-            exploration_annealing_function,
-            generator: SmallRng::from_entropy(),
+            learning_annealing_function: self.learning_annealing_function,
+            exploration_annealing_function: self.exploration_annealing_function,
+            generator: match self.seed {
+                Some(seed) => { SmallRng::seed_from_u64(seed) }
+                None => { SmallRng::from_entropy() }
+            },
+            tie_break: self.tie_break,
+            learning: self.learning,
+            overlay: None,
+            last_decision: None,
+            ply_exploration: self.ply_exploration,
+            update_mode: self.update_mode,
+            pending_updates: Vec::new(),
+            symmetric_propagation: self.symmetric_propagation,
+            learn_scope: self.learn_scope,
+            trajectory: HashSet::new(),
+            violations: LearningViolations::default(),
+            last_afterstate: None,
+            draw_reward: self.draw_reward,
+            terminal_states_dropped: 0,
+        }
+    }
+}
+
+/// Errors produced when building a [`Player`] with an invalid configuration
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub enum PlayerConfigError {
+    /// The learning rate must be between 0 and 1
+    InvalidLearningRate,
+    /// The exploration rate must be between 0 and 1
+    InvalidExplorationRate,
+    /// There must be nine ply exploration factors, each between 0 and 1
+    InvalidPlyExploration,
+    /// The fraction of updates propagated to symmetric states must be between 0 and 1
+    InvalidSymmetricPropagation,
+    /// The value of a draw must be between 0 and 1
+    InvalidDrawReward,
+    /// Updates can't be propagated to symmetric states when the player already stores them
+    /// as one
+    PropagationWhileSymmetric,
+}
+
+impl Player {
+    /// Create a new player
+    pub fn new(piece: PlayerPiece, initial_learning_rate: f64, initial_exploration_rate: f64,
+               learning_schedule: AnnealingSchedule, exploration_schedule: AnnealingSchedule) -> Player {
+        Player::builder(piece)
+            .learning_rate(initial_learning_rate)
+            .exploration_rate(initial_exploration_rate)
+            .learning_schedule(learning_schedule)
+            .exploration_schedule(exploration_schedule)
+            .build_unchecked()
+    }
+
+    /// Create a new player annealing its rates with functions rather than schedules, as
+    /// [`Player::new`] used to. The functions aren't saved, see
+    /// [`PlayerBuilder::learning_function`].
+    pub fn with_annealing_functions(piece: PlayerPiece, initial_learning_rate: f64, initial_exploration_rate: f64,
+                                    learning_annealing_function: impl Fn(f64, u32) -> f64 + Send + Sync + 'static,
+                                    exploration_annealing_function: impl Fn(f64, u32) -> f64 + Send + Sync + 'static) -> Player {
+        Player::builder(piece)
+            .learning_rate(initial_learning_rate)
+            .exploration_rate(initial_exploration_rate)
+            .learning_function(learning_annealing_function)
+            .exploration_function(exploration_annealing_function)
+            .build_unchecked()
+    }
+
+    /// Create a new player whose random choices are seeded, so it makes the same decisions
+    /// every time it's given the same positions
+    pub fn new_seeded(piece: PlayerPiece, initial_learning_rate: f64, initial_exploration_rate: f64,
+                      learning_schedule: AnnealingSchedule, exploration_schedule: AnnealingSchedule,
+                      seed: u64) -> Player {
+        Player::builder(piece)
+            .learning_rate(initial_learning_rate)
+            .exploration_rate(initial_exploration_rate)
+            .learning_schedule(learning_schedule)
+            .exploration_schedule(exploration_schedule)
+            .seed(seed)
+            .build_unchecked()
+    }
+
+    /// Start building a new player for the given piece, see [`PlayerBuilder`] for the defaults
+    pub fn builder(piece: PlayerPiece) -> PlayerBuilder {
+        PlayerBuilder {
+            piece,
+            initial_learning_rate: 0.75,
+            initial_exploration_rate: 0.2,
+            learning_schedule: AnnealingSchedule::Constant,
+            exploration_schedule: AnnealingSchedule::Constant,
+            learning_annealing_function: None,
+            exploration_annealing_function: None,
+            tie_break: TieBreak::default(),
+            learning: Learning::default(),
+            ply_exploration: PlyExploration::default(),
+            update_mode: UpdateMode::default(),
+            symmetric_propagation: None,
+            symmetric: false,
+            learn_scope: LearnScope::default(),
+            draw_reward: 0.,
+            seed: None,
         }
     }
 
     /// Get which piece the player plays
-    pub fn get_player_piece(&self) -> Piece {
+    pub fn get_player_piece(&self) -> PlayerPiece {
+        self.save_state.piece
+    }
+
+    /// Which piece the player plays, the same as [`Player::get_player_piece`]
+    pub fn piece(&self) -> PlayerPiece {
         self.save_state.piece
     }
 
@@ -91,88 +790,648 @@ impl Player {
         self.save_state.iteration
     }
 
-    /// Read in a player save state from a file, additionally requires the learning and
-    /// exploration annealing functions (as those can't be serialized).
-    pub fn new_from_file<P: AsRef<Path>>(file_path: P,
-                                         learning_annealing_function: fn(f64, u32) -> f64,
-                                         exploration_annealing_function: fn(f64, u32) -> f64,
-    ) -> Result<Player, PlayerError> {
-        let file = match File::open(file_path) {
-            Ok(f) => { f }
-            Err(_) => { return Err(PlayerError::InvalidFile) }
+    /// Get the current learning rate, after annealing for the current iteration
+    pub fn get_learning_rate(&self) -> f64 {
+        let (initial_rate, iteration) = (self.save_state.initial_learning_rate, self.save_state.iteration);
+        match self.learning_annealing_function {
+            Some(ref function) => { function(initial_rate, iteration) }
+            None => { self.save_state.learning_schedule.evaluate(initial_rate, iteration) }
+        }
+    }
+
+    /// Get the current exploration rate, after annealing for the current iteration (or as set
+    /// by the learning mode)
+    pub fn get_exploration_rate(&self) -> f64 {
+        match self.learning {
+            Learning::Scheduled => {
+                let (initial_rate, iteration) = (self.save_state.initial_exploration_rate, self.save_state.iteration);
+                match self.exploration_annealing_function {
+                    Some(ref function) => { function(initial_rate, iteration) }
+                    None => { self.save_state.exploration_schedule.evaluate(initial_rate, iteration) }
+                }
+            }
+            Learning::FixedExploration(rate) => { rate }
+            Learning::Frozen => { 0. }
+        }
+    }
+
+    /// The exploration rate for a move from the given position: the current exploration rate
+    /// (see [`Player::get_exploration_rate`]) scaled by the ply exploration factor for the
+    /// number of pieces on the board
+    pub fn exploration_rate_at(&self, board_state: &[Piece; 9]) -> f64 {
+        let ply = board_state.iter().filter(|piece| **piece != Piece::Empty).count();
+        self.get_exploration_rate() * self.ply_exploration.factor(ply)
+    }
+
+    /// Set the factors scaling the exploration rate by how far into the game the player is.
+    /// They're a training setting, so aren't saved.
+    pub fn set_ply_exploration(&mut self, ply_exploration: PlyExploration) {
+        self.ply_exploration = ply_exploration;
+    }
+
+    /// Get the factors scaling the exploration rate by how far into the game the player is
+    pub fn get_ply_exploration(&self) -> PlyExploration {
+        self.ply_exploration
+    }
+
+    /// Get the schedule the learning rate is saved with (a function set in its place is used
+    /// instead, but isn't saved)
+    pub fn get_learning_schedule(&self) -> AnnealingSchedule {
+        self.save_state.learning_schedule
+    }
+
+    /// Get the schedule the exploration rate is saved with
+    pub fn get_exploration_schedule(&self) -> AnnealingSchedule {
+        self.save_state.exploration_schedule
+    }
+
+    /// Set whether the player learns while playing. A frozen player always plays its best
+    /// move and leaves its values (and overlay) untouched.
+    pub fn set_learning(&mut self, learning: Learning) {
+        self.learning = learning;
+    }
+
+    /// Get whether the player learns while playing
+    pub fn get_learning(&self) -> Learning {
+        self.learning
+    }
+
+    /// Set when the player's value updates are applied, any updates still pending are
+    /// applied first
+    pub fn set_update_mode(&mut self, update_mode: UpdateMode) {
+        self.end_episode();
+        self.update_mode = update_mode;
+    }
+
+    /// Get when the player's value updates are applied
+    pub fn get_update_mode(&self) -> UpdateMode {
+        self.update_mode
+    }
+
+    /// Apply the updates recorded during the episode in [`UpdateMode::EndOfEpisode`], each
+    /// computed against the values from before the episode, so the order the updates were
+    /// recorded in doesn't matter. Does nothing in [`UpdateMode::Online`].
+    pub fn end_episode(&mut self) {
+        let pending = std::mem::take(&mut self.pending_updates);
+        let updated: Vec<([Piece; 9], f64, f64)> = pending.iter()
+            .map(|(state, target, rate)| {
+                let old_value = self.peek_state_value(state);
+                (*state, old_value, Self::backup(old_value, *target, *rate))
+            })
+            .collect();
+        for (state, old_value, new_value) in updated {
+            self.apply_update(&state, old_value, new_value);
+        }
+        self.trajectory.clear();
+        self.last_afterstate = None;
+    }
+
+    /// Forget the position reached by the player's last move, so nothing is backed up into it
+    /// from the new game. Call before the player's first move of each game.
+    pub fn start_new_game(&mut self) {
+        self.last_afterstate = None;
+    }
+
+    /// Back up the result of a finished game, given its final position, into the position the
+    /// player's last move reached: 1 for a win, 0 for a loss, and the draw reward (see
+    /// [`PlayerBuilder::draw_reward`]) if nobody won, including games stopped as dead draws.
+    /// When the player's own move finished the game there's nothing left to back up, that move
+    /// already did.
+    pub fn observe_terminal(&mut self, compact_state: &[Piece; 9]) {
+        let previous = match self.last_afterstate.take() {
+            Some(previous) => { previous }
+            None => { return }
         };
-        let mut reader = BufReader::new(file);
-        let save_state: SaveState = match borsh::de::from_reader(&mut reader) {
-            Ok(p) => p,
-            Err(_) => { return Err(PlayerError::UnableToRead) }
+        let reward = match rules::winner(compact_state) {
+            Some(piece) if piece == Piece::from(self.save_state.piece) => { 1f64 }
+            Some(_) => { 0f64 }
+            None => { self.draw_reward }
         };
+        let lrate = self.get_learning_rate();
+        self.update_state_value(&previous, reward, lrate);
+    }
+
+    /// Number of value updates so far which broke one of the learning invariants: values
+    /// outside of 0 to 1, finished positions moved away from their fixed values, and updates
+    /// to positions the player hasn't been through during the episode. Should always be zero,
+    /// anything else points at a bug in the learning.
+    pub fn learning_violations(&self) -> LearningViolations {
+        self.violations
+    }
 
-        Ok(Player {
+    /// Set the fraction of each update also applied to the symmetric images (rotations and
+    /// reflections) of the state which are already in the table, None to update only the
+    /// state itself. New states are never added by this. Fails if the fraction isn't between
+    /// 0 and 1, or if the player stores symmetric states as one.
+    pub fn set_symmetric_propagation(&mut self, fraction: Option<f64>) -> Result<(), PlayerConfigError> {
+        if let Some(fraction) = fraction {
+            if !(0. ..=1.).contains(&fraction) {
+                return Err(PlayerConfigError::InvalidSymmetricPropagation);
+            }
+            if self.save_state.symmetric {
+                return Err(PlayerConfigError::PropagationWhileSymmetric);
+            }
+        }
+        self.symmetric_propagation = fraction;
+        Ok(())
+    }
+
+    /// Get the fraction of each update applied to the symmetric images of the state, if any
+    pub fn get_symmetric_propagation(&self) -> Option<f64> {
+        self.symmetric_propagation
+    }
+
+    /// Whether the player stores one value for each position and all its rotations and
+    /// reflections, see [`PlayerBuilder::symmetric`]
+    pub fn is_symmetric(&self) -> bool {
+        self.save_state.symmetric
+    }
+
+    /// Set which states the player may add to its table and update while learning, useful to
+    /// keep one-off positions from eccentric opponents out of the table
+    pub fn set_learn_scope(&mut self, learn_scope: LearnScope) {
+        self.learn_scope = learn_scope;
+    }
+
+    /// Get which states the player may add to its table and update while learning
+    pub fn get_learn_scope(&self) -> LearnScope {
+        self.learn_scope
+    }
+
+    /// Get the value of a drawn game
+    pub fn get_draw_reward(&self) -> f64 {
+        self.draw_reward
+    }
+
+    /// Number of distinct states in the player's table, including its overlay if it has one
+    pub fn state_count(&self) -> usize {
+        let base = &self.save_state.state_space;
+        match self.overlay {
+            Some(ref overlay) => {
+                base.len() + overlay.keys().filter(|state| !base.contains_key(state)).count()
+            }
+            None => { base.len() }
+        }
+    }
+
+    /// Every state in the player's table with its value, overlay values taking precedence, in
+    /// no particular order
+    pub fn iter_states(&self) -> impl Iterator<Item = ([Piece; 9], f64)> + '_ {
+        let base = &self.save_state.state_space;
+        let overlay = self.overlay.as_ref();
+        let overlay_only = overlay.into_iter()
+            .flat_map(|overlay| overlay.iter())
+            .filter(move |(state, _)| !base.contains_key(state));
+        base.iter()
+            .map(move |(state, value)| {
+                (state, overlay.and_then(|overlay| overlay.get(&state)).copied().unwrap_or(value))
+            })
+            .chain(overlay_only)
+    }
+
+    /// The states of [`Player::iter_states`] sorted by state, the order they're written to a
+    /// save in
+    pub fn iter_states_sorted(&self) -> impl Iterator<Item = ([Piece; 9], f64)> + '_ {
+        let mut entries: Vec<([Piece; 9], f64)> = self.iter_states().collect();
+        entries.sort_by_key(|entry| entry.0);
+        entries.into_iter()
+    }
+
+    /// Seed the player's random choices from here on, e.g. to make a player read from a save
+    /// reproducible (they're seeded from system entropy when it's read)
+    pub fn reseed(&mut self, seed: u64) {
+        self.generator = SmallRng::seed_from_u64(seed);
+    }
+
+    /// A seed drawn from the player's random choices, so players derived from a seeded player
+    /// are seeded too
+    #[cfg(feature = "train")]
+    pub(crate) fn next_seed(&mut self) -> u64 {
+        self.generator.gen()
+    }
+
+    /// Replace every learned value with those given, in the overlay if the player has one so
+    /// the base state space is left unchanged
+    #[cfg(feature = "train")]
+    pub(crate) fn replace_learned_values(&mut self, values: StateTable) {
+        match self.overlay {
+            Some(ref mut overlay) => { *overlay = values }
+            None => { self.save_state.state_space = values }
+        }
+    }
+
+    /// Set the policy used to choose between equally valued moves
+    pub fn set_tie_break(&mut self, tie_break: TieBreak) {
+        self.tie_break = tie_break;
+    }
+
+    /// Get the policy used to choose between equally valued moves
+    pub fn get_tie_break(&self) -> TieBreak {
+        self.tie_break
+    }
+
+    /// Read in a player save state from a file, annealing its rates with the schedules it was
+    /// saved with. Fails with [`PlayerError::InvalidFile`] if the file can't be opened (so a
+    /// missing save can be replaced by a new player), [`PlayerError::BadMagic`] if it isn't a
+    /// save, [`PlayerError::UnsupportedVersion`] if it was written by a newer version and
+    /// [`PlayerError::CorruptFile`] if it's been damaged.
+    ///
+    /// ```no_run
+    /// use tictacrs::prelude::*;
+    ///
+    /// match Player::load("player_x_save.ttr") {
+    ///     Ok(player) => println!("{} positions learned", player.state_count()),
+    ///     Err(PlayerError::InvalidFile { .. }) => println!("No save yet"),
+    ///     Err(e) => println!("The save can't be used: {}", e),
+    /// }
+    /// ```
+    #[cfg(feature = "persistence")]
+    pub fn load<P: AsRef<Path>>(file_path: P) -> Result<Player, PlayerError> {
+        let bytes = match std::fs::read(&file_path) {
+            Ok(bytes) => { bytes }
+            Err(e) => { return Err(PlayerError::invalid_file(file_path, e)) }
+        };
+        Player::from_bytes(&bytes)
+    }
+
+    /// Read in a player as [`Player::load`] does, failing with [`PlayerError::PieceMismatch`]
+    /// if it plays the other piece (as when save files have been renamed or swapped)
+    #[cfg(feature = "persistence")]
+    pub fn load_expecting<P: AsRef<Path>>(file_path: P, expected_piece: PlayerPiece) -> Result<Player, PlayerError> {
+        let player = Player::load(file_path)?;
+        if player.piece() != expected_piece {
+            return Err(PlayerError::PieceMismatch { expected: expected_piece, found: player.piece() });
+        }
+        Ok(player)
+    }
+
+    /// The old way of loading a player, which annealed its rates with the given functions
+    /// rather than the schedules it was saved with
+    #[cfg(feature = "persistence")]
+    #[deprecated(note = "use Player::load")]
+    pub fn new_from_file<P: AsRef<Path>>(file_path: P,
+                                         learning_annealing_function: impl Fn(f64, u32) -> f64 + Send + Sync + 'static,
+                                         exploration_annealing_function: impl Fn(f64, u32) -> f64 + Send + Sync + 'static,
+    ) -> Result<Player, PlayerError> {
+        let mut player = Self::load(file_path)?;
+        player.learning_annealing_function = Some(Arc::new(learning_annealing_function));
+        player.exploration_annealing_function = Some(Arc::new(exploration_annealing_function));
+        Ok(player)
+    }
+
+    /// Read in a player from the bytes produced by [`Player::to_bytes`], or those of a save
+    /// from before saves had a header. Fails as [`Player::load`] does.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Player, PlayerError> {
+        Ok(Player::from_save_state(save_file::decode(bytes)?))
+    }
+
+    /// Serialize the player data, in the same format as the save files
+    pub fn to_bytes(&self) -> Result<Vec<u8>, PlayerError> {
+        save_file::encode(&self.save_state)
+    }
+
+    fn from_save_state(mut save_state: SaveState) -> Player {
+        // Older saves are brought up to date in memory, the file itself is left as it is
+        let terminal_states_dropped = migrate::apply_migrations(&mut save_state).changes.iter()
+            .filter(|change| change.from_revision == migrate::FINISHED_POSITIONS_REVISION)
+            .map(|change| change.count)
+            .sum();
+        Player {
             save_state,
-            learning_annealing_function,
-            exploration_annealing_function,
+            learning_annealing_function: None,
+            exploration_annealing_function: None,
             generator: SmallRng::from_entropy(),
-        })
+            tie_break: TieBreak::default(),
+            learning: Learning::default(),
+            overlay: None,
+            last_decision: None,
+            ply_exploration: PlyExploration::default(),
+            update_mode: UpdateMode::default(),
+            pending_updates: Vec::new(),
+            symmetric_propagation: None,
+            learn_scope: LearnScope::default(),
+            trajectory: HashSet::new(),
+            violations: LearningViolations::default(),
+            last_afterstate: None,
+            draw_reward: 0.,
+            terminal_states_dropped,
+        }
+    }
+
+    /// Remove the finished positions from a table, returning how many there were. Their values
+    /// follow from the rules, so they're never stored, but older saves contain them.
+    pub(crate) fn drop_terminal_states(state_space: &mut StateTable) -> usize {
+        let before = state_space.len();
+        state_space.retain(|state, _| !Self::is_terminal(state));
+        before - state_space.len()
     }
 
-    /// Save the player data to a file
+    /// Number of finished positions dropped from the saves (and overlays) the player was read
+    /// from. Finished positions are valued by the rules rather than stored, so this is only
+    /// non-zero for saves written by older versions.
+    pub fn terminal_states_dropped(&self) -> usize {
+        self.terminal_states_dropped
+    }
+
+    /// Save the player data to a file. The save is written to a temporary file next to it
+    /// first, then moved into place, so a failed or interrupted save leaves any earlier one
+    /// intact.
+    #[cfg(feature = "persistence")]
     pub fn save_player_state<P: AsRef<Path>>(&self, file_path: P) -> Result<(), PlayerError> {
-        let file = match File::create(file_path) {
+        save_atomically(file_path.as_ref(), &self.to_bytes()?)
+    }
+
+    /// Enable an (initially empty) overlay, so that further learning is kept separate from
+    /// the base state space, which is left unchanged
+    pub fn with_overlay(mut self) -> Player {
+        self.overlay = Some(StateTable::new());
+        self
+    }
+
+    /// Whether the player currently has an overlay
+    pub fn has_overlay(&self) -> bool {
+        self.overlay.is_some()
+    }
+
+    /// Discard everything learned in the overlay, keeping an empty overlay in place
+    pub fn clear_overlay(&mut self) {
+        if let Some(ref mut overlay) = self.overlay {
+            overlay.clear();
+        }
+    }
+
+    /// Merge the overlay into the base state space, leaving an empty overlay in place
+    pub fn merge_overlay(&mut self) {
+        if let Some(ref mut overlay) = self.overlay {
+            self.save_state.state_space.extend(overlay.drain());
+        }
+    }
+
+    /// Save the player's overlay to a file, an empty overlay is saved if the player has none
+    #[cfg(feature = "persistence")]
+    pub fn save_overlay<P: AsRef<Path>>(&self, file_path: P) -> Result<(), PlayerError> {
+        let overlay_state = OverlaySaveState {
+            piece: self.save_state.piece,
+            state_space: self.overlay.clone().unwrap_or_default(),
+        };
+        let bytes = match borsh::to_vec(&overlay_state) {
+            Ok(bytes) => { bytes }
+            Err(e) => { return Err(PlayerError::UnableToSave(e)) }
+        };
+        save_atomically(file_path.as_ref(), &bytes)
+    }
+
+    /// Read an overlay from a file, replacing any current overlay. Fails with
+    /// [`PlayerError::PieceMismatch`] if the overlay was learned by a player using a different
+    /// piece.
+    #[cfg(feature = "persistence")]
+    pub fn load_overlay<P: AsRef<Path>>(&mut self, file_path: P) -> Result<(), PlayerError> {
+        let file = match File::open(&file_path) {
             Ok(f) => { f }
-            Err(_) => { return Err(PlayerError::InvalidFile) }
+            Err(e) => { return Err(PlayerError::invalid_file(file_path, e)) }
         };
-        let mut writer = BufWriter::new(file);
-        match borsh::to_writer(&mut writer, &self.save_state) {
-            Ok(_) => {}
-            Err(_) => {
-                return Err(PlayerError::UnableToSave);
-            }
+        let mut reader = BufReader::new(file);
+        let overlay_state: OverlaySaveState = match borsh::de::from_reader(&mut reader) {
+            Ok(p) => p,
+            Err(e) => { return Err(PlayerError::UnableToRead(e)) }
         };
+        if overlay_state.piece != self.save_state.piece {
+            return Err(PlayerError::PieceMismatch { expected: self.save_state.piece, found: overlay_state.piece });
+        }
+        let mut state_space = overlay_state.state_space;
+        self.terminal_states_dropped += Self::drop_terminal_states(&mut state_space);
+        self.overlay = Some(state_space);
         Ok(())
     }
 
-    /// Given a board state, determine which move to make
-    pub fn make_move(&mut self, board_state: &[Piece; 9]) -> [u8; 2] {
+    /// Given a board state, determine which move to make. Returns an error if the game
+    /// is already over, or if it isn't this player's turn to move
+    pub fn make_move(&mut self, board_state: &[Piece; 9]) -> Result<[u8; 2], PlayerError> {
+        self.decide_move(board_state).map(|decision| decision.position())
+    }
+
+    /// Same as [`Player::make_move`], but also reports whether the move was exploratory and
+    /// the value of the position it reached
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// let mut player = Player::builder(PlayerPiece::X).exploration_rate(0.).seed(1).build().unwrap();
+    /// let decision = player.decide_move(&[Piece::Empty; 9]).unwrap();
+    /// assert!(!decision.exploratory);
+    /// assert_eq!(decision.value, 0.5);
+    /// assert_eq!(player.last_decision(), Some(decision));
+    /// ```
+    pub fn decide_move(&mut self, board_state: &[Piece; 9]) -> Result<MoveDecision, PlayerError> {
+        self.check_position(board_state)?;
+        self.decide_move_unchecked(board_state)
+    }
+
+    /// Determine which move to make on the given board. Unlike [`Player::make_move`], whose
+    /// turn is inferred from the piece counts, this uses the board's own turn tracking, so it
+    /// also works for boards which started with handicap pieces.
+    pub fn make_move_on_board(&mut self, board: &Board) -> Result<[u8; 2], PlayerError> {
+        match board.turn() {
+            None => { Err(PlayerError::GameAlreadyOver) }
+            Some(piece) if piece != self.save_state.piece => { Err(PlayerError::NotMyTurn) }
+            Some(_) => { self.make_move_unchecked(&board.get_compact_state()) }
+        }
+    }
+
+    /// Given a board state, determine which move to make, without checking that the position
+    /// is one where this player can move (used by the trainer, which only produces legal
+    /// positions). Fails with [`PlayerError::GameAlreadyOver`] if there's no empty square.
+    pub(crate) fn make_move_unchecked(&mut self, board_state: &[Piece; 9]) -> Result<[u8; 2], PlayerError> {
+        self.decide_move_unchecked(board_state).map(|decision| decision.position())
+    }
+
+    /// Same as [`Player::make_move_unchecked`], reporting how the move was chosen
+    pub(crate) fn decide_move_unchecked(&mut self, board_state: &[Piece; 9]) -> Result<MoveDecision, PlayerError> {
+        if rules::is_full(board_state) {
+            return Err(PlayerError::GameAlreadyOver);
+        }
+        self.trajectory.insert(*board_state);
+        // Classify the moves before choosing one adds them to the table
+        let piece = Piece::from(self.save_state.piece);
+        let knowledge: [Knowledge; 9] = std::array::from_fn(|square| {
+            let mut after = *board_state;
+            after[square] = piece;
+            self.knowledge_of(&after)
+        });
         // First, choose whether this move will be optimal, or exploratory
         let rand_val: f64 = self.generator.sample(Standard);
-        let exp_rate = (self.exploration_annealing_function)(self.save_state.initial_exploration_rate, self.save_state.iteration);
-        if rand_val < exp_rate {
+        let (chosen, exploratory) = if rand_val < self.exploration_rate_at(board_state) {
             // Make an exploratory move
-            self.make_random_move(board_state)
+            (self.make_random_move(board_state), true)
         } else {
             // Make an optimal move
-            self.make_optimal_move(board_state)
+            (self.make_optimal_move(board_state), false)
+        };
+        let mut after_move = *board_state;
+        after_move[(chosen[0] * 3 + chosen[1]) as usize] = self.save_state.piece.into();
+        self.trajectory.insert(after_move);
+        // Exploring follows a line the player doesn't think is best, so nothing is learned
+        // from it
+        if !exploratory {
+            self.back_up_last_afterstate(&after_move);
         }
+        let decision = MoveDecision {
+            position: chosen,
+            exploratory,
+            value: self.peek_state_value(&after_move),
+            knowledge: knowledge[(chosen[0] * 3 + chosen[1]) as usize],
+        };
+        self.last_decision = Some(decision);
+        // A move which finishes the game leaves nothing to back up into
+        self.last_afterstate = if Self::is_terminal(&after_move) { None } else { Some(after_move) };
+        Ok(decision)
     }
 
-    /// Convert a move from [u8;2] to string specification
-    pub fn to_human_move(comp_move:&[u8;2])->String{
-        let mut human_move: String = String::new();
-        match comp_move[0]{
-            0=>{
-                human_move.push_str("a");
-            },
-            1=>{
-                human_move.push_str("b");
+    /// Whether the player's last move was exploratory rather than greedy
+    pub fn last_move_was_exploratory(&self) -> bool {
+        self.last_decision.is_some_and(|decision| decision.exploratory)
+    }
+
+    /// The player's last move, and how it was chosen, if it has moved
+    pub fn last_decision(&self) -> Option<MoveDecision> {
+        self.last_decision
+    }
+
+    /// Explain why the chosen move might be played from the given state, combining tactical
+    /// facts (winning, blocking, forking) with the learned values of the alternatives. This
+    /// doesn't modify the player's state space. Panics if the chosen move isn't on the board.
+    pub fn explain_move(&self, compact_state: &[Piece; 9], chosen: [u8; 2]) -> MoveExplanation {
+        let piece = Piece::from(self.save_state.piece);
+        let opponent = Piece::from(self.save_state.piece.opponent());
+        let square = Coord::new(chosen[0], chosen[1]).expect("The chosen move must be on the board").to_index();
+        // Learned values of every possible move
+        let mut value = 0f64;
+        let mut best_value = f64::NEG_INFINITY;
+        let mut options = 0usize;
+        for idx in 0..9 {
+            if compact_state[idx] != Piece::Empty {
+                continue;
             }
-            2=>{
-                human_move.push_str("c");
+            let mut after_move = *compact_state;
+            after_move[idx] = piece;
+            let move_value = self.peek_state_value(&after_move);
+            best_value = best_value.max(move_value);
+            options += 1;
+            if idx == square {
+                value = move_value;
             }
-            _=>{panic!("Inavlid move found during translation")}
+        }
+        let won = lines_completed(compact_state, square, piece);
+        let blocked = lines_completed(compact_state, square, opponent);
+        let (reason, line) = if let Some(line) = won.first() {
+            (MoveReason::Win, Some(*line))
+        } else if let Some(line) = blocked.first() {
+            (MoveReason::Block, Some(*line))
+        } else if is_fork(compact_state, square, piece) {
+            (MoveReason::Fork, None)
+        } else {
+            (MoveReason::Value, None)
         };
-        match comp_move[1]{
-            0=>{
-                human_move.push_str("1");
-            },
-            1=>{
-                human_move.push_str("2");
-            }
-            2=>{
-                human_move.push_str("3");
-            }
-            _=>{panic!("Inavlid move found during translation")}
+        let mut after_chosen = *compact_state;
+        after_chosen[square] = piece;
+        let knowledge = self.knowledge_of(&after_chosen);
+        MoveExplanation { chosen, reason, line, value, best_value, options, knowledge }
+    }
+
+    /// Where the player's value for a position comes from, without adding it to the table
+    fn knowledge_of(&self, compact_state: &[Piece; 9]) -> Knowledge {
+        if Self::is_terminal(compact_state) {
+            return Knowledge::Terminal;
+        }
+        match self.lookup_state_value(compact_state) {
+            Some(_) => { Knowledge::Learned }
+            None => { Knowledge::DefaultValue }
+        }
+    }
+
+    /// The learned value of each possible move from a position, as (row, col) and the value
+    /// of the position after the move, without changing the player
+    pub fn move_values(&self, compact_state: &[Piece; 9]) -> Vec<([u8; 2], f64)> {
+        let piece = Piece::from(self.save_state.piece);
+        (0u8..9).filter(|idx| compact_state[*idx as usize] == Piece::Empty)
+            .map(|idx| {
+                let mut after_move = *compact_state;
+                after_move[idx as usize] = piece;
+                ([idx / 3, idx % 3], self.peek_state_value(&after_move))
+            })
+            .collect()
+    }
+
+    /// The moves with the best learned value from a position, in increasing square order,
+    /// without changing the player
+    pub(crate) fn greedy_moves(&self, compact_state: &[Piece; 9]) -> Vec<[u8; 2]> {
+        let values = self.move_values(compact_state);
+        let best = values.iter().map(|(_, value)| *value).fold(f64::NEG_INFINITY, f64::max);
+        values.iter().filter(|(_, value)| *value == best).map(|(chosen, _)| *chosen).collect()
+    }
+
+    /// The move the player thinks is best from a position, never exploring and without
+    /// changing the player (unseen positions are valued as for [`Player::make_move`]). Ties
+    /// are settled by the tie break policy, going to the lowest square index when it's random.
+    /// None if the game is over or it isn't this player's turn.
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// let player = Player::builder(PlayerPiece::X).build().unwrap();
+    /// assert_eq!(player.best_move(&[Piece::Empty; 9]), Some([0, 0]));
+    /// assert_eq!(player.state_count(), 0);
+    /// ```
+    pub fn best_move(&self, board_state: &[Piece; 9]) -> Option<[u8; 2]> {
+        self.check_position(board_state).ok()?;
+        let tied = self.greedy_moves(board_state);
+        self.settle_tie(&tied).or(tied.first().copied())
+    }
+
+    /// The value learned for a state (from the overlay if it has one there), or None if the
+    /// player has never stored one
+    pub fn learned_value(&self, compact_state: &[Piece; 9]) -> Option<f64> {
+        self.lookup_state_value(compact_state)
+    }
+
+    /// Value of a state, without adding previously unseen states to the state space
+    pub(crate) fn peek_state_value(&self, compact_state: &[Piece; 9]) -> f64 {
+        if Self::is_terminal(compact_state) {
+            return self.find_new_state_prob(compact_state);
+        }
+        match self.lookup_state_value(compact_state) {
+            Some(value) => { value }
+            None => { self.find_new_state_prob(compact_state) }
+        }
+    }
+
+    /// Check that the position is one this player can be asked to move from, i.e. the game
+    /// isn't over, and it is this player's turn (inferred from the piece counts, X moves first)
+    pub(crate) fn check_position(&self, compact_state: &[Piece; 9]) -> Result<(), PlayerError> {
+        if Self::is_terminal(compact_state) {
+            return Err(PlayerError::GameAlreadyOver);
+        }
+        let x_count = compact_state.iter().filter(|p| **p == Piece::X).count();
+        let o_count = compact_state.iter().filter(|p| **p == Piece::O).count();
+        let to_move = if x_count == o_count {
+            PlayerPiece::X
+        } else if x_count == o_count + 1 {
+            PlayerPiece::O
+        } else {
+            return Err(PlayerError::NotMyTurn);
         };
-        human_move
+        if to_move != self.save_state.piece {
+            return Err(PlayerError::NotMyTurn);
+        }
+        Ok(())
+    }
+
+    /// Convert a move from [u8;2] to string specification
+    pub fn to_human_move(comp_move:&[u8;2])->String{
+        match Coord::new(comp_move[0], comp_move[1]) {
+            Some(coord) => { coord.to_string() }
+            None => { panic!("Inavlid move found during translation") }
+        }
     }
 
     /// Update which iteration is the current one
@@ -181,15 +1440,210 @@ impl Player {
         self.save_state.iteration = new_iter;
     }
 
+    /// Move on to the next iteration, tapering the learning and exploration rates
+    pub fn increment_iteration(&mut self) {
+        self.save_state.iteration = self.save_state.iteration.saturating_add(1);
+    }
+
+    /// Count a game played against a person, see [`Player::get_games_played`]
+    pub fn record_game_played(&mut self) {
+        self.save_state.games_played = self.save_state.games_played.saturating_add(1);
+    }
+
+    /// Number of games played against people, separate from the training iteration
+    pub fn get_games_played(&self) -> u32 {
+        self.save_state.games_played
+    }
+
+    /// Hash of the state table (including the overlay), independent of the order states were
+    /// learned in. Useful for checking whether a player's table changed.
+    pub fn state_table_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for (state, value) in self.iter_states_sorted() {
+            state.hash(&mut hasher);
+            value.to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Remove every state from the table (and overlay) which the player can never be asked
+    /// about during a normal game, i.e. keep only reachable states which the player is about to
+    /// move from, or which its own move produced. The player's moves are unaffected.
+    pub fn compact(&mut self) -> CompactReport {
+        let piece = self.save_state.piece;
+        let keep: HashSet<[Piece; 9]> = reachable_states().into_iter()
+            .filter(|state| Self::can_be_queried(state, piece))
+            .collect();
+        let removed: Vec<[Piece; 9]> = self.iter_states()
+            .map(|(state, _)| state)
+            .filter(|state| !keep.contains(state))
+            .collect();
+        let states_before = self.state_count();
+        for state in &removed {
+            self.save_state.state_space.remove(state);
+            if let Some(ref mut overlay) = self.overlay {
+                overlay.remove(state);
+            }
+        }
+        CompactReport { states_before, states_removed: removed.len() }
+    }
+
+    /// Whether a reachable state is one a player using the given piece could look up, either
+    /// because it is about to move from it, or because its move produced it
+    fn can_be_queried(compact_state: &[Piece; 9], piece: PlayerPiece) -> bool {
+        let x_count = compact_state.iter().filter(|p| **p == Piece::X).count();
+        let o_count = compact_state.iter().filter(|p| **p == Piece::O).count();
+        if x_count == 0 {
+            // The empty board, which only X moves from
+            return piece == PlayerPiece::X;
+        }
+        let last_mover = if x_count > o_count { PlayerPiece::X } else { PlayerPiece::O };
+        if last_mover == piece {
+            return true;
+        }
+        // The other player moved last, so this player moves next unless the game is over
+        rules::game_state(compact_state) == GameState::InProgress
+    }
+
     /// Show a state that caused the player to lose, and reduce its value to 0.
     pub fn show_loosing_state(&mut self, compact_state: &[Piece;9]){
-        self.save_state.state_space.insert(*compact_state,0f64);
+        self.update_state_value(compact_state, 0f64, 1f64);
     }
 
-    /// Choose the optimal move (or choose randomly from equivalent moves)
+    /// Show a state from which the game can only be drawn (see
+    /// [`crate::game::board::Board::is_dead_draw`]), and reduce its value to that of a draw
+    pub fn show_drawn_state(&mut self, compact_state: &[Piece; 9]) {
+        self.update_state_value(compact_state, self.draw_reward, 1f64);
+    }
+
+    /// Move a value part of the way (given by the rate) towards a target value
+    fn backup(value: f64, target: f64, rate: f64) -> f64 {
+        value + rate * (target - value)
+    }
+
+    /// Back up the value of a state towards a target, immediately or at the end of the
+    /// episode depending on the update mode
+    #[track_caller]
+    fn update_state_value(&mut self, compact_state: &[Piece; 9], target: f64, rate: f64) {
+        if self.learning == Learning::Frozen {
+            return;
+        }
+        // Only checked once the player has moved this episode, so positions can still be
+        // shown to a player directly
+        if !self.trajectory.is_empty() && !self.trajectory.contains(compact_state) {
+            self.violations.off_trajectory += 1;
+            self.report_violation("position not on the player's trajectory", compact_state, target);
+        }
+        match self.update_mode {
+            UpdateMode::Online => {
+                let old_value = self.get_state_value(compact_state);
+                self.apply_update(compact_state, old_value, Self::backup(old_value, target, rate));
+            }
+            UpdateMode::EndOfEpisode => {
+                self.pending_updates.push((*compact_state, target, rate));
+            }
+        }
+    }
+
+    /// Change the value of a state, propagating part of the change to its symmetric images
+    /// already in the table if enabled
+    #[track_caller]
+    fn apply_update(&mut self, compact_state: &[Piece; 9], old_value: f64, new_value: f64) {
+        // Rounding can take a value just outside the range after many updates
+        let new_value = new_value.clamp(0., 1.);
+        self.set_state_value(compact_state, new_value);
+        if let Some(fraction) = self.symmetric_propagation {
+            let delta = fraction * (new_value - old_value);
+            for image in symmetric_images(compact_state) {
+                if let Some(value) = self.lookup_state_value(&image) {
+                    // The image's value can differ from the state's, so keep it in range
+                    self.set_state_value(&image, (value + delta).clamp(0., 1.));
+                }
+            }
+        }
+    }
+
+    /// The key a state's value is stored under, its canonical form if the player is symmetric
+    pub(crate) fn table_key(&self, compact_state: &[Piece; 9]) -> [Piece; 9] {
+        if self.save_state.symmetric { canonical_form(compact_state).0 } else { *compact_state }
+    }
+
+    /// Look up the value of a state, checking the overlay (if any) before the base state space
+    fn lookup_state_value(&self, compact_state: &[Piece; 9]) -> Option<f64> {
+        let key = self.table_key(compact_state);
+        if let Some(ref overlay) = self.overlay {
+            if let Some(value) = overlay.get(&key) {
+                return Some(*value);
+            }
+        }
+        self.save_state.state_space.get(&key).copied()
+    }
+
+    /// Get the value of a state, assigning a value to previously unseen states (unless updates
+    /// are held back until the end of the episode). Finished positions are valued by the
+    /// rules, and never added.
+    fn get_state_value(&mut self, compact_state: &[Piece; 9]) -> f64 {
+        if Self::is_terminal(compact_state) {
+            return self.find_new_state_prob(compact_state);
+        }
+        match self.lookup_state_value(compact_state) {
+            Some(value) => { value }
+            None => {
+                let value = self.find_new_state_prob(compact_state);
+                if self.update_mode == UpdateMode::Online {
+                    self.set_state_value(compact_state, value);
+                }
+                value
+            }
+        }
+    }
+
+    /// Set the value of a state, writing to the overlay if there is one, does nothing if the
+    /// player is frozen or the state is a finished position (whose value is fixed)
+    #[track_caller]
+    fn set_state_value(&mut self, compact_state: &[Piece; 9], value: f64) {
+        if self.learning == Learning::Frozen {
+            return;
+        }
+        if !(0. ..=1.).contains(&value) {
+            self.violations.out_of_range += 1;
+            self.report_violation("value out of range", compact_state, value);
+        }
+        if Self::is_terminal(compact_state) {
+            if value != self.find_new_state_prob(compact_state) {
+                self.violations.terminal_changed += 1;
+                self.report_violation("finished position changed", compact_state, value);
+            }
+            return;
+        }
+        let key = self.table_key(compact_state);
+        match self.overlay {
+            Some(ref mut overlay) => { overlay.insert(key, value); }
+            None => { self.save_state.state_space.insert(key, value); }
+        }
+    }
+
+    /// Panic on a value update breaking one of the learning invariants, with the
+    /// strict-learning feature (otherwise it's only counted)
+    #[track_caller]
+    fn report_violation(&self, problem: &str, compact_state: &[Piece; 9], new_value: f64) {
+        if cfg!(feature = "strict-learning") {
+            let state: String = compact_state.iter().map(|piece| compact_board_char(*piece)).collect();
+            let old_value = match self.lookup_state_value(compact_state) {
+                Some(value) => { value.to_string() }
+                None => { "unset".to_string() }
+            };
+            panic!("suspicious value update ({}) at {}: state {} changed from {} to {}",
+                   problem, Location::caller(), state, old_value, new_value);
+        }
+    }
+
+    /// Choose the optimal move (or choose randomly from equivalent moves), there must be an
+    /// empty square
     fn make_optimal_move(&mut self, compact_state: &[Piece; 9]) -> [u8; 2] {
-        // Variables to hold the current max probability, and
-        let mut max_probability: f64 = 0.;
+        // Variables to hold the current max probability, and the moves reaching it. Starting
+        // below any value means the first move is always a candidate.
+        let mut max_probability = f64::NEG_INFINITY;
         let mut best_moves: Vec<[u8; 2]> = Vec::with_capacity(9usize);
         // Get all the possible moves
         let potential_moves = self.get_potential_moves(compact_state);
@@ -203,48 +1657,73 @@ impl Player {
                 best_moves.push(potential_moves.next_moves[idx]);
             }
         }
-        // Update the state space
-        // First check if the current position is in the state space,
-        // assigning it a value if needed
-        if !self.save_state.state_space.contains_key(compact_state) {
-            self.save_state.state_space.insert(*compact_state, self.find_new_state_prob(compact_state));
-        }
-        let old_prob = self.save_state.state_space.get(compact_state).unwrap().clone();
-        let lrate = (self.learning_annealing_function)(self.save_state.initial_learning_rate, self.save_state.iteration);
-        self.save_state.state_space.entry(*compact_state)
-            .and_modify(|prob|
-                *prob += lrate * (max_probability - old_prob));
         // If there is only 1 best move, return that
         if best_moves.len() == 1 {
             best_moves[0usize]
-        } else if best_moves.len() > 1 {
-            // All the best moves are equal, just pick one at random
-            *best_moves.choose(&mut self.generator).unwrap()
         } else {
-            panic!("Couldn't select a move!")
+            // All the best moves are equal, use the tie break policy to choose
+            self.break_tie(&best_moves)
+        }
+    }
+
+    /// Back up the value of the position the player's last move reached towards the value of
+    /// the one its greedy move just reached, only when that move ends the game if limited to
+    /// terminal updates
+    fn back_up_last_afterstate(&mut self, after_move: &[Piece; 9]) {
+        if let Some(previous) = self.last_afterstate {
+            if self.learn_scope != LearnScope::TerminalOnly || Self::is_terminal(after_move) {
+                let target = self.peek_state_value(after_move);
+                let lrate = self.get_learning_rate();
+                self.update_state_value(&previous, target, lrate);
+            }
+        }
+    }
+
+    /// Choose one of several equally valued moves according to the tie break policy,
+    /// the moves must be in increasing square order
+    fn break_tie(&mut self, tied_moves: &[[u8; 2]]) -> [u8; 2] {
+        match self.settle_tie(tied_moves) {
+            Some(chosen) => { chosen }
+            None => { *tied_moves.choose(&mut self.generator).unwrap() }
         }
     }
 
-    /// If exploring, choose a random (non-optimal) move
-    fn make_random_move(&mut self, compact_state: &[Piece; 9]) -> [u8; 2] {
-        let mut max_probability = 0f64;
-        let potential_moves = self.get_potential_moves(compact_state);
-        // Get the max value
-        for idx in 0..potential_moves.probabilities.len() {
-            if potential_moves.probabilities[idx] > max_probability {
-                max_probability = potential_moves.probabilities[idx];
+    /// The move the tie break policy chooses from several equally valued ones (in increasing
+    /// square order), or None if it chooses randomly
+    pub(crate) fn settle_tie(&self, tied_moves: &[[u8; 2]]) -> Option<[u8; 2]> {
+        match self.tie_break {
+            TieBreak::Random => { None }
+            TieBreak::FirstIndex => { Some(tied_moves[0]) }
+            TieBreak::LastIndex => { Some(tied_moves[tied_moves.len() - 1]) }
+            TieBreak::PreferCenterThenCorners => {
+                // Lower rank is preferred: center, then corners, then edges
+                let rank = |m: &[u8; 2]| match (m[0], m[1]) {
+                    (1, 1) => 0,
+                    (0, 0) | (0, 2) | (2, 0) | (2, 2) => 1,
+                    _ => 2,
+                };
+                // min_by_key returns the first minimum, so ties go to the lowest index
+                tied_moves.iter().min_by_key(|m| rank(m)).copied()
             }
         }
+    }
+
+    /// If exploring, choose a random (non-optimal) move, there must be an empty square. The
+    /// values are only looked up, so exploring leaves the table as it was.
+    fn make_random_move(&mut self, compact_state: &[Piece; 9]) -> [u8; 2] {
+        let potential_moves = self.move_values(compact_state);
+        // Get the max value
+        let max_probability = potential_moves.iter()
+            .map(|(_, probability)| *probability)
+            .fold(f64::NEG_INFINITY, f64::max);
         //Get the moves that are less than max
-        let mut exploration_moves: Vec<[u8; 2]> = Vec::with_capacity(9usize);
-        for idx in 0..potential_moves.probabilities.len() {
-            if potential_moves.probabilities[idx] < max_probability {
-                exploration_moves.push(potential_moves.next_moves[idx]);
-            }
-        }
+        let exploration_moves: Vec<[u8; 2]> = potential_moves.iter()
+            .filter(|(_, probability)| *probability < max_probability)
+            .map(|(next_move, _)| *next_move)
+            .collect();
         // If all the moves have the same probability, choose randomly
-        if exploration_moves.len() == 0 {
-            *potential_moves.next_moves.choose(&mut self.generator).unwrap()
+        if exploration_moves.is_empty() {
+            potential_moves.choose(&mut self.generator).unwrap().0
         } else {
             // Choose a random value from the exploration moves
             *exploration_moves.choose(&mut self.generator).unwrap()
@@ -256,16 +1735,14 @@ impl Player {
         let mut next_moves: Vec<[u8; 2]> = Vec::with_capacity(9);
         let mut probabilities: Vec<f64> = Vec::with_capacity(9);
         // Get a mutable clone of the board for looking up/generating probabilities
-        let mut board = compact_state.clone();
-        let mut counter: u8 = 0;
-        for square in compact_state {
+        let mut board = *compact_state;
+        for (counter, square) in (0u8..).zip(compact_state.iter()) {
             if square.eq(&Piece::Empty) {
                 next_moves.push([counter / 3, counter % 3]);
                 probabilities.push(self.get_move_probability(&mut board,
                                                              [counter / 3, counter % 3],
-                                                             self.save_state.piece))
+                                                             self.save_state.piece.into()))
             }
-            counter += 1;
         }
         PotentialMoves {
             next_moves,
@@ -280,10 +1757,12 @@ impl Player {
             panic!("Encountered impossible state in get move probability")
         }
         compact_state[(potential_move[0] * 3 + potential_move[1]) as usize] = piece;
-        if !self.save_state.state_space.contains_key(compact_state) {
-            self.save_state.state_space.insert(*compact_state, self.find_new_state_prob(compact_state));
-        }
-        let probability = self.save_state.state_space.get(compact_state).unwrap().clone();
+        // Positions the player only considers are added to the table when learning everything
+        let probability = if self.learn_scope == LearnScope::All {
+            self.get_state_value(compact_state)
+        } else {
+            self.peek_state_value(compact_state)
+        };
         compact_state[(potential_move[0] * 3 + potential_move[1]) as usize] = Piece::Empty;
         probability
     }
@@ -291,189 +1770,1280 @@ impl Player {
 
     /// Calculates the winning probability for a previously unseen state
     fn find_new_state_prob(&self, compact_state: &[Piece; 9]) -> f64 {
-        if let Some(p) = Self::check_winner(compact_state) {
+        if let Some(p) = rules::winner(compact_state) {
             // If this player wins, it has a probability of 1
-            return if self.save_state.piece.eq(&p) {
+            return if self.save_state.piece == p {
                 1f64
             // If this player looses, it has a probability of 0
             } else {
                 0f64
             };
         }
-        // If there is no winner, and the board is full, the win probability is 0
-        if Self::check_full(compact_state) {
-            return 0f64;
+        // If there is no winner, and the board is full, it's a draw
+        if rules::is_full(compact_state) {
+            return self.draw_reward;
         }
         // Otherwise we don't know, so this new state gets a probability of 0.5
         0.5f64
     }
 
-    /// Check if the board is full
-    fn check_full(compact_state: &[Piece; 9]) -> bool {
-        for p in compact_state.iter() {
-            if p.eq(&Piece::Empty) {
-                return false;
-            }
-        }
-        true
+    /// Whether the game is over in the position, so its value follows from the rules
+    pub(crate) fn is_terminal(compact_state: &[Piece; 9]) -> bool {
+        rules::game_state(compact_state) != GameState::InProgress
     }
+}
 
-    /// Check who has won the game, returns None if no winner, and Some(Piece) where
-    /// Piece represents the winner
-    fn check_winner(compact_state: &[Piece; 9]) -> Option<Piece> {
-        match Self::check_winner_row(compact_state) {
-            None => {
-                match Self::check_winner_col(compact_state) {
-                    None => {
-                        Self::check_winner_diag(compact_state)
-                    }
-                    Some(piece) => { Some(piece) }
-                }
-            }
-            Some(piece) => { Some(piece) }
-        }
+/// Errors produced by a [`Player`]
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// let mut player = Player::new(PlayerPiece::O, 0.75, 0.0, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+/// // X moves first, so O can't move on an empty board
+/// assert_eq!(player.make_move(&Board::new().get_compact_state()), Err(PlayerError::NotMyTurn));
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PlayerError {
+    /// The file at the path couldn't be opened or created, usually because it (or its
+    /// directory) doesn't exist
+    InvalidFile { path: PathBuf, source: io::Error },
+    /// Writing the player out failed
+    UnableToSave(io::Error),
+    /// The file was opened, but doesn't hold a player
+    UnableToRead(io::Error),
+    /// The file neither starts with the save header nor is a save from before the header
+    BadMagic,
+    /// The save was written in a newer format than this build reads
+    UnsupportedVersion { found: u16, supported: u16 },
+    /// The save has been cut short or changed since it was written
+    CorruptFile,
+    /// The save is for a different piece than the one asked for
+    PieceMismatch { expected: PlayerPiece, found: PlayerPiece },
+    /// The position passed in is already won or drawn
+    GameAlreadyOver,
+    /// The position passed in has the other player to move
+    NotMyTurn,
+}
+
+impl PlayerError {
+    /// The error for a file which couldn't be opened or created
+    #[cfg(feature = "persistence")]
+    pub(crate) fn invalid_file(path: impl AsRef<Path>, source: io::Error) -> PlayerError {
+        PlayerError::InvalidFile { path: path.as_ref().to_path_buf(), source }
     }
-    fn check_winner_col(compact_state: &[Piece; 9]) -> Option<Piece> {
-        for col in 0..3 {
-            if compact_state[col + 0] == compact_state[col + 3] &&
-                compact_state[col + 0] == compact_state[col + 6] &&
-                compact_state[col + 0] != Piece::Empty {
-                return Some(compact_state[col + 0]);
+}
+
+impl PartialEq for PlayerError {
+    /// Errors are equal when they're the same error about the same thing, io errors being
+    /// compared by their kind
+    fn eq(&self, other: &PlayerError) -> bool {
+        match (self, other) {
+            (PlayerError::InvalidFile { path, source }, PlayerError::InvalidFile { path: other_path, source: other_source }) => {
+                path == other_path && source.kind() == other_source.kind()
+            }
+            (PlayerError::UnableToSave(source), PlayerError::UnableToSave(other_source))
+            | (PlayerError::UnableToRead(source), PlayerError::UnableToRead(other_source)) => {
+                source.kind() == other_source.kind()
+            }
+            (PlayerError::UnsupportedVersion { found, supported },
+             PlayerError::UnsupportedVersion { found: other_found, supported: other_supported }) => {
+                found == other_found && supported == other_supported
             }
+            (PlayerError::PieceMismatch { expected, found },
+             PlayerError::PieceMismatch { expected: other_expected, found: other_found }) => {
+                expected == other_expected && found == other_found
+            }
+            _ => { std::mem::discriminant(self) == std::mem::discriminant(other) }
         }
-        None
     }
+}
 
-    fn check_winner_row(compact_state: &[Piece; 9]) -> Option<Piece> {
-        for row in 0..3 {
-            if compact_state[3 * row + 0] == compact_state[3 * row + 1] &&
-                compact_state[3 * row + 0] == compact_state[3 * row + 2] &&
-                compact_state[3 * row + 0] != Piece::Empty {
-                return Some(compact_state[3 * row + 0]);
+impl fmt::Display for PlayerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayerError::InvalidFile { path, source } => { write!(f, "couldn't open {}: {}", path.display(), source) }
+            PlayerError::UnableToSave(source) => { write!(f, "couldn't save the player: {}", source) }
+            PlayerError::UnableToRead(source) => { write!(f, "the file doesn't hold a player: {}", source) }
+            PlayerError::BadMagic => { write!(f, "the file isn't a tictacrs save") }
+            PlayerError::UnsupportedVersion { found, supported } => {
+                write!(f, "the save is in format version {}, but this version of tictacrs only reads up to {}",
+                       found, supported)
             }
+            PlayerError::CorruptFile => { write!(f, "the save is damaged or incomplete") }
+            PlayerError::PieceMismatch { expected, found } => {
+                write!(f, "the save is for player {}, not player {}", found, expected)
+            }
+            PlayerError::GameAlreadyOver => { write!(f, "the game is already over") }
+            PlayerError::NotMyTurn => { write!(f, "it isn't the player's turn") }
         }
-        None
     }
+}
 
-    fn check_winner_diag(compact_state: &[Piece; 9]) -> Option<Piece> {
-        if compact_state[3 * 0 + 0] == compact_state[3 * 1 + 1] &&
-            compact_state[3 * 0 + 0] == compact_state[3 * 2 + 2] &&
-            compact_state[3 * 0 + 0] != Piece::Empty {
-            return Some(compact_state[3 * 0 + 0]);
-        }
-
-        if compact_state[3 * 2 + 0] == compact_state[3 * 1 + 1] &&
-            compact_state[3 * 2 + 0] == compact_state[3 * 0 + 2] &&
-            compact_state[3 * 2 + 0] != Piece::Empty {
-            return Some(compact_state[3 * 0 + 0]);
+impl Error for PlayerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PlayerError::InvalidFile { source, .. } => { Some(source) }
+            PlayerError::UnableToSave(source) | PlayerError::UnableToRead(source) => { Some(source) }
+            _ => { None }
         }
-        None
     }
 }
 
-pub enum PlayerError {
-    InvalidFile,
-    UnableToSave,
-    UnableToRead,
-}
-
 
 #[cfg(test)]
 mod tests {
-    use crate::agents::players::Player;
-    use crate::game::board::Piece;
+    use crate::agents::annealing::{AnnealingSchedule, EXPLORATION_SCHEDULE, LEARNING_SCHEDULE};
+    use crate::agents::players::{
+        CompactReport, Knowledge, LearnScope, Learning, LearningViolations, MoveDecision,
+        MoveReason, Player, PlayerConfigError, PlayerError, PlyExploration, TieBreak, UpdateMode,
+    };
+    use crate::game::board::{Board, Piece, PlayerPiece};
+    use crate::game::result::GameResult;
+    use crate::game::rules::{self, GameState};
+    use crate::game::states::reachable_states;
+    use crate::game::symmetry::{canonical_form, invert_transform, Transform};
+    use borsh::BorshSerialize;
+    use std::collections::{HashMap, HashSet};
+    use std::error::Error;
+    use std::io;
+    #[cfg(feature = "persistence")]
+    use crate::agents::players::write_flushed;
+    #[cfg(feature = "persistence")]
+    use std::io::Write;
+
+    fn test_player(piece: PlayerPiece) -> Player {
+        Player::new(piece, 0.5, 0.1, AnnealingSchedule::Constant, AnnealingSchedule::Constant)
+    }
 
     #[test]
-    fn test_check_winner_col() {
-        let test_board: [Piece; 9] = [
-            Piece::Empty, Piece::Empty, Piece::Empty,
-            Piece::Empty, Piece::Empty, Piece::Empty,
-            Piece::Empty, Piece::Empty, Piece::Empty,
-        ];
-        assert_eq!(Player::check_winner_col(&test_board), None);
+    fn test_make_move_full_board() {
+        let mut player = test_player(PlayerPiece::X);
         let test_board: [Piece; 9] = [
             Piece::X, Piece::O, Piece::X,
-            Piece::O, Piece::O, Piece::O,
+            Piece::X, Piece::O, Piece::O,
             Piece::O, Piece::X, Piece::X,
         ];
-        assert_eq!(Player::check_winner_col(&test_board), None);
+        assert_eq!(player.make_move(&test_board), Err(PlayerError::GameAlreadyOver));
+    }
+
+    #[test]
+    fn test_make_move_won_board() {
+        let mut player = test_player(PlayerPiece::O);
         let test_board: [Piece; 9] = [
-            Piece::X, Piece::Empty, Piece::Empty,
-            Piece::X, Piece::Empty, Piece::Empty,
-            Piece::X, Piece::Empty, Piece::Empty,
+            Piece::X, Piece::X, Piece::X,
+            Piece::O, Piece::O, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
         ];
-        assert_eq!(Player::check_winner_col(&test_board), Some(Piece::X));
+        assert_eq!(player.make_move(&test_board), Err(PlayerError::GameAlreadyOver));
     }
 
     #[test]
-    fn test_check_winner_row() {
+    fn test_make_move_out_of_turn() {
+        let mut player = test_player(PlayerPiece::O);
+        let empty_board: [Piece; 9] = [Piece::Empty; 9];
+        assert_eq!(player.make_move(&empty_board), Err(PlayerError::NotMyTurn));
+        let mut player = test_player(PlayerPiece::X);
         let test_board: [Piece; 9] = [
+            Piece::X, Piece::Empty, Piece::Empty,
             Piece::Empty, Piece::Empty, Piece::Empty,
             Piece::Empty, Piece::Empty, Piece::Empty,
-            Piece::Empty, Piece::Empty, Piece::Empty,
-        ];
-        assert_eq!(Player::check_winner_row(&test_board), None);
-        let test_board: [Piece; 9] = [
-            Piece::X, Piece::O, Piece::X,
-            Piece::O, Piece::O, Piece::X,
-            Piece::X, Piece::O, Piece::O,
-        ];
-        assert_eq!(Player::check_winner_row(&test_board), None);
-        let test_board: [Piece; 9] = [
-            Piece::X, Piece::X, Piece::X,
-            Piece::O, Piece::O, Piece::X,
-            Piece::X, Piece::O, Piece::O,
         ];
-        assert_eq!(Player::check_winner_row(&test_board), Some(Piece::X));
+        assert_eq!(player.make_move(&test_board), Err(PlayerError::NotMyTurn));
+        let mut player = test_player(PlayerPiece::O);
+        assert!(player.make_move(&test_board).is_ok());
+    }
+
+    #[test]
+    fn test_make_move_on_handicap_board() {
+        // O starts with the center, so it is X's move with the piece counts equal
+        let mut board = Board::with_handicap(&[(1, 1, Piece::O)]).unwrap();
+        let mut player_o = test_player(PlayerPiece::O);
+        let mut player_x = test_player(PlayerPiece::X);
+        assert_eq!(player_o.make_move_on_board(&board), Err(PlayerError::NotMyTurn));
+        let x_move = player_x.make_move_on_board(&board).unwrap();
+        board.player_move(&Player::to_human_move(&x_move), "X").unwrap();
+        // Counts are now equal, which make_move would read as X's turn, but the board knows
+        // it is O's
+        assert_eq!(player_x.make_move_on_board(&board), Err(PlayerError::NotMyTurn));
+        assert!(player_o.make_move_on_board(&board).is_ok());
     }
 
     #[test]
-    fn check_winner_diag() {
+    fn test_overlay() {
         let test_board: [Piece; 9] = [
-            Piece::Empty, Piece::Empty, Piece::Empty,
-            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::X, Piece::O, Piece::Empty,
+            Piece::Empty, Piece::X, Piece::Empty,
             Piece::Empty, Piece::Empty, Piece::Empty,
         ];
-        assert_eq!(Player::check_winner_diag(&test_board), None);
-        let test_board: [Piece; 9] = [
-            Piece::X, Piece::X, Piece::X,
-            Piece::O, Piece::O, Piece::O,
-            Piece::X, Piece::X, Piece::X,
+        let mut player = test_player(PlayerPiece::O);
+        player.make_move(&test_board).unwrap();
+        let base = player.save_state.state_space.clone();
+        let mut player = player.with_overlay();
+        assert!(player.has_overlay());
+        // Start a new episode, so the losing position can be shown directly
+        player.end_episode();
+        // Learning with the overlay leaves the base state space untouched
+        let losing_board: [Piece; 9] = [
+            Piece::X, Piece::O, Piece::Empty,
+            Piece::Empty, Piece::X, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::O,
         ];
-        assert_eq!(Player::check_winner_diag(&test_board), None);
-        let test_board: [Piece; 9] = [
-            Piece::X, Piece::O, Piece::O,
-            Piece::O, Piece::X, Piece::O,
+        player.show_loosing_state(&losing_board);
+        player.make_move(&test_board).unwrap();
+        assert_eq!(player.save_state.state_space, base);
+        // Lookups prefer the overlay value
+        player.overlay.as_mut().unwrap().insert(test_board, 0.25);
+        assert_eq!(player.lookup_state_value(&test_board), Some(0.25));
+        assert_ne!(base.get(&test_board), Some(&0.25));
+        // Merging moves the overlay values into the base state space
+        player.merge_overlay();
+        assert_eq!(player.save_state.state_space.get(&test_board), Some(&0.25));
+        assert_eq!(player.overlay.as_ref().map(|o| o.len()), Some(0));
+        player.show_loosing_state(&test_board);
+        player.clear_overlay();
+        assert_eq!(player.lookup_state_value(&test_board), Some(0.25));
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn test_overlay_file() {
+        let losing_board: [Piece; 9] = [
+            Piece::X, Piece::O, Piece::Empty,
+            Piece::Empty, Piece::X, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::O,
+        ];
+        let mut player = test_player(PlayerPiece::O).with_overlay();
+        player.show_loosing_state(&losing_board);
+        // Round trip the overlay through a file
+        let overlay_path = std::env::temp_dir().join("tictacrs_test_overlay.ttr");
+        player.save_overlay(&overlay_path).unwrap();
+        player.clear_overlay();
+        assert_eq!(player.lookup_state_value(&losing_board), None);
+        player.load_overlay(&overlay_path).unwrap();
+        assert_eq!(player.lookup_state_value(&losing_board), Some(0.));
+        let mut other_piece = test_player(PlayerPiece::X);
+        assert_eq!(other_piece.load_overlay(&overlay_path),
+                   Err(PlayerError::PieceMismatch { expected: PlayerPiece::X, found: PlayerPiece::O }));
+        std::fs::remove_file(&overlay_path).unwrap();
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn test_games_played() {
+        let directory = std::env::temp_dir().join("tictacrs_test_games_played");
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut player = Player::new(PlayerPiece::X, 0.5, 0.4, AnnealingSchedule::Constant,
+                                     AnnealingSchedule::ExponentialDecay { rate: 0.5 });
+        player.update_iteration(5);
+        for _ in 0..2 {
+            player.record_game_played();
+            player.increment_iteration();
+        }
+        let path = directory.join("player_x_save.ttr");
+        player.save_player_state(&path).unwrap();
+        let loaded = Player::load(&path).unwrap();
+        assert_eq!(loaded.get_games_played(), 2);
+        assert_eq!(loaded.get_iteration(), 7);
+        assert_eq!(loaded.get_exploration_rate(), 0.4 * 0.5f64.powi(7));
+        std::fs::remove_file(&path).unwrap();
+        // A save cut short can't be read
+        let bytes = player.to_bytes().unwrap();
+        let res = Player::from_bytes(&bytes[..bytes.len() - 2]);
+        assert_eq!(res.err(), Some(PlayerError::CorruptFile));
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn test_load() {
+        let directory = std::env::temp_dir().join("tictacrs_test_load");
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut player = test_player(PlayerPiece::O);
+        player.make_move(&[Piece::X, Piece::Empty, Piece::Empty, Piece::Empty, Piece::Empty,
+                           Piece::Empty, Piece::Empty, Piece::Empty, Piece::Empty]).unwrap();
+        player.update_iteration(7);
+        let path = directory.join("player_o_save.ttr");
+        player.save_player_state(&path).unwrap();
+        let loaded = Player::load(&path).unwrap();
+        assert_eq!(loaded.save_state.state_space, player.save_state.state_space);
+        assert_eq!(loaded.get_iteration(), 7);
+        assert_eq!(loaded.get_player_piece(), PlayerPiece::O);
+        // A missing save, and one which isn't a player, fail differently
+        let missing = directory.join("missing.ttr");
+        let err = Player::load(&missing).err().unwrap();
+        assert_eq!(err, PlayerError::InvalidFile { path: missing.clone(), source: io::ErrorKind::NotFound.into() });
+        // The message names the file, and the io error is kept as the cause
+        assert!(err.to_string().starts_with(&format!("couldn't open {}: ", missing.display())));
+        assert_eq!(err.source().unwrap().downcast_ref::<io::Error>().unwrap().kind(), io::ErrorKind::NotFound);
+        let corrupt = directory.join("corrupt.ttr");
+        std::fs::write(&corrupt, b"not a player").unwrap();
+        assert_eq!(Player::load(&corrupt).err(), Some(PlayerError::BadMagic));
+        // A damaged save is caught by its checksum
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        std::fs::write(&corrupt, &bytes).unwrap();
+        assert_eq!(Player::load(&corrupt).err(), Some(PlayerError::CorruptFile));
+        // Saves from before the header are still read
+        let headerless = directory.join("headerless.ttr");
+        std::fs::write(&headerless, borsh::to_vec(&player.save_state).unwrap()).unwrap();
+        assert_eq!(Player::load(&headerless).unwrap().save_state, player.save_state);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(PlayerError::NotMyTurn.to_string(), "it isn't the player's turn");
+        assert_eq!(PlayerError::PieceMismatch { expected: PlayerPiece::O, found: PlayerPiece::X }.to_string(),
+                   "the save is for player X, not player O");
+        assert_eq!(PlayerError::UnsupportedVersion { found: 9, supported: 5 }.to_string(),
+                   "the save is in format version 9, but this version of tictacrs only reads up to 5");
+        let err = Player::from_bytes(&[0, 0]).err().unwrap();
+        assert_eq!(err, PlayerError::BadMagic);
+        assert!(err.source().is_none());
+        let cause = io::Error::new(io::ErrorKind::InvalidData, "unexpected end of input");
+        let err = PlayerError::UnableToRead(cause);
+        assert_eq!(err.to_string(), "the file doesn't hold a player: unexpected end of input");
+        assert_eq!(err.source().unwrap().to_string(), "unexpected end of input");
+    }
+
+    /// Accepts a limited number of bytes, then fails every write
+    #[cfg(feature = "persistence")]
+    struct FailingWriter {
+        remaining: usize,
+    }
+
+    #[cfg(feature = "persistence")]
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.remaining == 0 {
+                return Err(io::Error::new(io::ErrorKind::StorageFull, "no space left"));
+            }
+            let written = buf.len().min(self.remaining);
+            self.remaining -= written;
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn test_save_atomically() {
+        let directory = std::env::temp_dir().join("tictacrs_test_save_atomically");
+        _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("player_x_save.ttr");
+        let mut player = test_player(PlayerPiece::X);
+        player.save_player_state(&path).unwrap();
+        player.update_iteration(3);
+        // Replacing an existing save leaves no temporary file behind
+        player.save_player_state(&path).unwrap();
+        assert_eq!(Player::load(&path).unwrap().get_iteration(), 3);
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+        // A save which can't be written leaves the earlier one intact
+        let saved = std::fs::read(&path).unwrap();
+        std::fs::create_dir(directory.join("player_x_save.ttr.tmp")).unwrap();
+        player.update_iteration(4);
+        assert!(matches!(player.save_player_state(&path),
+                         Err(PlayerError::InvalidFile { path: ref failed, .. }) if *failed == path));
+        assert_eq!(std::fs::read(&path).unwrap(), saved);
+        assert_eq!(Player::load(&path).unwrap().get_iteration(), 3);
+        // Errors part way through writing, including those only seen when the buffer is
+        // flushed, are reported
+        let bytes = player.to_bytes().unwrap();
+        let err = write_flushed(FailingWriter { remaining: 10 }, &bytes).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+        assert!(write_flushed(FailingWriter { remaining: 10 }, &bytes[..20]).is_err());
+        assert!(write_flushed(FailingWriter { remaining: 20 }, &bytes[..20]).is_ok());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn test_load_expecting() {
+        let path = std::env::temp_dir().join("tictacrs_test_load_expecting.ttr");
+        test_player(PlayerPiece::X).save_player_state(&path).unwrap();
+        assert_eq!(Player::load_expecting(&path, PlayerPiece::X).unwrap().piece(), PlayerPiece::X);
+        assert_eq!(Player::load_expecting(&path, PlayerPiece::O).err(),
+                   Some(PlayerError::PieceMismatch { expected: PlayerPiece::O, found: PlayerPiece::X }));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_all_moves_losing() {
+        // O took a1 after X's b2, and X has replied with c3
+        let mut previous = [Piece::Empty; 9];
+        previous[4] = Piece::X;
+        previous[0] = Piece::O;
+        let mut state = previous;
+        state[8] = Piece::X;
+        for losing_value in [0., -1e-12] {
+            let mut player = Player::new(PlayerPiece::O, 0.5, 0., AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+            player.last_afterstate = Some(previous);
+            player.trajectory.insert(previous);
+            for square in (0..9).filter(|square| state[*square] == Piece::Empty) {
+                let mut after_move = state;
+                after_move[square] = Piece::O;
+                player.save_state.state_space.insert(after_move, losing_value);
+            }
+            let chosen = player.make_move(&state).unwrap();
+            assert_eq!(state[(chosen[0] * 3 + chosen[1]) as usize], Piece::Empty);
+            // Learning from the losing moves keeps the value in range
+            assert!((0. ..=1.).contains(&player.lookup_state_value(&previous).unwrap()));
+        }
+        // A full board has no move, which is an error rather than a panic
+        let mut player = test_player(PlayerPiece::X);
+        let full: [Piece; 9] = [
+            Piece::X, Piece::O, Piece::X,
             Piece::O, Piece::O, Piece::X,
+            Piece::X, Piece::X, Piece::O,
         ];
-        assert_eq!(Player::check_winner_diag(&test_board), Some(Piece::X));
+        assert_eq!(player.make_move_unchecked(&full), Err(PlayerError::GameAlreadyOver));
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let mut player = test_player(PlayerPiece::X);
+        player.make_move(&[Piece::Empty; 9]).unwrap();
+        player.update_iteration(12);
+        let bytes = player.to_bytes().unwrap();
+        let restored = Player::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.save_state.state_space, player.save_state.state_space);
+        assert_eq!(restored.get_iteration(), 12);
+        assert_eq!(restored.get_player_piece(), PlayerPiece::X);
+        assert_eq!(Player::from_bytes(&bytes[1..]).err(), Some(PlayerError::BadMagic));
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut player = test_player(PlayerPiece::O);
+        player.set_tie_break(TieBreak::FirstIndex);
+        for _ in 0..50 {
+            let mut board = Board::new();
+            while board.turn().is_some() {
+                if board.turn() == Some(PlayerPiece::O) {
+                    let o_move = player.make_move(&board.get_compact_state()).unwrap();
+                    board.player_move(&Player::to_human_move(&o_move), "O").unwrap();
+                } else {
+                    let empty = board.get_compact_state().iter()
+                        .position(|p| *p == Piece::Empty).unwrap() as u8;
+                    board.player_move(&Player::to_human_move(&[empty / 3, empty % 3]), "X").unwrap();
+                }
+            }
+        }
+        // A handicap game adds states which can't occur in a normal game, exploring would add
+        // none
+        let mut handicap_board = Board::with_handicap(&[(1, 1, Piece::O)]).unwrap();
+        handicap_board.player_move("a1", "X").unwrap();
+        player.set_learning(Learning::FixedExploration(0.));
+        player.make_move_on_board(&handicap_board).unwrap();
+        player.set_learning(Learning::Scheduled);
+        // Compact a copy, and check the greedy moves are unchanged on every state O moves from
+        let mut compacted = player.clone();
+        let report = compacted.compact();
+        assert!(report.states_removed > 0);
+        assert_eq!(report.states_before - report.states_removed,
+                   compacted.save_state.state_space.len());
+        for state in reachable_states() {
+            if rules::game_state(&state) == GameState::InProgress
+                && state.iter().filter(|p| **p != Piece::Empty).count() % 2 == 1 {
+                // Each state is a new game, so nothing is backed up into the last one's move
+                player.start_new_game();
+                compacted.start_new_game();
+                assert_eq!(player.make_move(&state), compacted.make_move(&state));
+            }
+        }
+        // Nothing left to remove the second time
+        assert_eq!(compacted.compact().states_removed, 0);
+        let report = CompactReport { states_before: 5478, states_removed: 1234 };
+        assert_eq!(report.to_string(), "removed 1,234 of 5,478 states (22.5%)");
+        let empty = CompactReport { states_before: 0, states_removed: 0 };
+        assert_eq!(empty.to_string(), "removed 0 of 0 states");
     }
 
     #[test]
-    fn test_check_winner() {
+    fn test_explain_move() {
+        let player = test_player(PlayerPiece::X);
+        // X can win on row a, and needs to block O on row b
         let test_board: [Piece; 9] = [
-            Piece::Empty, Piece::Empty, Piece::Empty,
-            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::X, Piece::X, Piece::Empty,
+            Piece::O, Piece::O, Piece::Empty,
             Piece::Empty, Piece::Empty, Piece::Empty,
         ];
-        assert_eq!(Player::check_winner(&test_board), None);
+        let explanation = player.explain_move(&test_board, [0, 2]);
+        assert_eq!(explanation.reason, MoveReason::Win);
+        assert_eq!(explanation.line, Some([0, 1, 2]));
+        assert_eq!(explanation.value, 1.);
+        assert_eq!(explanation.options, 5);
+        assert_eq!(explanation.knowledge, Knowledge::Terminal);
+        assert_eq!(format!("{}", explanation), "I played a3 because it completes row a, winning the game");
+        let explanation = player.explain_move(&test_board, [1, 2]);
+        assert_eq!(explanation.reason, MoveReason::Block);
+        assert_eq!(format!("{}", explanation), "I played b3 because it blocks your three-in-a-row on row b");
+        // Playing b1 threatens both row b and column 1
         let test_board: [Piece; 9] = [
-            Piece::X, Piece::Empty, Piece::Empty,
-            Piece::Empty, Piece::Empty, Piece::Empty,
-            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::X, Piece::Empty, Piece::O,
+            Piece::Empty, Piece::Empty, Piece::X,
+            Piece::Empty, Piece::Empty, Piece::O,
         ];
-        assert_eq!(Player::check_winner(&test_board), None);
-        let test_board: [Piece; 9] = [
+        let explanation = player.explain_move(&test_board, [1, 0]);
+        assert_eq!(explanation.reason, MoveReason::Fork);
+        assert!(format!("{}", explanation).starts_with("I played b1 because it makes two threats"));
+        // Nothing tactical on the empty board, so it comes down to the values
+        let mut player = test_player(PlayerPiece::X);
+        let mut after_move = [Piece::Empty; 9];
+        after_move[4] = Piece::X;
+        player.save_state.state_space.insert(after_move, 0.81);
+        let explanation = player.explain_move(&[Piece::Empty; 9], [1, 1]);
+        assert_eq!(explanation.reason, MoveReason::Value);
+        assert_eq!(format!("{}", explanation),
+                   "I played b2 because it had the highest learned value 0.81 among 9 options");
+        assert_eq!(explanation.knowledge, Knowledge::Learned);
+        let explanation = player.explain_move(&[Piece::Empty; 9], [0, 0]);
+        assert_eq!(explanation.knowledge, Knowledge::DefaultValue);
+        assert_eq!(format!("{}", explanation),
+                   "I played a1 to explore, I haven't seen this position before, so I'm guessing (the best of 9 options was 0.81)");
+        after_move[4] = Piece::Empty;
+        after_move[0] = Piece::X;
+        player.save_state.state_space.insert(after_move, 0.2);
+        let explanation = player.explain_move(&[Piece::Empty; 9], [0, 0]);
+        assert_eq!(format!("{}", explanation),
+                   "I played a1 to explore, its learned value is 0.20 but the best of 9 options was 0.81");
+        // A stored value trained back to the default is still learned
+        after_move[0] = Piece::Empty;
+        after_move[8] = Piece::X;
+        player.save_state.state_space.insert(after_move, 0.5);
+        assert_eq!(player.explain_move(&[Piece::Empty; 9], [2, 2]).knowledge, Knowledge::Learned);
+        let untrained = test_player(PlayerPiece::X);
+        assert_eq!(format!("{}", untrained.explain_move(&[Piece::Empty; 9], [1, 1])),
+                   "I played b2 but I haven't seen this position before, so I'm guessing among 9 options");
+        // Explaining doesn't add anything to the state space
+        assert_eq!(player.save_state.state_space.len(), 3);
+    }
+
+    #[test]
+    fn test_old_save_format() {
+        // Saves used to store the player's piece as a Piece
+        #[derive(BorshSerialize)]
+        struct OldSaveState {
+            piece: Piece,
+            state_space: HashMap<[Piece; 9], f64>,
+            initial_learning_rate: f64,
+            initial_exploration_rate: f64,
+            iteration: u32,
+        }
+        let mut old = OldSaveState {
+            piece: Piece::O,
+            state_space: HashMap::from([([Piece::Empty; 9], 0.25)]),
+            initial_learning_rate: 0.5,
+            initial_exploration_rate: 0.1,
+            iteration: 3,
+        };
+        let player = Player::from_bytes(&borsh::to_vec(&old).unwrap()).unwrap();
+        assert_eq!(player.get_player_piece(), PlayerPiece::O);
+        assert_eq!(player.lookup_state_value(&[Piece::Empty; 9]), Some(0.25));
+        assert_eq!(player.get_iteration(), 3);
+        assert_eq!(player.get_games_played(), 0);
+        assert!(!player.is_symmetric());
+        // Old saves were trained on the binary's schedules
+        assert_eq!(player.get_learning_schedule(), LEARNING_SCHEDULE);
+        assert_eq!(player.get_exploration_schedule(), EXPLORATION_SCHEDULE);
+        // A player of the empty piece can't be loaded
+        old.piece = Piece::Empty;
+        let res = Player::from_bytes(&borsh::to_vec(&old).unwrap());
+        assert_eq!(res.err(), Some(PlayerError::BadMagic));
+    }
+
+    #[test]
+    fn test_saved_schedules() {
+        let learning = AnnealingSchedule::LinearDecay { floor: 0.1, steps: 4 };
+        let exploration = AnnealingSchedule::StepDecay { drop_rate: 0.5, step_size: 2 };
+        let mut player = Player::new(PlayerPiece::O, 0.5, 0.4, learning, exploration);
+        player.update_iteration(3);
+        // The player is read back annealing as it did, without being told how
+        let restored = Player::from_bytes(&player.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.get_learning_schedule(), learning);
+        assert_eq!(restored.get_exploration_schedule(), exploration);
+        assert_eq!(restored.get_learning_rate(), player.get_learning_rate());
+        assert_eq!(restored.get_exploration_rate(), 0.2);
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn test_loaded_with_functions() {
+        let learning = AnnealingSchedule::ExponentialDecay { rate: 0.5 };
+        let player = Player::new(PlayerPiece::X, 0.5, 0.4, learning, AnnealingSchedule::Constant);
+        let path = std::env::temp_dir().join("tictacrs_test_loaded_with_functions.ttr");
+        player.save_player_state(&path).unwrap();
+        // The functions take over from the saved schedules
+        #[allow(deprecated)]
+        let overridden = Player::new_from_file(&path, |_, _| 0.3, |_, _| 0.).unwrap();
+        assert_eq!(overridden.get_learning_rate(), 0.3);
+        assert_eq!(overridden.get_exploration_rate(), 0.);
+        assert_eq!(overridden.get_learning_schedule(), learning);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_annealed_rates() {
+        let mut player = Player::with_annealing_functions(PlayerPiece::X, 0.8, 0.2, |r, it| r / (it + 1) as f64,
+                                                          |r, it| r / (it + 1) as f64);
+        assert_eq!(player.get_learning_rate(), 0.8);
+        assert_eq!(player.get_exploration_rate(), 0.2);
+        player.update_iteration(3);
+        assert_eq!(player.get_learning_rate(), 0.2);
+        assert_eq!(player.get_exploration_rate(), 0.05);
+    }
+
+    #[test]
+    fn test_capturing_schedules() {
+        // Sweeping a parameter needs schedules which capture it
+        for drop_rate in [0.5f64, 0.25] {
+            let mut player = Player::builder(PlayerPiece::X)
+                .learning_rate(0.8)
+                .exploration_rate(0.4)
+                .learning_function(move |rate, iteration| rate * (1. - drop_rate).powi(iteration as i32))
+                .exploration_function(move |rate, iteration| rate / (1. + drop_rate * iteration as f64))
+                .build()
+                .unwrap();
+            player.update_iteration(2);
+            assert_eq!(player.get_learning_rate(), 0.8 * (1. - drop_rate).powi(2));
+            assert_eq!(player.get_exploration_rate(), 0.4 / (1. + 2. * drop_rate));
+            // Copies share the functions, but they can't be saved
+            let copy = player.clone();
+            assert_eq!(copy.get_learning_rate(), player.get_learning_rate());
+            let restored = Player::from_bytes(&player.to_bytes().unwrap()).unwrap();
+            assert_eq!(restored.get_learning_schedule(), AnnealingSchedule::Constant);
+            assert_eq!(restored.get_exploration_rate(), 0.4);
+        }
+    }
+
+    #[test]
+    fn test_frozen_player() {
+        let mut player = Player::new(PlayerPiece::X, 0.5, 1., AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        let mut best_state = [Piece::Empty; 9];
+        best_state[2] = Piece::X;
+        player.save_state.state_space.insert(best_state, 0.9);
+        player.set_learning(Learning::Frozen);
+        assert_eq!(player.get_exploration_rate(), 0.);
+        // Even with a stored exploration rate of 1 it never explores, and never learns
+        for _ in 0..500 {
+            assert_eq!(player.make_move(&[Piece::Empty; 9]), Ok([0, 2]));
+        }
+        player.show_loosing_state(&best_state);
+        assert_eq!(player.save_state.state_space.len(), 1);
+        assert_eq!(player.save_state.state_space[&best_state], 0.9);
+        // Learning with a fixed exploration rate overrides the schedule
+        player.set_learning(Learning::FixedExploration(0.05));
+        assert_eq!(player.get_exploration_rate(), 0.05);
+        player.set_learning(Learning::Scheduled);
+        assert_eq!(player.get_exploration_rate(), 1.);
+    }
+
+    #[test]
+    fn test_best_move() {
+        let mut player = test_player(PlayerPiece::O);
+        let mut state = [Piece::Empty; 9];
+        state[0] = Piece::X;
+        let mut best_state = state;
+        best_state[8] = Piece::O;
+        player.save_state.state_space.insert(best_state, 0.8);
+        assert_eq!(player.best_move(&state), Some([2, 2]));
+        assert_eq!(player.state_count(), 1);
+        // Ties go to the policy, or the lowest square when it's random
+        player.save_state.state_space.insert(best_state, 0.5);
+        assert_eq!(player.best_move(&state), Some([0, 1]));
+        player.set_tie_break(TieBreak::PreferCenterThenCorners);
+        assert_eq!(player.best_move(&state), Some([1, 1]));
+        assert_eq!(player.state_count(), 1);
+        // Positions it can't move from
+        assert_eq!(player.best_move(&[Piece::Empty; 9]), None);
+        let mut won = [Piece::Empty; 9];
+        won[..3].copy_from_slice(&[Piece::X; 3]);
+        won[3..5].copy_from_slice(&[Piece::O; 2]);
+        assert_eq!(player.best_move(&won), None);
+        // A frozen player's moves agree, and don't grow the table either
+        player.set_learning(Learning::Frozen);
+        for _ in 0..20 {
+            assert_eq!(player.make_move(&state).ok(), player.best_move(&state));
+        }
+        assert_eq!(player.state_count(), 1);
+    }
+
+    #[test]
+    fn test_end_of_episode_updates() {
+        // X takes a3, O replies b2 and X takes a1, backing a3 up towards a1's value, with a loss
+        // shown in the position a1 reaches either before the game or at the end of it
+        let mut best_state = [Piece::Empty; 9];
+        best_state[2] = Piece::X;
+        let mut reply = best_state;
+        reply[4] = Piece::O;
+        let mut next_state = reply;
+        next_state[0] = Piece::X;
+        let play = |update_mode: UpdateMode, loss_first: bool| {
+            let mut player = Player::builder(PlayerPiece::X)
+                .learning_rate(0.5)
+                .exploration_rate(0.)
+                .update_mode(update_mode)
+                .build()
+                .unwrap();
+            player.save_state.state_space.insert(best_state, 0.75);
+            player.save_state.state_space.insert(next_state, 0.875);
+            let before = player.state_table_hash();
+            if loss_first {
+                player.show_loosing_state(&next_state);
+            }
+            assert_eq!(player.make_move(&[Piece::Empty; 9]), Ok([0, 2]));
+            assert!(player.make_move(&reply).is_ok());
+            if !loss_first {
+                player.show_loosing_state(&next_state);
+            }
+            if update_mode == UpdateMode::EndOfEpisode {
+                // Nothing changes until the end of the episode
+                assert_eq!(player.state_table_hash(), before);
+            }
+            player.end_episode();
+            player
+        };
+        let batch = play(UpdateMode::EndOfEpisode, false);
+        assert_eq!(batch.state_table_hash(), play(UpdateMode::EndOfEpisode, true).state_table_hash());
+        assert_eq!(batch.save_state.state_space[&best_state], 0.8125);
+        assert_eq!(batch.save_state.state_space[&next_state], 0.);
+        // Online updates share the same backup, but depend on the order
+        let online = play(UpdateMode::Online, false);
+        assert_eq!(online.save_state.state_space[&best_state], 0.8125);
+        assert_eq!(online.save_state.state_space[&next_state], 0.);
+        // Shown the loss first, X moves elsewhere from the reply, to an unseen position
+        let online_loss_first = play(UpdateMode::Online, true);
+        assert_eq!(online_loss_first.save_state.state_space[&best_state], 0.625);
+        assert_ne!(online.state_table_hash(), online_loss_first.state_table_hash());
+    }
+
+    #[test]
+    fn test_symmetric_propagation() {
+        // X in a1 with O to move, and its mirror image with X in a3
+        let mut state = [Piece::Empty; 9];
+        state[0] = Piece::X;
+        let mut mirror = [Piece::Empty; 9];
+        mirror[2] = Piece::X;
+        let mut other_corner = [Piece::Empty; 9];
+        other_corner[8] = Piece::X;
+        let mut player = Player::builder(PlayerPiece::O)
+            .symmetric_propagation(0.25)
+            .build()
+            .unwrap();
+        player.save_state.state_space.insert(state, 0.8);
+        player.save_state.state_space.insert(mirror, 0.6);
+        player.show_loosing_state(&state);
+        assert_eq!(player.save_state.state_space[&state], 0.);
+        // The mirror moves by a quarter of the change, the missing corner isn't added
+        assert_eq!(player.save_state.state_space[&mirror], 0.6 - 0.25 * 0.8);
+        assert!(!player.save_state.state_space.contains_key(&other_corner));
+        assert_eq!(player.save_state.state_space.len(), 2);
+        // Without propagation only the state itself changes
+        assert_eq!(player.set_symmetric_propagation(None), Ok(()));
+        player.save_state.state_space.insert(state, 0.8);
+        player.show_loosing_state(&state);
+        assert_eq!(player.save_state.state_space[&mirror], 0.6 - 0.25 * 0.8);
+        assert_eq!(player.set_symmetric_propagation(Some(-1.)),
+                   Err(PlayerConfigError::InvalidSymmetricPropagation));
+        assert_eq!(player.get_symmetric_propagation(), None);
+    }
+
+    #[test]
+    fn test_symmetric_player() {
+        let mut player = Player::builder(PlayerPiece::X).exploration_rate(0.).symmetric(true).seed(1)
+            .build().unwrap();
+        assert!(player.is_symmetric());
+        // X in a1 and b3 against O in a2, with O to move
+        let mut state = [Piece::Empty; 9];
+        state[0] = Piece::X;
+        state[1] = Piece::O;
+        state[5] = Piece::X;
+        player.show_loosing_state(&state);
+        // Every image of it shares the one value
+        for transform in Transform::all() {
+            assert_eq!(player.learned_value(&transform.apply(&state)), Some(0.));
+        }
+        assert_eq!(player.state_count(), 1);
+        // X to move, with c3 stored as much the best reply in some other frame
+        let mut position = state;
+        position[5] = Piece::Empty;
+        let mut best = position;
+        best[8] = Piece::X;
+        player.save_state.state_space.insert(canonical_form(&best).0, 0.9);
+        for transform in Transform::all() {
+            let image = transform.apply(&position);
+            let chosen = player.make_move(&image).unwrap();
+            assert_eq!(image[(chosen[0] * 3 + chosen[1]) as usize], Piece::Empty);
+            assert_eq!(invert_transform(chosen, transform), [2, 2]);
+            assert_eq!(player.best_move(&image), Some(chosen));
+            player.start_new_game();
+        }
+        // The choice is saved with the player, and can't be combined with propagation
+        let restored = Player::from_bytes(&player.to_bytes().unwrap()).unwrap();
+        assert!(restored.is_symmetric());
+        assert_eq!(restored.learned_value(&best), Some(0.9));
+        assert_eq!(player.set_symmetric_propagation(Some(0.5)), Err(PlayerConfigError::PropagationWhileSymmetric));
+        assert_eq!(Player::builder(PlayerPiece::X).symmetric(true).symmetric_propagation(0.5).build().err(),
+                   Some(PlayerConfigError::PropagationWhileSymmetric));
+    }
+
+    #[test]
+    fn test_learn_scope() {
+        // The human (X) plays b2, a3 and c1 while the computer takes the first empty square,
+        // a1 then a2, and the human wins on the diagonal
+        let play = |learn_scope: LearnScope| {
+            let mut player = Player::builder(PlayerPiece::O)
+                .exploration_rate(0.)
+                .tie_break(TieBreak::FirstIndex)
+                .learn_scope(learn_scope)
+                .build()
+                .unwrap();
+            let mut board = Board::new();
+            player.start_new_game();
+            for human_move in ["b2", "a3", "c1"] {
+                board.player_move(human_move, "X").unwrap();
+                if board.result().is_some() {
+                    player.observe_terminal(&board.get_compact_state());
+                    break;
+                }
+                let chosen = player.make_move_on_board(&board).unwrap();
+                board.player_move(&Player::to_human_move(&chosen), "O").unwrap();
+            }
+            assert_eq!(board.result(), Some(GameResult::Win(PlayerPiece::X)));
+            assert_eq!(board.get_compact_state()[0..2], [Piece::O, Piece::O]);
+            player.state_count()
+        };
+        // Everything: the 8 + 6 positions considered, which include the two moved to
+        assert_eq!(play(LearnScope::All), 14);
+        // The two positions moved to, the second backed up as a loss
+        assert_eq!(play(LearnScope::OwnLinesOnly), 2);
+        // Only the losing position, neither move ended the game
+        assert_eq!(play(LearnScope::TerminalOnly), 1);
+    }
+
+    #[test]
+    fn test_observe_terminal() {
+        // X plays b2, a3 and c1 while O takes the first empty square, a1 then a2
+        let mut player = Player::builder(PlayerPiece::O)
+            .learning_rate(0.5)
+            .exploration_rate(0.)
+            .tie_break(TieBreak::FirstIndex)
+            .draw_reward(0.75)
+            .build()
+            .unwrap();
+        assert_eq!(player.get_draw_reward(), 0.75);
+        let mut board = Board::new();
+        player.start_new_game();
+        let mut reached = Vec::new();
+        for human_move in ["b2", "a3"] {
+            board.player_move(human_move, "X").unwrap();
+            let chosen = player.make_move_on_board(&board).unwrap();
+            board.player_move(&Player::to_human_move(&chosen), "O").unwrap();
+            reached.push(board.get_compact_state());
+        }
+        board.player_move("c1", "X").unwrap();
+        // The loss is backed up into the position O's last move reached, once
+        player.observe_terminal(&board.get_compact_state());
+        assert_eq!(player.learned_value(&reached[1]), Some(0.25));
+        player.observe_terminal(&board.get_compact_state());
+        assert_eq!(player.learned_value(&reached[1]), Some(0.25));
+        // A draw is backed up as the draw reward
+        let drawn: [Piece; 9] = [
             Piece::X, Piece::O, Piece::X,
             Piece::O, Piece::O, Piece::X,
             Piece::X, Piece::X, Piece::O,
         ];
-        assert_eq!(Player::check_winner(&test_board), None);
-        let test_board: [Piece; 9] = [
-            Piece::X, Piece::Empty, Piece::Empty,
+        assert_eq!(player.peek_state_value(&drawn), 0.75);
+        player.last_afterstate = Some(reached[0]);
+        player.observe_terminal(&drawn);
+        assert_eq!(player.learned_value(&reached[0]), Some(0.625));
+        // Nothing is left to back up when O's own move finished the game
+        let mut won: [Piece; 9] = [
+            Piece::O, Piece::O, Piece::Empty,
+            Piece::X, Piece::X, Piece::Empty,
             Piece::X, Piece::Empty, Piece::Empty,
+        ];
+        player.start_new_game();
+        assert_eq!(player.make_move(&won), Ok([0, 2]));
+        won[2] = Piece::O;
+        let before = player.state_table_hash();
+        player.observe_terminal(&won);
+        assert_eq!(player.state_table_hash(), before);
+        // A new game starts without a position to back up into
+        player.last_afterstate = Some(reached[0]);
+        player.start_new_game();
+        player.observe_terminal(&drawn);
+        assert_eq!(player.state_table_hash(), before);
+    }
+
+    #[test]
+    fn test_exploratory_moves() {
+        let mut seen = HashSet::new();
+        for seed in 0..20 {
+            let mut player = Player::builder(PlayerPiece::O).exploration_rate(0.5).seed(seed).build().unwrap();
+            // O replies to X's b2, then X takes the first empty square
+            let mut state = [Piece::Empty; 9];
+            state[4] = Piece::X;
+            let chosen = player.make_move(&state).unwrap();
+            state[(chosen[0] * 3 + chosen[1]) as usize] = Piece::O;
+            let reached = state;
+            let first_empty = state.iter().position(|piece| *piece == Piece::Empty).unwrap();
+            state[first_empty] = Piece::X;
+            // Every move from there looks better than the position O reached
+            for square in (0..9).filter(|square| state[*square] == Piece::Empty) {
+                let mut after_move = state;
+                after_move[square] = Piece::O;
+                player.save_state.state_space.insert(after_move, 0.875);
+            }
+            let before = player.state_table_hash();
+            player.make_move(&state).unwrap();
+            let exploratory = player.last_move_was_exploratory();
+            if exploratory {
+                assert_eq!(player.state_table_hash(), before);
+            } else {
+                assert_ne!(player.state_table_hash(), before);
+                assert!(player.learned_value(&reached).unwrap() > 0.5);
+            }
+            seen.insert(exploratory);
+        }
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    fn test_learning_violations() {
+        let mut player = Player::builder(PlayerPiece::X).exploration_rate(0.).seed(1).build().unwrap();
+        let empty = [Piece::Empty; 9];
+        player.make_move(&empty).unwrap();
+        // Learning normally breaks none of the invariants
+        assert_eq!(player.learning_violations(), LearningViolations::default());
+        let violate = |player: &mut Player, violation: usize| {
+            let mut won = [Piece::Empty; 9];
+            won[0..3].copy_from_slice(&[Piece::X; 3]);
+            let mut elsewhere = [Piece::Empty; 9];
+            elsewhere[8] = Piece::O;
+            match violation {
+                0 => { player.set_state_value(&empty, 1.5) }
+                1 => { player.set_state_value(&won, 0.5) }
+                _ => { player.update_state_value(&elsewhere, 0., 1.) }
+            }
+        };
+        for violation in 0..3 {
+            if cfg!(feature = "strict-learning") {
+                let mut player = player.clone();
+                let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    violate(&mut player, violation);
+                })).unwrap_err();
+                let message = panic.downcast_ref::<String>().unwrap();
+                let expected = ["(value out of range)", "(finished position changed)",
+                                "(position not on the player's trajectory)"][violation];
+                assert!(message.contains(expected), "{}", message);
+                assert!(message.contains("players.rs"), "{}", message);
+            } else {
+                violate(&mut player, violation);
+            }
+        }
+        if !cfg!(feature = "strict-learning") {
+            let violations = player.learning_violations();
+            assert_eq!(violations, LearningViolations { out_of_range: 1, terminal_changed: 1, off_trajectory: 1 });
+            assert_eq!(violations.total(), 3);
+            assert_eq!(violations.to_string(), "3 suspicious value updates: 1 out of range, 1 to \
+                                                finished positions, 1 off the trajectory");
+        }
+        // The trajectory starts again each episode, and isn't checked before the first move
+        player.end_episode();
+        let mut elsewhere = [Piece::Empty; 9];
+        elsewhere[8] = Piece::O;
+        player.show_loosing_state(&elsewhere);
+        assert_eq!(player.learning_violations().off_trajectory, if cfg!(feature = "strict-learning") { 0 } else { 1 });
+    }
+
+    #[test]
+    fn test_terminal_states_not_stored() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(5).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(6).build().unwrap();
+        let mut board = Board::new();
+        for _ in 0..200 {
+            board.clear_board();
+            while let Some(piece) = board.turn() {
+                let player = if piece == PlayerPiece::X { &mut player_x } else { &mut player_o };
+                let chosen = player.make_move_on_board(&board).unwrap();
+                board.player_move(&Player::to_human_move(&chosen), &piece.to_string()).unwrap();
+            }
+        }
+        for player in [&player_x, &player_o] {
+            assert!(player.state_count() > 0);
+            assert!(!player.save_state.state_space.keys().any(|state| Player::is_terminal(&state)));
+        }
+        // Storing the finished positions, as older versions did, doesn't change any move
+        let mut stored = player_x.clone();
+        for state in reachable_states().into_iter().filter(Player::is_terminal) {
+            let value = stored.find_new_state_prob(&state);
+            stored.save_state.state_space.insert(state, value);
+        }
+        for player in [&mut player_x, &mut stored] {
+            player.set_learning(Learning::Frozen);
+            player.set_tie_break(TieBreak::FirstIndex);
+        }
+        for state in reachable_states() {
+            if Player::can_be_queried(&state, PlayerPiece::X) && player_x.check_position(&state).is_ok() {
+                assert_eq!(player_x.make_move(&state), stored.make_move(&state));
+            }
+        }
+        // Reading the older save drops them again, reporting how many there were
+        let terminal_count = reachable_states().into_iter().filter(Player::is_terminal).count();
+        let bytes = stored.to_bytes().unwrap();
+        let read = Player::from_bytes(&bytes).unwrap();
+        assert_eq!(read.terminal_states_dropped(), terminal_count);
+        assert_eq!(read.state_count(), player_x.state_count());
+        assert_eq!(player_x.terminal_states_dropped(), 0);
+    }
+
+    #[test]
+    fn test_iter_states() {
+        let mut player = Player::builder(PlayerPiece::X).seed(3).build().unwrap().with_overlay();
+        let mut board = Board::new();
+        for square in ["b2", "a1", "c3"] {
+            let piece = board.turn().unwrap();
+            board.player_move(square, &piece.to_string()).unwrap();
+            player.save_state.state_space.insert(board.get_compact_state(), 0.25);
+        }
+        player.show_loosing_state(&board.get_compact_state());
+        let mut elsewhere = [Piece::Empty; 9];
+        elsewhere[0] = Piece::X;
+        player.show_drawn_state(&elsewhere);
+        assert_eq!(player.iter_states().count(), player.state_count());
+        // Overlay values take precedence over the base table's
+        let values: HashMap<[Piece; 9], f64> = player.iter_states().collect();
+        assert_eq!(values[&board.get_compact_state()], 0.);
+        assert_eq!(values[&elsewhere], player.lookup_state_value(&elsewhere).unwrap());
+        // Sorted iteration is in the order states are saved in
+        player.merge_overlay();
+        let sorted: Vec<([Piece; 9], f64)> = player.iter_states_sorted().collect();
+        assert_eq!(borsh::to_vec(&sorted).unwrap(), borsh::to_vec(&player.save_state.state_space).unwrap());
+    }
+
+    #[test]
+    fn test_builder() {
+        let player = Player::builder(PlayerPiece::X).build().unwrap();
+        assert_eq!(player.get_player_piece(), PlayerPiece::X);
+        assert_eq!(player.get_learning_rate(), 0.75);
+        assert_eq!(player.get_exploration_rate(), 0.2);
+        assert_eq!(player.get_tie_break(), TieBreak::Random);
+        assert_eq!(player.get_learning(), Learning::Scheduled);
+        assert_eq!(player.get_iteration(), 0);
+        let build = || Player::builder(PlayerPiece::O)
+            .learning_rate(0.5)
+            .exploration_rate(0.5)
+            .learning_function(|r, it| r / (it + 1) as f64)
+            .exploration_function(|r, it| r / (it + 2) as f64)
+            .tie_break(TieBreak::LastIndex)
+            .learning(Learning::FixedExploration(0.3))
+            .seed(7)
+            .build()
+            .unwrap();
+        let mut player = build();
+        player.update_iteration(1);
+        assert_eq!(player.get_learning_rate(), 0.25);
+        assert_eq!(player.get_exploration_rate(), 0.3);
+        player.set_learning(Learning::Scheduled);
+        assert_eq!(player.get_exploration_rate(), 0.5 / 3.);
+        assert_eq!(player.get_tie_break(), TieBreak::LastIndex);
+        // The same seed makes the same decisions
+        let mut first = build();
+        let mut second = build();
+        let state = [Piece::X, Piece::Empty, Piece::Empty, Piece::Empty, Piece::Empty,
+                     Piece::Empty, Piece::Empty, Piece::Empty, Piece::Empty];
+        for _ in 0..50 {
+            assert_eq!(first.make_move(&state), second.make_move(&state));
+        }
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_seeded_players() {
+        use crate::agents::trainer::Trainer;
+        let train = |seed: u64| {
+            let mut player_x = Player::new_seeded(PlayerPiece::X, 0.5, 0.3, AnnealingSchedule::Constant, AnnealingSchedule::Constant, seed);
+            let mut player_o = Player::new_seeded(PlayerPiece::O, 0.5, 0.3, AnnealingSchedule::Constant, AnnealingSchedule::Constant, seed + 1);
+            let moves: Vec<[u8; 2]> = Trainer::transitions(&mut player_x, &mut player_o, 200).unwrap()
+                .map(|transition| transition.action)
+                .collect();
+            (moves, player_x, player_o)
+        };
+        let (moves, player_x, player_o) = train(11);
+        let (same_moves, same_x, same_o) = train(11);
+        assert_eq!(moves, same_moves);
+        assert_eq!(player_x.save_state.state_space, same_x.save_state.state_space);
+        assert_eq!(player_o.save_state.state_space, same_o.save_state.state_space);
+        assert_ne!(train(12).0, moves);
+        // A player read from bytes can be seeded afterwards
+        let mut restored = Player::from_bytes(&player_x.to_bytes().unwrap()).unwrap();
+        let mut copy = Player::from_bytes(&player_x.to_bytes().unwrap()).unwrap();
+        restored.reseed(4);
+        copy.reseed(4);
+        for _ in 0..50 {
+            restored.start_new_game();
+            copy.start_new_game();
+            assert_eq!(restored.make_move(&[Piece::Empty; 9]), copy.make_move(&[Piece::Empty; 9]));
+        }
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        assert_eq!(Player::builder(PlayerPiece::X).learning_rate(1.5).build().err(),
+                   Some(PlayerConfigError::InvalidLearningRate));
+        assert_eq!(Player::builder(PlayerPiece::X).learning_rate(f64::NAN).build().err(),
+                   Some(PlayerConfigError::InvalidLearningRate));
+        assert_eq!(Player::builder(PlayerPiece::X).exploration_rate(-0.1).build().err(),
+                   Some(PlayerConfigError::InvalidExplorationRate));
+        assert_eq!(Player::builder(PlayerPiece::X).learning(Learning::FixedExploration(2.)).build().err(),
+                   Some(PlayerConfigError::InvalidExplorationRate));
+        assert_eq!(Player::builder(PlayerPiece::X).symmetric_propagation(1.5).build().err(),
+                   Some(PlayerConfigError::InvalidSymmetricPropagation));
+        assert_eq!(Player::builder(PlayerPiece::X).draw_reward(-0.5).build().err(),
+                   Some(PlayerConfigError::InvalidDrawReward));
+    }
+
+    #[test]
+    fn test_tie_break() {
+        // X to move with three empty squares (a2, a3, b1), none of which end the game,
+        // so an untrained player values all three successors equally
+        let test_board: [Piece; 9] = [
             Piece::X, Piece::Empty, Piece::Empty,
+            Piece::Empty, Piece::O, Piece::X,
+            Piece::O, Piece::X, Piece::O,
+        ];
+        let mut player = Player::new(PlayerPiece::X, 0.5, 0.0, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        player.set_tie_break(TieBreak::FirstIndex);
+        assert_eq!(player.make_move(&test_board), Ok([0, 1]));
+        player.set_tie_break(TieBreak::LastIndex);
+        assert_eq!(player.make_move(&test_board), Ok([1, 0]));
+        player.set_tie_break(TieBreak::PreferCenterThenCorners);
+        assert_eq!(player.make_move(&test_board), Ok([0, 2]));
+        player.set_tie_break(TieBreak::Random);
+        for _ in 0..20 {
+            let chosen = player.make_move(&test_board).unwrap();
+            assert!([[0, 1], [0, 2], [1, 0]].contains(&chosen));
+        }
+        // With the center free, it is preferred over the corners
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::O, Piece::X,
+            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::O, Piece::X, Piece::O,
+        ];
+        player.set_tie_break(TieBreak::PreferCenterThenCorners);
+        assert_eq!(player.make_move(&test_board), Ok([1, 1]));
+    }
+
+    #[test]
+    fn test_decide_move() {
+        let mut player = Player::builder(PlayerPiece::O).exploration_rate(0.).seed(2).build().unwrap();
+        assert_eq!(player.last_decision(), None);
+        let mut state = [Piece::Empty; 9];
+        for (idx, piece) in [(0, Piece::X), (1, Piece::X), (5, Piece::X), (3, Piece::O), (4, Piece::O)] {
+            state[idx] = piece;
+        }
+        // Blocking at a3 is greedy
+        player.set_state_value(&{ let mut after = state; after[2] = Piece::O; after }, 0.9);
+        let decision = player.decide_move(&state).unwrap();
+        assert_eq!(decision, MoveDecision {
+            position: [0, 2], exploratory: false, value: 0.9, knowledge: Knowledge::Learned,
+        });
+        assert_eq!(player.last_decision(), Some(decision));
+        assert!(!player.last_move_was_exploratory());
+        assert_eq!(player.make_move(&state), Ok(decision.position()));
+        // The checks are the same as make_move's
+        assert_eq!(player.decide_move(&[Piece::Empty; 9]), Err(PlayerError::NotMyTurn));
+        // Always exploring, every move is marked as such
+        player.set_learning(Learning::FixedExploration(1.));
+        assert!(player.decide_move(&state).unwrap().exploratory);
+        assert!(player.last_move_was_exploratory());
+    }
+
+    #[test]
+    fn test_decide_move_knowledge() {
+        // A fresh player has only the default to go on, even once considering its moves has
+        // added them to the table
+        let mut player = Player::builder(PlayerPiece::X).exploration_rate(0.).seed(5).build().unwrap();
+        let decision = player.decide_move(&[Piece::Empty; 9]).unwrap();
+        assert_eq!(decision.knowledge, Knowledge::DefaultValue);
+        let mut after_move = [Piece::Empty; 9];
+        let [row, col] = decision.position;
+        after_move[(row * 3 + col) as usize] = Piece::X;
+        assert!(player.learned_value(&after_move).is_some());
+        // A stored value counts as learned, even at the default
+        let mut player = Player::builder(PlayerPiece::X).exploration_rate(0.).seed(6).build().unwrap();
+        for square in 0..9 {
+            let mut after_move = [Piece::Empty; 9];
+            after_move[square] = Piece::X;
+            player.set_state_value(&after_move, if square == 4 { 0.5 } else { 0.1 });
+        }
+        let decision = player.decide_move(&[Piece::Empty; 9]).unwrap();
+        assert_eq!((decision.position, decision.value, decision.knowledge), ([1, 1], 0.5, Knowledge::Learned));
+        // Winning finishes the game
+        let mut state = [Piece::Empty; 9];
+        for (idx, piece) in [(0, Piece::X), (1, Piece::X), (8, Piece::X), (3, Piece::O), (4, Piece::O)] {
+            state[idx] = piece;
+        }
+        let mut player = Player::builder(PlayerPiece::O).exploration_rate(0.).seed(7).build().unwrap();
+        let decision = player.decide_move(&state).unwrap();
+        assert_eq!((decision.position, decision.knowledge), ([1, 2], Knowledge::Terminal));
+    }
+
+    #[test]
+    fn test_ply_exploration() {
+        // Always exploring, except for the last two moves of a game
+        let factors = PlyExploration::new([1., 1., 1., 1., 1., 1., 1., 0., 0.]).unwrap();
+        let mut player_x = Player::builder(PlayerPiece::X).exploration_rate(1.).ply_exploration(factors)
+            .seed(3).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).exploration_rate(1.).ply_exploration(factors)
+            .seed(4).build().unwrap();
+        assert_eq!(player_x.exploration_rate_at(&[Piece::Empty; 9]), 1.);
+        let mut late_moves = 0;
+        for _ in 0..200 {
+            let mut board = Board::new();
+            while board.result().is_none() {
+                let player = if board.turn() == Some(PlayerPiece::X) { &mut player_x } else { &mut player_o };
+                let state = board.get_compact_state();
+                let ply = state.iter().filter(|piece| **piece != Piece::Empty).count();
+                let [row, col] = player.make_move(&state).unwrap();
+                board.make_auto_player_move(row, col, player.get_player_piece());
+                assert_eq!(player.last_move_was_exploratory(), ply < 7);
+                if ply >= 7 {
+                    late_moves += 1;
+                }
+            }
+        }
+        assert!(late_moves > 0);
+        assert_eq!(PlyExploration::new([2.; 9]), Err(PlayerConfigError::InvalidPlyExploration));
+    }
+
+    #[test]
+    fn test_anti_diagonal_win_values() {
+        // O to move, and can win on the anti-diagonal at c1 (X could win there too)
+        let before: [Piece; 9] = [
+            Piece::X, Piece::X, Piece::O,
+            Piece::X, Piece::O, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
         ];
-        assert_eq!(Player::check_winner(&test_board), Some(Piece::X));
+        let mut won = before;
+        won[6] = Piece::O;
+        let mut player_o = Player::builder(PlayerPiece::O).exploration_rate(0.).build().unwrap();
+        let player_x = Player::builder(PlayerPiece::X).build().unwrap();
+        assert_eq!(player_o.peek_state_value(&won), 1.);
+        assert_eq!(player_x.peek_state_value(&won), 0.);
+        // The winning move is taken, and the position O's move before reached learned as a
+        // winning one
+        let mut previous = before;
+        previous[3] = Piece::Empty;
+        player_o.last_afterstate = Some(previous);
+        player_o.trajectory.insert(previous);
+        assert_eq!(player_o.make_move(&before), Ok([2, 0]));
+        assert!(player_o.learned_value(&previous).unwrap() > 0.5);
     }
 }
\ No newline at end of file
@@ -0,0 +1,102 @@
+//! An opponent which moves uniformly at random, for benchmarking trained players against
+
+use rand::rngs::SmallRng;
+use rand::seq::IteratorRandom;
+use rand::SeedableRng;
+use crate::agents::agent::{Agent, AgentError};
+use crate::game::board::{Piece, PlayerPiece};
+use crate::game::rules;
+
+/// Plays on one of the empty squares, chosen uniformly at random
+///
+/// ```
+/// use tictacrs::agents::random::RandomAgent;
+/// use tictacrs::prelude::*;
+///
+/// let mut agent = RandomAgent::new(PlayerPiece::O, 7);
+/// // Only the middle square is left
+/// let state = [Piece::X, Piece::O, Piece::X,
+///              Piece::X, Piece::Empty, Piece::O,
+///              Piece::O, Piece::X, Piece::O];
+/// assert_eq!(agent.choose_move(&state), Ok([1, 1]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RandomAgent {
+    piece: PlayerPiece,
+    generator: SmallRng,
+}
+
+impl RandomAgent {
+    /// A random agent playing the given piece, which makes the same moves whenever it's
+    /// created with the same seed
+    pub fn new(piece: PlayerPiece, seed: u64) -> RandomAgent {
+        RandomAgent { piece, generator: SmallRng::seed_from_u64(seed) }
+    }
+
+    /// A random agent playing the given piece, seeded from the operating system
+    pub fn from_entropy(piece: PlayerPiece) -> RandomAgent {
+        RandomAgent { piece, generator: SmallRng::from_entropy() }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn piece(&self) -> PlayerPiece {
+        self.piece
+    }
+
+    fn choose_move(&mut self, state: &[Piece; 9]) -> Result<[u8; 2], AgentError> {
+        if rules::winner(state).is_some() {
+            return Err(AgentError::GameAlreadyOver);
+        }
+        match (0u8..9).filter(|idx| state[*idx as usize] == Piece::Empty).choose(&mut self.generator) {
+            Some(idx) => { Ok([idx / 3, idx % 3]) }
+            None => { Err(AgentError::GameAlreadyOver) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_only_empty_squares() {
+        let mut agent = RandomAgent::new(PlayerPiece::X, 1);
+        let mut state = [Piece::Empty; 9];
+        state[0] = Piece::X;
+        state[4] = Piece::O;
+        state[8] = Piece::X;
+        let mut chosen = HashSet::new();
+        for _ in 0..1000 {
+            let [row, col] = agent.choose_move(&state).unwrap();
+            assert_eq!(state[(row * 3 + col) as usize], Piece::Empty);
+            chosen.insert([row, col]);
+        }
+        // Every empty square comes up
+        assert_eq!(chosen.len(), 6);
+        let mut empty_board = HashSet::new();
+        for _ in 0..1000 {
+            empty_board.insert(agent.choose_move(&[Piece::Empty; 9]).unwrap());
+        }
+        assert_eq!(empty_board.len(), 9);
+    }
+
+    #[test]
+    fn test_seeded() {
+        let mut first = RandomAgent::new(PlayerPiece::O, 5);
+        let mut second = RandomAgent::new(PlayerPiece::O, 5);
+        for _ in 0..20 {
+            assert_eq!(first.choose_move(&[Piece::Empty; 9]), second.choose_move(&[Piece::Empty; 9]));
+        }
+    }
+
+    #[test]
+    fn test_game_over() {
+        let mut agent = RandomAgent::new(PlayerPiece::O, 2);
+        let mut won = [Piece::Empty; 9];
+        won[..3].copy_from_slice(&[Piece::X; 3]);
+        assert_eq!(agent.choose_move(&won), Err(AgentError::GameAlreadyOver));
+        assert_eq!(agent.choose_move(&[Piece::O; 9]), Err(AgentError::GameAlreadyOver));
+    }
+}
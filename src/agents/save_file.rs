@@ -0,0 +1,128 @@
+//! The header written in front of player saves, which marks the file as a save, records the
+//! format version it was written in and checks the save arrived whole. Saves from before the
+//! header (format version 0) start straight away with the player, and are still read.
+use crate::agents::players::{PlayerError, SaveState};
+use crate::meta::SAVE_FORMAT_VERSION;
+
+/// Bytes a save with a header starts with. A headerless save starts with the player's piece,
+/// which is never one of these.
+pub(crate) const MAGIC: [u8; 4] = *b"TTRS";
+/// Length of the header: the magic, then the format version (u16), the length of the rest of
+/// the save and its CRC-32 (both u32), all little endian
+pub(crate) const HEADER_LEN: usize = 14;
+
+/// CRC-32 of the bytes, as used by zip and PNG
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// The bytes of a save, header first
+pub(crate) fn encode(save_state: &SaveState) -> Result<Vec<u8>, PlayerError> {
+    let payload = match borsh::to_vec(save_state) {
+        Ok(payload) => { payload }
+        Err(e) => { return Err(PlayerError::UnableToSave(e)) }
+    };
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&SAVE_FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&crc32(&payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+/// Read a save, with or without a header. Fails with [`PlayerError::BadMagic`] if the bytes
+/// neither start with the header nor are a headerless save,
+/// [`PlayerError::UnsupportedVersion`] if they were written by a newer format, and
+/// [`PlayerError::CorruptFile`] if they were cut short or changed since.
+pub(crate) fn decode(bytes: &[u8]) -> Result<SaveState, PlayerError> {
+    let Some(header) = bytes.strip_prefix(&MAGIC) else {
+        return match borsh::from_slice(bytes) {
+            Ok(save_state) => { Ok(save_state) }
+            Err(_) => { Err(PlayerError::BadMagic) }
+        };
+    };
+    if bytes.len() < HEADER_LEN {
+        return Err(PlayerError::CorruptFile);
+    }
+    let found = u16::from_le_bytes([header[0], header[1]]);
+    if found > SAVE_FORMAT_VERSION {
+        return Err(PlayerError::UnsupportedVersion { found, supported: SAVE_FORMAT_VERSION });
+    }
+    let length = u32::from_le_bytes([header[2], header[3], header[4], header[5]]) as usize;
+    let checksum = u32::from_le_bytes([header[6], header[7], header[8], header[9]]);
+    let payload = &bytes[HEADER_LEN..];
+    if payload.len() != length || crc32(payload) != checksum {
+        return Err(PlayerError::CorruptFile);
+    }
+    match borsh::from_slice(payload) {
+        Ok(save_state) => { Ok(save_state) }
+        Err(e) => { Err(PlayerError::UnableToRead(e)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::annealing::AnnealingSchedule;
+    use crate::game::board::{Piece, PlayerPiece};
+
+    fn save_state() -> SaveState {
+        SaveState {
+            piece: PlayerPiece::O,
+            state_space: [([Piece::Empty; 9], 0.5)].into_iter().collect(),
+            initial_learning_rate: 0.5,
+            initial_exploration_rate: 0.1,
+            iteration: 3,
+            games_played: 1,
+            symmetric: false,
+            learning_schedule: AnnealingSchedule::Constant,
+            exploration_schedule: AnnealingSchedule::Constant,
+        }
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_header() {
+        let bytes = encode(&save_state()).unwrap();
+        assert_eq!(bytes[..4], MAGIC);
+        assert_eq!(bytes[4..6], SAVE_FORMAT_VERSION.to_le_bytes());
+        assert_eq!(decode(&bytes).unwrap(), save_state());
+        // Any flipped bit is caught, in the payload by the checksum
+        for idx in [HEADER_LEN, bytes.len() / 2, bytes.len() - 1] {
+            let mut flipped = bytes.clone();
+            flipped[idx] ^= 0b100;
+            assert_eq!(decode(&flipped), Err(PlayerError::CorruptFile));
+        }
+        let mut flipped = bytes.clone();
+        flipped[0] ^= 1;
+        assert_eq!(decode(&flipped), Err(PlayerError::BadMagic));
+        // However short the save is cut
+        for len in [4, HEADER_LEN - 1, HEADER_LEN, bytes.len() - 1] {
+            assert_eq!(decode(&bytes[..len]), Err(PlayerError::CorruptFile));
+        }
+        let mut newer = bytes.clone();
+        newer[4..6].copy_from_slice(&(SAVE_FORMAT_VERSION + 1).to_le_bytes());
+        assert_eq!(decode(&newer), Err(PlayerError::UnsupportedVersion {
+            found: SAVE_FORMAT_VERSION + 1, supported: SAVE_FORMAT_VERSION }));
+    }
+
+    #[test]
+    fn test_headerless_save() {
+        let bytes = borsh::to_vec(&save_state()).unwrap();
+        assert_eq!(decode(&bytes).unwrap(), save_state());
+        assert_eq!(decode(b"not a player"), Err(PlayerError::BadMagic));
+    }
+}
@@ -0,0 +1,315 @@
+//! Games played by people, against each other or against a computer player, with the
+//! bookkeeping the computer needs to learn from them. Reading moves and showing the board is
+//! left to the caller.
+
+use crate::agents::agent::{Agent, AgentError};
+use crate::agents::players::{Learning, Player};
+use crate::game::board::{Board, BoardError, MoveLegality, PlayerPiece};
+use crate::game::result::GameResult;
+
+/// A game in progress, which may have started with handicap pieces on the board
+///
+/// ```
+/// use tictacrs::agents::session::Session;
+/// use tictacrs::prelude::*;
+///
+/// let mut session = Session::new(Board::new());
+/// for square in ["b2", "a1", "a3", "c1", "b1", "b3", "a2", "c2", "c3"] {
+///     session.play(square).unwrap();
+/// }
+/// assert_eq!(session.result(), Some(GameResult::Draw));
+/// assert_eq!(session.turn(), None);
+/// ```
+#[derive(Clone)]
+pub struct Session {
+    board: Board,
+    /// Only offer to end a dead draw early once a game
+    dead_draw_offered: bool,
+    /// Whether the players agreed to end the game as a draw
+    ended_early: bool,
+}
+
+impl Session {
+    pub fn new(board: Board) -> Session {
+        Session { board, dead_draw_offered: false, ended_early: false }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Whose move it is, None once the game is over
+    pub fn turn(&self) -> Option<PlayerPiece> {
+        if self.ended_early { None } else { self.board.turn() }
+    }
+
+    /// How the game ended, a game ended early counting as a draw, or None if it hasn't
+    pub fn result(&self) -> Option<GameResult> {
+        self.board.result().or(self.ended_early.then_some(GameResult::Draw))
+    }
+
+    /// Whether the players agreed to end the game as a draw before the board filled up
+    pub fn ended_early(&self) -> bool {
+        self.ended_early
+    }
+
+    /// Play a move, in the usual notation, for whoever's turn it is
+    pub fn play(&mut self, move_specification: &str) -> Result<(), BoardError> {
+        let turn = self.turn().ok_or(BoardError::InvalidState)?;
+        self.board.player_move(move_specification, &turn.to_string())
+    }
+
+    /// Have an agent choose a move and play it, returning the move as row and column. The
+    /// board's own turn tracking decides whose move it is, so this also works for boards which
+    /// started with handicap pieces. Fails without changing the board if it isn't the agent's
+    /// turn or the agent chooses a square which can't be played.
+    pub fn play_agent(&mut self, agent: &mut dyn Agent) -> Result<[u8; 2], AgentError> {
+        match self.turn() {
+            None => { return Err(AgentError::GameAlreadyOver) }
+            Some(piece) if piece != agent.piece() => { return Err(AgentError::NotMyTurn) }
+            Some(_) => {}
+        }
+        let chosen = agent.choose_move(&self.board.get_compact_state())?;
+        match self.board.move_legality(chosen[0] as usize, chosen[1] as usize) {
+            MoveLegality::Legal => {}
+            legality => { return Err(AgentError::IllegalMove(chosen, legality)) }
+        }
+        self.board.make_auto_player_move(chosen[0], chosen[1], agent.piece());
+        Ok(chosen)
+    }
+
+    /// Whether to offer the players a draw, which is so the first time it's found nobody can
+    /// win any more
+    pub fn offer_dead_draw(&mut self) -> bool {
+        if self.dead_draw_offered || self.turn().is_none() || !self.board.is_dead_draw() {
+            return false;
+        }
+        self.dead_draw_offered = true;
+        true
+    }
+
+    /// End the game as a draw, once the players have accepted the offer
+    pub fn end_as_draw(&mut self) {
+        self.ended_early = true;
+    }
+}
+
+/// A game between a person and a learning computer player, which learns from the game
+/// unless the sides are swapped
+#[derive(Clone)]
+pub struct ComputerGame {
+    session: Session,
+    sides: Sides,
+    /// The piece of the learning player, whichever side it's playing now
+    learner: PlayerPiece,
+    /// Whether the learner has been shown the result and the game counted
+    finished: bool,
+}
+
+impl ComputerGame {
+    /// Start a game on the given board against the learner, which plays its own piece
+    pub fn new(board: Board, learner: &mut Player) -> ComputerGame {
+        learner.start_new_game();
+        let piece = learner.get_player_piece();
+        ComputerGame { session: Session::new(board), sides: Sides::new(piece.opponent()), learner: piece, finished: false }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn session_mut(&mut self) -> &mut Session {
+        &mut self.session
+    }
+
+    pub fn sides(&self) -> Sides {
+        self.sides
+    }
+
+    /// Whether it's the computer's move, on whichever side it's playing now
+    pub fn is_computer_turn(&self) -> bool {
+        self.session.turn() == Some(self.sides.computer())
+    }
+
+    /// Swap the sides, after which the learner learns nothing from the rest of the game
+    pub fn swap(&mut self, learner: &mut Player) {
+        learner.set_learning(Learning::Frozen);
+        self.sides.swap();
+    }
+
+    /// Show the learner how the game ended and count the game as played, returning who the
+    /// result counts for, or None if the game was abandoned unfinished. Only the first call
+    /// of a game changes the learner.
+    pub fn finish(&mut self, learner: &mut Player) -> Option<SinglePlayerOutcome> {
+        let result = self.session.result();
+        if !self.finished {
+            self.finished = true;
+            if let Some(result) = result {
+                // The learner's last move is backed up from the final position
+                learner.observe_result(&self.session.board.get_compact_state(), result.for_piece(self.learner));
+            }
+            learner.record_game_played();
+            learner.increment_iteration();
+        }
+        result.map(|result| self.sides.outcome(result))
+    }
+}
+
+/// Which side the human and the computer play in a one player game, which can be swapped
+/// part way through
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sides {
+    human: PlayerPiece,
+    swaps: u32,
+}
+
+impl Sides {
+    pub fn new(human: PlayerPiece) -> Sides {
+        Sides { human, swaps: 0 }
+    }
+
+    pub fn human(&self) -> PlayerPiece {
+        self.human
+    }
+
+    pub fn computer(&self) -> PlayerPiece {
+        self.human.opponent()
+    }
+
+    /// Hand the human's moves to the computer and the computer's to the human
+    pub fn swap(&mut self) {
+        self.human = self.human.opponent();
+        self.swaps += 1;
+    }
+
+    /// Whether the sides were swapped at any point, even if swapped back since
+    pub fn swapped(&self) -> bool {
+        self.swaps > 0
+    }
+
+    /// Who a result counts for. A game where the sides were swapped doesn't count for either.
+    pub fn outcome(&self, result: GameResult) -> SinglePlayerOutcome {
+        match result {
+            _ if self.swapped() => { SinglePlayerOutcome::Swapped(result) }
+            GameResult::Win(piece) if piece == self.human => { SinglePlayerOutcome::HumanWon }
+            GameResult::Win(_) => { SinglePlayerOutcome::ComputerWon }
+            GameResult::Draw => { SinglePlayerOutcome::Draw }
+        }
+    }
+}
+
+/// How a one player game ended, for the human
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SinglePlayerOutcome {
+    HumanWon,
+    ComputerWon,
+    Draw,
+    /// The sides were swapped during the game, with the result it ended in
+    Swapped(GameResult),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::Piece;
+    use crate::game::coords::Coord;
+
+    #[test]
+    fn test_play_agent() {
+        let mut computer = Player::builder(PlayerPiece::O).seed(1).build().unwrap();
+        let mut session = Session::new(Board::new());
+        assert_eq!(session.play_agent(&mut computer), Err(AgentError::NotMyTurn));
+        session.play("b2").unwrap();
+        let chosen = session.play_agent(&mut computer).unwrap();
+        assert_ne!(chosen, [1, 1]);
+        assert_eq!(session.turn(), Some(PlayerPiece::X));
+        assert_eq!(session.play("b2"), Err(BoardError::NotEmpty));
+    }
+
+    #[test]
+    fn test_dead_draw() {
+        let mut session = Session::new(Board::new());
+        for square in ["b2", "a1", "a3", "c1", "b1", "b3", "a2", "c2"] {
+            assert!(!session.offer_dead_draw());
+            session.play(square).unwrap();
+        }
+        // Only c3 is left, which doesn't complete a line
+        assert!(session.offer_dead_draw());
+        // Only offered once
+        assert!(!session.offer_dead_draw());
+        session.end_as_draw();
+        assert_eq!(session.turn(), None);
+        assert_eq!(session.result(), Some(GameResult::Draw));
+        assert!(session.ended_early());
+        assert_eq!(session.play("c2"), Err(BoardError::InvalidState));
+    }
+
+    #[test]
+    fn test_swap_sides() {
+        let mut board = Board::new();
+        let mut sides = Sides::new(PlayerPiece::X);
+        assert_eq!(sides.outcome(GameResult::Win(PlayerPiece::X)), SinglePlayerOutcome::HumanWon);
+        assert_eq!(sides.outcome(GameResult::Win(PlayerPiece::O)), SinglePlayerOutcome::ComputerWon);
+        assert_eq!(sides.outcome(GameResult::Draw), SinglePlayerOutcome::Draw);
+        board.player_move("b2", "X").unwrap();
+        assert_eq!(board.turn(), Some(sides.computer()));
+        board.player_move("a1", "O").unwrap();
+        // Swapping when it's the human's turn hands them the computer's piece, so the computer
+        // now moves for X and the human replies as O
+        sides.swap();
+        assert_eq!((sides.human(), sides.computer()), (PlayerPiece::O, PlayerPiece::X));
+        assert_eq!(board.turn(), Some(sides.computer()));
+        board.player_move("c3", "X").unwrap();
+        assert_eq!(board.turn(), Some(sides.human()));
+        board.player_move("a3", "O").unwrap();
+        assert!(sides.swapped());
+        let result = GameResult::Win(PlayerPiece::X);
+        assert_eq!(sides.outcome(result), SinglePlayerOutcome::Swapped(result));
+        // Swapping back restores the sides, but the game still doesn't count
+        sides.swap();
+        assert_eq!((sides.human(), sides.computer()), (PlayerPiece::X, PlayerPiece::O));
+        assert_eq!(board.turn(), Some(sides.human()));
+        board.player_move("a2", "X").unwrap();
+        assert_eq!(board.turn(), Some(sides.computer()));
+        assert_eq!(sides.outcome(GameResult::Draw), SinglePlayerOutcome::Swapped(GameResult::Draw));
+    }
+
+    #[test]
+    fn test_computer_game_learns() {
+        let mut computer = Player::builder(PlayerPiece::O).seed(1).build().unwrap();
+        let mut game = ComputerGame::new(Board::new(), &mut computer);
+        while game.session().turn().is_some() {
+            if game.is_computer_turn() {
+                game.session_mut().play_agent(&mut computer).unwrap();
+            } else {
+                // The human takes the first empty square
+                let state = game.session().board().get_compact_state();
+                let square = state.iter().position(|piece| *piece == Piece::Empty).unwrap() as u8;
+                let coord = Coord::new(square / 3, square % 3).unwrap();
+                game.session_mut().play(&coord.to_string()).unwrap();
+            }
+        }
+        let outcome = game.finish(&mut computer).unwrap();
+        assert_ne!(outcome, SinglePlayerOutcome::Swapped(GameResult::Draw));
+        assert_eq!(computer.get_games_played(), 1);
+        assert_eq!(computer.get_iteration(), 1);
+        assert!(computer.state_count() > 0);
+        // Finishing again doesn't count the game twice
+        assert_eq!(game.finish(&mut computer), Some(outcome));
+        assert_eq!(computer.get_games_played(), 1);
+    }
+
+    #[test]
+    fn test_swapped_game_freezes_learner() {
+        let mut computer = Player::builder(PlayerPiece::O).seed(1).build().unwrap();
+        let mut game = ComputerGame::new(Board::new(), &mut computer);
+        game.session_mut().play("b2").unwrap();
+        game.swap(&mut computer);
+        assert_eq!(computer.get_learning(), Learning::Frozen);
+        assert!(game.sides().swapped());
+        assert!(!game.is_computer_turn());
+        // Abandoned games still count as played
+        assert_eq!(game.finish(&mut computer), None);
+        assert_eq!(computer.get_games_played(), 1);
+    }
+}
@@ -0,0 +1,219 @@
+//! Play games out from a position with trained players, without changing them, to see how
+//! they would end
+use std::error::Error;
+use std::fmt;
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use crate::agents::agent::{Agent, AgentError};
+use crate::agents::players::Player;
+use crate::agents::trainer::{GameRecord, Transition};
+use crate::game::board::{Board, MoveLegality, PlayerPiece};
+use crate::game::result::GameResult;
+
+/// How often each result came up in [`rollout_distribution`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RolloutDistribution {
+    pub x_wins: u32,
+    pub o_wins: u32,
+    pub draws: u32,
+    /// Games still going after the maximum number of moves
+    pub unfinished: u32,
+}
+
+impl RolloutDistribution {
+    /// Number of games played out
+    pub fn samples(&self) -> u32 {
+        self.x_wins + self.o_wins + self.draws + self.unfinished
+    }
+}
+
+/// Errors produced when playing out a position
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub enum SimulateError {
+    /// The players passed as X and O don't use those pieces
+    InvalidPlayers,
+    /// One of the agents couldn't choose a move
+    NoMove(AgentError),
+    /// The game was still going after the maximum number of moves, which were these
+    Unfinished(Vec<Transition>),
+}
+
+impl fmt::Display for SimulateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulateError::InvalidPlayers => { write!(f, "the players must play X and O, in that order") }
+            SimulateError::NoMove(e) => { write!(f, "an agent couldn't move: {}", e) }
+            SimulateError::Unfinished(moves) => {
+                write!(f, "the game was still going after {} moves", moves.len())
+            }
+        }
+    }
+}
+
+impl Error for SimulateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SimulateError::NoMove(e) => { Some(e) }
+            _ => { None }
+        }
+    }
+}
+
+/// Play the game out from the board for at most `max_plies` moves, with both agents always
+/// making their best move (see [`Agent::greedy_move`], so a [`Player`] settles ties by its tie
+/// break policy). No values are recorded in the moves, and the agents aren't changed.
+///
+/// ```
+/// use tictacrs::agents::simulate::rollout;
+/// use tictacrs::prelude::*;
+///
+/// let player_x = Player::builder(PlayerPiece::X).build().unwrap();
+/// let player_o = Player::builder(PlayerPiece::O).build().unwrap();
+/// let played = rollout(&Board::new(), &player_x, &player_o, 9).unwrap();
+/// assert_eq!(played.moves.last().unwrap().result, Some(played.result));
+/// ```
+pub fn rollout(board: &Board, x: &dyn Agent, o: &dyn Agent, max_plies: usize) -> Result<GameRecord, SimulateError> {
+    if x.piece() != PlayerPiece::X || o.piece() != PlayerPiece::O {
+        return Err(SimulateError::InvalidPlayers);
+    }
+    let moves = play_out(board, max_plies, |piece, board| {
+        let agent = if piece == PlayerPiece::X { x } else { o };
+        agent.greedy_move(&board.get_compact_state())
+    })?;
+    match moves.last().and_then(|last| last.result).or(board.result()) {
+        Some(result) => { Ok(GameRecord { moves, result }) }
+        None => { Err(SimulateError::Unfinished(moves)) }
+    }
+}
+
+/// Play the game out from the board `samples` times, as in [`rollout`] except each player
+/// explores at its current exploration rate, and equally good moves are chosen between
+/// randomly. The games are reproducible for a given seed.
+pub fn rollout_distribution(board: &Board, x: &Player, o: &Player, max_plies: usize, samples: u32,
+                            seed: u64) -> Result<RolloutDistribution, SimulateError> {
+    if x.get_player_piece() != PlayerPiece::X || o.get_player_piece() != PlayerPiece::O {
+        return Err(SimulateError::InvalidPlayers);
+    }
+    let mut generator = SmallRng::seed_from_u64(seed);
+    let mut distribution = RolloutDistribution::default();
+    for _ in 0..samples {
+        let moves = play_out(board, max_plies, |piece, board| {
+            let player = if piece == PlayerPiece::X { x } else { o };
+            let values = player.move_values(&board.get_compact_state());
+            let best = values.iter().map(|(_, value)| *value).fold(f64::NEG_INFINITY, f64::max);
+            let explore = generator.gen::<f64>() < player.get_exploration_rate();
+            // Exploring picks from the moves which aren't best, as the player does in training
+            let candidates: Vec<[u8; 2]> = values.iter()
+                .filter(|(_, value)| (*value < best) == explore)
+                .map(|(chosen, _)| *chosen)
+                .collect();
+            let candidates = if candidates.is_empty() {
+                values.iter().map(|(chosen, _)| *chosen).collect()
+            } else {
+                candidates
+            };
+            candidates.choose(&mut generator).copied().ok_or(AgentError::GameAlreadyOver)
+        })?;
+        match moves.last().and_then(|last| last.result).or(board.result()) {
+            Some(GameResult::Win(PlayerPiece::X)) => { distribution.x_wins += 1 }
+            Some(GameResult::Win(PlayerPiece::O)) => { distribution.o_wins += 1 }
+            Some(GameResult::Draw) => { distribution.draws += 1 }
+            None => { distribution.unfinished += 1 }
+        }
+    }
+    Ok(distribution)
+}
+
+/// Play the game out, choosing the move for each piece with `choose`
+fn play_out<F>(board: &Board, max_plies: usize, mut choose: F) -> Result<Vec<Transition>, SimulateError>
+where
+    F: FnMut(PlayerPiece, &Board) -> Result<[u8; 2], AgentError>,
+{
+    let mut board = board.clone();
+    let mut moves = Vec::new();
+    while let Some(piece) = board.turn() {
+        if moves.len() >= max_plies {
+            break;
+        }
+        let state = board.get_compact_state();
+        let action = choose(piece, &board).map_err(SimulateError::NoMove)?;
+        match board.move_legality(action[0] as usize, action[1] as usize) {
+            MoveLegality::Legal => {}
+            legality => { return Err(SimulateError::NoMove(AgentError::IllegalMove(action, legality))) }
+        }
+        board.make_auto_player_move(action[0], action[1], piece);
+        moves.push(Transition {
+            iteration: 0,
+            mover: piece,
+            state,
+            action,
+            next_state: board.get_compact_state(),
+            exploratory: false,
+            value: None,
+            result: board.result(),
+        });
+    }
+    Ok(moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::players::TieBreak;
+    use crate::agents::random::RandomAgent;
+    use crate::game::board::Piece;
+
+    #[test]
+    fn test_rollout_forced_win() {
+        // X has two ways to win, so whatever O does X wins on the next move
+        let board = Board::new().try_apply_all(&[(0, 0, Piece::X), (1, 1, Piece::O), (0, 1, Piece::X),
+                                                 (1, 2, Piece::O), (1, 0, Piece::X)]).unwrap();
+        let player_x = Player::builder(PlayerPiece::X).build().unwrap();
+        let player_o = Player::builder(PlayerPiece::O).build().unwrap();
+        let played = rollout(&board, &player_x, &player_o, 9).unwrap();
+        assert_eq!(played.result, GameResult::Win(PlayerPiece::X));
+        assert_eq!(played.moves.len(), 2);
+        assert_eq!(played.moves[0].mover, PlayerPiece::O);
+        assert_eq!(played.moves[1].result, Some(played.result));
+        // Stopped short of the win
+        match rollout(&board, &player_x, &player_o, 1) {
+            Err(e @ SimulateError::Unfinished(_)) => {
+                assert_eq!(e.to_string(), "the game was still going after 1 moves");
+            }
+            other => { panic!("expected an unfinished game, got {:?}", other) }
+        }
+        // The players aren't changed
+        assert_eq!(player_x.state_count(), 0);
+        assert_eq!(player_o.state_count(), 0);
+        assert_eq!(rollout(&board, &player_o, &player_x, 9), Err(SimulateError::InvalidPlayers));
+        // Agents which can't choose without changing can't be rolled out
+        let random = RandomAgent::new(PlayerPiece::O, 1);
+        assert!(matches!(rollout(&board, &player_x, &random, 9), Err(SimulateError::NoMove(_))));
+    }
+
+    #[test]
+    fn test_rollout_tie_break() {
+        // Every move of an untrained player is tied, so its tie break policy decides
+        let player_x = Player::builder(PlayerPiece::X).tie_break(TieBreak::LastIndex).build().unwrap();
+        let player_o = Player::builder(PlayerPiece::O).tie_break(TieBreak::PreferCenterThenCorners).build().unwrap();
+        let played = rollout(&Board::new(), &player_x, &player_o, 9).unwrap();
+        assert_eq!(played.moves[0].action, [2, 2]);
+        assert_eq!(played.moves[1].action, [1, 1]);
+        assert_eq!(played.moves[0].action, player_x.best_move(&[Piece::Empty; 9]).unwrap());
+    }
+
+    #[test]
+    fn test_rollout_distribution() {
+        let player_x = Player::builder(PlayerPiece::X).exploration_rate(0.5).build().unwrap();
+        let player_o = Player::builder(PlayerPiece::O).exploration_rate(0.5).build().unwrap();
+        let distribution = rollout_distribution(&Board::new(), &player_x, &player_o, 9, 200, 3).unwrap();
+        assert_eq!(distribution.samples(), 200);
+        assert_eq!(distribution.unfinished, 0);
+        assert!(distribution.x_wins > 0 && distribution.o_wins > 0 && distribution.draws > 0);
+        assert_eq!(distribution, rollout_distribution(&Board::new(), &player_x, &player_o, 9, 200, 3).unwrap());
+        let short = rollout_distribution(&Board::new(), &player_x, &player_o, 2, 10, 3).unwrap();
+        assert_eq!(short, RolloutDistribution { unfinished: 10, ..RolloutDistribution::default() });
+    }
+}
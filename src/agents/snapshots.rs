@@ -0,0 +1,156 @@
+//! Snapshots of both players' tables taken every so often during training (see
+//! [`crate::agents::trainer::TrainingOptions::snapshots`]), and the history of a state's value
+//! across them, to see when training learned or unlearned a position
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use crate::agents::players::{Player, PlayerError};
+use crate::game::board::{Piece, PlayerPiece};
+
+/// Lists the snapshots in a directory, one `iteration<TAB>x file<TAB>o file` line each, in the
+/// order they were taken
+pub const INDEX_FILE: &str = "index.txt";
+
+/// How often to take snapshots during training, and where to keep them. Reusing a directory
+/// adds to its index, replacing any snapshots already taken at the same iterations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshots {
+    /// Number of games between snapshots
+    pub every: u32,
+    pub directory: PathBuf,
+}
+
+/// One snapshot listed in the index
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotEntry {
+    /// Number of training games played when the snapshot was taken
+    pub iteration: u32,
+    pub player_x: PathBuf,
+    pub player_o: PathBuf,
+}
+
+/// Save both players into the directory (creating it if needed) and add them to its index,
+/// unless a snapshot at this iteration is already listed, whose files are then replaced
+pub fn write_snapshot(directory: &Path, iteration: u32, player_x: &Player, player_o: &Player)
+                      -> Result<SnapshotEntry, PlayerError> {
+    if let Err(e) = fs::create_dir_all(directory) {
+        return Err(PlayerError::invalid_file(directory, e));
+    }
+    let names = [format!("snapshot_{:08}_x.ttr", iteration), format!("snapshot_{:08}_o.ttr", iteration)];
+    player_x.save_player_state(directory.join(&names[0]))?;
+    player_o.save_player_state(directory.join(&names[1]))?;
+    let listed = read_index(directory).is_ok_and(|entries| entries.iter().any(|entry| entry.iteration == iteration));
+    if listed {
+        return Ok(SnapshotEntry { iteration, player_x: directory.join(&names[0]), player_o: directory.join(&names[1]) });
+    }
+    let appended = OpenOptions::new().create(true).append(true).open(directory.join(INDEX_FILE))
+        .and_then(|mut index| writeln!(index, "{}\t{}\t{}", iteration, names[0], names[1]));
+    if let Err(e) = appended {
+        return Err(PlayerError::UnableToSave(e));
+    }
+    Ok(SnapshotEntry { iteration, player_x: directory.join(&names[0]), player_o: directory.join(&names[1]) })
+}
+
+/// The snapshots listed in a directory's index, skipping lines which can't be read
+pub fn read_index(directory: &Path) -> Result<Vec<SnapshotEntry>, PlayerError> {
+    let index_path = directory.join(INDEX_FILE);
+    let index = match fs::read_to_string(&index_path) {
+        Ok(index) => { index }
+        Err(e) => { return Err(PlayerError::invalid_file(index_path, e)) }
+    };
+    Ok(index.lines().filter_map(|line| {
+        let mut fields = line.split('\t');
+        let iteration = fields.next()?.parse().ok()?;
+        let (player_x, player_o) = (fields.next()?, fields.next()?);
+        Some(SnapshotEntry { iteration, player_x: directory.join(player_x), player_o: directory.join(player_o) })
+    }).collect())
+}
+
+/// A state's learned value in one player's table at each snapshot, see [`state_history`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateHistory {
+    pub piece: PlayerPiece,
+    /// The iteration of each snapshot and the value then, None before the state was learned
+    pub values: Vec<(u32, Option<f64>)>,
+}
+
+impl StateHistory {
+    /// The consecutive snapshots between which the value changed most, as the iterations of
+    /// the two and the change, or None if it never had a value in two snapshots in a row
+    pub fn biggest_change(&self) -> Option<(u32, u32, f64)> {
+        self.values.windows(2)
+            .filter_map(|pair| match pair {
+                [(from, Some(before)), (to, Some(after))] => { Some((*from, *to, after - before)) }
+                _ => { None }
+            })
+            .max_by(|a, b| a.2.abs().total_cmp(&b.2.abs()))
+    }
+}
+
+impl fmt::Display for StateHistory {
+    /// One line per snapshot, with a bar as long as the value
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (iteration, value) in &self.values {
+            match value {
+                Some(value) => {
+                    let bar = "#".repeat((value.clamp(0., 1.) * 40.).round() as usize);
+                    writeln!(f, "{:>9}  {:.3}  {}", iteration, value, bar)?;
+                }
+                None => { writeln!(f, "{:>9}  -      not learned yet", iteration)? }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The value of a state in the given player's table at every snapshot in the directory
+pub fn state_history(directory: &Path, state: &[Piece; 9], piece: PlayerPiece)
+                     -> Result<StateHistory, PlayerError> {
+    let mut values = Vec::new();
+    for entry in read_index(directory)? {
+        let path = match piece {
+            PlayerPiece::X => { &entry.player_x }
+            PlayerPiece::O => { &entry.player_o }
+        };
+        let player = Player::load_expecting(path, piece)?;
+        values.push((entry.iteration, player.learned_value(state)));
+    }
+    Ok(StateHistory { piece, values })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_biggest_change() {
+        let history = StateHistory {
+            piece: PlayerPiece::X,
+            values: vec![(100, None), (200, Some(0.5)), (300, Some(0.45)), (400, Some(0.2)), (500, Some(0.25))],
+        };
+        assert_eq!(history.biggest_change(), Some((300, 400, 0.2 - 0.45)));
+        assert_eq!(history.to_string().lines().next(), Some("      100  -      not learned yet"));
+        assert_eq!(history.to_string().lines().nth(1), Some(&*format!("      200  0.500  {}", "#".repeat(20))));
+        let unseen = StateHistory { piece: PlayerPiece::O, values: vec![(100, None), (200, Some(0.5))] };
+        assert_eq!(unseen.biggest_change(), None);
+    }
+
+    #[test]
+    fn test_reuse_directory() {
+        let directory = std::env::temp_dir().join("tictacrs_snapshots_reuse");
+        _ = fs::remove_dir_all(&directory);
+        let player_x = Player::builder(PlayerPiece::X).build().unwrap();
+        let player_o = Player::builder(PlayerPiece::O).build().unwrap();
+        for iteration in [100, 200] {
+            write_snapshot(&directory, iteration, &player_x, &player_o).unwrap();
+        }
+        // A second run into the same directory replaces the snapshots rather than listing them twice
+        for iteration in [100, 200, 300] {
+            write_snapshot(&directory, iteration, &player_x, &player_o).unwrap();
+        }
+        let iterations: Vec<u32> = read_index(&directory).unwrap().iter().map(|entry| entry.iteration).collect();
+        assert_eq!(iterations, vec![100, 200, 300]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
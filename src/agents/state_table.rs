@@ -0,0 +1,191 @@
+//! The table of values a player learns, held in a flat vector indexed by
+//! [`encode_state`] rather than hashed, saved in the same layout as the map it replaced
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::ops::Index;
+use borsh::{BorshDeserialize, BorshSerialize};
+use crate::game::board::Piece;
+use crate::game::states::{decode_state, encode_state, STATE_CODES};
+
+/// Values of positions, keyed by position
+#[derive(Clone, Default)]
+pub(crate) struct StateTable {
+    /// The value stored for each code, empty until the first value is stored
+    values: Vec<Option<f64>>,
+    /// Number of positions with a value
+    len: usize,
+}
+
+impl StateTable {
+    pub(crate) fn new() -> StateTable {
+        StateTable::default()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn get(&self, compact_state: &[Piece; 9]) -> Option<&f64> {
+        self.values.get(encode_state(compact_state) as usize).and_then(Option::as_ref)
+    }
+
+    pub(crate) fn contains_key(&self, compact_state: &[Piece; 9]) -> bool {
+        self.get(compact_state).is_some()
+    }
+
+    /// Store a value, returning the one it replaced
+    pub(crate) fn insert(&mut self, compact_state: [Piece; 9], value: f64) -> Option<f64> {
+        if self.values.is_empty() {
+            self.values = vec![None; STATE_CODES];
+        }
+        let previous = self.values[encode_state(&compact_state) as usize].replace(value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    /// Remove a value, returning it
+    pub(crate) fn remove(&mut self, compact_state: &[Piece; 9]) -> Option<f64> {
+        let removed = self.values.get_mut(encode_state(compact_state) as usize).and_then(Option::take);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.values.clear();
+        self.len = 0;
+    }
+
+    /// Every position with a value, in sorted order
+    pub(crate) fn iter(&self) -> impl Iterator<Item = ([Piece; 9], f64)> + '_ {
+        (0u16..).zip(&self.values)
+            .filter_map(|(code, value)| value.map(|value| (decode_state(code), value)))
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = [Piece; 9]> + '_ {
+        self.iter().map(|(state, _)| state)
+    }
+
+    /// Keep only the positions for which the predicate holds
+    pub(crate) fn retain<F: FnMut(&[Piece; 9], &mut f64) -> bool>(&mut self, mut keep: F) {
+        for (code, slot) in (0u16..).zip(self.values.iter_mut()) {
+            if let Some(value) = slot {
+                if !keep(&decode_state(code), value) {
+                    *slot = None;
+                    self.len -= 1;
+                }
+            }
+        }
+    }
+
+    /// Move every value out of the table, leaving it empty
+    pub(crate) fn drain(&mut self) -> impl Iterator<Item = ([Piece; 9], f64)> {
+        std::mem::take(self).into_iter()
+    }
+}
+
+impl PartialEq for StateTable {
+    /// Tables are equal when they hold the same values, however they came to
+    fn eq(&self, other: &StateTable) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl fmt::Debug for StateTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl Index<&[Piece; 9]> for StateTable {
+    type Output = f64;
+
+    fn index(&self, compact_state: &[Piece; 9]) -> &f64 {
+        self.get(compact_state).expect("No value stored for the position")
+    }
+}
+
+impl IntoIterator for StateTable {
+    type Item = ([Piece; 9], f64);
+    type IntoIter = Box<dyn Iterator<Item = ([Piece; 9], f64)>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new((0u16..).zip(self.values)
+            .filter_map(|(code, value)| value.map(|value| (decode_state(code), value))))
+    }
+}
+
+impl FromIterator<([Piece; 9], f64)> for StateTable {
+    fn from_iter<I: IntoIterator<Item = ([Piece; 9], f64)>>(iter: I) -> StateTable {
+        let mut table = StateTable::new();
+        table.extend(iter);
+        table
+    }
+}
+
+impl Extend<([Piece; 9], f64)> for StateTable {
+    fn extend<I: IntoIterator<Item = ([Piece; 9], f64)>>(&mut self, iter: I) {
+        for (state, value) in iter {
+            self.insert(state, value);
+        }
+    }
+}
+
+impl BorshSerialize for StateTable {
+    /// Written as a map from position to value would be, with the positions in sorted order
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.len as u32).serialize(writer)?;
+        for (state, value) in self.iter() {
+            state.serialize(writer)?;
+            value.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for StateTable {
+    fn deserialize_reader<R: io::Read>(reader: &mut R) -> io::Result<StateTable> {
+        Ok(HashMap::<[Piece; 9], f64>::deserialize_reader(reader)?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_table() {
+        let mut corner = [Piece::Empty; 9];
+        corner[0] = Piece::X;
+        let mut table = StateTable::new();
+        assert_eq!(table.len(), 0);
+        assert_eq!(table.get(&corner), None);
+        assert_eq!(table.insert(corner, 0.25), None);
+        assert_eq!(table.insert([Piece::Empty; 9], 0.5), None);
+        assert_eq!(table.insert(corner, 0.75), Some(0.25));
+        assert_eq!(table.len(), 2);
+        assert_eq!(table[&corner], 0.75);
+        // Sorted by position
+        assert_eq!(table.keys().collect::<Vec<_>>(), vec![[Piece::Empty; 9], corner]);
+        table.retain(|state, _| *state != corner);
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.remove(&[Piece::Empty; 9]), Some(0.5));
+        assert_eq!(table.remove(&[Piece::Empty; 9]), None);
+        assert_eq!(table, StateTable::new());
+    }
+
+    #[test]
+    fn test_saved_like_a_map() {
+        let mut won = [Piece::X; 9];
+        won[4] = Piece::O;
+        let map = HashMap::from([([Piece::Empty; 9], 0.5), (won, 1.), ([Piece::O; 9], 0.)]);
+        let table: StateTable = map.clone().into_iter().collect();
+        let bytes = borsh::to_vec(&table).unwrap();
+        assert_eq!(bytes, borsh::to_vec(&map).unwrap());
+        assert_eq!(borsh::from_slice::<StateTable>(&bytes).unwrap(), table);
+    }
+}
@@ -1,109 +1,1914 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
 use std::path::{Path, PathBuf};
-use indicatif::ProgressBar;
-use crate::agents::players::Player;
-use crate::game::board::{Board, Piece};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use serde::{Serialize, Serializer};
+use crate::agents::agent::{Agent, AgentError};
+use crate::agents::health::{HealthThresholds, TableHealth, ValueDistribution};
+use crate::agents::players::{Learning, Player, PlayerError, TieBreak, UpdateMode};
+use crate::agents::snapshots::{write_snapshot, Snapshots};
+use crate::agents::state_table::StateTable;
+use crate::formatting::{count, duration, percent, rate};
+use crate::game::board::{Board, MoveLegality, Piece, PlayerPiece};
+use crate::game::result::GameResult;
 
-pub struct Trainer {
-    iteration: u32,
-}
+/// Trains a pair of players by having them play against each other
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// let mut player_x = Player::new(PlayerPiece::X, 0.75, 0.2, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+/// let mut player_o = Player::new(PlayerPiece::O, 0.75, 0.2, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+/// let out_directory = std::env::temp_dir().join("tictacrs_trainer_doc");
+/// std::fs::create_dir_all(&out_directory).unwrap();
+/// let report = Trainer::train(&mut player_x, &mut player_o, 10, &out_directory, false)
+///     .unwrap_or_else(|_| panic!("training failed"));
+/// assert_eq!(report.x_wins + report.o_wins + report.draws, 10);
+/// assert!(report.save_error.is_none() && report.player_x_path.exists() && report.player_o_path.exists());
+/// assert!(out_directory.join(TRAINING_REPORT_FILE).exists());
+/// std::fs::remove_dir_all(&out_directory).unwrap();
+/// ```
+pub struct Trainer;
 
 impl Trainer {
-    /// Given two players, train them and save the results into the out_directory,
-    /// returns a tuple of the player_x save data path, and the player_o save data path
+    /// Set both players to use the same tie break policy, useful for making training runs
+    /// reproducible by using a deterministic policy
+    pub fn set_tie_break(player1: &mut Player, player2: &mut Player, tie_break: TieBreak) {
+        player1.set_tie_break(tie_break);
+        player2.set_tie_break(tie_break);
+    }
+
+    /// Set both players to apply their value updates in the same way, see [`UpdateMode`]
+    pub fn set_update_mode(player1: &mut Player, player2: &mut Player, update_mode: UpdateMode) {
+        player1.set_update_mode(update_mode);
+        player2.set_update_mode(update_mode);
+    }
+
+    /// Given two players, train them and save the results into the out_directory, along with
+    /// a report of how training went (see [`TRAINING_REPORT_FILE`]), which is returned. If
+    /// saving the players fails the report is still written and returned, with the error in
+    /// [`TrainingReport::save_error`].
     pub fn train(player1: &mut Player,
                  player2: &mut Player,
                  iterations: u32,
                  out_directory: &Path,
                  progress_bar: bool,
-    ) -> Result<(PathBuf, PathBuf), TrainerError> {
-        let mut pbar: Option<ProgressBar> = None;
-        if progress_bar {
-            pbar = Some(ProgressBar::new(iterations as u64));
-        }
+    ) -> Result<TrainingReport, TrainerError> {
+        Self::train_with_callback(player1, player2, iterations, out_directory, progress_bar, |_| {})
+    }
+
+    /// Same as [`Trainer::train`], but calls `on_game` with a summary of every training game
+    /// after it's played
+    pub fn train_with_callback<F>(player1: &mut Player,
+                                  player2: &mut Player,
+                                  iterations: u32,
+                                  out_directory: &Path,
+                                  progress_bar: bool,
+                                  mut on_game: F,
+    ) -> Result<TrainingReport, TrainerError>
+    where
+        F: FnMut(GameSummary),
+    {
+        let phases = [TrainingPhase { learners: PhaseLearners::Both, iterations }];
+        let options = TrainingOptions { progress_bar, ..TrainingOptions::default() };
+        Self::train_phases(player1, player2, &phases, out_directory, options,
+                           |_, game| on_game(game), |_, _, _| {})
+    }
+
+    /// Train the players through a sequence of phases, during each of which only some of the
+    /// players learn. A player not learning in a phase is frozen (see [`Learning::Frozen`]) so it
+    /// still plays, greedily, but its values don't change. Afterwards, even if training failed,
+    /// each player's learning mode is restored. Training carries on from the players' iteration (the later of the two, if
+    /// they differ), so players loaded from earlier saves pick up where they left off, and each
+    /// game leaves them at the iteration of the next. Calls `on_game` with the phase index and
+    /// a summary of every training game after it's played; iterations carry on counting across
+    /// phases. With a health check in the
+    /// options, `on_health` is called with the number of games played, the piece and the
+    /// problem whenever one of the players' tables is flagged (see
+    /// [`ValueDistribution::health`]).
+    pub fn train_phases<F, H>(player1: &mut Player,
+                              player2: &mut Player,
+                              phases: &[TrainingPhase],
+                              out_directory: &Path,
+                              options: TrainingOptions,
+                              mut on_game: F,
+                              mut on_health: H,
+    ) -> Result<TrainingReport, TrainerError>
+    where
+        F: FnMut(usize, GameSummary),
+        H: FnMut(u32, PlayerPiece, TableHealth),
+    {
         if player1.get_player_piece() == player2.get_player_piece() {
             return Err(TrainerError::InvalidPlayers);
         }
-        let mut training_board: Board = Board::new();
-        for it in 0..iterations {
-            if let Some(ref bar) = pbar {
-                bar.inc(1);
+        let learning1 = player1.get_learning();
+        let learning2 = player2.get_learning();
+        let started = Instant::now();
+        let start = player1.get_iteration().max(player2.get_iteration());
+        if phases.iter().try_fold(start, |end, phase| end.checked_add(phase.iterations)).is_none() {
+            return Err(TrainerError::TooManyIterations);
+        }
+        let played = Self::play_phases(player1, player2, phases, out_directory, &options, &mut on_game,
+                                       &mut on_health);
+        // Whether or not every phase finished, frozen players learn again
+        player1.set_learning(learning1);
+        player2.set_learning(learning2);
+        let (phases, checkpoint_error) = played?;
+        let mut report = Self::finish_training(player1, player2, out_directory, phases, started);
+        report.checkpoint_error = checkpoint_error;
+        Ok(report)
+    }
+
+    /// Play the games of [`Trainer::train_phases`], freezing whichever player doesn't learn in
+    /// each phase, and return how each phase went, along with the first checkpoint which failed
+    /// to save (training carries on past it). Stops at any other error, leaving the players in
+    /// that phase's learning modes for the caller to restore.
+    fn play_phases<F, H>(player1: &mut Player,
+                         player2: &mut Player,
+                         phases: &[TrainingPhase],
+                         out_directory: &Path,
+                         options: &TrainingOptions,
+                         on_game: &mut F,
+                         on_health: &mut H,
+    ) -> Result<(Vec<PhaseReport>, Option<PlayerError>), TrainerError>
+    where
+        F: FnMut(usize, GameSummary),
+        H: FnMut(u32, PlayerPiece, TableHealth),
+    {
+        let mut pbar: Option<ProgressBar> = None;
+        if options.progress_bar {
+            let total: u64 = phases.iter().map(|phase| phase.iterations as u64).sum();
+            let bar = ProgressBar::new(total);
+            if options.checkpoint_every.is_some() {
+                // The default bar, with room to note the last checkpoint
+                bar.set_style(ProgressStyle::with_template("{wide_bar} {pos}/{len} {msg}")
+                    .expect("The template is valid"));
             }
-            training_board.clear_board();
-            // Update the players for the current iteration
-            player1.update_iteration(it);
-            player2.update_iteration(it);
-            // Variable to hold the previous board state, to show to loosing player
-            // in order to update their value function
-            let mut prev_board1: [Piece; 9] =
-                [
-                    Piece::Empty, Piece::Empty, Piece::Empty,
-                    Piece::Empty, Piece::Empty, Piece::Empty,
-                    Piece::Empty, Piece::Empty, Piece::Empty,
-                ];
-            let mut prev_board2: [Piece; 9] =
-                [
-                    Piece::Empty, Piece::Empty, Piece::Empty,
-                    Piece::Empty, Piece::Empty, Piece::Empty,
-                    Piece::Empty, Piece::Empty, Piece::Empty,
-                ];
-            loop {
-                // Get the first players move
-                let p1_move = player1.make_move(&training_board.get_compact_state());
-                training_board.make_auto_player_move(p1_move[0], p1_move[1], player1.get_player_piece());
-                // If there is some winner, end the iteration
-                if let Some(_) = training_board.check_winner() {
-                    // Since player1 must have won, show the previous board as a losing position
-                    // to player2
-                    player2.show_loosing_state(&prev_board2);
-                    break;
+            pbar = Some(bar);
+        }
+        let (learning1, learning2) = (player1.get_learning(), player2.get_learning());
+        let mut phase_reports = Vec::with_capacity(phases.len());
+        let mut it = player1.get_iteration().max(player2.get_iteration());
+        let mut checkpoint_error = None;
+        let mut game_moves: Vec<Transition> = Vec::with_capacity(9);
+        for (index, phase) in phases.iter().enumerate() {
+            for (player, learning) in [(&mut *player1, learning1), (&mut *player2, learning2)] {
+                if phase.learners.includes(player.get_player_piece()) {
+                    player.set_learning(learning);
+                } else {
+                    player.set_learning(Learning::Frozen);
+                }
+            }
+            let mut phase_report = PhaseReport::new(phase.learners);
+            let mut transitions = Transitions::new(player1, player2, it, phase.iterations)?
+                .early_draws(options.early_draws);
+            while let Some(transition) = transitions.next() {
+                game_moves.push(transition);
+                let Some(result) = transition.result else { continue };
+                if let Some(ref bar) = pbar {
+                    bar.inc(1);
                 }
-                if training_board.is_full() {
-                    break;
+                on_game(index, GameSummary::new(&game_moves, result));
+                game_moves.clear();
+                phase_report.record(result);
+                it += 1;
+                if let Some(check) = options.health_check {
+                    if check.every > 0 && it.is_multiple_of(check.every) {
+                        for player in [&*transitions.player1, &*transitions.player2] {
+                            let health = ValueDistribution::of_player(player).health(&check.thresholds);
+                            if health != TableHealth::Healthy {
+                                on_health(it, player.get_player_piece(), health);
+                            }
+                        }
+                    }
                 }
-                prev_board1 = training_board.get_compact_state();
-                // If the first player didn't win, get the second players move
-                let p2_move = player2.make_move(&training_board.get_compact_state());
-                training_board.make_auto_player_move(p2_move[0], p2_move[1], player2.get_player_piece());
-                if let Some(_) = training_board.check_winner() {
-                    // Since player2 must have won, show the previous board as a losing position
-                    // to player1
-                    player1.show_loosing_state(&prev_board1);
-                    break;
+                if let Some(ref snapshots) = options.snapshots {
+                    if snapshots.every > 0 && it.is_multiple_of(snapshots.every) {
+                        let (player_x, player_o) = match transitions.player1.get_player_piece() {
+                            PlayerPiece::X => { (&*transitions.player1, &*transitions.player2) }
+                            PlayerPiece::O => { (&*transitions.player2, &*transitions.player1) }
+                        };
+                        if let Err(e) = write_snapshot(&snapshots.directory, it, player_x, player_o) {
+                            return Err(TrainerError::FailedToSave(e));
+                        }
+                    }
                 }
-                if training_board.is_full() {
-                    break;
+                if let Some(every) = options.checkpoint_every {
+                    if every > 0 && it.is_multiple_of(every) {
+                        let mut checkpoint_failed = false;
+                        for player in [&*transitions.player1, &*transitions.player2] {
+                            let file = match player.get_player_piece() {
+                                PlayerPiece::X => { "player_x_save.ttr" }
+                                PlayerPiece::O => { "player_o_save.ttr" }
+                            };
+                            if let Err(e) = player.save_player_state(out_directory.join(file)) {
+                                checkpoint_failed = true;
+                                checkpoint_error.get_or_insert(e);
+                            }
+                        }
+                        if let Some(ref bar) = pbar {
+                            let saved = if checkpoint_failed { "failed" } else { "saved" };
+                            bar.set_message(format!("(checkpoint {} after {} games)", saved, count(it as u64)));
+                        }
+                    }
                 }
-                prev_board2 = training_board.get_compact_state();
+            }
+            phase_reports.push(phase_report);
+        }
+        Ok((phase_reports, checkpoint_error))
+    }
+
+    /// Train the players on several threads at once, splitting the `iterations` games between
+    /// worker threads (see [`ParallelOptions::threads`]). Each worker trains its own copies of
+    /// the players, seeded from them, through its share of the games, carrying on from the
+    /// players' iteration, and the tables the workers learn are then merged back into the
+    /// players (see [`MergePolicy`]). Seeded players trained on the same number of threads
+    /// learn the same tables on every run. The players are saved, along with a report, as in
+    /// [`Trainer::train`].
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+    /// let mut player_o = Player::builder(PlayerPiece::O).seed(2).build().unwrap();
+    /// let out_directory = std::env::temp_dir().join("tictacrs_trainer_parallel_doc");
+    /// std::fs::create_dir_all(&out_directory).unwrap();
+    /// let options = ParallelOptions { threads: 2, ..ParallelOptions::default() };
+    /// let report = Trainer::train_parallel(&mut player_x, &mut player_o, 100, &out_directory, options)
+    ///     .unwrap_or_else(|_| panic!("training failed"));
+    /// assert_eq!(report.x_wins + report.o_wins + report.draws, 100);
+    /// assert_eq!(player_x.get_iteration(), 100);
+    /// std::fs::remove_dir_all(&out_directory).unwrap();
+    /// ```
+    pub fn train_parallel(player1: &mut Player,
+                          player2: &mut Player,
+                          iterations: u32,
+                          out_directory: &Path,
+                          options: ParallelOptions,
+    ) -> Result<TrainingReport, TrainerError> {
+        if player1.get_player_piece() == player2.get_player_piece() {
+            return Err(TrainerError::InvalidPlayers);
+        }
+        let started = Instant::now();
+        let start = player1.get_iteration().max(player2.get_iteration());
+        let end = start.checked_add(iterations).ok_or(TrainerError::TooManyIterations)?;
+        let runs = Self::run_workers(player1, player2, start, iterations, &options)?;
+        let mut results = PhaseReport::new(PhaseLearners::Both);
+        for run in &runs {
+            results.add(&run.results);
+        }
+        let merged1 = options.merge.merge_tables(runs.iter().map(|run| (&run.player1, &run.visits[0])));
+        let merged2 = options.merge.merge_tables(runs.iter().map(|run| (&run.player2, &run.visits[1])));
+        player1.replace_learned_values(merged1);
+        player2.replace_learned_values(merged2);
+        player1.update_iteration(end);
+        player2.update_iteration(end);
+        Ok(Self::finish_training(player1, player2, out_directory, vec![results], started))
+    }
+
+    /// Split the games between the workers and train them, returning what each learned in the
+    /// order of the games they played
+    fn run_workers(player1: &mut Player, player2: &mut Player, start: u32, iterations: u32,
+                   options: &ParallelOptions) -> Result<Vec<WorkerRun>, TrainerError> {
+        let threads = match options.threads {
+            0 => { std::thread::available_parallelism().map_or(1, |threads| threads.get()) }
+            threads => { threads }
+        };
+        // No more workers than games, so every worker learns something
+        let workers = threads.min(iterations.max(1) as usize) as u32;
+        let mut jobs = Vec::with_capacity(workers as usize);
+        let mut first = start;
+        for worker in 0..workers {
+            let games = iterations / workers + u32::from(worker < iterations % workers);
+            let mut worker1 = player1.clone();
+            worker1.reseed(player1.next_seed());
+            let mut worker2 = player2.clone();
+            worker2.reseed(player2.next_seed());
+            jobs.push((worker1, worker2, first, games));
+            first += games;
+        }
+        let pool = match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => { pool }
+            Err(e) => { return Err(TrainerError::NoThreads(e.to_string())) }
+        };
+        let pbar = options.progress_bar.then(|| ProgressBar::new(iterations as u64));
+        Ok(pool.install(|| {
+            jobs.into_par_iter()
+                .map(|(worker1, worker2, first, games)| {
+                    WorkerRun::train(worker1, worker2, first, games, options.early_draws, pbar.as_ref())
+                })
+                .collect()
+        }))
+    }
+
+    /// Save the trained players and a report of how training went, which is returned
+    fn finish_training(player1: &Player, player2: &Player, out_directory: &Path, phases: Vec<PhaseReport>,
+                       started: Instant) -> TrainingReport {
+        let (player_x, player_o) = match player1.get_player_piece() {
+            PlayerPiece::X => { (player1, player2) }
+            PlayerPiece::O => { (player2, player1) }
+        };
+        let mut totals = PhaseReport::new(PhaseLearners::Both);
+        for phase in &phases {
+            totals.add(phase);
+        }
+        let mut report = TrainingReport {
+            iterations: totals.games,
+            x_wins: totals.x_wins,
+            o_wins: totals.o_wins,
+            draws: totals.draws,
+            phases,
+            player_x: PlayerSummary::of(player_x),
+            player_o: PlayerSummary::of(player_o),
+            elapsed: started.elapsed(),
+            player_x_path: out_directory.join("player_x_save.ttr"),
+            player_o_path: out_directory.join("player_o_save.ttr"),
+            save_error: None,
+            checkpoint_error: None,
+        };
+        report.save_error = Self::save_players(player_x, player_o, &report.player_x_path, &report.player_o_path).err();
+        let report_path = out_directory.join(TRAINING_REPORT_FILE);
+        if let Err(e) = fs::write(&report_path, report.to_json()) {
+            report.save_error.get_or_insert(PlayerError::invalid_file(report_path, e));
+        }
+        report
+    }
+
+    /// Play `iterations` training games between the players, player1 moving first, lazily
+    /// yielding every move made as a [`Transition`]. The players learn from the games exactly
+    /// as in [`Trainer::train`], carrying on from their iteration, but nothing is saved; seed the players (see
+    /// [`crate::agents::players::PlayerBuilder::seed`]) to make the games reproducible.
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+    /// let mut player_o = Player::builder(PlayerPiece::O).seed(2).build().unwrap();
+    /// let transitions = Trainer::transitions(&mut player_x, &mut player_o, 10)
+    ///     .unwrap_or_else(|_| panic!("invalid players"));
+    /// let games = transitions.filter(|transition| transition.result.is_some()).count();
+    /// assert_eq!(games, 10);
+    /// ```
+    pub fn transitions<'a>(player1: &'a mut Player,
+                           player2: &'a mut Player,
+                           iterations: u32,
+    ) -> Result<Transitions<'a>, TrainerError> {
+        if player1.get_player_piece() == player2.get_player_piece() {
+            return Err(TrainerError::InvalidPlayers);
+        }
+        let start = player1.get_iteration().max(player2.get_iteration());
+        Transitions::new(player1, player2, start, iterations)
+    }
+
+    /// Play one complete training game, player1 moving first, and return its moves and result.
+    /// The players learn from the game exactly as in [`Trainer::train`], at their current
+    /// iterations. If `early_draws` is set the game ends as soon as it's a dead draw (see
+    /// [`Transitions::early_draws`]).
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+    /// let mut player_o = Player::builder(PlayerPiece::O).seed(2).build().unwrap();
+    /// let record = Trainer::play_training_game(&mut player_x, &mut player_o, false)
+    ///     .unwrap_or_else(|_| panic!("invalid players"));
+    /// assert_eq!(record.moves.last().unwrap().result, Some(record.result));
+    /// ```
+    pub fn play_training_game(player1: &mut Player,
+                              player2: &mut Player,
+                              early_draws: bool,
+    ) -> Result<GameRecord, TrainerError> {
+        let iteration = player1.get_iteration();
+        Self::play_game_at(player1, player2, iteration, early_draws)
+    }
+
+    /// Play one complete game between any two agents, agent1 moving first, and return its
+    /// moves (all numbered as iteration 0) and result. Both agents are shown the result.
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// struct FirstEmpty(PlayerPiece);
+    ///
+    /// impl Agent for FirstEmpty {
+    ///     fn piece(&self) -> PlayerPiece {
+    ///         self.0
+    ///     }
+    ///
+    ///     fn choose_move(&mut self, state: &[Piece; 9]) -> Result<[u8; 2], AgentError> {
+    ///         let idx = state.iter().position(|piece| *piece == Piece::Empty).unwrap() as u8;
+    ///         Ok([idx / 3, idx % 3])
+    ///     }
+    /// }
+    ///
+    /// let mut player_o = Player::builder(PlayerPiece::O).seed(2).build().unwrap();
+    /// let record = Trainer::play_game(&mut FirstEmpty(PlayerPiece::X), &mut player_o, false)
+    ///     .unwrap_or_else(|_| panic!("invalid agents"));
+    /// assert_eq!(record.moves[0].action, [0, 0]);
+    /// ```
+    pub fn play_game(agent1: &mut dyn Agent,
+                     agent2: &mut dyn Agent,
+                     early_draws: bool,
+    ) -> Result<GameRecord, TrainerError> {
+        Self::play_game_at(agent1, agent2, 0, early_draws)
+    }
+
+    fn play_game_at(agent1: &mut dyn Agent,
+                    agent2: &mut dyn Agent,
+                    iteration: u32,
+                    early_draws: bool,
+    ) -> Result<GameRecord, TrainerError> {
+        if agent1.piece() == agent2.piece() {
+            return Err(TrainerError::InvalidPlayers);
+        }
+        agent1.start_game();
+        agent2.start_game();
+        let mut game = TrainingGame::new(early_draws);
+        let mut moves = Vec::new();
+        loop {
+            let transition = match game.step(agent1, agent2, iteration) {
+                Ok(transition) => { transition }
+                Err(e) => { return Err(TrainerError::NoMove(e)) }
+            };
+            moves.push(transition);
+            if let Some(result) = transition.result {
+                return Ok(GameRecord { moves, result });
             }
         }
+    }
 
-        // Save the players data to desired files
-        let player_x_file_path = out_directory.join("player_x_save.ttr");
-        let player_o_file_path = out_directory.join("player_o_save.ttr");
-        if player1.get_player_piece() == Piece::X {
-            match player1.save_player_state(&player_x_file_path) {
-                Ok(_) => {}
-                Err(_) => { return Err(TrainerError::FailedToSave) }
+    /// Play `games` games between the players to see how well they've learned, each making
+    /// its best move (as [`Learning::Frozen`], so nothing is learned and nothing explored), with
+    /// X moving first as in training. Afterwards each player's learning mode is restored.
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+    /// let mut player_o = Player::builder(PlayerPiece::O).seed(2).build().unwrap();
+    /// let report = Trainer::evaluate(&mut player_x, &mut player_o, 20)
+    ///     .unwrap_or_else(|_| panic!("invalid players"));
+    /// assert_eq!(report.x_wins + report.o_wins + report.draws, 20);
+    /// println!("{}", report);
+    /// ```
+    pub fn evaluate(player_a: &mut Player, player_b: &mut Player, games: u32) -> Result<EvalReport, TrainerError> {
+        let a_is_x = player_a.get_player_piece() == PlayerPiece::X;
+        Self::play_frozen(player_a, player_b, games, |_| a_is_x)
+    }
+
+    /// Play `games` games between two trained players, each making its best move as in
+    /// [`Trainer::evaluate`], with X moving first as usual. A player only knows the positions
+    /// of the piece it was trained with, so the players keep their pieces throughout. Reseed
+    /// the players (see [`Player::reseed`]) to make their choices between equally good moves
+    /// reproducible.
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+    /// let mut player_o = Player::builder(PlayerPiece::O).seed(2).build().unwrap();
+    /// let report = Trainer::duel(&mut player_x, &mut player_o, 10)
+    ///     .unwrap_or_else(|_| panic!("invalid players"));
+    /// assert_eq!(report.x_wins + report.o_wins + report.draws, 10);
+    /// ```
+    pub fn duel(player_a: &mut Player, player_b: &mut Player, games: u32) -> Result<EvalReport, TrainerError> {
+        let a_is_x = player_a.get_player_piece() == PlayerPiece::X;
+        Self::play_frozen(player_a, player_b, games, |_| a_is_x)
+    }
+
+    /// Play frozen games between the players, `a_first` deciding for each game whether
+    /// player_a moves first, and report how they went
+    fn play_frozen<F>(player_a: &mut Player, player_b: &mut Player, games: u32, a_first: F)
+        -> Result<EvalReport, TrainerError>
+    where
+        F: Fn(u32) -> bool,
+    {
+        if player_a.get_player_piece() == player_b.get_player_piece() {
+            return Err(TrainerError::InvalidPlayers);
+        }
+        let learning_a = player_a.get_learning();
+        let learning_b = player_b.get_learning();
+        player_a.set_learning(Learning::Frozen);
+        player_b.set_learning(Learning::Frozen);
+        let mut report = EvalReport { games, ..EvalReport::default() };
+        let mut moves = 0;
+        let mut positions = HashSet::new();
+        for game in 0..games {
+            let (first, second) = if a_first(game) {
+                (&mut *player_a, &mut *player_b)
+            } else {
+                (&mut *player_b, &mut *player_a)
             };
-            match player2.save_player_state(&player_o_file_path) {
-                Ok(_) => {}
-                Err(_) => { return Err(TrainerError::FailedToSave) }
-            }
-        } else {
-            match player2.save_player_state(&player_x_file_path) {
-                Ok(_) => {}
-                Err(_) => { return Err(TrainerError::FailedToSave) }
+            let iteration = first.get_iteration();
+            let record = match Self::play_game_at(first, second, iteration, false) {
+                Ok(record) => { record }
+                Err(e) => {
+                    player_a.set_learning(learning_a);
+                    player_b.set_learning(learning_b);
+                    return Err(e);
+                }
             };
-            match player1.save_player_state(&player_o_file_path) {
-                Ok(_) => {}
-                Err(_) => { return Err(TrainerError::FailedToSave) }
+            moves += record.moves.len();
+            for transition in &record.moves {
+                positions.insert(transition.state);
+                positions.insert(transition.next_state);
+            }
+            match record.result.winner() {
+                Some(PlayerPiece::X) => { report.x_wins += 1 }
+                Some(PlayerPiece::O) => { report.o_wins += 1 }
+                None => { report.draws += 1 }
+            }
+        }
+        player_a.set_learning(learning_a);
+        player_b.set_learning(learning_b);
+        report.average_length = if games > 0 { moves as f64 / games as f64 } else { 0. };
+        report.distinct_states = positions.len();
+        Ok(report)
+    }
+
+    /// Save both players, trying the second even if the first fails, and returning the first
+    /// error
+    fn save_players(player_x: &Player, player_o: &Player, player_x_path: &Path, player_o_path: &Path)
+        -> Result<(), PlayerError> {
+        let saved_x = player_x.save_player_state(player_x_path);
+        let saved_o = player_o.save_player_state(player_o_path);
+        saved_x.and(saved_o)
+    }
+}
+
+/// Name of the report written alongside the trained players, see [`TrainingReport`]
+pub const TRAINING_REPORT_FILE: &str = "training_report.json";
+
+/// How a training run went, returned by [`Trainer::train_phases`] and saved as JSON alongside
+/// the players (see [`TRAINING_REPORT_FILE`])
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TrainingReport {
+    /// Number of training games played
+    pub iterations: u32,
+    pub x_wins: u32,
+    pub o_wins: u32,
+    pub draws: u32,
+    /// How the games of each phase went, in order, a single phase if trained without phases
+    pub phases: Vec<PhaseReport>,
+    pub player_x: PlayerSummary,
+    pub player_o: PlayerSummary,
+    /// How long the training games took, not counting saving
+    #[serde(rename = "elapsed_seconds", serialize_with = "serialize_seconds")]
+    pub elapsed: Duration,
+    /// Where the players were saved, or would have been if saving failed
+    pub player_x_path: PathBuf,
+    pub player_o_path: PathBuf,
+    /// Why saving the players or the report failed, if it did
+    #[serde(serialize_with = "serialize_error")]
+    pub save_error: Option<PlayerError>,
+    /// Why the first checkpoint which failed to save did (see
+    /// [`TrainingOptions::checkpoint_every`]), if one did. Training carries on regardless.
+    #[serde(serialize_with = "serialize_error")]
+    pub checkpoint_error: Option<PlayerError>,
+}
+
+/// How the games of one training phase went, see [`TrainingReport::phases`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PhaseReport {
+    /// The players which learned during the phase
+    pub learners: PhaseLearners,
+    /// Number of games played during the phase
+    pub games: u32,
+    pub x_wins: u32,
+    pub o_wins: u32,
+    pub draws: u32,
+}
+
+impl PhaseReport {
+    fn new(learners: PhaseLearners) -> PhaseReport {
+        PhaseReport { learners, games: 0, x_wins: 0, o_wins: 0, draws: 0 }
+    }
+
+    /// Count a game with the given result
+    fn record(&mut self, result: GameResult) {
+        self.games += 1;
+        match result.winner() {
+            Some(PlayerPiece::X) => { self.x_wins += 1 }
+            Some(PlayerPiece::O) => { self.o_wins += 1 }
+            None => { self.draws += 1 }
+        }
+    }
+
+    /// Count the games of another report too
+    fn add(&mut self, other: &PhaseReport) {
+        self.games += other.games;
+        self.x_wins += other.x_wins;
+        self.o_wins += other.o_wins;
+        self.draws += other.draws;
+    }
+}
+
+impl fmt::Display for PhaseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: X won {}, O won {}, {} draws", self.learners, self.games, count(self.x_wins as u64),
+               count(self.o_wins as u64), count(self.draws as u64))
+    }
+}
+
+/// The state of one of the players at the end of training, see [`TrainingReport`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PlayerSummary {
+    /// The rates at the final iteration
+    pub exploration_rate: f64,
+    pub learning_rate: f64,
+    /// Number of positions in the player's table
+    pub states: usize,
+}
+
+impl PlayerSummary {
+    fn of(player: &Player) -> PlayerSummary {
+        PlayerSummary {
+            exploration_rate: player.get_exploration_rate(),
+            learning_rate: player.get_learning_rate(),
+            states: player.state_count(),
+        }
+    }
+}
+
+impl TrainingReport {
+    /// The report as written to [`TRAINING_REPORT_FILE`]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("The report only holds numbers, paths and text")
+    }
+
+    /// The paths the players were saved to, X then O, or the error if saving failed
+    pub fn into_paths(self) -> Result<(PathBuf, PathBuf), TrainerError> {
+        match self.save_error {
+            None => { Ok((self.player_x_path, self.player_o_path)) }
+            Some(e) => { Err(TrainerError::FailedToSave(e)) }
+        }
+    }
+}
+
+impl fmt::Display for TrainingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let share = |games: u32| percent(games as f64 / self.iterations.max(1) as f64);
+        write!(f, "Trained {} games in {} ({} games/s): X won {} ({}), O won {} ({}), {} draws ({})",
+               count(self.iterations as u64), duration(self.elapsed),
+               rate(self.iterations as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)),
+               count(self.x_wins as u64), share(self.x_wins), count(self.o_wins as u64), share(self.o_wins),
+               count(self.draws as u64), share(self.draws))?;
+        if self.phases.len() > 1 {
+            for phase in &self.phases {
+                write!(f, "\nPhase {}", phase)?;
             }
         }
-        Ok((player_x_file_path, player_o_file_path))
+        for (piece, summary) in [(PlayerPiece::X, &self.player_x), (PlayerPiece::O, &self.player_o)] {
+            write!(f, "\nPlayer {}: {} positions, exploring {}, learning rate {}", piece,
+                   count(summary.states as u64), percent(summary.exploration_rate), rate(summary.learning_rate))?;
+        }
+        if let Some(ref e) = self.checkpoint_error {
+            write!(f, "\nWarning: a checkpoint failed to save: {}", e)?;
+        }
+        Ok(())
+    }
+}
+
+fn serialize_seconds<S: Serializer>(elapsed: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(elapsed.as_secs_f64())
+}
+
+fn serialize_error<S: Serializer>(error: &Option<PlayerError>, serializer: S) -> Result<S::Ok, S::Error> {
+    error.as_ref().map(|e| e.to_string()).serialize(serializer)
+}
+
+/// Settings for [`Trainer::train_phases`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TrainingOptions {
+    /// Whether to show a progress bar
+    pub progress_bar: bool,
+    /// Whether games end as soon as they're dead draws (see [`Transitions::early_draws`])
+    pub early_draws: bool,
+    /// How often to check the players' tables for signs training has gone wrong, if at all
+    pub health_check: Option<HealthCheck>,
+    /// How often to save snapshots of both players' tables, and where, if at all
+    pub snapshots: Option<Snapshots>,
+    /// Number of games between checkpoints, if any, when both players are saved over their
+    /// save files in the output directory. Each file is replaced atomically (see
+    /// [`Player::save_player_state`]), so an interrupted run can be resumed from
+    /// the last checkpoint. A checkpoint which fails to save doesn't stop training, see
+    /// [`TrainingReport::checkpoint_error`].
+    pub checkpoint_every: Option<u32>,
+}
+
+/// A periodic check of the players' tables during training, see [`Trainer::train_phases`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthCheck {
+    /// Number of games between checks
+    pub every: u32,
+    /// When a table is flagged
+    pub thresholds: HealthThresholds,
+}
+
+/// A single move made during training, see [`Trainer::transitions`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    /// The training game the move was made in
+    pub iteration: u32,
+    /// The piece of the player which moved
+    pub mover: PlayerPiece,
+    /// The position moved from
+    pub state: [Piece; 9],
+    /// The row and column moved to
+    pub action: [u8; 2],
+    /// The position after the move
+    pub next_state: [Piece; 9],
+    /// Whether the move was exploratory, rather than the best move the player knew of
+    pub exploratory: bool,
+    /// The mover's value of the position after the move, if it's a player which keeps one
+    /// (see [`Agent::last_decision`])
+    pub value: Option<f64>,
+    /// The result of the game, if the move ended it
+    pub result: Option<GameResult>,
+}
+
+/// The moves and result of a complete training game, see [`Trainer::play_training_game`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    /// Every move made, in order, the last carrying the result
+    pub moves: Vec<Transition>,
+    pub result: GameResult,
+}
+
+impl GameRecord {
+    /// Summary of the game, as passed to the callbacks of [`Trainer::train_phases`]
+    pub fn summary(&self) -> GameSummary {
+        GameSummary::new(&self.moves, self.result)
+    }
+}
+
+/// How the games played by [`Trainer::evaluate`] or [`Trainer::duel`] went
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EvalReport {
+    /// Number of games played
+    pub games: u32,
+    pub x_wins: u32,
+    pub o_wins: u32,
+    pub draws: u32,
+    /// Mean number of moves in a game
+    pub average_length: f64,
+    /// Number of different positions reached in any of the games, including the empty board
+    pub distinct_states: usize,
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let share = |games: u32| percent(games as f64 / self.games.max(1) as f64);
+        write!(f, "{} games: X won {} ({}), O won {} ({}), {} draws ({}), {:.1} moves on average, {} \
+                   different positions", count(self.games as u64), count(self.x_wins as u64), share(self.x_wins),
+               count(self.o_wins as u64), share(self.o_wins), count(self.draws as u64), share(self.draws),
+               self.average_length, count(self.distinct_states as u64))
+    }
+}
+
+/// The state of a training game in progress, shared by [`Trainer::play_training_game`] and
+/// [`Transitions`]
+struct TrainingGame {
+    board: Board,
+    /// Whether the game ends as soon as it's a dead draw
+    early_draws: bool,
+}
+
+impl TrainingGame {
+    fn new(early_draws: bool) -> TrainingGame {
+        TrainingGame {
+            board: Board::new(),
+            early_draws,
+        }
+    }
+
+    /// Have whichever player's turn it is move, showing the players the result if the move
+    /// ends the game. Fails without changing the board if the mover chooses a square which
+    /// can't be played. Must not be called once the game is over.
+    fn step(&mut self, player1: &mut dyn Agent, player2: &mut dyn Agent, iteration: u32)
+        -> Result<Transition, AgentError> {
+        // Player1 moves first, so it's their move whenever the board has an even number of pieces
+        let state = self.board.get_compact_state();
+        let player1_moves = state.iter().filter(|piece| **piece != Piece::Empty).count() % 2 == 0;
+        let mover: &mut dyn Agent = if player1_moves { &mut *player1 } else { &mut *player2 };
+        let action = mover.choose_move(&state)?;
+        match self.board.move_legality(action[0] as usize, action[1] as usize) {
+            MoveLegality::Legal => {}
+            legality => { return Err(AgentError::IllegalMove(action, legality)) }
+        }
+        let exploratory = mover.last_move_was_exploratory();
+        let value = mover.last_decision().map(|decision| decision.value);
+        let mover_piece = mover.piece();
+        self.board.make_auto_player_move(action[0], action[1], mover_piece);
+        let next_state = self.board.get_compact_state();
+        let result = if self.early_draws { self.board.early_result() } else { self.board.result() };
+        if let Some(result) = result {
+            // Both players back up the result into the position their last move reached
+            player1.observe_result(&next_state, result.for_piece(player1.piece()));
+            player2.observe_result(&next_state, result.for_piece(player2.piece()));
+        }
+        Ok(Transition {
+            iteration,
+            mover: mover_piece,
+            state,
+            action,
+            next_state,
+            exploratory,
+            value,
+            result,
+        })
+    }
+}
+
+/// Iterator over the moves of a series of training games, created by
+/// [`Trainer::transitions`]
+pub struct Transitions<'a> {
+    player1: &'a mut Player,
+    player2: &'a mut Player,
+    game: TrainingGame,
+    /// The game currently being played
+    iteration: u32,
+    /// The iteration after the last game
+    end: u32,
+    /// Whether the next move is the first of a game
+    new_game: bool,
+}
+
+impl<'a> Transitions<'a> {
+    /// Iterate over `iterations` games, numbered from `start`, failing if the last one's
+    /// number doesn't fit in the players' iteration count
+    fn new(player1: &'a mut Player, player2: &'a mut Player, start: u32,
+           iterations: u32) -> Result<Transitions<'a>, TrainerError> {
+        Ok(Transitions {
+            player1,
+            player2,
+            game: TrainingGame::new(false),
+            iteration: start,
+            end: start.checked_add(iterations).ok_or(TrainerError::TooManyIterations)?,
+            new_game: true,
+        })
+    }
+
+    /// End games as draws as soon as neither player can win any more (see
+    /// [`Board::is_dead_draw`]) rather than playing them out, saving the remaining moves. Both
+    /// players back up a draw from it.
+    pub fn early_draws(mut self, early_draws: bool) -> Transitions<'a> {
+        self.game.early_draws = early_draws;
+        self
+    }
+}
+
+impl Iterator for Transitions<'_> {
+    type Item = Transition;
+
+    fn next(&mut self) -> Option<Transition> {
+        if self.iteration >= self.end {
+            return None;
+        }
+        if self.new_game {
+            // Update the players for the current iteration
+            self.player1.update_iteration(self.iteration);
+            self.player2.update_iteration(self.iteration);
+            self.player1.start_new_game();
+            self.player2.start_new_game();
+            self.game = TrainingGame::new(self.game.early_draws);
+            self.new_game = false;
+        }
+        let transition = self.game.step(self.player1, self.player2, self.iteration)
+            .expect("Players only choose empty squares, and training games stop once the game is over");
+        if transition.result.is_some() {
+            // Move the players on, so training resumed from them starts at the next game
+            self.iteration += 1;
+            self.player1.update_iteration(self.iteration);
+            self.player2.update_iteration(self.iteration);
+            self.new_game = true;
+        }
+        Some(transition)
     }
 }
 
+/// Settings for [`Trainer::train_parallel`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParallelOptions {
+    /// Number of worker threads, 0 for one per core. The games are split between the workers
+    /// by their number, so it has to stay the same for a run to be reproducible.
+    pub threads: usize,
+    /// How the tables the workers learn are combined
+    pub merge: MergePolicy,
+    /// Whether to show a progress bar
+    pub progress_bar: bool,
+    /// Whether games end as soon as they're dead draws (see [`Transitions::early_draws`])
+    pub early_draws: bool,
+}
+
+/// How [`Trainer::train_parallel`] combines the values its workers learn for each position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePolicy {
+    /// The mean of the workers' values
+    Mean,
+    /// The workers' values weighted by how often each moved into the position, so those which
+    /// learned the most about it count the most. Positions no worker moved into (only valued
+    /// before training, or through symmetry) take the mean.
+    #[default]
+    VisitWeighted,
+}
+
+impl MergePolicy {
+    /// Combine the values the workers have for one position, each with the number of times
+    /// that worker's player moved into it
+    pub fn merge(&self, values: &[(f64, u32)]) -> f64 {
+        let visits: u64 = values.iter().map(|(_, visits)| *visits as u64).sum();
+        match self {
+            MergePolicy::VisitWeighted if visits > 0 => {
+                values.iter().map(|(value, visits)| value * *visits as f64).sum::<f64>() / visits as f64
+            }
+            _ => { values.iter().map(|(value, _)| value).sum::<f64>() / values.len() as f64 }
+        }
+    }
+
+    /// Merge the workers' tables, each given with its player's visit counts
+    fn merge_tables<'a>(&self, workers: impl Iterator<Item = (&'a Player, &'a HashMap<[Piece; 9], u32>)>)
+        -> StateTable {
+        let mut values: HashMap<[Piece; 9], Vec<(f64, u32)>> = HashMap::new();
+        for (player, visits) in workers {
+            for (state, value) in player.iter_states() {
+                values.entry(state).or_default().push((value, visits.get(&state).copied().unwrap_or(0)));
+            }
+        }
+        values.into_iter().map(|(state, values)| (state, self.merge(&values))).collect()
+    }
+}
+
+/// What one of [`Trainer::train_parallel`]'s workers learned
+struct WorkerRun {
+    player1: Player,
+    player2: Player,
+    /// How many times each player moved into each position, by the key it's stored under
+    visits: [HashMap<[Piece; 9], u32>; 2],
+    /// How the worker's games went
+    results: PhaseReport,
+}
+
+impl WorkerRun {
+    /// Train the worker's players through `games` games numbered from `first`
+    fn train(mut player1: Player, mut player2: Player, first: u32, games: u32, early_draws: bool,
+             pbar: Option<&ProgressBar>) -> WorkerRun {
+        let piece1 = player1.get_player_piece();
+        let mut moved_into: [HashMap<[Piece; 9], u32>; 2] = [HashMap::new(), HashMap::new()];
+        let mut results = PhaseReport::new(PhaseLearners::Both);
+        let transitions = Transitions::new(&mut player1, &mut player2, first, games)
+            .expect("The games were checked to fit in the iteration count");
+        for transition in transitions.early_draws(early_draws) {
+            let mover = if transition.mover == piece1 { 0 } else { 1 };
+            *moved_into[mover].entry(transition.next_state).or_insert(0) += 1;
+            if let Some(result) = transition.result {
+                results.record(result);
+                if let Some(bar) = pbar {
+                    bar.inc(1);
+                }
+            }
+        }
+        let visits = [Self::by_key(&player1, &moved_into[0]), Self::by_key(&player2, &moved_into[1])];
+        WorkerRun { player1, player2, visits, results }
+    }
+
+    /// Visits to positions counted under the keys the player stores them by, which combines
+    /// symmetric positions for a symmetric player
+    fn by_key(player: &Player, moved_into: &HashMap<[Piece; 9], u32>) -> HashMap<[Piece; 9], u32> {
+        let mut visits = HashMap::new();
+        for (state, count) in moved_into {
+            *visits.entry(player.table_key(state)).or_insert(0) += count;
+        }
+        visits
+    }
+}
+
+/// Which of the players learn during a [`TrainingPhase`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PhaseLearners {
+    /// Both players learn
+    Both,
+    /// Only the player using X learns, O is frozen
+    XOnly,
+    /// Only the player using O learns, X is frozen
+    OOnly,
+}
+
+impl PhaseLearners {
+    /// Whether the player using the given piece learns
+    pub fn includes(&self, piece: PlayerPiece) -> bool {
+        match self {
+            PhaseLearners::Both => { true }
+            PhaseLearners::XOnly => { piece == PlayerPiece::X }
+            PhaseLearners::OOnly => { piece == PlayerPiece::O }
+        }
+    }
+}
+
+impl fmt::Display for PhaseLearners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhaseLearners::Both => { write!(f, "both") }
+            PhaseLearners::XOnly => { write!(f, "x-only") }
+            PhaseLearners::OOnly => { write!(f, "o-only") }
+        }
+    }
+}
+
+/// A stretch of training games, see [`Trainer::train_phases`]
+///
+/// ```
+/// use tictacrs::agents::trainer::{PhaseLearners, TrainingPhase};
+///
+/// let phases = TrainingPhase::parse_list("both:5000,x-only:2000").unwrap();
+/// assert_eq!(phases, vec![
+///     TrainingPhase { learners: PhaseLearners::Both, iterations: 5000 },
+///     TrainingPhase { learners: PhaseLearners::XOnly, iterations: 2000 },
+/// ]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrainingPhase {
+    /// The players which learn during the phase
+    pub learners: PhaseLearners,
+    /// Number of games played during the phase
+    pub iterations: u32,
+}
+
+impl TrainingPhase {
+    /// Parse a comma separated list of phases, e.g. "both:5000,x-only:5000,o-only:5000"
+    pub fn parse_list(phases: &str) -> Result<Vec<TrainingPhase>, TrainerError> {
+        phases.split(',').map(|phase| phase.parse()).collect()
+    }
+}
+
+impl fmt::Display for TrainingPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.learners, self.iterations)
+    }
+}
+
+impl FromStr for TrainingPhase {
+    type Err = TrainerError;
+
+    /// Parse a single phase, the learners (both, x-only or o-only) and a number of games
+    /// separated by a colon
+    fn from_str(s: &str) -> Result<TrainingPhase, TrainerError> {
+        let (learners, iterations) = match s.trim().split_once(':') {
+            Some(parts) => { parts }
+            None => { return Err(TrainerError::InvalidPhases) }
+        };
+        let learners = match learners.trim().to_ascii_lowercase().as_str() {
+            "both" => { PhaseLearners::Both }
+            "x-only" => { PhaseLearners::XOnly }
+            "o-only" => { PhaseLearners::OOnly }
+            _ => { return Err(TrainerError::InvalidPhases) }
+        };
+        match iterations.trim().parse::<u32>() {
+            Ok(iterations) if iterations > 0 => { Ok(TrainingPhase { learners, iterations }) }
+            _ => { Err(TrainerError::InvalidPhases) }
+        }
+    }
+}
+
+/// How one training game went, see [`Trainer::train_phases`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameSummary {
+    /// The training game's iteration
+    pub iteration: u32,
+    pub result: GameResult,
+    /// Number of moves made
+    pub moves: u32,
+    /// Number of exploratory moves made by X, then by O
+    pub exploratory_moves: [u32; 2],
+}
+
+impl GameSummary {
+    /// Summarise a game from its moves
+    fn new(moves: &[Transition], result: GameResult) -> GameSummary {
+        let mut exploratory_moves = [0; 2];
+        for transition in moves.iter().filter(|transition| transition.exploratory) {
+            match transition.mover {
+                PlayerPiece::X => { exploratory_moves[0] += 1 }
+                PlayerPiece::O => { exploratory_moves[1] += 1 }
+            }
+        }
+        GameSummary {
+            iteration: moves.first().map_or(0, |transition| transition.iteration),
+            result,
+            moves: moves.len() as u32,
+            exploratory_moves,
+        }
+    }
+
+    /// Number of exploratory moves made by the given piece
+    pub fn exploratory_moves_by(&self, piece: PlayerPiece) -> u32 {
+        match piece {
+            PlayerPiece::X => { self.exploratory_moves[0] }
+            PlayerPiece::O => { self.exploratory_moves[1] }
+        }
+    }
+}
+
+/// Errors produced while training
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// // Both players using the same piece can't be trained together
+/// let mut player1 = Player::new(PlayerPiece::X, 0.75, 0.2, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+/// let mut player2 = Player::new(PlayerPiece::X, 0.75, 0.2, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+/// let res = Trainer::train(&mut player1, &mut player2, 10, &std::env::temp_dir(), false);
+/// assert!(matches!(res, Err(TrainerError::InvalidPlayers)));
+/// ```
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum TrainerError {
-    FailedToSave,
+    /// Saving the players (or a snapshot of them) failed
+    FailedToSave(PlayerError),
+    /// Both players play the same piece
     InvalidPlayers,
-}
\ No newline at end of file
+    /// A training phase couldn't be parsed
+    InvalidPhases,
+    /// One of the agents couldn't choose a move
+    NoMove(AgentError),
+    /// The worker threads for parallel training couldn't be started
+    NoThreads(String),
+    /// Training would take the players' iteration past the largest it can count to
+    TooManyIterations,
+}
+
+impl fmt::Display for TrainerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrainerError::FailedToSave(e) => { write!(f, "couldn't save the trained players: {}", e) }
+            TrainerError::InvalidPlayers => { write!(f, "the two players must play different pieces") }
+            TrainerError::InvalidPhases => {
+                write!(f, "phases must be both, x-only or o-only and a number of games, e.g. both:1000")
+            }
+            TrainerError::NoMove(e) => { write!(f, "an agent couldn't move: {}", e) }
+            TrainerError::NoThreads(reason) => { write!(f, "couldn't start the training threads: {}", reason) }
+            TrainerError::TooManyIterations => {
+                write!(f, "training would take the players past game {}", count(u32::MAX as u64))
+            }
+        }
+    }
+}
+
+impl Error for TrainerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TrainerError::FailedToSave(e) => { Some(e) }
+            TrainerError::NoMove(e) => { Some(e) }
+            _ => { None }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agents::agent::{Agent, AgentError};
+    use crate::agents::annealing::AnnealingSchedule;
+    use crate::agents::certify::certify;
+    use crate::agents::players::{Learning, Player, PlayerError, TieBreak};
+    use crate::agents::health::{HealthThresholds, TableHealth};
+    use crate::agents::snapshots::{read_index, state_history, Snapshots};
+    use crate::agents::evaluate::evaluate;
+    use crate::agents::random::RandomAgent;
+    use crate::agents::trainer::{EvalReport, GameSummary, HealthCheck, MergePolicy, ParallelOptions, PhaseLearners, Trainer,
+                                 TrainerError, TrainingOptions, TrainingPhase, Transition, TRAINING_REPORT_FILE};
+    use crate::game::board::{Board, MoveLegality, Piece};
+    use crate::game::board::PlayerPiece;
+    use crate::game::result::{GameOutcomeFor, GameResult};
+    use crate::game::threats::winning_squares;
+    use std::collections::HashSet;
+    use std::error::Error;
+
+    #[test]
+    fn test_train_callback() {
+        // The first player always moves first, so without exploration and with both players
+        // taking the first empty square, O completes the diagonal a3-c1
+        let mut player_x = Player::new(PlayerPiece::X, 0.5, 0., AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        let mut player_o = Player::new(PlayerPiece::O, 0.5, 0., AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        Trainer::set_tie_break(&mut player_o, &mut player_x, TieBreak::FirstIndex);
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_callback");
+        std::fs::create_dir_all(&out_directory).unwrap();
+        let mut results = Vec::new();
+        let trained = Trainer::train_with_callback(&mut player_o, &mut player_x, 1, &out_directory,
+                                                   false, |game| results.push(game));
+        assert!(trained.is_ok());
+        assert_eq!(results, vec![GameSummary { iteration: 0, result: GameResult::Win(PlayerPiece::O), moves: 7,
+                                               exploratory_moves: [0, 0] }]);
+        assert_eq!(results[0].result.for_piece(PlayerPiece::X), GameOutcomeFor::Loss);
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_exploratory_move_counts() {
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_exploratory_counts");
+        std::fs::create_dir_all(&out_directory).unwrap();
+        for (rate, seed) in [(0., 1), (1., 3)] {
+            let mut player_x = Player::builder(PlayerPiece::X).exploration_rate(rate).seed(seed).build().unwrap();
+            let mut player_o = Player::builder(PlayerPiece::O).exploration_rate(rate).seed(seed + 1).build().unwrap();
+            let mut games = Vec::new();
+            Trainer::train_with_callback(&mut player_x, &mut player_o, 50, &out_directory, false,
+                                         |game| games.push(game)).unwrap_or_else(|_| panic!("training failed"));
+            assert_eq!(games.len(), 50);
+            for game in games {
+                let exploratory = game.exploratory_moves_by(PlayerPiece::X) + game.exploratory_moves_by(PlayerPiece::O);
+                // Never exploring marks no move, always exploring marks every one
+                assert_eq!(exploratory, if rate == 0. { 0 } else { game.moves });
+            }
+        }
+        // The moves record the values the players saw, and a game's record summarises it
+        let mut player_x = Player::builder(PlayerPiece::X).seed(5).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(6).build().unwrap();
+        let record = Trainer::play_training_game(&mut player_x, &mut player_o, false).unwrap();
+        assert!(record.moves.iter().all(|t| t.value.is_some_and(|value| (0. ..=1.).contains(&value))));
+        let summary = record.summary();
+        assert_eq!((summary.result, summary.moves), (record.result, record.moves.len() as u32));
+        let mut random = RandomAgent::new(PlayerPiece::O, 1);
+        let record = Trainer::play_game(&mut player_x, &mut random, false).unwrap();
+        assert!(record.moves.iter().all(|t| t.value.is_some() == (t.mover == PlayerPiece::X)));
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(7).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(8).build().unwrap();
+        let transitions: Vec<Transition> = Trainer::transitions(&mut player_x, &mut player_o, 50)
+            .unwrap().collect();
+        let games: Vec<&[Transition]> = transitions.split_inclusive(|t| t.result.is_some()).collect();
+        assert_eq!(games.len(), 50);
+        for (iteration, game) in games.iter().enumerate() {
+            let last = game[game.len() - 1];
+            // One transition per move, with only the last carrying the result
+            let moves = last.next_state.iter().filter(|piece| **piece != Piece::Empty).count();
+            assert_eq!(game.len(), moves);
+            assert_eq!(game[0].state, [Piece::Empty; 9]);
+            // Replaying the moves gives the same result
+            let mut board = Board::new();
+            for transition in game.iter() {
+                board.make_auto_player_move(transition.action[0], transition.action[1], transition.mover);
+            }
+            assert!(last.result.is_some());
+            assert_eq!(last.result, board.result());
+            for (idx, transition) in game.iter().enumerate() {
+                assert_eq!(transition.iteration, iteration as u32);
+                let mover = if idx % 2 == 0 { PlayerPiece::X } else { PlayerPiece::O };
+                assert_eq!(transition.mover, mover);
+                let mut expected = transition.state;
+                expected[(transition.action[0] * 3 + transition.action[1]) as usize] = mover.into();
+                assert_eq!(transition.next_state, expected);
+                if let Some(next) = game.get(idx + 1) {
+                    assert_eq!(transition.next_state, next.state);
+                    assert_eq!(transition.result, None);
+                }
+            }
+        }
+        // Exploring 20% of the time, some of the moves are exploratory
+        assert!(transitions.iter().any(|t| t.exploratory));
+        assert!(transitions.iter().any(|t| !t.exploratory));
+        let mut player_x2 = Player::builder(PlayerPiece::X).build().unwrap();
+        assert!(matches!(Trainer::transitions(&mut player_x, &mut player_x2, 1),
+                         Err(TrainerError::InvalidPlayers)));
+    }
+
+    #[test]
+    fn test_play_training_game() {
+        // Without exploring, and taking the first empty square, the first player to move
+        // completes the diagonal a3-c1
+        for (first, second) in [(PlayerPiece::X, PlayerPiece::O), (PlayerPiece::O, PlayerPiece::X)] {
+            let mut player1 = Player::builder(first).exploration_rate(0.).tie_break(TieBreak::FirstIndex)
+                .build().unwrap();
+            let mut player2 = Player::builder(second).exploration_rate(0.).tie_break(TieBreak::FirstIndex)
+                .build().unwrap();
+            let record = Trainer::play_training_game(&mut player1, &mut player2, false).unwrap();
+            assert_eq!(record.result, GameResult::Win(first));
+            let actions: Vec<[u8; 2]> = record.moves.iter().map(|t| t.action).collect();
+            assert_eq!(actions, vec![[0, 0], [0, 1], [0, 2], [1, 0], [1, 1], [1, 2], [2, 0]]);
+            // The loser backed the loss up into the position after its last move
+            assert_eq!(player2.peek_state_value(&record.moves[5].next_state), 0.125);
+        }
+        // Exploring players reach draws too, and every record replays to its result
+        let mut player_x = Player::builder(PlayerPiece::X).exploration_rate(1.).seed(5).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).exploration_rate(1.).seed(6).build().unwrap();
+        let mut results = HashSet::new();
+        for _ in 0..50 {
+            let record = Trainer::play_training_game(&mut player_x, &mut player_o, false).unwrap();
+            let mut board = Board::new();
+            for transition in &record.moves {
+                assert_eq!(transition.state, board.get_compact_state());
+                board.make_auto_player_move(transition.action[0], transition.action[1], transition.mover);
+            }
+            assert_eq!(board.result(), Some(record.result));
+            assert!(record.moves[..record.moves.len() - 1].iter().all(|t| t.result.is_none()));
+            results.insert(record.result);
+        }
+        assert_eq!(results.len(), 3);
+        let mut player_x2 = player_x.clone();
+        assert!(matches!(Trainer::play_training_game(&mut player_x, &mut player_x2, false),
+                         Err(TrainerError::InvalidPlayers)));
+    }
+
+    /// Takes the first empty square, and remembers the outcomes it was shown
+    struct FirstEmpty {
+        piece: PlayerPiece,
+        outcomes: Vec<GameOutcomeFor>,
+    }
+
+    impl Agent for FirstEmpty {
+        fn piece(&self) -> PlayerPiece {
+            self.piece
+        }
+
+        fn choose_move(&mut self, state: &[Piece; 9]) -> Result<[u8; 2], AgentError> {
+            match state.iter().position(|piece| *piece == Piece::Empty) {
+                Some(idx) => { Ok([idx as u8 / 3, idx as u8 % 3]) }
+                None => { Err(AgentError::GameAlreadyOver) }
+            }
+        }
+
+        fn observe_result(&mut self, _final_state: &[Piece; 9], outcome: GameOutcomeFor) {
+            self.outcomes.push(outcome);
+        }
+    }
+
+    /// Never manages to move
+    struct Stuck(PlayerPiece);
+
+    impl Agent for Stuck {
+        fn piece(&self) -> PlayerPiece {
+            self.0
+        }
+
+        fn choose_move(&mut self, _state: &[Piece; 9]) -> Result<[u8; 2], AgentError> {
+            Err(AgentError::NoMove("stuck".to_string()))
+        }
+    }
+
+    /// Always chooses the given square, whether or not it can be played
+    struct Stubborn(PlayerPiece, [u8; 2]);
+
+    impl Agent for Stubborn {
+        fn piece(&self) -> PlayerPiece {
+            self.0
+        }
+
+        fn choose_move(&mut self, _state: &[Piece; 9]) -> Result<[u8; 2], AgentError> {
+            Ok(self.1)
+        }
+    }
+
+    #[test]
+    fn test_illegal_moves() {
+        let mut stub_x = FirstEmpty { piece: PlayerPiece::X, outcomes: Vec::new() };
+        // X takes a1 first, so O choosing it again is rejected rather than looping forever
+        assert_eq!(Trainer::play_game(&mut stub_x, &mut Stubborn(PlayerPiece::O, [0, 0]), false),
+                   Err(TrainerError::NoMove(AgentError::IllegalMove([0, 0], MoveLegality::Occupied(PlayerPiece::X)))));
+        // A square off the board is rejected rather than panicking
+        let mut stub_o = FirstEmpty { piece: PlayerPiece::O, outcomes: Vec::new() };
+        let err = Trainer::play_game(&mut Stubborn(PlayerPiece::X, [3, 1]), &mut stub_o, false).unwrap_err();
+        assert_eq!(err, TrainerError::NoMove(AgentError::IllegalMove([3, 1], MoveLegality::OutOfBounds)));
+        assert_eq!(err.to_string(), "an agent couldn't move: the agent chose row 3, column 1, which is off the board");
+        // The same checks apply to a player's moves
+        let mut player_o = Player::builder(PlayerPiece::O).build().unwrap();
+        assert!(matches!(Trainer::play_game(&mut Stubborn(PlayerPiece::X, [1, 5]), &mut player_o, false),
+                         Err(TrainerError::NoMove(AgentError::IllegalMove(..)))));
+    }
+
+    #[test]
+    fn test_play_game() {
+        let mut stub_x = FirstEmpty { piece: PlayerPiece::X, outcomes: Vec::new() };
+        let mut stub_o = FirstEmpty { piece: PlayerPiece::O, outcomes: Vec::new() };
+        let record = Trainer::play_game(&mut stub_x, &mut stub_o, false).unwrap();
+        assert_eq!(record.result, GameResult::Win(PlayerPiece::X));
+        assert!(record.moves.iter().all(|t| t.iteration == 0 && !t.exploratory));
+        assert_eq!(stub_x.outcomes, vec![GameOutcomeFor::Win]);
+        assert_eq!(stub_o.outcomes, vec![GameOutcomeFor::Loss]);
+        // Against a player, which learns from the game as in training
+        let mut player_o = Player::builder(PlayerPiece::O).exploration_rate(0.).tie_break(TieBreak::FirstIndex)
+            .build().unwrap();
+        let record = Trainer::play_game(&mut stub_x, &mut player_o, false).unwrap();
+        assert_eq!(record.result, GameResult::Win(PlayerPiece::X));
+        assert_eq!(player_o.peek_state_value(&record.moves[5].next_state), 0.125);
+        assert_eq!(Trainer::play_game(&mut stub_x, &mut Stuck(PlayerPiece::O), false),
+                   Err(TrainerError::NoMove(AgentError::NoMove("stuck".to_string()))));
+        assert!(matches!(Trainer::play_game(&mut stub_x, &mut Stuck(PlayerPiece::X), false),
+                         Err(TrainerError::InvalidPlayers)));
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(2).learning(Learning::FixedExploration(0.3))
+            .build().unwrap();
+        Trainer::transitions(&mut player_x, &mut player_o, 200).unwrap().for_each(drop);
+        let (x_hash, o_hash) = (player_x.state_table_hash(), player_o.state_table_hash());
+        let report = Trainer::evaluate(&mut player_x, &mut player_o, 51).unwrap();
+        assert_eq!(report.games, 51);
+        assert_eq!(report.x_wins + report.o_wins + report.draws, 51);
+        assert!((5. ..=9.).contains(&report.average_length));
+        assert!(report.distinct_states > 5);
+        // Nothing was learned, and the players learn as before afterwards
+        assert_eq!((player_x.state_table_hash(), player_o.state_table_hash()), (x_hash, o_hash));
+        assert_eq!(player_o.get_learning(), Learning::FixedExploration(0.3));
+        // Always taking the first empty square, X moves first every game and completes the
+        // diagonal a3-c1, whichever player is passed first
+        let mut fresh_x = Player::builder(PlayerPiece::X).tie_break(TieBreak::FirstIndex).build().unwrap();
+        let mut fresh_o = Player::builder(PlayerPiece::O).tie_break(TieBreak::FirstIndex).build().unwrap();
+        let report = Trainer::evaluate(&mut fresh_x, &mut fresh_o, 4).unwrap();
+        assert_eq!(report, EvalReport { games: 4, x_wins: 4, o_wins: 0, draws: 0, average_length: 7.,
+                                        distinct_states: 8 });
+        assert_eq!(report, Trainer::evaluate(&mut fresh_o, &mut fresh_x, 4).unwrap());
+        assert_eq!(report.to_string(), "4 games: X won 4 (100.0%), O won 0 (0.0%), 0 draws (0.0%), 7.0 moves \
+                                        on average, 8 different positions");
+        assert!(matches!(Trainer::evaluate(&mut fresh_x, &mut player_x, 1), Err(TrainerError::InvalidPlayers)));
+    }
+
+    #[test]
+    fn test_early_draws() {
+        // Learning at the full rate, so a backed up draw replaces the value
+        let mut player_x = Player::builder(PlayerPiece::X).learning_rate(1.).seed(3).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).learning_rate(1.).seed(4).build().unwrap();
+        let transitions: Vec<Transition> = Trainer::transitions(&mut player_x, &mut player_o, 200)
+            .unwrap().early_draws(true).collect();
+        let games: Vec<&[Transition]> = transitions.split_inclusive(|t| t.result.is_some()).collect();
+        assert_eq!(games.len(), 200);
+        let mut early = 0;
+        for game in games {
+            let last = game[game.len() - 1];
+            let mut board = Board::new();
+            for transition in game.iter() {
+                // No game carries on past a dead draw
+                assert_eq!(board.early_result(), None);
+                board.make_auto_player_move(transition.action[0], transition.action[1], transition.mover);
+            }
+            assert_eq!(last.result, board.early_result());
+            if board.result().is_none() {
+                assert!(board.is_dead_draw());
+                // The player who moved into the dead draw values it as a draw
+                let mover = if last.mover == PlayerPiece::X { &player_x } else { &player_o };
+                assert_eq!(mover.peek_state_value(&last.next_state), 0.);
+                early += 1;
+            }
+        }
+        assert!(early > 0);
+    }
+
+    #[test]
+    fn test_health_check() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(7).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(8).build().unwrap();
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_health");
+        std::fs::create_dir_all(&out_directory).unwrap();
+        let phases = [TrainingPhase { learners: PhaseLearners::Both, iterations: 25 }];
+        // Thresholds so strict any table is flagged
+        let health_check = HealthCheck {
+            every: 10,
+            thresholds: HealthThresholds { epsilon: 0.01, max_fraction: 0.01 },
+        };
+        let options = TrainingOptions { health_check: Some(health_check), ..TrainingOptions::default() };
+        let mut flagged = Vec::new();
+        Trainer::train_phases(&mut player_x, &mut player_o, &phases, &out_directory, options,
+                              |_, _| {}, |games, piece, health| flagged.push((games, piece, health)))
+            .unwrap();
+        let checks: Vec<(u32, PlayerPiece)> = flagged.iter().map(|(games, piece, _)| (*games, *piece)).collect();
+        assert_eq!(checks, vec![(10, PlayerPiece::X), (10, PlayerPiece::O), (20, PlayerPiece::X),
+                                (20, PlayerPiece::O)]);
+        assert!(flagged.iter().all(|(_, _, health)| *health != TableHealth::Healthy));
+        // Nothing is checked without a health check
+        flagged.clear();
+        Trainer::train_phases(&mut player_x, &mut player_o, &phases, &out_directory,
+                              TrainingOptions::default(),
+                              |_, _| {}, |games, piece, health| flagged.push((games, piece, health)))
+            .unwrap();
+        assert!(flagged.is_empty());
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(9).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(10).build().unwrap();
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_snapshots");
+        _ = std::fs::remove_dir_all(&out_directory);
+        std::fs::create_dir_all(&out_directory).unwrap();
+        let directory = out_directory.join("snapshots");
+        let phases = [TrainingPhase { learners: PhaseLearners::Both, iterations: 500 }];
+        let options = TrainingOptions {
+            snapshots: Some(Snapshots { every: 100, directory: directory.clone() }),
+            ..TrainingOptions::default()
+        };
+        Trainer::train_phases(&mut player_x, &mut player_o, &phases, &out_directory, options,
+                              |_, _| {}, |_, _, _| {}).unwrap();
+        let index = read_index(&directory).unwrap();
+        let iterations: Vec<u32> = index.iter().map(|entry| entry.iteration).collect();
+        assert_eq!(iterations, vec![100, 200, 300, 400, 500]);
+        // Positions X moved into which leave O a winning square are learned as losing over time
+        let first = Player::load(&index[0].player_x).unwrap();
+        let losing: Vec<[Piece; 9]> = player_x.iter_states()
+            .map(|(state, _)| state)
+            .filter(|state| !winning_squares(state, Piece::O).is_empty() && first.learned_value(state).is_some())
+            .collect();
+        assert!(!losing.is_empty());
+        let mean = |player: &Player| losing.iter().map(|state| player.learned_value(state).unwrap())
+            .sum::<f64>() / losing.len() as f64;
+        assert!(mean(&player_x) < mean(&first));
+        let history = state_history(&directory, &losing[0], PlayerPiece::X).unwrap();
+        assert_eq!(history.values.len(), 5);
+        assert_eq!(history.values[4], (500, player_x.learned_value(&losing[0])));
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_failed_snapshot_restores_learning() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(13).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(14).build().unwrap();
+        player_o.set_learning(Learning::FixedExploration(0.2));
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_failed_snapshot");
+        _ = std::fs::remove_dir_all(&out_directory);
+        std::fs::create_dir_all(&out_directory).unwrap();
+        // A file where the snapshot directory should be
+        let directory = out_directory.join("snapshots");
+        std::fs::write(&directory, b"").unwrap();
+        let phases = [TrainingPhase { learners: PhaseLearners::XOnly, iterations: 20 }];
+        let options = TrainingOptions {
+            snapshots: Some(Snapshots { every: 10, directory }),
+            ..TrainingOptions::default()
+        };
+        let trained = Trainer::train_phases(&mut player_x, &mut player_o, &phases, &out_directory, options,
+                                            |_, _| {}, |_, _, _| {});
+        assert!(matches!(trained, Err(TrainerError::FailedToSave(_))));
+        // O was frozen for the phase, but learns again after the failure
+        assert_eq!(player_x.get_learning(), Learning::Scheduled);
+        assert_eq!(player_o.get_learning(), Learning::FixedExploration(0.2));
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(11).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(12).build().unwrap();
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_checkpoints");
+        _ = std::fs::remove_dir_all(&out_directory);
+        std::fs::create_dir_all(&out_directory).unwrap();
+        let x_path = out_directory.join("player_x_save.ttr");
+        let o_path = out_directory.join("player_o_save.ttr");
+        let phases = [TrainingPhase { learners: PhaseLearners::Both, iterations: 100 }];
+        let options = TrainingOptions { checkpoint_every: Some(25), ..TrainingOptions::default() };
+        // Part way through, the players saved are those from the last checkpoint
+        let mut checkpointed = Vec::new();
+        Trainer::train_phases(&mut player_x, &mut player_o, &phases, &out_directory, options,
+                              |_, game| {
+                                  let it = game.iteration;
+                                  if it > 25 && it % 25 == 10 {
+                                      let x = Player::load_expecting(&x_path, PlayerPiece::X).unwrap();
+                                      let o = Player::load_expecting(&o_path, PlayerPiece::O).unwrap();
+                                      checkpointed.push((it, x.get_iteration(), o.get_iteration()));
+                                  }
+                              }, |_, _, _| {}).unwrap();
+        assert_eq!(checkpointed, vec![(35, 25, 25), (60, 50, 50), (85, 75, 75)]);
+        assert_eq!(Player::load(&x_path).unwrap().get_iteration(), 100);
+        // Nothing is left behind but the saves and the report
+        let mut files: Vec<String> = std::fs::read_dir(&out_directory).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["player_o_save.ttr", "player_x_save.ttr", TRAINING_REPORT_FILE]);
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_failed_checkpoint() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(15).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(16).build().unwrap();
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_failed_checkpoint");
+        _ = std::fs::remove_dir_all(&out_directory);
+        // A directory where X's save should be, so every save of X fails
+        std::fs::create_dir_all(out_directory.join("player_x_save.ttr")).unwrap();
+        let phases = [TrainingPhase { learners: PhaseLearners::OOnly, iterations: 50 }];
+        let options = TrainingOptions { checkpoint_every: Some(10), ..TrainingOptions::default() };
+        let report = Trainer::train_phases(&mut player_x, &mut player_o, &phases, &out_directory, options,
+                                           |_, _| {}, |_, _, _| {}).unwrap();
+        // Training carries on past the failed checkpoints, and reports them
+        assert_eq!(report.iterations, 50);
+        assert!(report.checkpoint_error.is_some() && report.save_error.is_some());
+        assert!(report.to_string().contains("Warning: a checkpoint failed to save"));
+        assert_eq!(player_x.get_learning(), Learning::Scheduled);
+        // O is still saved
+        assert_eq!(Player::load(out_directory.join("player_o_save.ttr")).unwrap().get_iteration(), 50);
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_duel() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(1).tie_break(TieBreak::FirstIndex).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(2).tie_break(TieBreak::FirstIndex).build().unwrap();
+        Trainer::transitions(&mut player_x, &mut player_o, 2000).unwrap().for_each(drop);
+        let learning = player_x.get_learning();
+        let states = player_x.state_count();
+        // Greedy players with a fixed tie break play the same game every time
+        let report = Trainer::duel(&mut player_o, &mut player_x, 10).unwrap();
+        assert_eq!(report.games, 10);
+        assert!([report.x_wins, report.o_wins, report.draws].contains(&10));
+        assert_eq!(report.distinct_states as f64, report.average_length + 1.);
+        // X moves first whichever order the players are given in
+        assert_eq!(Trainer::duel(&mut player_x, &mut player_o, 10).unwrap(), report);
+        // Nothing is learned, and learning is restored
+        assert_eq!(player_x.get_learning(), learning);
+        assert_eq!(player_x.state_count(), states);
+        let mut same = Player::builder(PlayerPiece::X).build().unwrap();
+        assert_eq!(Trainer::duel(&mut player_x, &mut same, 1), Err(TrainerError::InvalidPlayers));
+    }
+
+    #[test]
+    fn test_merge_policy() {
+        let values = [(1.0, 3), (0.0, 1)];
+        assert_eq!(MergePolicy::VisitWeighted.merge(&values), 0.75);
+        assert_eq!(MergePolicy::Mean.merge(&values), 0.5);
+        // Without any visits the values count equally
+        assert_eq!(MergePolicy::VisitWeighted.merge(&[(1.0, 0), (0.5, 0)]), 0.75);
+    }
+
+    #[test]
+    fn test_train_parallel() {
+        let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(2).build().unwrap();
+        let options = ParallelOptions { threads: 4, ..ParallelOptions::default() };
+        let runs = Trainer::run_workers(&mut player_x, &mut player_o, 0, 402, &options).unwrap();
+        let games: Vec<u32> = runs.iter().map(|run| run.results.games).collect();
+        assert_eq!(games, vec![101, 101, 100, 100]);
+        // Workers play different games, and the merged table has every position any of them learned
+        assert_ne!(runs[0].player1.iter_states_sorted().collect::<Vec<_>>(),
+                   runs[1].player1.iter_states_sorted().collect::<Vec<_>>());
+        let merged = MergePolicy::VisitWeighted.merge_tables(runs.iter().map(|run| (&run.player1, &run.visits[0])));
+        for run in &runs {
+            assert!(run.player1.iter_states().all(|(state, _)| merged.contains_key(&state)));
+            assert!(merged.len() > run.player1.state_count());
+        }
+        // Seeded players trained on the same number of threads learn the same tables
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_parallel");
+        _ = std::fs::remove_dir_all(&out_directory);
+        std::fs::create_dir_all(&out_directory).unwrap();
+        let mut tables = Vec::new();
+        for _ in 0..2 {
+            let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+            let mut player_o = Player::builder(PlayerPiece::O).seed(2).build().unwrap();
+            let report = Trainer::train_parallel(&mut player_o, &mut player_x, 400, &out_directory, options).unwrap();
+            assert_eq!(report.iterations, 400);
+            assert_eq!(report.x_wins + report.o_wins + report.draws, 400);
+            assert_eq!((player_x.get_iteration(), player_o.get_iteration()), (400, 400));
+            assert_eq!(Player::load(&report.player_x_path).unwrap().state_count(), player_x.state_count());
+            tables.push(player_x.iter_states_sorted().collect::<Vec<_>>());
+        }
+        assert_eq!(tables[0], tables[1]);
+        let mut same = Player::builder(PlayerPiece::X).seed(3).build().unwrap();
+        assert_eq!(Trainer::train_parallel(&mut player_x, &mut same, 1, &out_directory, options),
+                   Err(TrainerError::InvalidPlayers));
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_train_parallel_beats_random() {
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_parallel_random");
+        _ = std::fs::remove_dir_all(&out_directory);
+        std::fs::create_dir_all(&out_directory).unwrap();
+        let mut parallel_x = Player::builder(PlayerPiece::X).seed(3).build().unwrap();
+        let mut parallel_o = Player::builder(PlayerPiece::O).seed(4).build().unwrap();
+        let options = ParallelOptions { threads: 4, ..ParallelOptions::default() };
+        Trainer::train_parallel(&mut parallel_x, &mut parallel_o, 4000, &out_directory, options).unwrap();
+        let mut serial_x = Player::builder(PlayerPiece::X).seed(3).build().unwrap();
+        let mut serial_o = Player::builder(PlayerPiece::O).seed(4).build().unwrap();
+        Trainer::transitions(&mut serial_x, &mut serial_o, 4000).unwrap().for_each(drop);
+        parallel_x.set_learning(Learning::Frozen);
+        serial_x.set_learning(Learning::Frozen);
+        let parallel = evaluate(&mut parallel_x, &mut RandomAgent::new(PlayerPiece::O, 5), 1000).unwrap();
+        let serial = evaluate(&mut serial_x, &mut RandomAgent::new(PlayerPiece::O, 5), 1000).unwrap();
+        // Training on more threads learns about as well from the same number of games
+        assert!(parallel.wins * 100 >= serial.wins * 97, "parallel {:?}, serial {:?}", parallel, serial);
+        assert!(parallel.wins > 900);
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_parse_phases() {
+        let phases = TrainingPhase::parse_list("both:5000, X-only:10 ,o-only:1").unwrap();
+        assert_eq!(phases, vec![
+            TrainingPhase { learners: PhaseLearners::Both, iterations: 5000 },
+            TrainingPhase { learners: PhaseLearners::XOnly, iterations: 10 },
+            TrainingPhase { learners: PhaseLearners::OOnly, iterations: 1 },
+        ]);
+        assert_eq!(phases[1].to_string(), "x-only:10");
+        for invalid in ["", "both", "both:", "both:0", "both:-5", "neither:10", "both:10,", "x:10"] {
+            assert_eq!(TrainingPhase::parse_list(invalid), Err(TrainerError::InvalidPhases));
+        }
+    }
+
+    #[test]
+    fn test_failed_to_save() {
+        // A file where the output directory should be
+        let out_directory = std::env::temp_dir().join("tictacrs_test_failed_to_save");
+        std::fs::write(&out_directory, b"").unwrap();
+        let mut player_x = Player::new(PlayerPiece::X, 0.5, 0.1, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        let mut player_o = Player::new(PlayerPiece::O, 0.5, 0.1, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        // Training still finishes, with the error in the report
+        let report = Trainer::train(&mut player_x, &mut player_o, 1, &out_directory, false).unwrap();
+        assert_eq!(report.iterations, 1);
+        let err = report.into_paths().unwrap_err();
+        assert!(matches!(err, TrainerError::FailedToSave(PlayerError::InvalidFile { ref path, .. })
+                         if *path == out_directory.join("player_x_save.ttr")));
+        assert!(err.to_string().starts_with(&format!("couldn't save the trained players: couldn't open {}",
+                                                     out_directory.join("player_x_save.ttr").display())));
+        // The chain of causes leads down to the io error
+        let cause = err.source().unwrap();
+        assert!(cause.source().unwrap().downcast_ref::<std::io::Error>().is_some());
+        assert_eq!(TrainerError::InvalidPlayers.to_string(), "the two players must play different pieces");
+        std::fs::remove_file(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_training_report() {
+        let out_directory = std::env::temp_dir().join("tictacrs_test_training_report");
+        _ = std::fs::remove_dir_all(&out_directory);
+        std::fs::create_dir_all(&out_directory).unwrap();
+        let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(2).learning(Learning::FixedExploration(0.25))
+            .build().unwrap();
+        let report = Trainer::train(&mut player_o, &mut player_x, 50, &out_directory, false).unwrap();
+        assert_eq!(report.iterations, 50);
+        assert_eq!(report.x_wins + report.o_wins + report.draws, 50);
+        assert_eq!(report.player_x.states, player_x.state_count());
+        assert_eq!(report.player_o.states, player_o.state_count());
+        assert_eq!(report.player_o.exploration_rate, 0.25);
+        assert_eq!(report.save_error, None);
+        let saved: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(out_directory.join(TRAINING_REPORT_FILE)).unwrap()).unwrap();
+        assert_eq!(saved["iterations"], 50);
+        assert_eq!(saved["player_o"]["states"], player_o.state_count());
+        assert!(saved["elapsed_seconds"].is_f64());
+        assert!(saved["save_error"].is_null());
+        assert!(report.to_string().starts_with("Trained 50 games in "));
+        assert!(report.to_string().contains(&format!("Player O: {} positions, exploring 25.0%",
+                                                     player_o.state_count())));
+        let (x_path, o_path) = report.into_paths().unwrap();
+        assert_eq!(Player::load(x_path).unwrap().state_count(), player_x.state_count());
+        // When one player can't be saved, the other and the report still are
+        std::fs::remove_file(out_directory.join("player_x_save.ttr")).unwrap();
+        std::fs::create_dir(out_directory.join("player_x_save.ttr")).unwrap();
+        let report = Trainer::train(&mut player_x, &mut player_o, 10, &out_directory, false).unwrap();
+        assert!(matches!(report.save_error, Some(PlayerError::InvalidFile { ref path, .. })
+                         if *path == out_directory.join("player_x_save.ttr")));
+        assert_eq!(Player::load(&o_path).unwrap().get_iteration(), player_o.get_iteration());
+        let saved: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(out_directory.join(TRAINING_REPORT_FILE)).unwrap()).unwrap();
+        assert_eq!(saved["iterations"], 10);
+        assert!(saved["save_error"].as_str().unwrap().contains("player_x_save.ttr"));
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_resume() {
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_resume");
+        _ = std::fs::remove_dir_all(&out_directory);
+        std::fs::create_dir_all(&out_directory).unwrap();
+        let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(2).build().unwrap();
+        let (x_path, o_path) = Trainer::train(&mut player_x, &mut player_o, 30, &out_directory, false)
+            .unwrap().into_paths().unwrap();
+        assert_eq!(player_x.get_iteration(), 30);
+        // Resuming twice from the saves counts every game played
+        for (iterations, total) in [(20, 50), (25, 75)] {
+            let mut loaded_x = Player::load_expecting(&x_path, PlayerPiece::X).unwrap();
+            let mut loaded_o = Player::load_expecting(&o_path, PlayerPiece::O).unwrap();
+            assert_eq!(loaded_x.iter_states_sorted().collect::<Vec<_>>(),
+                       player_x.iter_states_sorted().collect::<Vec<_>>());
+            let report = Trainer::train(&mut loaded_x, &mut loaded_o, iterations, &out_directory, false).unwrap();
+            assert_eq!(report.iterations, iterations);
+            assert_eq!(loaded_x.get_iteration(), total);
+            assert_eq!(loaded_o.get_iteration(), total);
+            // Positions learned before are kept
+            assert!(player_x.iter_states().all(|(state, _)| loaded_x.learned_value(&state).is_some()));
+            player_x = loaded_x;
+        }
+        assert_eq!(Player::load(&x_path).unwrap().get_iteration(), 75);
+        // The transitions carry on from the players too
+        let first = Trainer::transitions(&mut player_x, &mut player_o, 1).unwrap().next().unwrap();
+        assert_eq!(first.iteration, 75);
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_frozen_phases() {
+        let mut player_x = Player::new(PlayerPiece::X, 0.5, 0.3, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        let mut player_o = Player::new(PlayerPiece::O, 0.5, 0.3, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        player_o.set_learning(Learning::FixedExploration(0.3));
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_phases");
+        std::fs::create_dir_all(&out_directory).unwrap();
+        let phases = TrainingPhase::parse_list("both:20,x-only:50,o-only:50").unwrap();
+        let mut hashes = Vec::new();
+        let mut games = Vec::new();
+        Trainer::train_phases(&mut player_x, &mut player_o, &phases, &out_directory,
+                              TrainingOptions::default(), |phase, game| games.push((phase, game.iteration)),
+                              |_, _, _| {}).unwrap();
+        assert_eq!(games.len(), 120);
+        assert_eq!(games[20], (1, 20));
+        assert_eq!(games[119], (2, 119));
+        // Run each phase on its own to check the tables between phases
+        for phase in phases {
+            let before = (player_x.state_table_hash(), player_o.state_table_hash());
+            Trainer::train_phases(&mut player_x, &mut player_o, &[phase], &out_directory,
+                                  TrainingOptions::default(), |_, _| {}, |_, _, _| {}).unwrap();
+            let after = (player_x.state_table_hash(), player_o.state_table_hash());
+            hashes.push((phase.learners, before.0 == after.0, before.1 == after.1));
+        }
+        // A frozen player's table is unchanged, while a learning player's table changes
+        assert_eq!(hashes, vec![
+            (PhaseLearners::Both, false, false),
+            (PhaseLearners::XOnly, false, true),
+            (PhaseLearners::OOnly, true, false),
+        ]);
+        // Learning modes are restored afterwards
+        assert_eq!(player_x.get_learning(), Learning::Scheduled);
+        assert_eq!(player_o.get_learning(), Learning::FixedExploration(0.3));
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_phase_reports() {
+        let mut player_x = Player::new(PlayerPiece::X, 0.5, 0.3, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        let mut player_o = Player::new(PlayerPiece::O, 0.5, 0.3, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_phase_reports");
+        std::fs::create_dir_all(&out_directory).unwrap();
+        let phases = TrainingPhase::parse_list("both:20,o-only:30").unwrap();
+        let mut results = [[0u32; 3]; 2];
+        let report = Trainer::train_phases(&mut player_x, &mut player_o, &phases, &out_directory,
+                                           TrainingOptions::default(), |phase, game| {
+                                               let column = match game.result.winner() {
+                                                   Some(PlayerPiece::X) => { 0 }
+                                                   Some(PlayerPiece::O) => { 1 }
+                                                   None => { 2 }
+                                               };
+                                               results[phase][column] += 1;
+                                           }, |_, _, _| {}).unwrap();
+        assert_eq!(report.phases.len(), 2);
+        for ((phase, counted), learners) in report.phases.iter().zip(results).zip([PhaseLearners::Both, PhaseLearners::OOnly]) {
+            assert_eq!(phase.learners, learners);
+            assert_eq!([phase.x_wins, phase.o_wins, phase.draws], counted);
+        }
+        assert_eq!(report.phases[1].games, 30);
+        assert_eq!(report.x_wins, report.phases[0].x_wins + report.phases[1].x_wins);
+        assert!(report.to_string().contains("Phase o-only:30: X won "));
+        std::fs::remove_dir_all(&out_directory).unwrap();
+    }
+
+    #[test]
+    fn test_too_many_iterations() {
+        let mut player_x = Player::new(PlayerPiece::X, 0.5, 0.3, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        let mut player_o = Player::new(PlayerPiece::O, 0.5, 0.3, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+        player_x.update_iteration(u32::MAX - 5);
+        let out_directory = std::env::temp_dir().join("tictacrs_trainer_too_many_iterations");
+        // Nothing is played or saved when the games wouldn't fit
+        let phases = TrainingPhase::parse_list("both:5,x-only:1").unwrap();
+        let err = Trainer::train_phases(&mut player_x, &mut player_o, &phases, &out_directory,
+                                        TrainingOptions::default(), |_, _| {}, |_, _, _| {}).unwrap_err();
+        assert_eq!(err, TrainerError::TooManyIterations);
+        assert_eq!(player_x.get_iteration(), u32::MAX - 5);
+        assert!(matches!(Trainer::transitions(&mut player_x, &mut player_o, 6), Err(TrainerError::TooManyIterations)));
+        let options = ParallelOptions { threads: 2, ..ParallelOptions::default() };
+        assert_eq!(Trainer::train_parallel(&mut player_x, &mut player_o, 6, &out_directory, options).unwrap_err(),
+                   TrainerError::TooManyIterations);
+        assert!(!out_directory.exists());
+    }
+
+    #[test]
+    fn test_convergence() {
+        // Both rates fall to nothing over the games, and a draw is worth half a win
+        let schedule: fn(f64, u32) -> f64 = |rate, iteration| rate * (1. - iteration as f64 / 10_000.);
+        let player = |piece: PlayerPiece, seed: u64| {
+            Player::builder(piece)
+                .learning_rate(0.5)
+                .exploration_rate(0.3)
+                .learning_function(schedule)
+                .exploration_function(schedule)
+                .draw_reward(0.5)
+                .seed(seed)
+                .build()
+                .unwrap()
+        };
+        let mut player_x = player(PlayerPiece::X, 3);
+        let mut player_o = player(PlayerPiece::O, 103);
+        Trainer::transitions(&mut player_x, &mut player_o, 10_000).unwrap().for_each(drop);
+        // Certifying follows every reply, so neither player can lose to a random opponent
+        for player in [&player_x, &player_o] {
+            let certificate = certify(player);
+            assert!(certificate.is_certified(), "{}", certificate);
+        }
+    }
+}
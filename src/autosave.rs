@@ -0,0 +1,212 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Settings for periodically saving the computer player during one player games
+#[derive(Clone, Copy)]
+pub(crate) struct AutosaveSettings {
+    /// How often to autosave in the middle of a game
+    pub(crate) interval: Duration,
+    /// How many autosave files to keep for each player
+    pub(crate) keep: usize,
+}
+
+/// Keeps track of when the last autosave happened
+pub(crate) struct Autosaver {
+    settings: AutosaveSettings,
+    last_save: Instant,
+}
+
+impl Autosaver {
+    pub(crate) fn new(settings: AutosaveSettings) -> Autosaver {
+        Autosaver {
+            settings,
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Whether enough time has passed since the last autosave that another is due
+    pub(crate) fn is_due(&self) -> bool {
+        self.last_save.elapsed() >= self.settings.interval
+    }
+
+    /// Write an autosave using the provided save function (which is passed the path to write
+    /// to), then remove all but the newest autosaves
+    pub(crate) fn autosave<F, E>(&mut self, directory: &Path, stem: &str, save: F) -> Result<PathBuf, E>
+    where
+        F: FnOnce(&Path) -> Result<(), E>,
+    {
+        let path = next_autosave_path(directory, stem, now_millis());
+        save(&path)?;
+        self.last_save = Instant::now();
+        // Failing to remove old autosaves isn't a problem for the save itself
+        _ = prune_autosaves(directory, stem, self.settings.keep);
+        Ok(path)
+    }
+}
+
+/// Milliseconds since the unix epoch
+fn now_millis() -> u128 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0)
+}
+
+/// Path for a new autosave file, of the form `<stem>_autosave_<millis>_<sequence>.ttr`,
+/// the sequence number distinguishes autosaves written within the same millisecond
+fn next_autosave_path(directory: &Path, stem: &str, timestamp: u128) -> PathBuf {
+    let mut sequence: u32 = 0;
+    loop {
+        let path = directory.join(format!("{}_autosave_{}_{}.ttr", stem, timestamp, sequence));
+        if !path.exists() {
+            return path;
+        }
+        sequence += 1;
+    }
+}
+
+/// Parse the (timestamp, sequence) out of an autosave file name for the given stem
+fn parse_autosave_name(file_name: &str, stem: &str) -> Option<(u128, u32)> {
+    let rest = file_name.strip_prefix(stem)?.strip_prefix("_autosave_")?.strip_suffix(".ttr")?;
+    let (timestamp, sequence) = rest.split_once('_')?;
+    Some((timestamp.parse().ok()?, sequence.parse().ok()?))
+}
+
+/// All autosaves for the given stem, oldest first
+fn list_autosaves(directory: &Path, stem: &str) -> io::Result<Vec<((u128, u32), PathBuf)>> {
+    let mut autosaves = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        let key = match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) => parse_autosave_name(name, stem),
+            None => None,
+        };
+        if let Some(key) = key {
+            autosaves.push((key, path));
+        }
+    }
+    autosaves.sort();
+    Ok(autosaves)
+}
+
+/// Remove all but the newest `keep` autosaves for the given stem, returning the removed paths
+pub(crate) fn prune_autosaves(directory: &Path, stem: &str, keep: usize) -> io::Result<Vec<PathBuf>> {
+    let autosaves = list_autosaves(directory, stem)?;
+    let remove_count = autosaves.len().saturating_sub(keep);
+    let mut removed = Vec::with_capacity(remove_count);
+    for (_, path) in autosaves.into_iter().take(remove_count) {
+        fs::remove_file(&path)?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+/// The newest autosave for the given stem, if there is one
+pub(crate) fn newest_autosave(directory: &Path, stem: &str) -> Option<PathBuf> {
+    list_autosaves(directory, stem).ok()?.pop().map(|(_, path)| path)
+}
+
+/// The newest autosave for the given stem, if it was written after the main save file (or
+/// the main save file doesn't exist). Both files' modification times are compared, rather than
+/// the time in the autosave's name, as the filesystem may keep them more coarsely.
+pub(crate) fn newer_autosave(directory: &Path, stem: &str, main_save: &Path) -> Option<PathBuf> {
+    let autosave = newest_autosave(directory, stem)?;
+    let main_modified = match fs::metadata(main_save).and_then(|m| m.modified()) {
+        Ok(modified) => modified,
+        Err(_) => return Some(autosave),
+    };
+    let autosave_modified = fs::metadata(&autosave).and_then(|m| m.modified()).ok()?;
+    if autosave_modified > main_modified {
+        Some(autosave)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("tictacrs_autosave_{}", name));
+        _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn touch(path: &Path) -> Result<(), io::Error> {
+        fs::write(path, b"save")
+    }
+
+    #[test]
+    fn test_prune_autosaves() {
+        let directory = test_directory("prune");
+        for timestamp in [30u128, 10, 20, 40] {
+            touch(&next_autosave_path(&directory, "player_x", timestamp)).unwrap();
+        }
+        // Autosaves for a different player are left alone
+        touch(&next_autosave_path(&directory, "player_o", 5)).unwrap();
+        touch(&directory.join("player_x_save.ttr")).unwrap();
+        let removed = prune_autosaves(&directory, "player_x", 2).unwrap();
+        assert_eq!(removed, vec![directory.join("player_x_autosave_10_0.ttr"),
+                                 directory.join("player_x_autosave_20_0.ttr")]);
+        assert!(directory.join("player_x_autosave_30_0.ttr").exists());
+        assert!(directory.join("player_x_autosave_40_0.ttr").exists());
+        assert!(directory.join("player_o_autosave_5_0.ttr").exists());
+        assert!(directory.join("player_x_save.ttr").exists());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_newest_autosave_with_timestamp_ties() {
+        let directory = test_directory("newest");
+        assert_eq!(newest_autosave(&directory, "player_x"), None);
+        for timestamp in [100u128, 100, 100, 99] {
+            touch(&next_autosave_path(&directory, "player_x", timestamp)).unwrap();
+        }
+        // The sequence number breaks the tie, numerically rather than alphabetically
+        for sequence in 3..11 {
+            touch(&directory.join(format!("player_x_autosave_100_{}.ttr", sequence))).unwrap();
+        }
+        assert_eq!(newest_autosave(&directory, "player_x"),
+                   Some(directory.join("player_x_autosave_100_10.ttr")));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_autosaver() {
+        let directory = test_directory("autosaver");
+        let mut autosaver = Autosaver::new(AutosaveSettings { interval: Duration::from_secs(3600), keep: 2 });
+        assert!(!autosaver.is_due());
+        for _ in 0..4 {
+            autosaver.autosave(&directory, "player_o", touch).unwrap();
+        }
+        assert_eq!(list_autosaves(&directory, "player_o").unwrap().len(), 2);
+        // No main save, so the autosave is newer
+        let main_save = directory.join("player_o_save.ttr");
+        assert!(newer_autosave(&directory, "player_o", &main_save).is_some());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_newer_autosave_modification_times() {
+        let directory = test_directory("newer");
+        let main_save = directory.join("player_x_save.ttr");
+        touch(&main_save).unwrap();
+        let saved = SystemTime::now();
+        fs::File::options().write(true).open(&main_save).unwrap().set_modified(saved).unwrap();
+        // Named after a later time than the main save, but written in the same tick of a
+        // filesystem keeping whole seconds
+        let autosave = next_autosave_path(&directory, "player_x", now_millis() + 500);
+        touch(&autosave).unwrap();
+        let set_autosave_modified = |modified: SystemTime| {
+            fs::File::options().write(true).open(&autosave).unwrap().set_modified(modified).unwrap()
+        };
+        set_autosave_modified(saved);
+        assert_eq!(newer_autosave(&directory, "player_x", &main_save), None);
+        set_autosave_modified(saved - Duration::from_secs(1));
+        assert_eq!(newer_autosave(&directory, "player_x", &main_save), None);
+        set_autosave_modified(saved + Duration::from_secs(1));
+        assert_eq!(newer_autosave(&directory, "player_x", &main_save), Some(autosave.clone()));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+//! Human friendly formatting of the numbers in reports, always using '.' as the decimal point
+//! and ',' to separate thousands, whatever the locale
+
+use std::time::Duration;
+
+/// A count with thousands separators
+///
+/// ```
+/// assert_eq!(tictacrs::formatting::count(1_000_000), "1,000,000");
+/// ```
+pub fn count(value: u64) -> String {
+    let digits = value.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/// A duration in hours, minutes and seconds such as `1h 02m 04s`, or in seconds to one
+/// decimal place when under a minute
+///
+/// ```
+/// use std::time::Duration;
+///
+/// assert_eq!(tictacrs::formatting::duration(Duration::from_secs_f64(3724.118)), "1h 02m 04s");
+/// ```
+pub fn duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, _) => { format!("{:.1}s", duration.as_secs_f64()) }
+        (0, minutes, seconds) => { format!("{}m {:02}s", minutes, seconds) }
+        (hours, minutes, seconds) => { format!("{}h {:02}m {:02}s", count(hours), minutes, seconds) }
+    }
+}
+
+/// A rate with precision adapted to its size: two significant digits below 1, two decimal
+/// places up to 100, and a whole count above that
+///
+/// ```
+/// use tictacrs::formatting::rate;
+///
+/// assert_eq!(rate(0.75), "0.75");
+/// assert_eq!(rate(0.00123), "0.0012");
+/// assert_eq!(rate(12345.6), "12,346");
+/// ```
+pub fn rate(value: f64) -> String {
+    let magnitude = value.abs();
+    if magnitude == 0. || !magnitude.is_finite() {
+        format!("{}", value)
+    } else if magnitude < 1. {
+        // The exponent of the leading digit, after rounding to two significant digits
+        let scientific = format!("{:.1e}", magnitude);
+        let exponent: i32 = scientific.split_once('e').and_then(|(_, e)| e.parse().ok()).unwrap_or(0);
+        format!("{:.*}", (1 - exponent).max(0) as usize, value)
+    } else if magnitude < 100. {
+        format!("{:.2}", value)
+    } else {
+        let sign = if value < 0. { "-" } else { "" };
+        format!("{}{}", sign, count(magnitude.round() as u64))
+    }
+}
+
+/// A fraction as a percentage with one decimal place
+///
+/// ```
+/// assert_eq!(tictacrs::formatting::percent(0.0314), "3.1%");
+/// ```
+pub fn percent(fraction: f64) -> String {
+    format!("{:.1}%", fraction * 100.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count() {
+        assert_eq!(count(0), "0");
+        assert_eq!(count(999), "999");
+        assert_eq!(count(1000), "1,000");
+        assert_eq!(count(65536), "65,536");
+        assert_eq!(count(1_000_000), "1,000,000");
+        assert_eq!(count(u64::MAX), "18,446,744,073,709,551,615");
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(duration(Duration::ZERO), "0.0s");
+        assert_eq!(duration(Duration::from_millis(4125)), "4.1s");
+        assert_eq!(duration(Duration::from_secs(60)), "1m 00s");
+        assert_eq!(duration(Duration::from_secs(754)), "12m 34s");
+        assert_eq!(duration(Duration::from_secs_f64(3724.118)), "1h 02m 04s");
+        assert_eq!(duration(Duration::from_secs(3600 * 1234)), "1,234h 00m 00s");
+    }
+
+    #[test]
+    fn test_rate() {
+        assert_eq!(rate(0.), "0");
+        assert_eq!(rate(0.2), "0.20");
+        assert_eq!(rate(0.4249), "0.42");
+        assert_eq!(rate(0.05), "0.050");
+        assert_eq!(rate(0.001), "0.0010");
+        assert_eq!(rate(-0.0314), "-0.031");
+        assert_eq!(rate(1.), "1.00");
+        assert_eq!(rate(42.125), "42.12");
+        assert_eq!(rate(1234.5), "1,235");
+        assert_eq!(rate(f64::NAN), "NaN");
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent(0.), "0.0%");
+        assert_eq!(percent(0.5), "50.0%");
+        assert_eq!(percent(1.), "100.0%");
+        assert_eq!(percent(0.12345), "12.3%");
+    }
+}
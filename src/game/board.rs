@@ -1,9 +1,20 @@
 use std::fmt;
-use std::fmt::format;
-use std::io::Write;
 use borsh::{BorshSerialize, BorshDeserialize};
+use crate::game::coords::{Coord, DigitLayout, COL_LABELS, ROW_LABELS};
+use crate::game::result::GameResult;
+use crate::game::rules;
+use crate::game::threats::LINES;
+use crate::meta::compact_board_char;
 
-#[derive(Copy, Debug, Clone, Hash, BorshSerialize, BorshDeserialize, PartialOrd, Eq,  Ord)]
+/// A single square's contents
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// assert_eq!(format!("{}", Piece::X), "X");
+/// assert_eq!(format!("{}", Piece::Empty), " ");
+/// ```
+#[derive(Copy, Debug, Clone, Hash, BorshSerialize, BorshDeserialize, PartialEq, PartialOrd, Eq,  Ord)]
 pub enum Piece {
     Empty,
     X,
@@ -20,60 +31,98 @@ impl fmt::Display for Piece {
     }
 }
 
-impl PartialEq for Piece {
-    fn eq(&self, other: &Self) -> bool {
+/// A piece one of the players plays with, unlike [`Piece`] this can't be empty
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// assert_eq!(Piece::from(PlayerPiece::O), Piece::O);
+/// assert_eq!(PlayerPiece::try_from(Piece::X), Ok(PlayerPiece::X));
+/// assert_eq!(PlayerPiece::try_from(Piece::Empty), Err(BoardError::InvalidPiece));
+/// assert_eq!(PlayerPiece::X.opponent(), PlayerPiece::O);
+/// ```
+// Serialized with the same discriminants as the matching Piece, so saves written when
+// players stored a Piece still load
+#[derive(Copy, Debug, Clone, Hash, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+#[borsh(use_discriminant = true)]
+pub enum PlayerPiece {
+    X = 1,
+    O = 2,
+}
+
+impl PlayerPiece {
+    /// The piece the other player plays with
+    pub fn opponent(&self) -> PlayerPiece {
         match self {
-            Piece::Empty => {
-                match other {
-                    Piece::Empty => true,
-                    Piece::X => false,
-                    Piece::O => false,
-                }
-            }
-            Piece::X => {
-                match other {
-                    Piece::Empty => false,
-                    Piece::X => true,
-                    Piece::O => false,
-                }
-            }
-            Piece::O => {
-                match other {
-                    Piece::Empty => false,
-                    Piece::X => false,
-                    Piece::O => true,
-                }
-            }
+            PlayerPiece::X => { PlayerPiece::O }
+            PlayerPiece::O => { PlayerPiece::X }
+        }
+    }
+}
+
+impl From<PlayerPiece> for Piece {
+    fn from(piece: PlayerPiece) -> Piece {
+        match piece {
+            PlayerPiece::X => { Piece::X }
+            PlayerPiece::O => { Piece::O }
         }
     }
 }
 
+impl TryFrom<Piece> for PlayerPiece {
+    type Error = BoardError;
+
+    fn try_from(piece: Piece) -> Result<PlayerPiece, BoardError> {
+        match piece {
+            Piece::X => { Ok(PlayerPiece::X) }
+            Piece::O => { Ok(PlayerPiece::O) }
+            Piece::Empty => { Err(BoardError::InvalidPiece) }
+        }
+    }
+}
+
+impl PartialEq<Piece> for PlayerPiece {
+    fn eq(&self, other: &Piece) -> bool {
+        Piece::from(*self) == *other
+    }
+}
+
+impl PartialEq<PlayerPiece> for Piece {
+    fn eq(&self, other: &PlayerPiece) -> bool {
+        *self == Piece::from(*other)
+    }
+}
+
+impl fmt::Display for PlayerPiece {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Piece::from(*self))
+    }
+}
+
+/// A Tic-Tac-Toe board
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// let mut board = Board::new();
+/// board.player_move("b2", "X").unwrap();
+/// assert_eq!(board.get_compact_state()[4], Piece::X);
+/// assert_eq!(board.check_winner(), None);
+/// ```
+#[derive(Clone)]
 pub struct Board {
     squares: [[Piece; 3]; 3],
+    /// Which piece makes the first move after any handicap pieces are placed
+    first_to_move: PlayerPiece,
+    /// Number of pieces placed on the board as a handicap before the game started
+    handicap_count: u8,
+    /// Every move made since the game started, as (row, col, piece), oldest first
+    history: Vec<(usize, usize, Piece)>,
 }
 
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut repr = String::new();
-        repr.push_str(
-            &format!(
-                "
-     1   2   3
-       |   |
-a    {} | {} | {}
-    ___|___|___
-       |   |
-b    {} | {} | {}
-    ___|___|___
-       |   |
-c    {} | {} | {}
-       |   |   \n",
-                self.squares[0][0], self.squares[0][1], self.squares[0][2],
-                self.squares[1][0], self.squares[1][1], self.squares[1][2],
-                self.squares[2][0], self.squares[2][1], self.squares[2][2],
-            )
-        );
-        write!(f, "{}", repr)
+        write!(f, "{}", self.render(None))
     }
 }
 
@@ -83,30 +132,192 @@ impl PartialEq for Board {
     }
 }
 
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Board {
     pub fn new() -> Board {
         Board {
             squares: [[Piece::Empty, Piece::Empty, Piece::Empty],
                 [Piece::Empty, Piece::Empty, Piece::Empty],
-                [Piece::Empty, Piece::Empty, Piece::Empty], ]
+                [Piece::Empty, Piece::Empty, Piece::Empty], ],
+            first_to_move: PlayerPiece::X,
+            handicap_count: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /// Create a board with some pieces already placed as a handicap, each given as
+    /// (row, col, piece). The side with fewer handicap pieces moves first (X if they have the
+    /// same number), after which play alternates as usual.
+    pub fn with_handicap(pieces: &[(usize, usize, Piece)]) -> Result<Board, BoardError> {
+        let mut board = Board::new();
+        for (row, col, piece) in pieces {
+            if *row > 2 || *col > 2 {
+                return Err(BoardError::InvalidMove(format!("({}, {})", row, col)));
+            }
+            if *piece == Piece::Empty {
+                return Err(BoardError::InvalidPiece);
+            }
+            if board.squares[*row][*col] != Piece::Empty {
+                return Err(BoardError::NotEmpty);
+            }
+            board.squares[*row][*col] = *piece;
+        }
+        // The handicap can't already decide the game
+        if board.check_winner().is_some() || board.is_full() {
+            return Err(BoardError::InvalidState);
+        }
+        let x_count = pieces.iter().filter(|(_, _, p)| *p == Piece::X).count();
+        let o_count = pieces.iter().filter(|(_, _, p)| *p == Piece::O).count();
+        board.first_to_move = if o_count < x_count { PlayerPiece::O } else { PlayerPiece::X };
+        board.handicap_count = pieces.len() as u8;
+        Ok(board)
+    }
+
+    /// Create a board from its compact state (see [`Board::get_compact_state`]), which must be
+    /// one a game from the empty board could reach: X moves first, so there must be as many Xs
+    /// as Os or one more, otherwise [`BoardError::InvalidState`]. The board has no move
+    /// history.
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// let mut state = [Piece::Empty; 9];
+    /// state[4] = Piece::X;
+    /// let board = Board::from_compact_state(state).unwrap();
+    /// assert_eq!(board.turn(), Some(PlayerPiece::O));
+    /// state[0] = Piece::X;
+    /// assert_eq!(Board::from_compact_state(state).err(), Some(BoardError::InvalidState));
+    /// ```
+    pub fn from_compact_state(state: [Piece; 9]) -> Result<Board, BoardError> {
+        let x_count = state.iter().filter(|p| **p == Piece::X).count();
+        let o_count = state.iter().filter(|p| **p == Piece::O).count();
+        if x_count != o_count && x_count != o_count + 1 {
+            return Err(BoardError::InvalidState);
+        }
+        let mut board = Board::new();
+        for (idx, piece) in state.iter().enumerate() {
+            board.squares[idx / 3][idx % 3] = *piece;
+        }
+        Ok(board)
+    }
+
+    /// Parse a handicap specification such as "b2:X" or "b2:X,a1:O" into the
+    /// (row, col, piece) triples accepted by [`Board::with_handicap`]
+    pub fn parse_handicap(specification: &str) -> Result<Vec<(usize, usize, Piece)>, BoardError> {
+        let mut pieces = Vec::new();
+        for entry in specification.split(',') {
+            let (square, piece) = match entry.trim().split_once(':') {
+                Some(parts) => parts,
+                None => { return Err(BoardError::InvalidMove(entry.trim().to_string())) }
+            };
+            let square: Coord = square.parse()?;
+            let piece = match piece.trim() {
+                "X" | "x" => Piece::X,
+                "O" | "o" => Piece::O,
+                _ => { return Err(BoardError::InvalidPiece) }
+            };
+            pieces.push((square.row() as usize, square.col() as usize, piece));
+        }
+        Ok(pieces)
+    }
+
+    /// Render the board in the given style
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    /// use tictacrs::game::board::RenderStyle;
+    ///
+    /// let mut board = Board::new();
+    /// board.player_move("b2", "X").unwrap();
+    /// assert_eq!(board.render_as(RenderStyle::Spoken),
+    ///            "Row a: all empty. Row b: empty, X, empty. Row c: all empty.");
+    /// assert_eq!(board.render_as(RenderStyle::Grid(None)), board.to_string());
+    /// ```
+    pub fn render_as(&self, style: RenderStyle) -> String {
+        match style {
+            RenderStyle::Grid(hints) => { self.render(hints) }
+            RenderStyle::Spoken => {
+                let rows: Vec<String> = ROW_LABELS.iter().zip(self.squares.iter())
+                    .map(|(label, row)| {
+                        let squares: Vec<String> = row.iter().map(|piece| match piece {
+                            Piece::Empty => { "empty".to_string() }
+                            piece => { piece.to_string() }
+                        }).collect();
+                        if row.iter().all(|piece| *piece == Piece::Empty) {
+                            format!("Row {}: all empty.", label)
+                        } else {
+                            format!("Row {}: {}.", label, squares.join(", "))
+                        }
+                    })
+                    .collect();
+                rows.join(" ")
+            }
+        }
+    }
+
+    /// Render the board as its Display does, optionally showing the digit naming each empty
+    /// square in the given layout
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    /// use tictacrs::game::coords::DigitLayout;
+    ///
+    /// let mut board = Board::new();
+    /// board.player_move("a1", "X").unwrap();
+    /// assert!(board.render(Some(DigitLayout::Reading)).contains("a    X | 2 | 3"));
+    /// assert_eq!(board.render(None), board.to_string());
+    /// ```
+    pub fn render(&self, hints: Option<DigitLayout>) -> String {
+        // The labels come from the same place the move parser reads them from
+        let mut repr = format!("\n     {}   {}   {}\n", COL_LABELS[0], COL_LABELS[1], COL_LABELS[2]);
+        for (row, label) in ROW_LABELS.iter().enumerate() {
+            let squares: Vec<String> = (0..3).map(|col| {
+                match (self.squares[row][col], hints) {
+                    (Piece::Empty, Some(layout)) => {
+                        Coord::new(row as u8, col as u8).map(|coord| coord.to_digit(layout).to_string())
+                            .expect("Every square of the board has a coordinate")
+                    }
+                    (piece, _) => { piece.to_string() }
+                }
+            }).collect();
+            repr.push_str("       |   |\n");
+            repr.push_str(&format!("{}    {} | {} | {}\n", label, squares[0], squares[1], squares[2]));
+            if row < 2 {
+                repr.push_str("    ___|___|___\n");
+            }
+        }
+        repr.push_str("       |   |   \n");
+        repr
+    }
+
+    /// Whether this board started with handicap pieces on it
+    pub fn is_handicapped(&self) -> bool {
+        self.handicap_count > 0
+    }
+
+    /// Which piece is to move, or None if the game is over. Play alternates starting from X,
+    /// or from the side with fewer pieces for a handicapped board.
+    pub fn turn(&self) -> Option<PlayerPiece> {
+        if self.check_winner().is_some() || self.is_full() {
+            return None;
+        }
+        let placed = self.get_compact_state().iter().filter(|p| **p != Piece::Empty).count();
+        let moves_made = placed - self.handicap_count as usize;
+        if moves_made.is_multiple_of(2) {
+            Some(self.first_to_move)
+        } else {
+            Some(self.first_to_move.opponent())
         }
     }
 
     pub fn player_move(&mut self, move_specification: &str, piece_specification: &str) -> Result<(), BoardError> {
-        let move_specification_chars: Vec<char> = move_specification.chars().collect();
-        let row: usize = match move_specification_chars[0] {
-            'a' | 'A' => 0,
-            'b' | 'B' => 1,
-            'c' | 'C' => 2,
-            _ => { return Err(BoardError::InvalidMove) }
-        };
-        let col: usize = match move_specification_chars[1] {
-            '1' => 0,
-            '2' => 1,
-            '3' => 2,
-            _ => { return Err(BoardError::InvalidMove) }
-        };
-        self.make_move(row, col, piece_specification)?;
+        let coord: Coord = move_specification.parse()?;
+        self.make_move(coord.row() as usize, coord.col() as usize, piece_specification)?;
         Ok(())
     }
 
@@ -119,27 +330,62 @@ impl Board {
         match val {
             "X" | "x" => {
                 self.squares[row][col] = Piece::X;
+                self.history.push((row, col, Piece::X));
                 Ok(())
             }
             "O" | "o" => {
                 self.squares[row][col] = Piece::O;
+                self.history.push((row, col, Piece::O));
                 Ok(())
             }
             _ => { Err(BoardError::InvalidPiece) }
         }
     }
 
-    /// Make a move using a Piece object instead of a str
-    pub(crate) fn make_auto_player_move(&mut self, row:u8, col:u8, piece: Piece){
-        self.squares[row as usize][col as usize] = piece;
+    /// Make a move using a PlayerPiece object instead of a str
+    pub(crate) fn make_auto_player_move(&mut self, row:u8, col:u8, piece: PlayerPiece){
+        self.squares[row as usize][col as usize] = piece.into();
+        self.history.push((row as usize, col as usize, piece.into()));
+    }
+
+    /// Remove the piece at (row, col), used to undo a move made with make_auto_player_move
+    pub(crate) fn clear_square(&mut self, row: u8, col: u8) {
+        self.squares[row as usize][col as usize] = Piece::Empty;
+        self.history.retain(|(r, c, _)| (*r, *c) != (row as usize, col as usize));
+    }
+
+    /// Take back the most recent move, returning the (row, col, piece) removed, or None if no
+    /// moves have been made. Handicap pieces aren't moves, so are never taken back.
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// let mut board = Board::new();
+    /// board.player_move("b2", "X").unwrap();
+    /// assert_eq!(board.undo_move(), Some((1, 1, Piece::X)));
+    /// assert_eq!(board.undo_move(), None);
+    /// ```
+    pub fn undo_move(&mut self) -> Option<(usize, usize, Piece)> {
+        let (row, col, piece) = self.history.pop()?;
+        self.squares[row][col] = Piece::Empty;
+        Some((row, col, piece))
+    }
+
+    /// The moves made since the game started, as (row, col, piece), in the order they were made
+    pub fn history(&self) -> &[(usize, usize, Piece)] {
+        &self.history
     }
 
+    /// Remove all pieces from the board, including any handicap pieces
     pub fn clear_board(&mut self){
         for row in 0..3{
             for col in 0..3{
                 self.squares[row][col] = Piece::Empty;
             }
         }
+        self.first_to_move = PlayerPiece::X;
+        self.handicap_count = 0;
+        self.history.clear();
     }
 
     pub fn get_compact_state(&self) -> [Piece; 9] {
@@ -154,73 +400,286 @@ impl Board {
 
     /// Check if the board is full, returns true if the board is full, and false otherwise
     pub fn is_full(&self)->bool{
-        for row in 0..3{
-            for col in 0..3{
-                if self.squares[row][col]==Piece::Empty{
-                    return false
-                }
-            }
-        }
-        true
+        rules::is_full(&self.get_compact_state())
     }
 
     /// Determine if there is a winner, if neither player has won return None
     pub fn check_winner(&self) -> Option<Piece> {
-        if let Some(winner) = self.check_winner_col() {
-            return Some(winner);
+        rules::winner(&self.get_compact_state())
+    }
+
+    /// The result of the game, or None if it isn't over yet. A board which is full but has a
+    /// completed line is a win, not a draw.
+    pub fn result(&self) -> Option<GameResult> {
+        rules::game_state(&self.get_compact_state()).result()
+    }
+
+    /// Whether neither side can complete a line any more, although the board isn't full yet (a
+    /// dead draw). A line can still be completed while it holds only one side's pieces and
+    /// that side has enough moves left to fill it, with the other side playing elsewhere.
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// let mut board = Board::new();
+    /// for (square, piece) in [("b2", "X"), ("a1", "O"), ("a2", "X"), ("c2", "O"), ("b1", "X"),
+    ///                         ("b3", "O"), ("a3", "X")] {
+    ///     assert!(!board.is_dead_draw());
+    ///     board.player_move(square, piece).unwrap();
+    /// }
+    /// // O blocks the last open line, after which nobody can win
+    /// board.player_move("c1", "O").unwrap();
+    /// assert!(board.is_dead_draw());
+    /// assert_eq!(board.result(), None);
+    /// assert_eq!(board.early_result(), Some(GameResult::Draw));
+    /// ```
+    pub fn is_dead_draw(&self) -> bool {
+        let to_move = match self.turn() {
+            Some(piece) => { piece }
+            None => { return false }
+        };
+        let compact_state = self.get_compact_state();
+        let empty = compact_state.iter().filter(|p| **p == Piece::Empty).count();
+        let winnable = |line: &[usize; 3], piece: PlayerPiece| {
+            if line.iter().any(|idx| compact_state[*idx] == Piece::from(piece.opponent())) {
+                return false;
+            }
+            let needed = line.iter().filter(|idx| compact_state[**idx] == Piece::Empty).count();
+            // Moves are made alternately, so filling the line also takes the other side's
+            // moves in between (and first, if it's their turn)
+            let moves = if piece == to_move { 2 * needed - 1 } else { 2 * needed };
+            moves <= empty
+        };
+        !LINES.iter().any(|line| winnable(line, PlayerPiece::X) || winnable(line, PlayerPiece::O))
+    }
+
+    /// Same as [`Board::result`], except that a dead draw (see [`Board::is_dead_draw`]) is
+    /// already a draw
+    pub fn early_result(&self) -> Option<GameResult> {
+        match self.result() {
+            None if self.is_dead_draw() => { Some(GameResult::Draw) }
+            result => { result }
+        }
+    }
+
+    /// Whether the square at (row, col) can currently be played, and if not why. Doesn't
+    /// modify the board.
+    pub fn move_legality(&self, row: usize, col: usize) -> MoveLegality {
+        if row > 2 || col > 2 {
+            return MoveLegality::OutOfBounds;
         }
-        if let Some(winner) = self.check_winner_row() {
-            return Some(winner);
+        if let Some(result) = self.result() {
+            return MoveLegality::GameOver(result);
         }
-        if let Some(winner) = self.check_winner_diag() {
-            return Some(winner);
+        match PlayerPiece::try_from(self.squares[row][col]) {
+            Ok(piece) => { MoveLegality::Occupied(piece) }
+            Err(_) => { MoveLegality::Legal }
         }
-        None
     }
 
-    fn check_winner_col(&self) -> Option<Piece> {
-        for col in 0usize..3 {
-            if self.squares[0][col].eq(&self.squares[1][col]) &&
-                self.squares[0][col].eq(&self.squares[2][col]) &&
-                !self.squares[0][col].eq(&Piece::Empty) {
-                return Some(self.squares[0][col]);
+    /// Check and apply a sequence of (row, col, piece) moves on a copy of the board, returning
+    /// the resulting board, or the index of the first illegal move and why it's illegal. The
+    /// board itself is never changed. Moves must be to empty squares on the board, by the
+    /// piece whose turn it is (otherwise [`BoardError::InvalidState`], as for moves after the
+    /// game is over).
+    ///
+    /// ```
+    /// use tictacrs::prelude::*;
+    ///
+    /// let board = Board::new();
+    /// let moves = [(1, 1, Piece::X), (0, 0, Piece::O), (1, 1, Piece::X)];
+    /// assert_eq!(board.try_apply_all(&moves).err(), Some((2, BoardError::NotEmpty)));
+    /// assert_eq!(board.try_apply_all(&moves[..2]).unwrap().turn(), Some(PlayerPiece::X));
+    /// ```
+    pub fn try_apply_all(&self, moves: &[(u8, u8, Piece)]) -> Result<Board, (usize, BoardError)> {
+        let mut board = self.clone();
+        for (index, (row, col, piece)) in moves.iter().enumerate() {
+            let piece = match PlayerPiece::try_from(*piece) {
+                Ok(piece) => { piece }
+                Err(_) => { return Err((index, BoardError::InvalidPiece)) }
+            };
+            match board.move_legality(*row as usize, *col as usize) {
+                MoveLegality::Legal => {}
+                MoveLegality::Occupied(_) => { return Err((index, BoardError::NotEmpty)) }
+                MoveLegality::OutOfBounds => {
+                    return Err((index, BoardError::InvalidMove(format!("({}, {})", row, col))))
+                }
+                MoveLegality::GameOver(_) => { return Err((index, BoardError::InvalidState)) }
+            }
+            if board.turn() != Some(piece) {
+                return Err((index, BoardError::InvalidState));
             }
+            board.make_auto_player_move(*row, *col, piece);
         }
-        None
+        Ok(board)
     }
-    fn check_winner_row(&self) -> Option<Piece> {
-        for row in 0usize..3 {
-            if self.squares[row][0].eq(&self.squares[row][1]) &&
-                self.squares[row][0].eq(&self.squares[row][2]) &&
-                !self.squares[row][0].eq(&Piece::Empty) {
-                return Some(self.squares[row][0]);
+
+    /// The [`MoveLegality`] of every square on the board, indexed by row then column
+    pub fn legality_grid(&self) -> [[MoveLegality; 3]; 3] {
+        let mut grid = [[MoveLegality::Legal; 3]; 3];
+        for (row, grid_row) in grid.iter_mut().enumerate() {
+            for (col, legality) in grid_row.iter_mut().enumerate() {
+                *legality = self.move_legality(row, col);
             }
         }
-        None
+        grid
     }
+}
 
-    fn check_winner_diag(&self) -> Option<Piece> {
-        if self.squares[0][0].eq(&self.squares[1][1]) &&
-            self.squares[0][0].eq(&self.squares[2][2]) &&
-            !self.squares[0][0].eq(&Piece::Empty) {
-            return Some(self.squares[0][0]);
+/// Render two boards side by side, marking the squares which differ with brackets, and
+/// listing them underneath
+///
+/// ```
+/// use tictacrs::prelude::*;
+/// use tictacrs::game::board::diff_display;
+///
+/// let mut played = Board::new();
+/// played.player_move("b2", "X").unwrap();
+/// let diff = diff_display(&Board::new(), &played);
+/// assert!(diff.contains("[X]"));
+/// assert!(diff.ends_with("1 square differs: b2\n"));
+/// ```
+pub fn diff_display(a: &Board, b: &Board) -> String {
+    let a_state = a.get_compact_state();
+    let b_state = b.get_compact_state();
+    let changed: Vec<Coord> = Coord::all()
+        .filter(|coord| a_state[coord.to_index()] != b_state[coord.to_index()])
+        .collect();
+    // Every square is three characters wide, so both grids line up whatever they hold
+    let cell = |piece: Piece, coord: &Coord| {
+        let symbol = compact_board_char(piece);
+        if changed.contains(coord) {
+            format!("[{}]", symbol)
+        } else {
+            format!(" {} ", symbol)
         }
-        if self.squares[2][0].eq(&self.squares[1][1]) &&
-            self.squares[2][0].eq(&self.squares[0][2]) &&
-            !self.squares[2][0].eq(&Piece::Empty) {
-            return Some(self.squares[2][0]);
+    };
+    let header: String = COL_LABELS.iter().map(|label| format!(" {} ", label)).collect();
+    let grid_width = 3 + header.len();
+    let mut lines = vec![
+        format!("{:<width$}{}{}", "left", DIFF_GAP, "right", width = grid_width),
+        format!("   {}{}   {}", header, DIFF_GAP, header).trim_end().to_string(),
+    ];
+    for (row, label) in ROW_LABELS.iter().enumerate() {
+        let mut line = String::new();
+        for state in [&a_state, &b_state] {
+            if !line.is_empty() {
+                line.push_str(DIFF_GAP);
+            }
+            line.push_str(&format!("{}  ", label));
+            for coord in Coord::all().filter(|coord| coord.row() as usize == row) {
+                line.push_str(&cell(state[coord.to_index()], &coord));
+            }
         }
-        None
+        lines.push(line.trim_end().to_string());
     }
+    let squares: Vec<String> = changed.iter().map(|coord| coord.to_string()).collect();
+    lines.push(match changed.len() {
+        0 => { "boards are identical".to_string() }
+        1 => { format!("1 square differs: {}", squares[0]) }
+        n => { format!("{} squares differ: {}", n, squares.join(", ")) }
+    });
+    let mut repr = lines.join("\n");
+    repr.push('\n');
+    repr
+}
+
+/// Space between the two grids rendered by [`diff_display`]
+const DIFF_GAP: &str = "    ";
+
+/// Assert that two boards hold the same pieces, showing a [`diff_display`] of them if not
+///
+/// Only exported with the `test-support` feature, which doctests aren't built with, so the
+/// example isn't run; the unit tests in this module cover it instead.
+///
+/// ```ignore
+/// use tictacrs::assert_boards_eq;
+/// use tictacrs::prelude::*;
+///
+/// let mut board = Board::new();
+/// board.player_move("a1", "X").unwrap();
+/// board.clear_board();
+/// assert_boards_eq!(board, Board::new());
+/// ```
+#[cfg(any(test, feature = "test-support"))]
+#[macro_export]
+macro_rules! assert_boards_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if left != right {
+                    panic!("assertion `left == right` failed for boards\n{}",
+                           $crate::game::board::diff_display(left, right));
+                }
+            }
+        }
+    };
 }
 
+/// How a board is shown to the players, see [`Board::render_as`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RenderStyle {
+    /// The usual grid, optionally showing the digit naming each empty square in the given
+    /// layout
+    Grid(Option<DigitLayout>),
+    /// A sentence per row without any decoration, for screen readers
+    Spoken,
+}
+
+/// Whether a square can be played, see [`Board::move_legality`]
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// let mut board = Board::new();
+/// board.player_move("a1", "X").unwrap();
+/// assert_eq!(board.move_legality(0, 0), MoveLegality::Occupied(PlayerPiece::X));
+/// assert_eq!(board.move_legality(1, 1), MoveLegality::Legal);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MoveLegality {
+    Legal,
+    /// The square already holds the given piece
+    Occupied(PlayerPiece),
+    /// The row or column is outside the board
+    OutOfBounds,
+    /// The game has already finished with the given result
+    GameOver(GameResult),
+}
+
+/// Errors produced when making a move on a [`Board`]
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// let mut board = Board::new();
+/// board.player_move("a1", "X").unwrap();
+/// assert_eq!(board.player_move("a1", "O"), Err(BoardError::NotEmpty));
+/// ```
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum BoardError {
     NotEmpty,
     InvalidPiece,
-    InvalidMove,
+    /// The move, as it was given, isn't a square on the board
+    InvalidMove(String),
+    /// The resulting position isn't a valid game position
+    InvalidState,
 }
 
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardError::NotEmpty => { write!(f, "that square is already taken") }
+            BoardError::InvalidPiece => { write!(f, "the piece must be X or O") }
+            BoardError::InvalidMove(given) => { write!(f, "\"{}\" isn't a square on the board", given) }
+            BoardError::InvalidState => { write!(f, "that isn't a position a game can reach") }
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -230,6 +689,36 @@ mod tests {
         _ = Board::new();
     }
 
+    #[test]
+    fn test_try_apply_all() {
+        let board = Board::new();
+        let moves = [(1, 1, Piece::X), (0, 0, Piece::O), (0, 2, Piece::X), (2, 0, Piece::O),
+                     (1, 0, Piece::X)];
+        let applied = board.try_apply_all(&moves).unwrap();
+        assert_eq!(applied.get_compact_state(), [
+            Piece::O, Piece::Empty, Piece::X,
+            Piece::X, Piece::X, Piece::Empty,
+            Piece::O, Piece::Empty, Piece::Empty,
+        ]);
+        assert_eq!(board.get_compact_state(), [Piece::Empty; 9]);
+        // Occupied square
+        let occupied = [(1, 1, Piece::X), (0, 0, Piece::O), (0, 2, Piece::X), (0, 2, Piece::O)];
+        assert_eq!(board.try_apply_all(&occupied).err(), Some((3, BoardError::NotEmpty)));
+        // Out of turn
+        let out_of_turn = [(1, 1, Piece::X), (0, 0, Piece::X)];
+        assert_eq!(board.try_apply_all(&out_of_turn).err(), Some((1, BoardError::InvalidState)));
+        // Continuing past a win
+        let past_win = [(0, 0, Piece::X), (1, 0, Piece::O), (0, 1, Piece::X), (1, 1, Piece::O),
+                        (0, 2, Piece::X), (1, 2, Piece::O)];
+        assert_eq!(board.try_apply_all(&past_win).err(), Some((5, BoardError::InvalidState)));
+        // Off the board, or not a piece
+        assert_eq!(board.try_apply_all(&[(3, 0, Piece::X)]).err(), Some((0, BoardError::InvalidMove("(3, 0)".to_string()))));
+        assert_eq!(board.try_apply_all(&[(0, 0, Piece::Empty)]).err(), Some((0, BoardError::InvalidPiece)));
+        // Handicapped boards start from the side with fewer pieces
+        let handicapped = Board::with_handicap(&[(1, 1, Piece::X)]).unwrap();
+        assert!(handicapped.try_apply_all(&[(0, 0, Piece::O), (0, 1, Piece::X)]).is_ok());
+    }
+
     #[test]
     fn test_make_move() -> Result<(), BoardError> {
         let mut test_board = Board::new();
@@ -270,12 +759,15 @@ mod tests {
         let mut test_board = Board::new();
         let res = test_board.player_move("z2", "o");
         assert!(res.is_err());
-        assert_eq!(res, Err(BoardError::InvalidMove));
+        assert_eq!(res, Err(BoardError::InvalidMove("z2".to_string())));
 
         let mut test_board = Board::new();
         let res = test_board.player_move("c5", "o");
         assert!(res.is_err());
-        assert_eq!(res, Err(BoardError::InvalidMove));
+        assert_eq!(res, Err(BoardError::InvalidMove("c5".to_string())));
+        assert_eq!(res.unwrap_err().to_string(), "\"c5\" isn't a square on the board");
+        test_board.player_move("b2", "x").unwrap();
+        assert_eq!(test_board.player_move("b2", "o").unwrap_err().to_string(), "that square is already taken");
     }
 
     #[test]
@@ -285,7 +777,6 @@ mod tests {
         test_board.player_move("a1", "o").unwrap();
         test_board.player_move("a2", "o").unwrap();
         test_board.player_move("a3", "o").unwrap();
-        assert_eq!(test_board.check_winner_row(), Some(Piece::O));
         assert_eq!(test_board.check_winner(), Some(Piece::O));
 
         let mut test_board = Board::new();
@@ -293,10 +784,170 @@ mod tests {
         test_board.player_move("a1", "o").unwrap();
         test_board.player_move("b1", "o").unwrap();
         test_board.player_move("c1", "o").unwrap();
-        assert_eq!(test_board.check_winner_col(), Some(Piece::O));
         assert_eq!(test_board.check_winner(), Some(Piece::O));
     }
 
+    #[test]
+    fn test_from_compact_state() -> Result<(), BoardError> {
+        let mut board = Board::new();
+        for (m, p) in [("b2", "X"), ("a1", "O"), ("c3", "X"), ("a3", "O"), ("a2", "X")] {
+            let state = board.get_compact_state();
+            assert_eq!(Board::from_compact_state(state)?.get_compact_state(), state);
+            board.player_move(m, p)?;
+        }
+        let rebuilt = Board::from_compact_state(board.get_compact_state())?;
+        assert!(rebuilt == board);
+        assert_eq!(rebuilt.turn(), board.turn());
+        assert!(rebuilt.history().is_empty());
+        // Positions no game reaches
+        let mut five_x = [Piece::X; 9];
+        five_x[5..].copy_from_slice(&[Piece::O, Piece::Empty, Piece::Empty, Piece::Empty]);
+        assert_eq!(Board::from_compact_state(five_x).err(), Some(BoardError::InvalidState));
+        let mut o_first = [Piece::Empty; 9];
+        o_first[0] = Piece::O;
+        assert_eq!(Board::from_compact_state(o_first).err(), Some(BoardError::InvalidState));
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_move() -> Result<(), BoardError> {
+        let mut board = Board::new();
+        assert_eq!(board.undo_move(), None);
+        board.player_move("b2", "X")?;
+        board.player_move("a1", "O")?;
+        assert_eq!(board.undo_move(), Some((0, 0, Piece::O)));
+        assert_eq!(board.turn(), Some(PlayerPiece::O));
+        board.player_move("c3", "O")?;
+        board.make_auto_player_move(0, 2, PlayerPiece::X);
+        assert_eq!(board.history(), [(1, 1, Piece::X), (2, 2, Piece::O), (0, 2, Piece::X)]);
+        assert_eq!(board.undo_move(), Some((0, 2, Piece::X)));
+        assert_eq!(board.undo_move(), Some((2, 2, Piece::O)));
+        assert_eq!(board.history(), [(1, 1, Piece::X)]);
+        let mut expected = [Piece::Empty; 9];
+        expected[4] = Piece::X;
+        assert_eq!(board.get_compact_state(), expected);
+        // Clearing the board forgets the moves
+        board.clear_board();
+        assert!(board.history().is_empty());
+        assert_eq!(board.undo_move(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_winning_move() -> Result<(), BoardError> {
+        let mut board = Board::with_handicap(&[(2, 0, Piece::O)])?;
+        for (m, p) in [("a1", "X"), ("b1", "O"), ("a2", "X"), ("b2", "O"), ("a3", "X")] {
+            board.player_move(m, p)?;
+        }
+        assert_eq!(board.result(), Some(GameResult::Win(PlayerPiece::X)));
+        assert_eq!(board.undo_move(), Some((0, 2, Piece::X)));
+        assert_eq!(board.result(), None);
+        assert_eq!(board.turn(), Some(PlayerPiece::X));
+        // The handicap piece stays once every move is taken back
+        while board.undo_move().is_some() {}
+        assert_eq!(board.get_compact_state()[6], Piece::O);
+        assert_eq!(board.turn(), Some(PlayerPiece::X));
+        Ok(())
+    }
+
+    #[test]
+    fn test_result() -> Result<(), BoardError> {
+        let mut board = Board::new();
+        assert_eq!(board.result(), None);
+        // X wins on the final move, filling the board
+        for (m, p) in [("a1", "X"), ("a3", "O"), ("a2", "X"), ("b1", "O"), ("c1", "X"),
+                       ("b3", "O"), ("b2", "X"), ("c2", "O"), ("c3", "X")] {
+            board.player_move(m, p)?;
+        }
+        assert!(board.is_full());
+        assert_eq!(board.result(), Some(GameResult::Win(PlayerPiece::X)));
+        board.clear_board();
+        for (m, p) in [("a1", "X"), ("a2", "O"), ("a3", "X"), ("b2", "O"), ("b1", "X"),
+                       ("b3", "O"), ("c2", "X"), ("c1", "O"), ("c3", "X")] {
+            board.player_move(m, p)?;
+        }
+        assert_eq!(board.result(), Some(GameResult::Draw));
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_legality() -> Result<(), BoardError> {
+        let mut board = Board::new();
+        board.player_move("a1", "X")?;
+        board.player_move("b2", "O")?;
+        assert_eq!(board.move_legality(0, 0), MoveLegality::Occupied(PlayerPiece::X));
+        assert_eq!(board.move_legality(1, 1), MoveLegality::Occupied(PlayerPiece::O));
+        assert_eq!(board.move_legality(2, 2), MoveLegality::Legal);
+        assert_eq!(board.move_legality(3, 0), MoveLegality::OutOfBounds);
+        assert_eq!(board.move_legality(0, 7), MoveLegality::OutOfBounds);
+        let grid = board.legality_grid();
+        assert_eq!(grid.iter().flatten().filter(|l| **l == MoveLegality::Legal).count(), 7);
+        // Once the game is won, every square reports the result
+        board.player_move("a2", "X")?;
+        board.player_move("c1", "O")?;
+        board.player_move("a3", "X")?;
+        let result = GameResult::Win(PlayerPiece::X);
+        assert_eq!(board.legality_grid(), [[MoveLegality::GameOver(result); 3]; 3]);
+        assert_eq!(board.move_legality(5, 5), MoveLegality::OutOfBounds);
+        Ok(())
+    }
+
+    #[test]
+    fn test_turn() {
+        let mut test_board = Board::new();
+        assert_eq!(test_board.turn(), Some(PlayerPiece::X));
+        test_board.player_move("a1", "X").unwrap();
+        assert_eq!(test_board.turn(), Some(PlayerPiece::O));
+        test_board.player_move("b1", "O").unwrap();
+        assert_eq!(test_board.turn(), Some(PlayerPiece::X));
+    }
+
+    #[test]
+    fn test_handicap() {
+        // X already has the center, so O moves first
+        let mut test_board = Board::with_handicap(&Board::parse_handicap("b2:X").unwrap()).unwrap();
+        assert!(test_board.is_handicapped());
+        assert_eq!(test_board.squares[1][1], Piece::X);
+        assert_eq!(test_board.turn(), Some(PlayerPiece::O));
+        test_board.player_move("a1", "O").unwrap();
+        assert_eq!(test_board.turn(), Some(PlayerPiece::X));
+        test_board.player_move("a2", "X").unwrap();
+        assert_eq!(test_board.turn(), Some(PlayerPiece::O));
+        test_board.player_move("a3", "O").unwrap();
+        assert_eq!(test_board.turn(), Some(PlayerPiece::X));
+        test_board.player_move("c2", "X").unwrap();
+        assert_eq!(test_board.check_winner(), Some(Piece::X));
+        assert_eq!(test_board.turn(), None);
+
+        // O starts with the center, so X moves first and then play alternates, leaving
+        // O with an extra piece
+        let mut test_board = Board::with_handicap(&[(1, 1, Piece::O)]).unwrap();
+        assert_eq!(test_board.turn(), Some(PlayerPiece::X));
+        test_board.player_move("a1", "X").unwrap();
+        assert_eq!(test_board.turn(), Some(PlayerPiece::O));
+
+        // Clearing the board removes the handicap
+        test_board.clear_board();
+        assert_boards_eq!(test_board, Board::new());
+        assert!(!test_board.is_handicapped());
+        assert_eq!(test_board.turn(), Some(PlayerPiece::X));
+    }
+
+    #[test]
+    fn test_invalid_handicap() {
+        assert_eq!(Board::with_handicap(&[(3, 1, Piece::X)]).err(), Some(BoardError::InvalidMove("(3, 1)".to_string())));
+        assert_eq!(Board::with_handicap(&[(1, 1, Piece::Empty)]).err(), Some(BoardError::InvalidPiece));
+        assert_eq!(Board::with_handicap(&[(1, 1, Piece::X), (1, 1, Piece::O)]).err(),
+                   Some(BoardError::NotEmpty));
+        assert_eq!(Board::with_handicap(&[(0, 0, Piece::X), (1, 1, Piece::X), (2, 2, Piece::X)]).err(),
+                   Some(BoardError::InvalidState));
+        assert_eq!(Board::parse_handicap("b2").err(), Some(BoardError::InvalidMove("b2".to_string())));
+        assert_eq!(Board::parse_handicap("d2:X").err(), Some(BoardError::InvalidMove("d2".to_string())));
+        assert_eq!(Board::parse_handicap("b2:Z").err(), Some(BoardError::InvalidPiece));
+        assert_eq!(Board::parse_handicap("b2:X, a1:o").unwrap(),
+                   vec![(1, 1, Piece::X), (0, 0, Piece::O)]);
+    }
+
     #[test]
     fn test_compact_representation() {
         let mut test_board = Board::new();
@@ -332,4 +983,185 @@ mod tests {
                        Piece::Empty, Piece::X, Piece::Empty,
                    ]);
     }
+
+    /// Whether the side to move, or the other side, can complete a line in some continuation
+    /// of the game, found by searching every continuation
+    fn can_be_won(board: &mut Board, memo: &mut std::collections::HashMap<[Piece; 9], bool>) -> bool {
+        let state = board.get_compact_state();
+        if let Some(&known) = memo.get(&state) {
+            return known;
+        }
+        let winnable = match (board.result(), board.turn()) {
+            (Some(result), _) => { result.winner().is_some() }
+            (None, Some(to_move)) => {
+                let empty: Vec<Coord> = Coord::all().filter(|c| state[c.to_index()] == Piece::Empty).collect();
+                empty.iter().any(|coord| {
+                    board.make_auto_player_move(coord.row(), coord.col(), to_move);
+                    let winnable = can_be_won(board, memo);
+                    board.clear_square(coord.row(), coord.col());
+                    winnable
+                })
+            }
+            (None, None) => { false }
+        };
+        memo.insert(state, winnable);
+        winnable
+    }
+
+    #[test]
+    fn test_dead_draw_matches_search() {
+        let mut memo = std::collections::HashMap::new();
+        let mut earliest = 9;
+        for state in crate::game::states::reachable_states() {
+            let mut board = Board::new();
+            for coord in Coord::all() {
+                if let Ok(piece) = PlayerPiece::try_from(state[coord.to_index()]) {
+                    board.make_auto_player_move(coord.row(), coord.col(), piece);
+                }
+            }
+            let dead = board.is_dead_draw();
+            if board.result().is_some() {
+                assert!(!dead);
+                continue;
+            }
+            assert_eq!(dead, !can_be_won(&mut board, &mut memo), "{}", board);
+            if dead {
+                earliest = earliest.min(state.iter().filter(|p| **p != Piece::Empty).count());
+            }
+        }
+        // The earliest a game can be a dead draw is after six moves
+        assert_eq!(earliest, 6);
+    }
+
+    #[test]
+    fn test_dead_draw_positions() {
+        let board_with = |moves: &[(&str, &str)]| {
+            let mut board = Board::new();
+            for (square, piece) in moves {
+                board.player_move(square, piece).unwrap();
+            }
+            board
+        };
+        // The earliest dead draw, every line is blocked after six moves
+        let earliest = board_with(&[("a2", "X"), ("a1", "O"), ("c1", "X"), ("c2", "O"),
+                                    ("c3", "X"), ("a3", "O")]);
+        assert!(earliest.is_dead_draw());
+        assert_eq!(earliest.early_result(), Some(GameResult::Draw));
+        // Most lines are blocked, but the diagonal a3-c1 is still open for X
+        let open = board_with(&[("b2", "X"), ("a2", "O"), ("c2", "X"), ("b3", "O"),
+                                ("a3", "X"), ("c3", "O")]);
+        assert!(!open.is_dead_draw());
+        assert_eq!(open.early_result(), None);
+        // Row c is open for O, but X takes the last square first
+        let mut short = board_with(&[("b2", "X"), ("a1", "O"), ("a2", "X"), ("c2", "O"),
+                                     ("b1", "X"), ("b3", "O"), ("a3", "X")]);
+        assert!(!short.is_dead_draw());
+        short.player_move("c1", "O").unwrap();
+        assert!(short.is_dead_draw());
+        // Finished games aren't dead draws, they're over
+        assert!(!board_with(&[("a1", "X"), ("b1", "O"), ("a2", "X"), ("b2", "O"),
+                              ("a3", "X")]).is_dead_draw());
+        assert!(!Board::new().is_dead_draw());
+    }
+
+    #[test]
+    fn test_render_hints() {
+        let mut board = Board::new();
+        board.player_move("a1", "X").unwrap();
+        board.player_move("c3", "O").unwrap();
+        let rows = |layout: Option<DigitLayout>| -> Vec<String> {
+            board.render(layout).lines()
+                .filter(|line| line.starts_with(ROW_LABELS))
+                .map(|line| line.to_string())
+                .collect()
+        };
+        assert_eq!(rows(Some(DigitLayout::Reading)), ["a    X | 2 | 3", "b    4 | 5 | 6", "c    7 | 8 | O"]);
+        assert_eq!(rows(Some(DigitLayout::Numpad)), ["a    X | 8 | 9", "b    4 | 5 | 6", "c    1 | 2 | O"]);
+        assert_eq!(rows(None), ["a    X |   |  ", "b      |   |  ", "c      |   | O"]);
+        // Each hint parses back to the square it's shown in
+        for layout in [DigitLayout::Reading, DigitLayout::Numpad] {
+            for (row, line) in rows(Some(layout)).iter().enumerate() {
+                for (col, square) in line[5..].split(" | ").enumerate() {
+                    if let Ok(coord) = Coord::parse_with_layout(square, layout) {
+                        assert_eq!(coord, Coord::new(row as u8, col as u8).unwrap());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_spoken() {
+        let board = Board::new().try_apply_all(&[(0, 0, Piece::X), (0, 2, Piece::O), (1, 1, Piece::X)])
+            .unwrap();
+        assert_eq!(board.render_as(RenderStyle::Spoken),
+                   "Row a: X, empty, O. Row b: empty, X, empty. Row c: all empty.");
+        let full = Board::new().try_apply_all(&[(2, 0, Piece::X), (2, 1, Piece::O), (2, 2, Piece::X)])
+            .unwrap();
+        assert_eq!(full.render_as(RenderStyle::Spoken),
+                   "Row a: all empty. Row b: all empty. Row c: X, O, X.");
+    }
+
+    #[test]
+    fn test_diff_display_identical() {
+        let mut board = Board::new();
+        board.player_move("a1", "X").unwrap();
+        let diff = diff_display(&board, &board);
+        assert_eq!(diff, "\
+left            right
+    1  2  3         1  2  3
+a   X  .  .     a   X  .  .
+b   .  .  .     b   .  .  .
+c   .  .  .     c   .  .  .
+boards are identical
+");
+    }
+
+    #[test]
+    fn test_diff_display_single_square() {
+        let mut before = Board::new();
+        before.player_move("a1", "X").unwrap();
+        let mut after = Board::new();
+        after.player_move("a1", "X").unwrap();
+        after.player_move("b2", "O").unwrap();
+        let diff = diff_display(&before, &after);
+        let lines: Vec<&str> = diff.lines().collect();
+        assert_eq!(lines[3], "b   . [.] .     b   . [O] .");
+        assert_eq!(lines[5], "1 square differs: b2");
+        // The right grid stays aligned with a marked square
+        assert!(lines[2..5].iter().all(|line| line[16..].starts_with(&line[..4])));
+    }
+
+    #[test]
+    fn test_diff_display_all_squares() {
+        let mut a = Board::new();
+        let mut b = Board::new();
+        for (idx, coord) in Coord::all().enumerate() {
+            let (a_piece, b_piece) = if idx % 2 == 0 { (Piece::X, Piece::O) } else { (Piece::O, Piece::X) };
+            a.squares[coord.row() as usize][coord.col() as usize] = a_piece;
+            b.squares[coord.row() as usize][coord.col() as usize] = b_piece;
+        }
+        let diff = diff_display(&a, &b);
+        let lines: Vec<&str> = diff.lines().collect();
+        assert_eq!(lines[2], "a  [X][O][X]    a  [O][X][O]");
+        assert_eq!(lines[5], "9 squares differ: a1, a2, a3, b1, b2, b3, c1, c2, c3");
+        assert!(lines[2..5].iter().all(|line| line[16..].starts_with(&line[..3])));
+    }
+
+    #[test]
+    fn test_assert_boards_eq() {
+        // The example from the macro's docs, which isn't run as a doctest
+        let mut board = Board::new();
+        board.player_move("a1", "X").unwrap();
+        board.clear_board();
+        assert_boards_eq!(board, Board::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "1 square differs: c3")]
+    fn test_assert_boards_eq_panics() {
+        let mut board = Board::new();
+        board.player_move("c3", "X").unwrap();
+        assert_boards_eq!(board, Board::new());
+    }
 }
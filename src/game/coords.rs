@@ -0,0 +1,235 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::game::board::BoardError;
+
+/// Labels for the rows of the board, as used in human notation ("b2" is row b)
+pub const ROW_LABELS: [char; 3] = ['a', 'b', 'c'];
+/// Labels for the columns of the board, as used in human notation ("b2" is column 2)
+pub const COL_LABELS: [char; 3] = ['1', '2', '3'];
+
+/// Names the squares can be given by instead of their notation, with their indices into the
+/// compact state
+const SQUARE_NAMES: [(&str, usize); 11] = [
+    ("top left", 0), ("top", 1), ("top right", 2),
+    ("left", 3), ("centre", 4), ("center", 4), ("middle", 4), ("right", 5),
+    ("bottom left", 6), ("bottom", 7), ("bottom right", 8),
+];
+
+/// How single digit moves are mapped onto the squares of the board
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DigitLayout {
+    /// 1 to 9 in reading order, 1 is a1, 3 is a3 and 9 is c3 (as on a phone keypad)
+    #[default]
+    Reading,
+    /// As on a numeric keypad, 7 8 9 along the top row and 1 2 3 along the bottom, so 7 is a1
+    /// and 3 is c3
+    Numpad,
+}
+
+/// A square on the board, convertible between (row, col), index into the compact state, and
+/// human notation such as "b2"
+///
+/// ```
+/// use tictacrs::game::coords::Coord;
+///
+/// let coord: Coord = "b3".parse().unwrap();
+/// assert_eq!(coord, Coord::new(1, 2).unwrap());
+/// assert_eq!((coord.row(), coord.col()), (1, 2));
+/// assert_eq!(coord.to_index(), 5);
+/// assert_eq!(Coord::new(3, 0), None);
+/// assert_eq!(Coord::from_index(5), Some(coord));
+/// assert_eq!(coord.to_string(), "b3");
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Coord {
+    row: u8,
+    col: u8,
+}
+
+impl Coord {
+    /// Create a coordinate, or None if it's outside the board
+    pub fn new(row: u8, col: u8) -> Option<Coord> {
+        if row < 3 && col < 3 {
+            Some(Coord { row, col })
+        } else {
+            None
+        }
+    }
+
+    /// Row of the square, from 0 (row a) to 2
+    pub fn row(&self) -> u8 {
+        self.row
+    }
+
+    /// Column of the square, from 0 (column 1) to 2
+    pub fn col(&self) -> u8 {
+        self.col
+    }
+
+    /// Index of the square in the compact state
+    pub fn to_index(&self) -> usize {
+        (self.row * 3 + self.col) as usize
+    }
+
+    /// Coordinate of an index into the compact state, or None if it's outside the board
+    pub fn from_index(index: usize) -> Option<Coord> {
+        if index < 9 {
+            Some(Coord { row: (index / 3) as u8, col: (index % 3) as u8 })
+        } else {
+            None
+        }
+    }
+
+    /// All nine squares, in compact state order
+    pub fn all() -> impl Iterator<Item = Coord> {
+        (0..9).filter_map(Coord::from_index)
+    }
+
+    /// Coordinate of a digit from 1 to 9 in the given layout, or None for any other character
+    pub fn from_digit(digit: char, layout: DigitLayout) -> Option<Coord> {
+        let number = digit.to_digit(10)? as usize;
+        if number == 0 {
+            return None;
+        }
+        match layout {
+            DigitLayout::Reading => { Coord::from_index(number - 1) }
+            DigitLayout::Numpad => { Coord::new((2 - (number - 1) / 3) as u8, ((number - 1) % 3) as u8) }
+        }
+    }
+
+    /// Digit naming the square in the given layout
+    pub fn to_digit(&self, layout: DigitLayout) -> char {
+        let number = match layout {
+            DigitLayout::Reading => { self.to_index() + 1 }
+            DigitLayout::Numpad => { (2 - self.row as usize) * 3 + self.col as usize + 1 }
+        };
+        char::from_digit(number as u32, 10).unwrap()
+    }
+
+    /// Coordinate of a square given by name, such as "top left", "centre" or "bottom", in
+    /// either case and with spaces or hyphens, or None if it isn't one
+    ///
+    /// ```
+    /// use tictacrs::game::coords::Coord;
+    ///
+    /// assert_eq!(Coord::from_name("Top-Left"), "a1".parse().ok());
+    /// assert_eq!(Coord::from_name("centre"), "b2".parse().ok());
+    /// assert_eq!(Coord::from_name("bottom"), "c2".parse().ok());
+    /// ```
+    pub fn from_name(name: &str) -> Option<Coord> {
+        let words: Vec<String> = name.split(|c: char| c.is_whitespace() || c == '-')
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_ascii_lowercase())
+            .collect();
+        let name = words.join(" ");
+        SQUARE_NAMES.iter()
+            .find(|(square_name, _)| *square_name == name)
+            .and_then(|(_, index)| Coord::from_index(*index))
+    }
+
+    /// Parse either human notation such as "b2", or a single digit in the given layout
+    ///
+    /// ```
+    /// use tictacrs::game::coords::{Coord, DigitLayout};
+    ///
+    /// assert_eq!(Coord::parse_with_layout("7", DigitLayout::Reading), "c1".parse());
+    /// assert_eq!(Coord::parse_with_layout("7", DigitLayout::Numpad), "a1".parse());
+    /// assert_eq!(Coord::parse_with_layout("b2", DigitLayout::Numpad), "b2".parse());
+    /// ```
+    pub fn parse_with_layout(s: &str, layout: DigitLayout) -> Result<Coord, BoardError> {
+        let invalid = || BoardError::InvalidMove(s.trim().to_string());
+        let chars: Vec<char> = s.trim().chars().collect();
+        match chars[..] {
+            [digit] => { Coord::from_digit(digit, layout).ok_or_else(invalid) }
+            [row, col] => {
+                let row = ROW_LABELS.iter().position(|label| *label == row.to_ascii_lowercase());
+                let col = COL_LABELS.iter().position(|label| *label == col);
+                match (row, col) {
+                    (Some(row), Some(col)) => { Ok(Coord { row: row as u8, col: col as u8 }) }
+                    _ => { Err(invalid()) }
+                }
+            }
+            _ => { Err(invalid()) }
+        }
+    }
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", ROW_LABELS[self.row as usize], COL_LABELS[self.col as usize])
+    }
+}
+
+impl FromStr for Coord {
+    type Err = BoardError;
+
+    /// Parse human notation, a row letter (either case) followed by a column number, or a
+    /// single digit from 1 to 9 in reading order
+    fn from_str(s: &str) -> Result<Coord, BoardError> {
+        Coord::parse_with_layout(s, DigitLayout::Reading)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::Board;
+
+    #[test]
+    fn test_round_trips() {
+        let coords: Vec<Coord> = Coord::all().collect();
+        assert_eq!(coords.len(), 9);
+        for (index, coord) in coords.iter().enumerate() {
+            assert_eq!(coord.to_index(), index);
+            assert_eq!(Coord::from_index(index), Some(*coord));
+            assert_eq!(Coord::new(coord.row(), coord.col()), Some(*coord));
+            assert_eq!(coord.to_string().parse::<Coord>(), Ok(*coord));
+            assert_eq!(coord.to_string().to_uppercase().parse::<Coord>(), Ok(*coord));
+        }
+        assert_eq!(Coord::from_index(9), None);
+        assert_eq!(Coord::new(3, 0), None);
+        for invalid in ["", "b", "d1", "a0", "a4", "b22", "2b", "0", "10", "x"] {
+            assert_eq!(invalid.parse::<Coord>(), Err(BoardError::InvalidMove(invalid.to_string())));
+        }
+    }
+
+    #[test]
+    fn test_digits() {
+        let reading = ["a1", "a2", "a3", "b1", "b2", "b3", "c1", "c2", "c3"];
+        let numpad = ["c1", "c2", "c3", "b1", "b2", "b3", "a1", "a2", "a3"];
+        for (digit, (reading, numpad)) in ('1'..='9').zip(reading.iter().zip(numpad)) {
+            let in_reading = Coord::parse_with_layout(&digit.to_string(), DigitLayout::Reading);
+            assert_eq!(in_reading, reading.parse());
+            assert_eq!(in_reading.unwrap().to_digit(DigitLayout::Reading), digit);
+            assert_eq!(digit.to_string().parse::<Coord>(), reading.parse());
+            let in_numpad = Coord::parse_with_layout(&digit.to_string(), DigitLayout::Numpad);
+            assert_eq!(in_numpad, numpad.parse());
+            assert_eq!(in_numpad.unwrap().to_digit(DigitLayout::Numpad), digit);
+        }
+        for layout in [DigitLayout::Reading, DigitLayout::Numpad] {
+            assert_eq!(Coord::from_digit('0', layout), None);
+            assert_eq!(Coord::parse_with_layout("0", layout), Err(BoardError::InvalidMove("0".to_string())));
+        }
+    }
+
+    #[test]
+    fn test_display_labels_parse() {
+        // Read the labels back off a rendered board: the column header, then the first
+        // character of each line showing a row of pieces
+        let rendered = Board::new().to_string();
+        let lines: Vec<&str> = rendered.lines().filter(|line| !line.trim().is_empty()).collect();
+        let col_labels: Vec<&str> = lines[0].split_whitespace().collect();
+        let row_labels: Vec<char> = lines.iter()
+            .filter_map(|line| line.chars().next())
+            .filter(|c| c.is_alphabetic())
+            .collect();
+        assert_eq!(col_labels.len(), 3);
+        assert_eq!(row_labels.len(), 3);
+        for (row, row_label) in row_labels.iter().enumerate() {
+            for (col, col_label) in col_labels.iter().enumerate() {
+                let coord: Coord = format!("{}{}", row_label, col_label).parse().unwrap();
+                assert_eq!(coord, Coord { row: row as u8, col: col as u8 });
+            }
+        }
+    }
+}
@@ -1 +1,7 @@
-pub mod board;
\ No newline at end of file
+pub mod board;
+pub mod coords;
+pub mod result;
+pub mod rules;
+pub mod states;
+pub mod symmetry;
+pub mod threats;
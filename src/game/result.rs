@@ -0,0 +1,62 @@
+use crate::game::board::PlayerPiece;
+
+/// The result of a finished game
+///
+/// ```
+/// use tictacrs::prelude::*;
+///
+/// let result = GameResult::Win(PlayerPiece::O);
+/// assert_eq!(result.for_piece(PlayerPiece::O), GameOutcomeFor::Win);
+/// assert_eq!(result.for_piece(PlayerPiece::X), GameOutcomeFor::Loss);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GameResult {
+    /// The given piece completed a line
+    Win(PlayerPiece),
+    /// The board filled up without anyone completing a line
+    Draw,
+}
+
+/// The result of a finished game from the point of view of one of the players
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GameOutcomeFor {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl GameResult {
+    /// The result as seen by the player using the given piece
+    pub fn for_piece(&self, piece: PlayerPiece) -> GameOutcomeFor {
+        match self {
+            GameResult::Win(winner) if *winner == piece => { GameOutcomeFor::Win }
+            GameResult::Win(_) => { GameOutcomeFor::Loss }
+            GameResult::Draw => { GameOutcomeFor::Draw }
+        }
+    }
+
+    /// The winning piece, if there is one
+    pub fn winner(&self) -> Option<PlayerPiece> {
+        match self {
+            GameResult::Win(winner) => { Some(*winner) }
+            GameResult::Draw => { None }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_piece() {
+        assert_eq!(GameResult::Win(PlayerPiece::X).for_piece(PlayerPiece::X), GameOutcomeFor::Win);
+        assert_eq!(GameResult::Win(PlayerPiece::X).for_piece(PlayerPiece::O), GameOutcomeFor::Loss);
+        assert_eq!(GameResult::Win(PlayerPiece::O).for_piece(PlayerPiece::O), GameOutcomeFor::Win);
+        assert_eq!(GameResult::Win(PlayerPiece::O).for_piece(PlayerPiece::X), GameOutcomeFor::Loss);
+        assert_eq!(GameResult::Draw.for_piece(PlayerPiece::X), GameOutcomeFor::Draw);
+        assert_eq!(GameResult::Draw.for_piece(PlayerPiece::O), GameOutcomeFor::Draw);
+        assert_eq!(GameResult::Win(PlayerPiece::O).winner(), Some(PlayerPiece::O));
+        assert_eq!(GameResult::Draw.winner(), None);
+    }
+}
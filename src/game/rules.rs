@@ -0,0 +1,226 @@
+//! When a game is over and who won, from the compact state of a board. [`Board`] and
+//! [`Player`] both follow these rules, so they can't disagree about a position.
+//!
+//! [`Board`]: crate::game::board::Board
+//! [`Player`]: crate::agents::players::Player
+use crate::game::board::{Piece, PlayerPiece};
+use crate::game::result::GameResult;
+use crate::game::threats::LINES;
+
+/// Whether a game is still going, or how it ended
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GameState {
+    InProgress,
+    /// The given piece completed a line
+    Won(Piece),
+    /// The board filled up without anyone completing a line
+    Draw,
+}
+
+impl GameState {
+    /// The result of the game, or None if it's still going
+    pub fn result(&self) -> Option<GameResult> {
+        match self {
+            GameState::InProgress => { None }
+            GameState::Won(piece) => { PlayerPiece::try_from(*piece).ok().map(GameResult::Win) }
+            GameState::Draw => { Some(GameResult::Draw) }
+        }
+    }
+}
+
+/// The piece which has completed a line, or None if neither has
+///
+/// ```
+/// use tictacrs::game::rules::winner;
+/// use tictacrs::prelude::*;
+///
+/// let mut state = [Piece::Empty; 9];
+/// for square in [2, 4, 6] {
+///     state[square] = Piece::O;
+/// }
+/// assert_eq!(winner(&state), Some(Piece::O));
+/// ```
+pub fn winner(compact_state: &[Piece; 9]) -> Option<Piece> {
+    winner_on(compact_state, &LINES)
+}
+
+/// Whether every square has a piece on it
+pub fn is_full(compact_state: &[Piece; 9]) -> bool {
+    !compact_state.contains(&Piece::Empty)
+}
+
+/// Whether the game is still going, or how it ended. A full board with a completed line is a
+/// win, not a draw.
+pub fn game_state(compact_state: &[Piece; 9]) -> GameState {
+    match winner(compact_state) {
+        Some(piece) => { GameState::Won(piece) }
+        None if is_full(compact_state) => { GameState::Draw }
+        None => { GameState::InProgress }
+    }
+}
+
+/// The piece which has completed one of the given lines, if any
+fn winner_on(compact_state: &[Piece; 9], lines: &[[usize; 3]]) -> Option<Piece> {
+    lines.iter()
+        .find(|line| compact_state[line[0]] != Piece::Empty
+            && line.iter().all(|square| compact_state[*square] == compact_state[line[0]]))
+        .map(|line| compact_state[line[0]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::board::Board;
+    use crate::game::states::reachable_states;
+
+    // Rows, columns and diagonals, as they're laid out in LINES
+    fn rows() -> &'static [[usize; 3]] {
+        &LINES[..3]
+    }
+
+    fn columns() -> &'static [[usize; 3]] {
+        &LINES[3..6]
+    }
+
+    fn diagonals() -> &'static [[usize; 3]] {
+        &LINES[6..]
+    }
+
+    #[test]
+    fn test_winner_columns() {
+        let test_board: [Piece; 9] = [
+            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
+        ];
+        assert_eq!(winner_on(&test_board, columns()), None);
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::O, Piece::X,
+            Piece::O, Piece::O, Piece::O,
+            Piece::O, Piece::X, Piece::X,
+        ];
+        assert_eq!(winner_on(&test_board, columns()), None);
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::Empty, Piece::Empty,
+            Piece::X, Piece::Empty, Piece::Empty,
+            Piece::X, Piece::Empty, Piece::Empty,
+        ];
+        assert_eq!(winner_on(&test_board, columns()), Some(Piece::X));
+    }
+
+    #[test]
+    fn test_winner_rows() {
+        let test_board: [Piece; 9] = [
+            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
+        ];
+        assert_eq!(winner_on(&test_board, rows()), None);
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::O, Piece::X,
+            Piece::O, Piece::O, Piece::X,
+            Piece::X, Piece::O, Piece::O,
+        ];
+        assert_eq!(winner_on(&test_board, rows()), None);
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::X, Piece::X,
+            Piece::O, Piece::O, Piece::X,
+            Piece::X, Piece::O, Piece::O,
+        ];
+        assert_eq!(winner_on(&test_board, rows()), Some(Piece::X));
+    }
+
+    #[test]
+    fn test_winner_diagonals() {
+        let test_board: [Piece; 9] = [
+            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
+        ];
+        assert_eq!(winner_on(&test_board, diagonals()), None);
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::X, Piece::X,
+            Piece::O, Piece::O, Piece::O,
+            Piece::X, Piece::X, Piece::X,
+        ];
+        assert_eq!(winner_on(&test_board, diagonals()), None);
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::O, Piece::O,
+            Piece::O, Piece::X, Piece::O,
+            Piece::O, Piece::O, Piece::X,
+        ];
+        assert_eq!(winner_on(&test_board, diagonals()), Some(Piece::X));
+        // O on the anti-diagonal, whatever is in the top left corner
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::X, Piece::O,
+            Piece::X, Piece::O, Piece::Empty,
+            Piece::O, Piece::Empty, Piece::Empty,
+        ];
+        assert_eq!(winner_on(&test_board, diagonals()), Some(Piece::O));
+        let test_board: [Piece; 9] = [
+            Piece::Empty, Piece::X, Piece::O,
+            Piece::X, Piece::O, Piece::Empty,
+            Piece::O, Piece::X, Piece::Empty,
+        ];
+        assert_eq!(winner_on(&test_board, diagonals()), Some(Piece::O));
+    }
+
+    #[test]
+    fn test_winner() {
+        let test_board: [Piece; 9] = [
+            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
+        ];
+        assert_eq!(winner(&test_board), None);
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::Empty, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::Empty,
+        ];
+        assert_eq!(winner(&test_board), None);
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::O, Piece::X,
+            Piece::O, Piece::O, Piece::X,
+            Piece::X, Piece::X, Piece::O,
+        ];
+        assert_eq!(winner(&test_board), None);
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::Empty, Piece::Empty,
+            Piece::X, Piece::Empty, Piece::Empty,
+            Piece::X, Piece::Empty, Piece::Empty,
+        ];
+        assert_eq!(winner(&test_board), Some(Piece::X));
+    }
+
+    #[test]
+    fn test_game_state() {
+        assert_eq!(game_state(&[Piece::Empty; 9]), GameState::InProgress);
+        let drawn: [Piece; 9] = [
+            Piece::X, Piece::O, Piece::X,
+            Piece::O, Piece::O, Piece::X,
+            Piece::X, Piece::X, Piece::O,
+        ];
+        assert!(is_full(&drawn));
+        assert_eq!(game_state(&drawn), GameState::Draw);
+        assert_eq!(game_state(&drawn).result(), Some(GameResult::Draw));
+        // Won on the last square, so full but not a draw
+        let won: [Piece; 9] = [
+            Piece::X, Piece::O, Piece::X,
+            Piece::O, Piece::X, Piece::O,
+            Piece::O, Piece::X, Piece::X,
+        ];
+        assert_eq!(game_state(&won), GameState::Won(Piece::X));
+        assert_eq!(game_state(&won).result(), Some(GameResult::Win(PlayerPiece::X)));
+    }
+
+    #[test]
+    fn test_board_follows_rules() {
+        for state in reachable_states() {
+            let board = Board::from_compact_state(state).unwrap();
+            assert_eq!(board.check_winner(), winner(&state));
+            assert_eq!(board.is_full(), is_full(&state));
+            assert_eq!(board.result(), game_state(&state).result());
+        }
+    }
+}
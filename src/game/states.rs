@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+use crate::game::board::{Board, Piece, PlayerPiece};
+
+/// The eight symmetries of the board (rotations and reflections), each given as the index of
+/// the square which moves to each index, starting with the identity
+pub const SYMMETRIES: [[usize; 9]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8],
+    [6, 3, 0, 7, 4, 1, 8, 5, 2],
+    [8, 7, 6, 5, 4, 3, 2, 1, 0],
+    [2, 5, 8, 1, 4, 7, 0, 3, 6],
+    [2, 1, 0, 5, 4, 3, 8, 7, 6],
+    [6, 7, 8, 3, 4, 5, 0, 1, 2],
+    [0, 3, 6, 1, 4, 7, 2, 5, 8],
+    [8, 5, 2, 7, 4, 1, 6, 3, 0],
+];
+
+/// Number of codes [`encode_state`] can produce, one for every arrangement of pieces (legal or
+/// not)
+pub const STATE_CODES: usize = 19_683;
+
+/// The position as a number below [`STATE_CODES`], reading the squares as base 3 digits (empty
+/// 0, X 1, O 2) with the top left square first, so codes sort in the same order as positions
+///
+/// ```
+/// use tictacrs::game::states::{decode_state, encode_state};
+/// use tictacrs::prelude::*;
+///
+/// let mut state = [Piece::Empty; 9];
+/// state[8] = Piece::O;
+/// assert_eq!(encode_state(&state), 2);
+/// assert_eq!(decode_state(2), state);
+/// ```
+pub fn encode_state(compact_state: &[Piece; 9]) -> u16 {
+    compact_state.iter().fold(0, |code, piece| code * 3 + match piece {
+        Piece::Empty => { 0 }
+        Piece::X => { 1 }
+        Piece::O => { 2 }
+    })
+}
+
+/// The position a code from [`encode_state`] stands for, panics if the code isn't below
+/// [`STATE_CODES`]
+pub fn decode_state(code: u16) -> [Piece; 9] {
+    assert!((code as usize) < STATE_CODES, "State codes are below {}", STATE_CODES);
+    let mut state = [Piece::Empty; 9];
+    let mut code = code;
+    for square in state.iter_mut().rev() {
+        *square = match code % 3 {
+            0 => { Piece::Empty }
+            1 => { Piece::X }
+            _ => { Piece::O }
+        };
+        code /= 3;
+    }
+    state
+}
+
+/// The distinct positions a state can be rotated or reflected into, not including the state
+/// itself (so at most seven)
+pub fn symmetric_images(compact_state: &[Piece; 9]) -> Vec<[Piece; 9]> {
+    let mut images: Vec<[Piece; 9]> = Vec::with_capacity(7);
+    for symmetry in &SYMMETRIES[1..] {
+        let image: [Piece; 9] = std::array::from_fn(|idx| compact_state[symmetry[idx]]);
+        if image != *compact_state && !images.contains(&image) {
+            images.push(image);
+        }
+    }
+    images
+}
+
+/// All positions which can be reached from the empty board by legal play, with X moving
+/// first and no moves made once the game has been won or drawn
+pub fn reachable_states() -> Vec<[Piece; 9]> {
+    let mut states: Vec<[Piece; 9]> = Vec::new();
+    let mut seen: HashSet<[Piece; 9]> = HashSet::new();
+    let mut board = Board::new();
+    visit_states(&mut board, PlayerPiece::X, &mut seen, &mut states);
+    states
+}
+
+/// Depth first search over the positions reachable from the current board
+fn visit_states(board: &mut Board, to_move: PlayerPiece, seen: &mut HashSet<[Piece; 9]>,
+                states: &mut Vec<[Piece; 9]>) {
+    let compact_state = board.get_compact_state();
+    if !seen.insert(compact_state) {
+        return;
+    }
+    states.push(compact_state);
+    if board.check_winner().is_some() || board.is_full() {
+        return;
+    }
+    for idx in 0u8..9 {
+        if compact_state[idx as usize] == Piece::Empty {
+            board.make_auto_player_move(idx / 3, idx % 3, to_move);
+            visit_states(board, to_move.opponent(), seen, states);
+            board.clear_square(idx / 3, idx % 3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reachable_states() {
+        let states = reachable_states();
+        // Well known count of legal tic-tac-toe positions
+        assert_eq!(states.len(), 5478);
+        assert_eq!(states[0], [Piece::Empty; 9]);
+        for state in states {
+            let x_count = state.iter().filter(|p| **p == Piece::X).count();
+            let o_count = state.iter().filter(|p| **p == Piece::O).count();
+            assert!(x_count == o_count || x_count == o_count + 1);
+        }
+    }
+
+    #[test]
+    fn test_encode_state() {
+        let mut previous = None;
+        for code in 0..STATE_CODES as u16 {
+            let state = decode_state(code);
+            assert_eq!(encode_state(&state), code);
+            // Codes sort like the positions they stand for
+            assert!(previous < Some(state));
+            previous = Some(state);
+        }
+        assert_eq!(encode_state(&[Piece::O; 9]) as usize, STATE_CODES - 1);
+    }
+
+    #[test]
+    fn test_symmetric_images() {
+        assert!(symmetric_images(&[Piece::Empty; 9]).is_empty());
+        // A corner maps onto the other three corners
+        let mut corner = [Piece::Empty; 9];
+        corner[0] = Piece::X;
+        let images = symmetric_images(&corner);
+        assert_eq!(images.len(), 3);
+        for idx in [2, 6, 8] {
+            let mut image = [Piece::Empty; 9];
+            image[idx] = Piece::X;
+            assert!(images.contains(&image));
+        }
+        // Without any symmetry of its own, a state has seven distinct images
+        let mut state = [Piece::Empty; 9];
+        state[0] = Piece::X;
+        state[1] = Piece::O;
+        assert_eq!(symmetric_images(&state).len(), 7);
+        for image in symmetric_images(&state) {
+            assert!(symmetric_images(&image).contains(&state));
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! The rotations and reflections of the board as transforms of positions and moves, and the
+//! canonical form which a position and all its images share
+use crate::game::board::Piece;
+use crate::game::states::SYMMETRIES;
+
+/// One of the eight symmetries of the board, see [`SYMMETRIES`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Transform(usize);
+
+impl Transform {
+    /// The transform leaving the board as it is
+    pub const IDENTITY: Transform = Transform(0);
+
+    /// Every transform, starting with the identity
+    pub fn all() -> impl Iterator<Item = Transform> {
+        (0..SYMMETRIES.len()).map(Transform)
+    }
+
+    /// The transform which undoes this one
+    pub fn inverse(self) -> Transform {
+        Transform::all()
+            .find(|inverse| (0..9).all(|idx| SYMMETRIES[self.0][SYMMETRIES[inverse.0][idx]] == idx))
+            .expect("The symmetries form a group")
+    }
+
+    /// The position the transform takes a position to
+    pub fn apply(self, compact_state: &[Piece; 9]) -> [Piece; 9] {
+        std::array::from_fn(|idx| compact_state[SYMMETRIES[self.0][idx]])
+    }
+}
+
+/// The smallest of a position's images, which every image of it shares, and the transform
+/// taking the position there
+///
+/// ```
+/// use tictacrs::game::symmetry::canonical_form;
+/// use tictacrs::prelude::*;
+///
+/// let mut corner = [Piece::Empty; 9];
+/// corner[0] = Piece::X;
+/// let mut other_corner = [Piece::Empty; 9];
+/// other_corner[8] = Piece::X;
+/// assert_eq!(canonical_form(&corner).0, canonical_form(&other_corner).0);
+/// ```
+pub fn canonical_form(compact_state: &[Piece; 9]) -> ([Piece; 9], Transform) {
+    Transform::all()
+        .map(|transform| (transform.apply(compact_state), transform))
+        .min_by(|a, b| a.0.cmp(&b.0))
+        .expect("There is always the identity")
+}
+
+/// Where the transform takes the square a move (as row and column) is made on
+pub fn apply_transform(chosen: [u8; 2], transform: Transform) -> [u8; 2] {
+    let square = (chosen[0] * 3 + chosen[1]) as usize;
+    let moved = SYMMETRIES[transform.0].iter().position(|from| *from == square)
+        .expect("Every symmetry moves every square") as u8;
+    [moved / 3, moved % 3]
+}
+
+/// The square a move made after the transform was on before it, undoing [`apply_transform`]
+pub fn invert_transform(chosen: [u8; 2], transform: Transform) -> [u8; 2] {
+    apply_transform(chosen, transform.inverse())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::states::{reachable_states, symmetric_images};
+
+    #[test]
+    fn test_canonical_form() {
+        for state in reachable_states() {
+            let (canonical, transform) = canonical_form(&state);
+            assert_eq!(transform.apply(&state), canonical);
+            assert_eq!(transform.inverse().apply(&canonical), state);
+            for image in symmetric_images(&state) {
+                assert_eq!(canonical_form(&image).0, canonical);
+            }
+        }
+        assert_eq!(canonical_form(&[Piece::Empty; 9]), ([Piece::Empty; 9], Transform::IDENTITY));
+    }
+
+    #[test]
+    fn test_transform_moves() {
+        let mut state = [Piece::Empty; 9];
+        state[0] = Piece::X;
+        state[5] = Piece::O;
+        for transform in Transform::all() {
+            let image = transform.apply(&state);
+            for idx in 0u8..9 {
+                let chosen = [idx / 3, idx % 3];
+                let moved = apply_transform(chosen, transform);
+                // A piece on the square ends up on the moved square
+                assert_eq!(image[(moved[0] * 3 + moved[1]) as usize], state[idx as usize]);
+                assert_eq!(invert_transform(moved, transform), chosen);
+            }
+        }
+        // The quarter turn takes the top left corner to the top right
+        assert_eq!(apply_transform([0, 0], Transform(1)), [0, 2]);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::game::board::Piece;
+use crate::game::coords::{COL_LABELS, ROW_LABELS};
+
+/// The eight lines of three squares (as indices into the compact state) which win the game
+pub const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2], [3, 4, 5], [6, 7, 8],
+    [0, 3, 6], [1, 4, 7], [2, 5, 8],
+    [0, 4, 8], [2, 4, 6],
+];
+
+/// Human readable name of one of the [`LINES`]
+pub fn describe_line(line: &[usize; 3]) -> String {
+    match line {
+        [0, 4, 8] => "the diagonal a1-c3".to_string(),
+        [2, 4, 6] => "the diagonal a3-c1".to_string(),
+        [a, b, _] if b - a == 1 => format!("row {}", ROW_LABELS[a / 3]),
+        [a, _, _] => format!("column {}", COL_LABELS[*a]),
+    }
+}
+
+/// The lines which the given piece would complete by playing at the square index
+pub fn lines_completed(compact_state: &[Piece; 9], square: usize, piece: Piece) -> Vec<[usize; 3]> {
+    if compact_state[square] != Piece::Empty {
+        return Vec::new();
+    }
+    LINES.iter()
+        .filter(|line| line.contains(&square))
+        .filter(|line| line.iter().filter(|idx| **idx != square)
+            .all(|idx| compact_state[*idx] == piece))
+        .copied()
+        .collect()
+}
+
+/// All empty squares (as indices) where the given piece would immediately win
+pub fn winning_squares(compact_state: &[Piece; 9], piece: Piece) -> Vec<usize> {
+    (0..9).filter(|square| !lines_completed(compact_state, *square, piece).is_empty())
+        .collect()
+}
+
+/// Whether playing the given piece at the square index creates at least two different
+/// immediate winning threats (a fork)
+pub fn is_fork(compact_state: &[Piece; 9], square: usize, piece: Piece) -> bool {
+    if compact_state[square] != Piece::Empty {
+        return false;
+    }
+    let mut after_move = *compact_state;
+    after_move[square] = piece;
+    winning_squares(&after_move, piece).len() >= 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_line() {
+        let names: Vec<String> = LINES.iter().map(describe_line).collect();
+        assert_eq!(names, vec!["row a", "row b", "row c", "column 1", "column 2", "column 3",
+                               "the diagonal a1-c3", "the diagonal a3-c1"]);
+    }
+
+    #[test]
+    fn test_winning_squares() {
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::X, Piece::Empty,
+            Piece::O, Piece::O, Piece::Empty,
+            Piece::X, Piece::Empty, Piece::Empty,
+        ];
+        assert_eq!(winning_squares(&test_board, Piece::X), vec![2]);
+        assert_eq!(winning_squares(&test_board, Piece::O), vec![5]);
+        assert_eq!(lines_completed(&test_board, 2, Piece::X), vec![[0, 1, 2]]);
+        assert!(lines_completed(&test_board, 0, Piece::X).is_empty());
+    }
+
+    #[test]
+    fn test_is_fork() {
+        // X in opposite corners, playing a third corner threatens both a row and a column
+        let test_board: [Piece; 9] = [
+            Piece::X, Piece::Empty, Piece::O,
+            Piece::Empty, Piece::O, Piece::Empty,
+            Piece::Empty, Piece::Empty, Piece::X,
+        ];
+        assert!(is_fork(&test_board, 6, Piece::X));
+        assert!(!is_fork(&test_board, 3, Piece::X));
+        assert!(!is_fork(&test_board, 0, Piece::X));
+    }
+}
@@ -0,0 +1,193 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, BufRead, IsTerminal, StdinLock, Write};
+
+/// Longest line kept, in bytes, anything past it is dropped with a warning
+pub(crate) const MAX_LINE_LENGTH: usize = 1024;
+
+/// How lines which can't be read are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InputMode {
+    /// Someone is typing, so they're told and asked again
+    Interactive,
+    /// The input is piped in, so there's nobody to ask and reading stops
+    Scripted,
+}
+
+impl InputMode {
+    /// Interactive if stdin is a terminal, scripted if it's piped in
+    pub(crate) fn of_stdin() -> InputMode {
+        if io::stdin().is_terminal() { InputMode::Interactive } else { InputMode::Scripted }
+    }
+}
+
+/// Why no line was read
+#[derive(Debug)]
+pub(crate) enum InputError {
+    /// The input ended
+    Eof,
+    /// A scripted line wasn't valid UTF-8, numbered from 1
+    InvalidUtf8 { line: usize },
+    Io(io::Error),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Eof => { write!(f, "the input ended") }
+            InputError::InvalidUtf8 { line } => { write!(f, "line {} of the input isn't valid UTF-8", line) }
+            InputError::Io(e) => { write!(f, "couldn't read the input: {}", e) }
+        }
+    }
+}
+
+/// Reads lines of input without buffering more than [`MAX_LINE_LENGTH`] of any one, counting
+/// them so problems can be reported by line
+pub(crate) struct LineReader<R: BufRead> {
+    reader: R,
+    mode: InputMode,
+    /// Number of lines read so far
+    line_number: usize,
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub(crate) fn new(reader: R, mode: InputMode) -> LineReader<R> {
+        LineReader { reader, mode, line_number: 0 }
+    }
+
+    /// The next line, without its line ending. Warnings about lines which were truncated, or
+    /// (when interactive) couldn't be read and were skipped, are written to `warnings`.
+    pub(crate) fn read_line(&mut self, warnings: &mut impl Write) -> Result<String, InputError> {
+        loop {
+            let (bytes, truncated) = self.read_bounded()?;
+            self.line_number += 1;
+            let text = match String::from_utf8(bytes) {
+                Ok(text) => { text }
+                // The cut can split a character, which is dropped along with the rest
+                Err(e) if truncated && e.utf8_error().error_len().is_none() => {
+                    let valid = e.utf8_error().valid_up_to();
+                    let mut bytes = e.into_bytes();
+                    bytes.truncate(valid);
+                    String::from_utf8(bytes).expect("Cut at the end of the valid characters")
+                }
+                Err(_) => {
+                    match self.mode {
+                        InputMode::Interactive => {
+                            _ = writeln!(warnings, "Sorry, that line couldn't be read as text, try again");
+                            continue;
+                        }
+                        InputMode::Scripted => {
+                            return Err(InputError::InvalidUtf8 { line: self.line_number });
+                        }
+                    }
+                }
+            };
+            if truncated {
+                _ = writeln!(warnings, "Line {} was longer than {} bytes, only the start of it was read",
+                             self.line_number, MAX_LINE_LENGTH);
+            }
+            return Ok(text.trim_end_matches(['\n', '\r']).to_string());
+        }
+    }
+
+    /// The bytes of the next line, at most [`MAX_LINE_LENGTH`] of them, and whether the rest
+    /// was dropped
+    fn read_bounded(&mut self) -> Result<(Vec<u8>, bool), InputError> {
+        let mut bytes = Vec::new();
+        let mut truncated = false;
+        let mut read_any = false;
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => { available }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => { continue }
+                Err(e) => { return Err(InputError::Io(e)) }
+            };
+            if available.is_empty() {
+                if !read_any {
+                    return Err(InputError::Eof);
+                }
+                return Ok((bytes, truncated));
+            }
+            read_any = true;
+            let (end, found) = match available.iter().position(|byte| *byte == b'\n') {
+                Some(newline) => { (newline + 1, true) }
+                None => { (available.len(), false) }
+            };
+            let kept = end.min(MAX_LINE_LENGTH - bytes.len());
+            truncated |= kept < end && !(found && kept + 1 == end);
+            bytes.extend_from_slice(&available[..kept]);
+            self.reader.consume(end);
+            if found {
+                return Ok((bytes, truncated));
+            }
+        }
+    }
+}
+
+thread_local! {
+    static STDIN: RefCell<LineReader<StdinLock<'static>>> =
+        RefCell::new(LineReader::new(io::stdin().lock(), InputMode::of_stdin()));
+}
+
+/// The next line from stdin, or None once it ends. A line which can't be read is fatal when
+/// the input is scripted, exiting with the line's number.
+pub(crate) fn stdin_line() -> Option<String> {
+    let line = STDIN.with(|lines| lines.borrow_mut().read_line(&mut io::stdout()));
+    match line {
+        Ok(line) => { Some(line) }
+        Err(InputError::Eof) => { None }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_utf8() {
+        let input: &[u8] = b"b2\n\xff\xfe\nc3\n";
+        // Interactive input skips the line, asking again
+        let mut lines = LineReader::new(input, InputMode::Interactive);
+        let mut warnings = Vec::new();
+        assert_eq!(lines.read_line(&mut warnings).unwrap(), "b2");
+        assert_eq!(lines.read_line(&mut warnings).unwrap(), "c3");
+        assert_eq!(String::from_utf8(warnings).unwrap(), "Sorry, that line couldn't be read as text, try again\n");
+        assert!(matches!(lines.read_line(&mut Vec::new()), Err(InputError::Eof)));
+        // Scripted input stops, reporting the line
+        let mut lines = LineReader::new(input, InputMode::Scripted);
+        assert_eq!(lines.read_line(&mut Vec::new()).unwrap(), "b2");
+        let error = lines.read_line(&mut Vec::new()).unwrap_err();
+        assert!(matches!(error, InputError::InvalidUtf8 { line: 2 }));
+        assert_eq!(error.to_string(), "line 2 of the input isn't valid UTF-8");
+    }
+
+    #[test]
+    fn test_long_line() {
+        // Two byte characters after one byte ones, so the cut splits a character
+        let mut input = format!("a{}", "é".repeat(2 * 1024 * 1024)).into_bytes();
+        input.extend_from_slice(b"\r\nq");
+        let mut lines = LineReader::new(io::BufReader::with_capacity(64, &input[..]), InputMode::Scripted);
+        let mut warnings = Vec::new();
+        let line = lines.read_line(&mut warnings).unwrap();
+        assert_eq!(line, format!("a{}", "é".repeat(MAX_LINE_LENGTH / 2 - 1)));
+        assert_eq!(String::from_utf8(warnings).unwrap(),
+                   format!("Line 1 was longer than {} bytes, only the start of it was read\n", MAX_LINE_LENGTH));
+        // The rest of the long line is skipped, and a last line without a newline is still read
+        assert_eq!(lines.read_line(&mut Vec::new()).unwrap(), "q");
+        assert!(matches!(lines.read_line(&mut Vec::new()), Err(InputError::Eof)));
+    }
+
+    #[test]
+    fn test_line_at_limit() {
+        let line = "a".repeat(MAX_LINE_LENGTH - 1);
+        let input = format!("{}\n", line);
+        let mut lines = LineReader::new(input.as_bytes(), InputMode::Scripted);
+        let mut warnings = Vec::new();
+        assert_eq!(lines.read_line(&mut warnings).unwrap(), line);
+        assert!(warnings.is_empty());
+    }
+}
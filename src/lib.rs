@@ -1,2 +1,5 @@
 pub mod game;
-pub mod agents;
\ No newline at end of file
+pub mod agents;
+pub mod formatting;
+pub mod meta;
+pub mod prelude;
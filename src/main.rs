@@ -1,83 +1,631 @@
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use clap::{Parser, Subcommand};
-use annealing::{INITIAL_EXPLORATION_RATE, INITIAL_LEARNING_RATE};
-use tictacrs::agents::players::Player;
-use tictacrs::agents::trainer::Trainer;
-use tictacrs::game::board::Piece;
+use model_store::{format_date, ModelStore};
+use tictacrs::agents::certify::certify;
+use tictacrs::agents::evaluate::{evaluate_baseline, Baseline, BaselineReport};
+use tictacrs::agents::health::ValueDistribution;
+use tictacrs::agents::migrate::migrate_file;
+use tictacrs::agents::players::{LearnScope, Learning, Player, PlyExploration};
+use tictacrs::agents::snapshots::state_history;
+use tictacrs::agents::trainer::{EvalReport, MergePolicy, ParallelOptions, Trainer, TrainingOptions, TrainingReport};
+use tictacrs::formatting::{count, percent};
+use tictacrs::game::board::{Board, Piece, PlayerPiece};
+use tictacrs::game::coords::DigitLayout;
+use tictacrs::meta::parse_compact_board;
+use autosave::AutosaveSettings;
+use output::OutputContext;
+use single_player::{Difficulty, ShareOptions, SinglePlayerOptions};
+use train_config::TrainConfig;
 
 mod two_player;
 mod single_player;
-mod annealing;
+mod autosave;
+mod train_config;
+mod model_store;
+mod tutorial;
+mod storage;
+mod output;
+mod input;
+mod self_test;
 
 fn main() {
     let cli = Cli::parse();
 
     match &cli.command {
-        Some(Commands::Play{trained_directory}) => {
+        Some(Commands::Play {
+                 trained_directory,
+                 handicap,
+                 profile,
+                 autosave_minutes,
+                 autosave_keep,
+                 teach,
+                 show_agent_stats,
+                 freeze,
+                 explore,
+                 recap,
+                 model,
+                 learn_scope,
+                 show_numbers,
+                 numpad,
+                 share,
+                 ascii,
+                 tutorial,
+                 accessible,
+                 read_only_models,
+                 players,
+                 piece,
+                 difficulty,
+             }
+        ) => {
+            if *tutorial {
+                if let Err(e) = tutorial::run_tutorial(&mut io::stdin().lock(), &mut io::stdout()) {
+                    println!("Tutorial ended early: {}", e);
+                }
+                return;
+            }
+            let trained_directory = match resolve_trained_directory(trained_directory, model) {
+                Ok(directory) => { directory }
+                Err(e) => {
+                    println!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            let handicap = match handicap {
+                None => { Vec::new() }
+                Some(spec) => {
+                    match Board::parse_handicap(spec).and_then(|h| Board::with_handicap(&h).map(|_| h)) {
+                        Ok(h) => { h }
+                        Err(e) => {
+                            println!("Invalid handicap \"{}\" ({}), expected e.g. \"b2:X\" or \"b2:X,a1:O\"", spec, e);
+                            return;
+                        }
+                    }
+                }
+            };
+            if *players == Some(GameMode::TwoPlayer) && piece.is_some() {
+                println!("--piece chooses the computer's piece, so it only applies to one player games");
+                std::process::exit(1);
+            }
+            if *players == Some(GameMode::TwoPlayer) && *difficulty != Difficulty::Learned {
+                println!("--difficulty chooses how the computer plays, so it only applies to one player games");
+                std::process::exit(1);
+            }
             println!("Welcome to TicTacRs!");
-            game(trained_directory.clone());
+            let options = SinglePlayerOptions {
+                trained_player_dir: trained_directory,
+                handicap,
+                profile: profile.clone(),
+                autosave: AutosaveSettings {
+                    interval: Duration::from_secs(60 * *autosave_minutes),
+                    keep: *autosave_keep,
+                },
+                teach: *teach,
+                show_agent_stats: *show_agent_stats,
+                recap: *recap || *teach,
+                learning: if *freeze { Learning::Frozen } else { Learning::FixedExploration(*explore) },
+                learn_scope: *learn_scope,
+                digit_layout: if *numpad { DigitLayout::Numpad } else { DigitLayout::Reading },
+                show_numbers: *show_numbers,
+                share: share.then_some(ShareOptions { annotations: true, ascii: *ascii }),
+                accessible: *accessible,
+                read_only_models: *read_only_models,
+                computer_piece: *piece,
+                difficulty: *difficulty,
+            };
+            game(&options, *players);
             println!("Thank you for playing!");
         }
         Some(Commands::Train {
                  iterations,
+                 phases,
+                 symmetric_propagation,
                  output_directory,
                  progress_bar,
+                 compact,
+                 ply_exploration,
+                 early_draw,
+                 health_every,
+                 health_epsilon,
+                 health_max_fraction,
+                 snapshot_every,
+                 snapshot_dir,
+                 checkpoint_every,
+                 threads,
+                 evaluate,
+                 resume,
+                 dry_run,
              }
         ) => {
-            let iterations: u32 = match iterations {
-                None => {10000}
-                Some(i) => {*i}
+            let mut out = OutputContext::stdio(cli.quiet);
+            // Quiet drops the progress bar along with the rest of the human output
+            let options = TrainingOptions {
+                checkpoint_every: *checkpoint_every,
+                ..train_config::training_options(*progress_bar && !out.is_quiet(), *early_draw,
+                                                 *health_every, *health_epsilon,
+                                                 *health_max_fraction, *snapshot_every,
+                                                 snapshot_dir.clone())
             };
-            let output_directory: PathBuf = match output_directory {
+            let config = match TrainConfig::resolve(*iterations, phases.as_deref(),
+                                                    *symmetric_propagation,
+                                                    output_directory.clone(), *compact, options,
+                                                    *evaluate)
+                .map(|config| config.resuming(*resume).threaded(*threads)
+                    .exploring_by_ply(ply_exploration.unwrap_or_default())) {
+                Ok(config) => { config }
+                Err(errors) => {
+                    out.error("Invalid training configuration:");
+                    for error in errors {
+                        out.error(format_args!("  - {}", error));
+                    }
+                    std::process::exit(1);
+                }
+            };
+            if *dry_run {
+                out.machine(&config);
+                return;
+            }
+            out.human(format_args!("Training iterations: {}", count(config.iterations as u64)));
+            let (mut player1, mut player2) = match config.players() {
+                Ok(players) => { players }
+                Err(e) => {
+                    out.error(format_args!("Invalid training configuration: {}", e));
+                    std::process::exit(1);
+                }
+            };
+            let start = player1.get_iteration().max(player2.get_iteration());
+            if config.resume && start > 0 {
+                out.human(format_args!("Resuming from game {}", count(start as u64)));
+            } else if config.resume {
+                out.human(format_args!("Nothing to resume in {}, starting afresh",
+                                       config.output_directory.display()));
+            }
+            let trained = match config.threads {
+                Some(threads) => {
+                    let options = ParallelOptions {
+                        threads,
+                        merge: MergePolicy::default(),
+                        progress_bar: config.options.progress_bar,
+                        early_draws: config.options.early_draws,
+                    };
+                    Trainer::train_parallel(&mut player1, &mut player2, config.iterations,
+                                            &config.output_directory, options)
+                }
+                None => {
+                    Trainer::train_phases(&mut player1, &mut player2, &config.phases,
+                                          &config.output_directory, config.options.clone(),
+                                          |_, _| {},
+                                          |games, piece, health| {
+                                              out.human(format_args!(
+                                                  "Warning: after {} games player {}'s table \
+                                                   is {}", count(games as u64), piece, health));
+                                          })
+                }
+            };
+            if let Ok(ref report) = trained {
+                out.human(report);
+            }
+            if let Some(check) = config.options.health_check {
+                for player in [&player1, &player2] {
+                    let distribution = ValueDistribution::of_player(player);
+                    out.human(format_args!("Player {} values: {} ({})", player.get_player_piece(),
+                                           distribution, distribution.health(&check.thresholds)));
+                }
+            }
+            // Learning should never break its invariants, so report any time it did
+            for player in [&player1, &player2] {
+                let violations = player.learning_violations();
+                if violations.total() > 0 {
+                    out.human(format_args!("Player {} made {}", player.get_player_piece(), violations));
+                }
+            }
+            let saved = trained.and_then(TrainingReport::into_paths);
+            if let Err(ref e) = saved {
+                out.error(format_args!("Training failed: {}", e));
+            }
+            if let (Some(games), Ok(_)) = (config.evaluation_games, &saved) {
+                match Trainer::evaluate(&mut player1, &mut player2, games) {
+                    Ok(report) => { out.machine(format_args!("Evaluation: {}", report)) }
+                    Err(e) => { out.error(format_args!("Evaluation failed: {}", e)) }
+                }
+            }
+            if let (true, Ok((player_x_path, player_o_path))) = (config.compact, saved) {
+                for (player, path) in [(&mut player1, player_x_path), (&mut player2, player_o_path)] {
+                    let report = player.compact();
+                    out.human(format_args!("Compacted player {}: {}", player.get_player_piece(), report));
+                    if let Err(e) = player.save_player_state(&path) {
+                        out.error(format_args!("Couldn't save compacted player to {}: {}", path.display(), e));
+                    }
+                }
+            }
+        }
+        Some(Commands::Models { command }) => {
+            let mut out = OutputContext::stdio(cli.quiet);
+            let store = match ModelStore::default_location() {
+                Some(store) => { store }
                 None => {
-                    std::env::current_dir().unwrap()
+                    out.error("Couldn't find a data directory for the model store, set TICTACRS_DATA_DIR");
+                    std::process::exit(1);
+                }
+            };
+            if let Err(e) = models(&store, command, &mut out) {
+                out.error(e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Migrate { input, output }) => {
+            let mut out = OutputContext::stdio(cli.quiet);
+            let output = output.as_ref().unwrap_or(input);
+            match migrate_file(input, output) {
+                Ok(report) => { out.human(format_args!("{}: {}", input.display(), report)) }
+                Err(e) => {
+                    out.error(format_args!("Couldn't migrate {}: {}", input.display(), e));
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(Commands::Certify { save }) => {
+            let mut out = OutputContext::stdio(cli.quiet);
+            let player = match Player::load(save) {
+                Ok(player) => { player }
+                Err(e) => {
+                    out.error(format_args!("Couldn't read the player {}: {}", save.display(), e));
+                    std::process::exit(1);
+                }
+            };
+            let certificate = certify(&player);
+            out.machine(&certificate);
+            if !certificate.is_certified() {
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::History { snapshots, state, piece }) => {
+            let mut out = OutputContext::stdio(cli.quiet);
+            let piece = piece.unwrap_or_else(|| last_mover(state));
+            let history = match state_history(snapshots, state, piece) {
+                Ok(history) => { history }
+                Err(e) => {
+                    out.error(format_args!("Couldn't read the snapshots in {}: {}", snapshots.display(), e));
+                    std::process::exit(1);
+                }
+            };
+            out.human(format_args!("Value of the position to {} at each snapshot", piece));
+            out.machine(history.to_string().trim_end());
+            match history.biggest_change() {
+                Some((from, to, change)) => {
+                    out.human(format_args!("Changed most between games {} and {} ({:+.3})", from, to, change))
+                }
+                None => { out.human("Not learned in two snapshots in a row") }
+            }
+        }
+        Some(Commands::Duel { x, o, games, seed }) => {
+            let mut out = OutputContext::stdio(cli.quiet);
+            let load = |save: &PathBuf, piece: PlayerPiece| {
+                Player::load_expecting(save, piece)
+                    .map_err(|e| format!("Couldn't read player {} from {}: {}", piece, save.display(), e))
+            };
+            let (mut player_x, mut player_o) = match (load(x, PlayerPiece::X), load(o, PlayerPiece::O)) {
+                (Ok(player_x), Ok(player_o)) => { (player_x, player_o) }
+                (Err(e), _) | (_, Err(e)) => {
+                    out.error(e);
+                    std::process::exit(1);
+                }
+            };
+            if let Some(seed) = seed {
+                player_x.reseed(*seed);
+                player_o.reseed(seed.wrapping_add(1));
+            }
+            match Trainer::duel(&mut player_x, &mut player_o, *games) {
+                Ok(report) => {
+                    out.human(format_args!("{} games, X moving first", count(*games as u64)));
+                    for row in duel_table(&report, x, o) {
+                        out.machine(row);
+                    }
+                }
+                Err(e) => {
+                    out.error(format_args!("The duel failed: {}", e));
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(Commands::Evaluate { model_dir, model, opponent, games, piece, json, fail_if_loses }) => {
+            let mut out = OutputContext::stdio(cli.quiet);
+            let directory = match resolve_trained_directory(model_dir, model) {
+                Ok(Some(directory)) => { directory }
+                Ok(None) => {
+                    out.error("No model directory given and no default model chosen");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    out.error(e);
+                    std::process::exit(1);
+                }
+            };
+            let reports = match evaluate_saves(&directory, *opponent, *piece, *games, rand::random()) {
+                Ok(reports) => { reports }
+                Err(e) => {
+                    out.error(e);
+                    std::process::exit(1);
                 }
-                Some(out) => {out.clone()}
             };
-            println!("Training iterations: {}", iterations);
-            let mut player1 = Player::new(Piece::X,
-                                          INITIAL_LEARNING_RATE,
-                                          INITIAL_EXPLORATION_RATE,
-                                          annealing::learning_rate_function,
-                                          annealing::exploration_rate_function);
-            let mut player2 = Player::new(Piece::O,
-                                          INITIAL_LEARNING_RATE,
-                                          INITIAL_EXPLORATION_RATE,
-                                          annealing::learning_rate_function,
-                                          annealing::exploration_rate_function);
-            _ = Trainer::train(&mut player1, &mut player2, iterations,
-                           &output_directory, *progress_bar)
+            if *json {
+                match serde_json::to_string_pretty(&reports) {
+                    Ok(json) => { out.machine(json) }
+                    Err(e) => {
+                        out.error(format_args!("Couldn't write the report: {}", e));
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                out.human(format_args!("Evaluating the model in {}", directory.display()));
+                for report in &reports {
+                    out.machine(report);
+                }
+            }
+            if *fail_if_loses && *opponent == Baseline::Minimax
+                && reports.iter().any(|report| report.evaluation.losses > 0) {
+                out.error("Lost to minimax");
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::SelfTest) => {
+            let mut out = OutputContext::stdio(cli.quiet);
+            let report = self_test::run_self_test();
+            out.machine(&report);
+            if !report.passed() {
+                std::process::exit(1);
+            }
         }
         None => {}
     }
 }
 
+/// Parse a position written compactly
+fn parse_state(state: &str) -> Result<[Piece; 9], String> {
+    parse_compact_board(state).ok_or_else(|| "expected 9 squares, each one of X, O or .".to_string())
+}
+
+/// Parse a player's piece
+fn parse_piece(piece: &str) -> Result<PlayerPiece, String> {
+    match piece {
+        "x" | "X" => { Ok(PlayerPiece::X) }
+        "o" | "O" => { Ok(PlayerPiece::O) }
+        _ => { Err("expected X or O".to_string()) }
+    }
+}
+
+/// Parse a rate, such as how often to explore, which must be between 0 and 1
+fn parse_rate(rate: &str) -> Result<f64, String> {
+    match rate.parse::<f64>() {
+        Ok(rate) if (0. ..=1.).contains(&rate) => { Ok(rate) }
+        _ => { Err("expected a number between 0 and 1".to_string()) }
+    }
+}
 
-/// Wrapper function to determine if two-player, or one-player mode is desired
-fn game(trained_player_dir: Option<PathBuf>) {
-    let mut new_game: bool = true;
-    // Game Loop
-    loop {
-        if new_game {
-            println!("One or two players? (1/2)");
-            let mut buffer = String::new();
-            io::stdin().read_line(&mut buffer).expect("Failed to read line");
-            let choice = buffer.trim();
-            match choice {
-                "1" => {
+/// Parse a profile name. Profiles name the files their learning is saved in, so are restricted
+/// to the same characters as model names.
+fn parse_profile(profile: &str) -> Result<String, String> {
+    model_store::check_name(profile)
+        .map(|_| profile.to_string())
+        .map_err(|_| "profile names may only contain letters, digits, '-' and '_'".to_string())
+}
 
-                    new_game = single_player::single_player(trained_player_dir.clone());
-                }
-                "2" => {
-                    new_game = two_player::two_player();
-                }
-                _ => {
-                    println!("Sorry, couldn't understand, please try again");
-                    continue;
-                }
+/// Parse the opponent to evaluate a model against
+fn parse_baseline(baseline: &str) -> Result<Baseline, String> {
+    match baseline {
+        "random" => { Ok(Baseline::Random) }
+        "minimax" => { Ok(Baseline::Minimax) }
+        "self" => { Ok(Baseline::SelfPlay) }
+        _ => { Err("expected one of random, minimax or self".to_string()) }
+    }
+}
+
+/// Which of a model's players to evaluate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EvaluatedPieces {
+    One(PlayerPiece),
+    Both,
+}
+
+/// Parse which of a model's players to evaluate
+fn parse_evaluated_pieces(pieces: &str) -> Result<EvaluatedPieces, String> {
+    match pieces {
+        "both" => { Ok(EvaluatedPieces::Both) }
+        _ => { parse_piece(pieces).map(EvaluatedPieces::One).map_err(|_| "expected X, O or both".to_string()) }
+    }
+}
+
+/// Evaluate the players saved in a model directory against a baseline, one report per piece.
+/// Against itself each player plays the other piece's save, so evaluating both plays that
+/// pairing once, reported for X.
+fn evaluate_saves(directory: &Path, baseline: Baseline, pieces: EvaluatedPieces, games: u32, seed: u64)
+                  -> Result<Vec<BaselineReport>, String> {
+    let load = |piece: PlayerPiece| {
+        let save = directory.join(model_store::PLAYER_FILES[piece_index(piece)]);
+        Player::load_expecting(&save, piece)
+            .map_err(|e| format!("Couldn't read player {} from {}: {}", piece, save.display(), e))
+    };
+    let evaluated = match pieces {
+        EvaluatedPieces::One(piece) => { vec![piece] }
+        // O's report would just mirror X's
+        EvaluatedPieces::Both if baseline == Baseline::SelfPlay => { vec![PlayerPiece::X] }
+        EvaluatedPieces::Both => { vec![PlayerPiece::X, PlayerPiece::O] }
+    };
+    let mut reports = Vec::new();
+    for piece in evaluated {
+        let mut player = load(piece)?;
+        let mut counterpart = match baseline {
+            Baseline::SelfPlay => { Some(load(piece.opponent())?) }
+            _ => { None }
+        };
+        let report = evaluate_baseline(&mut player, baseline, counterpart.as_mut(), games, seed)
+            .map_err(|e| format!("Couldn't evaluate player {}: {}", piece, e))?;
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+/// Index of a piece's save in [`model_store::PLAYER_FILES`]
+fn piece_index(piece: PlayerPiece) -> usize {
+    match piece {
+        PlayerPiece::X => { 0 }
+        PlayerPiece::O => { 1 }
+    }
+}
+
+/// Parse how the computer plays in one player games
+fn parse_difficulty(difficulty: &str) -> Result<Difficulty, String> {
+    match difficulty {
+        "learned" => { Ok(Difficulty::Learned) }
+        "impossible" => { Ok(Difficulty::Impossible) }
+        _ => { Err("expected learned or impossible".to_string()) }
+    }
+}
+
+/// The player who made the last move into a position, X if it has more Xs than Os
+fn last_mover(state: &[Piece; 9]) -> PlayerPiece {
+    let count = |piece| state.iter().filter(|square| **square == piece).count();
+    if count(Piece::X) > count(Piece::O) { PlayerPiece::X } else { PlayerPiece::O }
+}
+
+/// Parse the scope of learning in one player games
+fn parse_learn_scope(scope: &str) -> Result<LearnScope, String> {
+    match scope {
+        "all" => { Ok(LearnScope::All) }
+        "own-lines-only" => { Ok(LearnScope::OwnLinesOnly) }
+        "terminal-only" => { Ok(LearnScope::TerminalOnly) }
+        _ => { Err("expected one of all, own-lines-only or terminal-only".to_string()) }
+    }
+}
+
+/// Directory to load the computer players from: an explicit directory, a named model, or the
+/// default model if one has been chosen
+fn resolve_trained_directory(trained_directory: &Option<PathBuf>, model: &Option<String>)
+                             -> Result<Option<PathBuf>, String> {
+    resolve_trained_directory_from(trained_directory, model, ModelStore::default_location())
+}
+
+/// [`resolve_trained_directory`] with the models in the given store, if there is one
+fn resolve_trained_directory_from(trained_directory: &Option<PathBuf>, model: &Option<String>,
+                                  store: Option<ModelStore>) -> Result<Option<PathBuf>, String> {
+    if trained_directory.is_some() {
+        return Ok(trained_directory.clone());
+    }
+    match (model, store) {
+        (Some(name), Some(store)) => {
+            store.model_directory(name).map(Some).map_err(|e| format!("Model {}: {}", name, e))
+        }
+        (Some(_), None) => { Err("Couldn't find a data directory for the model store".to_string()) }
+        (None, Some(store)) => {
+            Ok(store.default_model().and_then(|name| store.model_directory(&name).ok()))
+        }
+        (None, None) => { Ok(None) }
+    }
+}
+
+/// Run one of the models subcommands. The listing is the only machine output, confirmations
+/// are human output.
+fn models(store: &ModelStore, command: &ModelsCommand, out: &mut OutputContext) -> Result<(), String> {
+    match command {
+        ModelsCommand::Save { name, from } => {
+            let source = match from {
+                Some(from) => { from.clone() }
+                None => { std::env::current_dir().map_err(|e| e.to_string())? }
+            };
+            let entry = store.save(name, &source).map_err(|e| format!("Couldn't save model {}: {}", name, e))?;
+            out.human(format_args!("Saved model {} (iteration {})", entry.name,
+                                   count(entry.iteration as u64)));
+        }
+        ModelsCommand::List => {
+            let entries = store.list();
+            if entries.is_empty() {
+                out.human("No saved models");
+                return Ok(());
+            }
+            let default = store.default_model();
+            out.machine(format_args!("  {:<20} {:<10} {:>9}", "name", "saved", "iteration"));
+            for entry in entries {
+                let marker = if default.as_deref() == Some(entry.name.as_str()) { "*" } else { " " };
+                out.machine(format_args!("{} {:<20} {:<10} {:>9}", marker, entry.name,
+                                         format_date(entry.saved), count(entry.iteration as u64)));
             }
-        } else {
+        }
+        ModelsCommand::Use { name } => {
+            store.set_default(name).map_err(|e| format!("Couldn't use model {}: {}", name, e))?;
+            out.human(format_args!("Playing against model {} by default", name));
+        }
+        ModelsCommand::Delete { name } => {
+            store.delete(name).map_err(|e| format!("Couldn't delete model {}: {}", name, e))?;
+            out.human(format_args!("Deleted model {}", name));
+        }
+    }
+    Ok(())
+}
+
+/// Parse the factors scaling the exploration rate at each ply of a training game
+fn parse_ply_exploration(factors: &str) -> Result<PlyExploration, String> {
+    factors.parse().map_err(|_| "expected nine comma separated numbers between 0 and 1".to_string())
+}
+
+/// Play games until told to stop, in the given mode or asking for one before each game
+fn game(options: &SinglePlayerOptions, mode: Option<GameMode>) {
+    let play = |mode| match mode {
+        GameMode::SinglePlayer => { single_player::single_player(options) }
+        GameMode::TwoPlayer => {
+            two_player::two_player(&options.handicap, options.digit_layout, options.render_style(),
+                                   options.share)
+        }
+    };
+    match mode {
+        Some(mode) => { while play(mode) {} }
+        None => { game_loop(input::stdin_line, play) }
+    }
+}
+
+/// Number of people playing a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GameMode {
+    SinglePlayer,
+    TwoPlayer,
+}
+
+/// Parse the answer to "One or two players?"
+fn parse_game_mode(choice: &str) -> Option<GameMode> {
+    match choice.trim() {
+        "1" => { Some(GameMode::SinglePlayer) }
+        "2" => { Some(GameMode::TwoPlayer) }
+        _ => { None }
+    }
+}
+
+/// Parse the number of players given on the command line
+fn parse_players(players: &str) -> Result<GameMode, String> {
+    parse_game_mode(players).ok_or_else(|| "expected 1 or 2".to_string())
+}
+
+/// Ask for a mode and play it, until the game played says not to play again or the answers
+/// run out. `next_line` gives each answer and `play` plays a game, returning whether to play
+/// another.
+fn game_loop<L, P>(mut next_line: L, mut play: P)
+where
+    L: FnMut() -> Option<String>,
+    P: FnMut(GameMode) -> bool,
+{
+    loop {
+        println!("One or two players? (1/2)");
+        let buffer = match next_line() {
+            Some(line) => { line }
+            None => { break; }
+        };
+        let mode = match parse_game_mode(&buffer) {
+            Some(mode) => { mode }
+            None => {
+                println!("Sorry, couldn't understand, please try again");
+                continue;
+            }
+        };
+        if !play(mode) {
             break;
         }
     }
@@ -89,6 +637,10 @@ struct Cli {
     /// Command to Run
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Don't print progress or messages, only the output of train and models commands and
+    /// any errors
+    #[arg(short, long, global = true)]
+    quiet: bool,
 }
 
 #[derive(Subcommand)]
@@ -96,19 +648,456 @@ enum Commands {
     /// Play Game
     Play {
         /// Directory containing the trained players
-        #[arg(short,long)]
+        #[arg(short, long, visible_alias = "model-dir")]
         trained_directory: Option<PathBuf>,
+        /// Name of a model in the model store to play against, instead of a directory
+        #[arg(long, conflicts_with = "trained_directory")]
+        model: Option<String>,
+        /// Pieces placed on the board before the game starts, e.g. "b2:X" or "b2:X,a1:O"
+        #[arg(long)]
+        handicap: Option<String>,
+        /// Name of a player profile, learning in one player games is saved separately for each
+        /// profile, leaving the trained players unchanged
+        #[arg(long, value_parser = parse_profile)]
+        profile: Option<String>,
+        /// How often (in minutes) to autosave the computer player during one player games
+        #[arg(long, default_value_t = 5)]
+        autosave_minutes: u64,
+        /// How many autosaves of each computer player to keep
+        #[arg(long, default_value_t = 3)]
+        autosave_keep: usize,
+        /// Explain each of the computer's moves
+        #[arg(long)]
+        teach: bool,
+        /// Show the computer's iteration and current exploration and learning rates with
+        /// each prompt (also shown in teach mode)
+        #[arg(long)]
+        show_agent_stats: bool,
+        /// Don't let the computer learn from one player games, it always plays its best move
+        #[arg(long)]
+        freeze: bool,
+        /// How often the computer makes an exploratory move while learning in one player games,
+        /// instead of following its training schedule
+        #[arg(long, default_value_t = 0.01, conflicts_with = "freeze", value_parser = parse_rate)]
+        explore: f64,
+        /// Show a recap of the moves after each one player game (always shown in teach mode)
+        #[arg(long)]
+        recap: bool,
+        /// Which states the computer learns in one player games: all, own-lines-only (only the
+        /// positions it moves from) or terminal-only (only the results of games)
+        #[arg(long, default_value = "all", value_parser = parse_learn_scope)]
+        learn_scope: LearnScope,
+        /// Show the digit for each empty square, moves can be given as a digit from 1 (a1) to
+        /// 9 (c3) instead of a row and column
+        #[arg(long)]
+        show_numbers: bool,
+        /// Read digit moves as on a numeric keypad, 7 8 9 along the top row (a1 to a3)
+        #[arg(long)]
+        numpad: bool,
+        /// Print a short summary of each game to paste into a chat (also available during a
+        /// game by typing :share)
+        #[arg(long)]
+        share: bool,
+        /// Use only ASCII characters in the summaries printed with --share
+        #[arg(long, requires = "share")]
+        ascii: bool,
+        /// Play a guided first game against a scripted opponent, explaining how to enter moves
+        /// and when to block or win
+        #[arg(long)]
+        tutorial: bool,
+        /// Describe the board and announce each move in plain sentences, for screen readers.
+        /// Squares can also be given by name, such as "top left" or "centre"
+        #[arg(long)]
+        accessible: bool,
+        /// Never write to the trained player directory (assumed when it isn't writable). With
+        /// --profile, what the computer learns is kept in the user's data directory instead,
+        /// otherwise it isn't saved
+        #[arg(long)]
+        read_only_models: bool,
+        /// Number of players, 1 or 2, instead of asking before each game
+        #[arg(long, value_parser = parse_players)]
+        players: Option<GameMode>,
+        /// Piece the computer plays in one player games, X or O, instead of asking which piece
+        /// you want before each game
+        #[arg(long, value_parser = parse_piece)]
+        piece: Option<PlayerPiece>,
+        /// How the computer plays in one player games: "learned" with the trained player, or
+        /// "impossible" perfectly, so it can't be beaten (and learns nothing)
+        #[arg(long, default_value = "learned", value_parser = parse_difficulty)]
+        difficulty: Difficulty,
     },
     /// Train the players
     Train {
         /// Number of training iterations to run
         #[arg(short, long, value_name = "iterations")]
         iterations: Option<u32>,
+        /// Train in phases during which only some players learn, the others only play, e.g.
+        /// "both:5000,x-only:5000,o-only:5000"
+        #[arg(long, value_name = "phases", conflicts_with = "iterations")]
+        phases: Option<String>,
+        /// Also apply a fraction (default 0.5) of each update to the mirrored and rotated
+        /// copies of a state the players have already seen
+        #[arg(long, value_name = "fraction", num_args = 0..=1, default_missing_value = "0.5")]
+        symmetric_propagation: Option<f64>,
         /// Where the trained player data will be saved to
         #[arg(short, long)]
         output_directory: Option<PathBuf>,
         /// Whether a progress bar should be shown
         #[arg(short, long)]
         progress_bar: bool,
+        /// Remove states the players can never be asked about before saving
+        #[arg(long)]
+        compact: bool,
+        /// Scale the exploration rate by how far into the game the players are, with one factor
+        /// between 0 and 1 for each of the nine moves, e.g. "1,1,0.8,0.8,0.5,0.5,0.2,0.2,0"
+        #[arg(long, value_name = "factors", value_parser = parse_ply_exploration)]
+        ply_exploration: Option<PlyExploration>,
+        /// End training games as draws as soon as neither player can win, instead of playing
+        /// them out
+        #[arg(long)]
+        early_draw: bool,
+        /// Check the spread of the players' values every this many games, warning if nearly
+        /// all of them are stuck at 0 or 1 (saturated) or still at 0.5 (not learning)
+        #[arg(long, value_name = "games")]
+        health_every: Option<u32>,
+        /// How close to 0, 0.5 or 1 a value has to be to count as stuck there
+        #[arg(long, default_value_t = 0.01, requires = "health_every")]
+        health_epsilon: f64,
+        /// Fraction of the values which can be stuck before warning
+        #[arg(long, default_value_t = 0.9, requires = "health_every")]
+        health_max_fraction: f64,
+        /// Save snapshots of both players every this many games, to see how values changed
+        /// during training with the history command
+        #[arg(long, value_name = "games", requires = "snapshot_dir")]
+        snapshot_every: Option<u32>,
+        /// Directory the snapshots are saved in, created if needed
+        #[arg(long, requires = "snapshot_every")]
+        snapshot_dir: Option<PathBuf>,
+        /// Save both players over their save files in the output directory every this many
+        /// games, so an interrupted run can be carried on with --resume
+        #[arg(long, value_name = "games")]
+        checkpoint_every: Option<u32>,
+        /// Train on this many threads at once, 0 for one per core, merging what each learns
+        /// afterwards. Can't be combined with phases, health checks, snapshots or checkpoints.
+        #[arg(long, value_name = "threads",
+              conflicts_with_all = ["phases", "health_every", "snapshot_every", "checkpoint_every"])]
+        threads: Option<usize>,
+        /// After training, play this many games between the trained players, each making its
+        /// best move with X going first, and print how they went
+        #[arg(long, value_name = "games")]
+        evaluate: Option<u32>,
+        /// Carry on training the players saved in the output directory, if there are any, from
+        /// where they left off
+        #[arg(long)]
+        resume: bool,
+        /// Validate the configuration and print it, without training
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Manage the store of named trained models
+    Models {
+        #[command(subcommand)]
+        command: ModelsCommand,
     },
+    /// Bring a player save written by an older version up to the current format
+    Migrate {
+        /// The save file to migrate
+        input: PathBuf,
+        /// Where to write the migrated save, defaults to replacing the input
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Check training, saving, playing and the rules all work on this machine, in a few
+    /// seconds, exiting with an error if any of them don't
+    SelfTest,
+    /// Check a trained player never loses from the start of a game whatever its opponent does,
+    /// printing one of the shortest games it loses if it can
+    Certify {
+        /// The player's save file
+        save: PathBuf,
+    },
+    /// Play two trained players against each other, each making its best move, and compare
+    /// how they did
+    Duel {
+        /// Save file of the player using X
+        #[arg(long, value_name = "save")]
+        x: PathBuf,
+        /// Save file of the player using O
+        #[arg(long, value_name = "save")]
+        o: PathBuf,
+        /// Number of games to play
+        #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u32).range(1..))]
+        games: u32,
+        /// Seed the players' choices between equally good moves, to make the duel reproducible
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Play a trained model against a baseline opponent, each of its players making its best
+    /// move, and report how often it won, drew and lost
+    Evaluate {
+        /// Directory the model's players were saved in, defaults to the default model
+        #[arg(long)]
+        model_dir: Option<PathBuf>,
+        /// Name of a model in the model store to evaluate, instead of a directory
+        #[arg(long, conflicts_with = "model_dir")]
+        model: Option<String>,
+        /// Opponent to play: random, minimax (perfect play) or self (the model's other player)
+        #[arg(long, value_parser = parse_baseline)]
+        opponent: Baseline,
+        /// Number of games each player plays
+        #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u32).range(1..))]
+        games: u32,
+        /// Which of the model's players to evaluate: X, O or both. Against itself both plays
+        /// the one pairing of X and O.
+        #[arg(long, default_value = "both", value_parser = parse_evaluated_pieces)]
+        piece: EvaluatedPieces,
+        /// Write the results as JSON
+        #[arg(long)]
+        json: bool,
+        /// Exit with an error if the model loses any game against minimax. Has no effect
+        /// against the other opponents.
+        #[arg(long)]
+        fail_if_loses: bool,
+    },
+    /// Show how a position's learned value changed across the snapshots taken during training
+    History {
+        /// Directory the snapshots were saved in
+        #[arg(long)]
+        snapshots: PathBuf,
+        /// The position, one of X, O or . per square from a1 to c3, e.g. X...O....
+        #[arg(long, value_parser = parse_state)]
+        state: [Piece; 9],
+        /// Whose table to look in, defaults to the player who moved into the position
+        #[arg(long, value_parser = parse_piece)]
+        piece: Option<PlayerPiece>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ModelsCommand {
+    /// Copy trained players into the store under a name
+    Save {
+        /// Name to save the model as
+        name: String,
+        /// Directory containing the trained players, defaults to the current directory
+        #[arg(short, long)]
+        from: Option<PathBuf>,
+    },
+    /// List the saved models, the default model is marked with a *
+    List,
+    /// Play against the named model by default
+    Use {
+        name: String,
+    },
+    /// Remove a model from the store
+    Delete {
+        name: String,
+    },
+}
+
+/// The header and a row for each player of a duel's results. Each player scores a point for a
+/// win and half a point for a draw, shown as a share of the games.
+fn duel_table(report: &EvalReport, x: &Path, o: &Path) -> Vec<String> {
+    let games = report.games.max(1) as f64;
+    let mut rows = vec![format!("{:<6} {:<30} {:>7} {:>7} {:>7} {:>7}", "piece", "save", "wins", "draws",
+                                "losses", "score")];
+    for (piece, save, wins, losses) in [(PlayerPiece::X, x, report.x_wins, report.o_wins),
+                                        (PlayerPiece::O, o, report.o_wins, report.x_wins)] {
+        let score = (wins as f64 + report.draws as f64 / 2.) / games;
+        rows.push(format!("{:<6} {:<30} {:>7} {:>7} {:>7} {:>7}", piece.to_string(), save.display().to_string(),
+                          count(wins as u64), count(report.draws as u64), count(losses as u64), percent(score)));
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use output::SharedBuffer;
+    use tictacrs::agents::annealing::AnnealingSchedule;
+    use tictacrs::agents::players::Player;
+
+    #[test]
+    fn test_game_loop() {
+        assert_eq!(parse_game_mode(" 1\n"), Some(GameMode::SinglePlayer));
+        assert_eq!(parse_game_mode("2"), Some(GameMode::TwoPlayer));
+        assert_eq!(parse_game_mode("3"), None);
+        // Unclear answers are asked again, and only the game played decides whether to go on
+        let mut answers = ["one", "1", "2", "1"].into_iter().map(String::from);
+        let mut played = Vec::new();
+        game_loop(|| answers.next(), |mode| {
+            played.push(mode);
+            played.len() < 2
+        });
+        assert_eq!(played, vec![GameMode::SinglePlayer, GameMode::TwoPlayer]);
+        assert_eq!(answers.next().as_deref(), Some("1"));
+        // Running out of answers stops
+        let mut played = Vec::new();
+        game_loop(|| None, |mode| {
+            played.push(mode);
+            true
+        });
+        assert!(played.is_empty());
+    }
+
+    #[test]
+    fn test_play_flags() {
+        let cli = Cli::try_parse_from(["tictacrs", "play", "--players", "1", "--piece", "O",
+                                       "--model-dir", "./models"]).unwrap();
+        match cli.command {
+            Some(Commands::Play { trained_directory, players, piece, difficulty, .. }) => {
+                assert_eq!(trained_directory, Some(PathBuf::from("./models")));
+                assert_eq!(players, Some(GameMode::SinglePlayer));
+                assert_eq!(piece, Some(PlayerPiece::O));
+                assert_eq!(difficulty, Difficulty::Learned);
+            }
+            _ => { panic!("expected the play command") }
+        }
+        assert!(Cli::try_parse_from(["tictacrs", "play", "--players", "3"]).is_err());
+        let cli = Cli::try_parse_from(["tictacrs", "play", "--difficulty", "impossible"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Play { difficulty: Difficulty::Impossible, .. })));
+        assert!(Cli::try_parse_from(["tictacrs", "play", "--difficulty", "hard"]).is_err());
+        let cli = Cli::try_parse_from(["tictacrs", "play", "--profile", "alice_2"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Play { profile: Some(name), .. }) if name == "alice_2"));
+        let cli = Cli::try_parse_from(["tictacrs", "play", "--explore", "0.5"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Play { explore, .. }) if explore == 0.5));
+        for explore in ["7", "-1", "1.01", "NaN", "often"] {
+            assert!(Cli::try_parse_from(["tictacrs", "play", "--explore", explore]).is_err());
+        }
+        // Profiles name files in the model directory, so can't point outside it
+        for profile in ["../../x", "a/b", ""] {
+            assert!(Cli::try_parse_from(["tictacrs", "play", "--profile", profile]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_duel() {
+        let cli = Cli::try_parse_from(["tictacrs", "duel", "--x", "a.ttr", "--o", "b.ttr", "--seed", "3"]).unwrap();
+        match cli.command {
+            Some(Commands::Duel { x, o, games, seed }) => {
+                assert_eq!((x, o), (PathBuf::from("a.ttr"), PathBuf::from("b.ttr")));
+                assert_eq!((games, seed), (1000, Some(3)));
+            }
+            _ => { panic!("expected the duel command") }
+        }
+        assert!(Cli::try_parse_from(["tictacrs", "duel", "--x", "a.ttr"]).is_err());
+        // A save only knows the positions of its own piece, so the players can't swap pieces
+        assert!(Cli::try_parse_from(["tictacrs", "duel", "--x", "a.ttr", "--o", "b.ttr", "--swap"]).is_err());
+        assert!(Cli::try_parse_from(["tictacrs", "duel", "--x", "a.ttr", "--o", "b.ttr", "--games", "0"]).is_err());
+        let report = EvalReport { games: 10, x_wins: 6, o_wins: 1, draws: 3, ..EvalReport::default() };
+        let table = duel_table(&report, Path::new("a.ttr"), Path::new("b.ttr"));
+        let cells: Vec<Vec<&str>> = table.iter().map(|row| row.split_whitespace().collect()).collect();
+        assert_eq!(cells, vec![
+            vec!["piece", "save", "wins", "draws", "losses", "score"],
+            vec!["X", "a.ttr", "6", "3", "1", "75.0%"],
+            vec!["O", "b.ttr", "1", "3", "6", "25.0%"],
+        ]);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let cli = Cli::try_parse_from(["tictacrs", "evaluate", "--model-dir", "m", "--opponent", "minimax",
+                                       "--piece", "o", "--fail-if-loses"]).unwrap();
+        match cli.command {
+            Some(Commands::Evaluate { model_dir, model: None, opponent, games, piece, json, fail_if_loses }) => {
+                assert_eq!((model_dir, opponent, games), (Some(PathBuf::from("m")), Baseline::Minimax, 1000));
+                assert_eq!((piece, json, fail_if_loses), (EvaluatedPieces::One(PlayerPiece::O), false, true));
+            }
+            _ => { panic!("expected the evaluate command") }
+        }
+        let cli = Cli::try_parse_from(["tictacrs", "evaluate", "--opponent", "self", "--json"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Evaluate { piece: EvaluatedPieces::Both, json: true, .. })));
+        assert!(Cli::try_parse_from(["tictacrs", "evaluate"]).is_err());
+        assert!(Cli::try_parse_from(["tictacrs", "evaluate", "--opponent", "human"]).is_err());
+        assert!(Cli::try_parse_from(["tictacrs", "evaluate", "--opponent", "random", "--piece", "Z"]).is_err());
+
+        let directory = std::env::temp_dir().join("tictacrs_main_evaluate");
+        _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        assert!(evaluate_saves(&directory, Baseline::Random, EvaluatedPieces::Both, 5, 1).unwrap_err()
+            .starts_with("Couldn't read player X"));
+        let mut player_x = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+        let mut player_o = Player::builder(PlayerPiece::O).seed(2).build().unwrap();
+        Trainer::transitions(&mut player_x, &mut player_o, 500).unwrap().for_each(drop);
+        player_x.save_player_state(directory.join(model_store::PLAYER_FILES[0])).unwrap();
+        player_o.save_player_state(directory.join(model_store::PLAYER_FILES[1])).unwrap();
+        let reports = evaluate_saves(&directory, Baseline::Random, EvaluatedPieces::Both, 6, 1).unwrap();
+        let pieces: Vec<PlayerPiece> = reports.iter().map(|report| report.piece).collect();
+        assert_eq!(pieces, [PlayerPiece::X, PlayerPiece::O]);
+        assert!(reports.iter().all(|report| report.evaluation.games() == 6));
+        // Against itself the pairing of X and O is only played once
+        let reports = evaluate_saves(&directory, Baseline::SelfPlay, EvaluatedPieces::Both, 6, 1).unwrap();
+        let pieces: Vec<PlayerPiece> = reports.iter().map(|report| report.piece).collect();
+        assert_eq!(pieces, [PlayerPiece::X]);
+        assert_eq!(reports[0].evaluation.games(), 6);
+        let reports = evaluate_saves(&directory, Baseline::Minimax, EvaluatedPieces::One(PlayerPiece::O), 4, 1).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].evaluation.wins, 0);
+        let json = serde_json::to_value(&reports).unwrap();
+        assert_eq!(json[0]["piece"], "O");
+        assert_eq!(json[0]["opponent"], "minimax");
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_evaluate_model_by_name() {
+        let directory = std::env::temp_dir().join("tictacrs_main_evaluate_model");
+        _ = std::fs::remove_dir_all(&directory);
+        let trained = directory.join("trained");
+        std::fs::create_dir_all(&trained).unwrap();
+        for (piece, file) in [(PlayerPiece::X, model_store::PLAYER_FILES[0]),
+                              (PlayerPiece::O, model_store::PLAYER_FILES[1])] {
+            Player::builder(piece).seed(1).build().unwrap().save_player_state(trained.join(file)).unwrap();
+        }
+        let store = ModelStore::new(directory.join("models"));
+        store.save("first", &trained).unwrap();
+        assert!(Cli::try_parse_from(["tictacrs", "evaluate", "--opponent", "random", "--model", "first",
+                                     "--model-dir", "m"]).is_err());
+        let cli = Cli::try_parse_from(["tictacrs", "evaluate", "--opponent", "random", "--model", "first",
+                                       "--piece", "X", "--games", "4"]).unwrap();
+        match cli.command {
+            Some(Commands::Evaluate { model_dir, model, opponent, games, piece, .. }) => {
+                let expected = store.model_directory("first").unwrap();
+                let resolved = resolve_trained_directory_from(&model_dir, &model, Some(store)).unwrap().unwrap();
+                assert_eq!(resolved, expected);
+                let reports = evaluate_saves(&resolved, opponent, piece, games, 1).unwrap();
+                assert_eq!(reports.len(), 1);
+                assert_eq!(reports[0].evaluation.games(), 4);
+            }
+            _ => { panic!("expected the evaluate command") }
+        }
+        let unknown = Some("missing".to_string());
+        let store = ModelStore::new(directory.join("models"));
+        assert!(resolve_trained_directory_from(&None, &unknown, Some(store)).unwrap_err().starts_with("Model missing"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_models_output_streams() {
+        let directory = std::env::temp_dir().join("tictacrs_main_models_output");
+        _ = std::fs::remove_dir_all(&directory);
+        let trained = directory.join("trained");
+        std::fs::create_dir_all(&trained).unwrap();
+        for (piece, file) in [(PlayerPiece::X, model_store::PLAYER_FILES[0]),
+                              (PlayerPiece::O, model_store::PLAYER_FILES[1])] {
+            Player::new(piece, 0.5, 0.1, AnnealingSchedule::Constant, AnnealingSchedule::Constant).save_player_state(trained.join(file)).unwrap();
+        }
+        let store = ModelStore::new(directory.join("models"));
+        let (human, machine) = (SharedBuffer::default(), SharedBuffer::default());
+        let mut out = OutputContext::new(Box::new(human.clone()), Box::new(machine.clone()), false);
+        models(&store, &ModelsCommand::List, &mut out).unwrap();
+        assert_eq!((human.contents(), machine.contents()), ("No saved models\n".to_string(), String::new()));
+        models(&store, &ModelsCommand::Save { name: "first".to_string(), from: Some(trained) }, &mut out).unwrap();
+        models(&store, &ModelsCommand::Use { name: "first".to_string() }, &mut out).unwrap();
+        models(&store, &ModelsCommand::List, &mut out).unwrap();
+        // Only the header and one row per model, with the chatter kept apart
+        let listing = machine.contents();
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].split_whitespace().collect::<Vec<_>>(), ["name", "saved", "iteration"]);
+        assert_eq!(lines[1].split_whitespace().next(), Some("*"));
+        assert_eq!(lines[1].split_whitespace().nth(1), Some("first"));
+        assert!(human.contents().contains("Saved model first"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
 }
@@ -0,0 +1,107 @@
+//! Version information about the crate and the files it writes, so tools built on top of it
+//! can tell which saves they understand without hard-coding it
+
+use crate::game::board::Piece;
+
+/// Version of the player and overlay save format written and read by this build, bumped
+/// whenever the layout of a save changes
+pub const SAVE_FORMAT_VERSION: u16 = 5;
+
+/// Characters used for empty, X and O squares when writing a board compactly, in the order
+/// of [`Piece`]
+pub const COMPACT_BOARD_CHARS: [char; 3] = ['.', 'X', 'O'];
+
+/// Version of the crate, as in its manifest
+///
+/// ```
+/// assert!(!tictacrs::meta::crate_version().is_empty());
+/// ```
+pub fn crate_version() -> &'static str {
+    env!("CARGO_PKG_VERSION")
+}
+
+/// Character used for a piece when writing a board compactly
+///
+/// ```
+/// use tictacrs::prelude::*;
+/// use tictacrs::meta::compact_board_char;
+///
+/// assert_eq!(compact_board_char(Piece::Empty), '.');
+/// assert_eq!(compact_board_char(Piece::O), 'O');
+/// ```
+pub fn compact_board_char(piece: Piece) -> char {
+    COMPACT_BOARD_CHARS[piece as usize]
+}
+
+/// Read a board written compactly, one character of [`COMPACT_BOARD_CHARS`] per square from
+/// a1 to c3 (either case), or None if it isn't one
+///
+/// ```
+/// use tictacrs::prelude::*;
+/// use tictacrs::meta::parse_compact_board;
+///
+/// let state = parse_compact_board("X...o....").unwrap();
+/// assert_eq!((state[0], state[4], state[8]), (Piece::X, Piece::O, Piece::Empty));
+/// assert_eq!(parse_compact_board("X..."), None);
+/// ```
+pub fn parse_compact_board(board: &str) -> Option<[Piece; 9]> {
+    let pieces = [Piece::Empty, Piece::X, Piece::O];
+    let mut state = [Piece::Empty; 9];
+    let mut chars = board.chars();
+    for square in state.iter_mut() {
+        let char = chars.next()?.to_ascii_uppercase();
+        *square = pieces[COMPACT_BOARD_CHARS.iter().position(|c| *c == char)?];
+    }
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(state)
+}
+
+/// Which parts of the save format this build understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatCapabilities {
+    /// Version of the save format, see [`SAVE_FORMAT_VERSION`]
+    pub save_format_version: u16,
+    /// Whether players can be saved and read at all (the persistence feature)
+    pub persistence: bool,
+    /// Whether overlays can be saved and read alongside a player
+    pub overlays: bool,
+}
+
+impl FormatCapabilities {
+    /// Capabilities of this build
+    pub const fn current() -> FormatCapabilities {
+        FormatCapabilities {
+            save_format_version: SAVE_FORMAT_VERSION,
+            persistence: cfg!(feature = "persistence"),
+            overlays: cfg!(feature = "persistence"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::board::Piece;
+    use crate::meta::{compact_board_char, crate_version, FormatCapabilities, COMPACT_BOARD_CHARS,
+                      SAVE_FORMAT_VERSION};
+
+    #[test]
+    fn test_golden_values() {
+        // Changing any of these changes what other versions can read, so must be deliberate
+        assert_eq!(SAVE_FORMAT_VERSION, 5);
+        assert_eq!(COMPACT_BOARD_CHARS, ['.', 'X', 'O']);
+        assert_eq!(crate_version(), env!("CARGO_PKG_VERSION"));
+        for (piece, expected) in [(Piece::Empty, '.'), (Piece::X, 'X'), (Piece::O, 'O')] {
+            assert_eq!(compact_board_char(piece), expected);
+        }
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = FormatCapabilities::current();
+        assert_eq!(capabilities.save_format_version, SAVE_FORMAT_VERSION);
+        assert_eq!(capabilities.persistence, cfg!(feature = "persistence"));
+        assert_eq!(capabilities.overlays, capabilities.persistence);
+    }
+}
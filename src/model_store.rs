@@ -0,0 +1,307 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tictacrs::agents::players::Player;
+use tictacrs::game::board::PlayerPiece;
+use crate::storage::data_directory;
+
+/// Save files making up a trained model
+pub(crate) const PLAYER_FILES: [&str; 2] = ["player_x_save.ttr", "player_o_save.ttr"];
+/// Index of the saved models, one `name<TAB>saved<TAB>iteration` line per model
+const INDEX_FILE: &str = "index.txt";
+/// Holds the name of the model used by default when playing
+const DEFAULT_FILE: &str = "default";
+
+/// A named collection of trained player pairs, each stored in its own directory
+pub(crate) struct ModelStore {
+    directory: PathBuf,
+}
+
+/// Metadata about a model in the store
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ModelEntry {
+    pub(crate) name: String,
+    /// When the model was saved, in seconds since the unix epoch
+    pub(crate) saved: u64,
+    /// Training iteration the X player had reached
+    pub(crate) iteration: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ModelStoreError {
+    /// Names may only contain letters, digits, '-' and '_'
+    InvalidName,
+    /// There is no model with the given name
+    NotFound,
+    /// The directory being saved doesn't contain both trained players
+    MissingPlayers,
+    /// Reading or writing the store failed
+    Io(String),
+}
+
+impl fmt::Display for ModelStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelStoreError::InvalidName => {
+                write!(f, "model names may only contain letters, digits, '-' and '_'")
+            }
+            ModelStoreError::NotFound => { write!(f, "no model with that name") }
+            ModelStoreError::MissingPlayers => {
+                write!(f, "the directory doesn't contain player_x_save.ttr and player_o_save.ttr")
+            }
+            ModelStoreError::Io(e) => { write!(f, "couldn't access the model store: {}", e) }
+        }
+    }
+}
+
+impl From<std::io::Error> for ModelStoreError {
+    fn from(e: std::io::Error) -> ModelStoreError {
+        ModelStoreError::Io(e.to_string())
+    }
+}
+
+impl ModelStore {
+    pub(crate) fn new(directory: PathBuf) -> ModelStore {
+        ModelStore { directory }
+    }
+
+    /// The store in the user's data directory: `$TICTACRS_DATA_DIR/models` if set, otherwise
+    /// `$XDG_DATA_HOME/tictacrs/models` or `~/.local/share/tictacrs/models`
+    pub(crate) fn default_location() -> Option<ModelStore> {
+        data_directory().map(|dir| ModelStore::new(dir.join("models")))
+    }
+
+    /// Copy the trained players in `source` into the store under the given name, replacing
+    /// any model already saved with that name
+    pub(crate) fn save(&self, name: &str, source: &Path) -> Result<ModelEntry, ModelStoreError> {
+        check_name(name)?;
+        if PLAYER_FILES.iter().any(|file| !source.join(file).is_file()) {
+            return Err(ModelStoreError::MissingPlayers);
+        }
+        let model_dir = self.directory.join(name);
+        fs::create_dir_all(&model_dir)?;
+        for file in PLAYER_FILES {
+            fs::copy(source.join(file), model_dir.join(file))?;
+        }
+        let saved = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let entry = ModelEntry { name: name.to_string(), saved, iteration: read_iteration(&model_dir) };
+        let mut entries = self.list();
+        entries.retain(|e| e.name != name);
+        entries.push(entry.clone());
+        self.write_index(&entries)?;
+        Ok(entry)
+    }
+
+    /// All models in the store, sorted by name. A missing or unreadable index is rebuilt from
+    /// the model directories.
+    pub(crate) fn list(&self) -> Vec<ModelEntry> {
+        let mut entries = match self.read_index() {
+            Some(entries) => entries,
+            None => self.scan(),
+        };
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries
+    }
+
+    /// Remove a model from the store, clearing the default if it was the default model
+    pub(crate) fn delete(&self, name: &str) -> Result<(), ModelStoreError> {
+        let model_dir = self.model_directory(name)?;
+        fs::remove_dir_all(model_dir)?;
+        let mut entries = self.list();
+        entries.retain(|e| e.name != name);
+        self.write_index(&entries)?;
+        if self.default_model().as_deref() == Some(name) {
+            fs::remove_file(self.directory.join(DEFAULT_FILE))?;
+        }
+        Ok(())
+    }
+
+    /// Use the named model by default when playing
+    pub(crate) fn set_default(&self, name: &str) -> Result<(), ModelStoreError> {
+        self.model_directory(name)?;
+        fs::write(self.directory.join(DEFAULT_FILE), name)?;
+        Ok(())
+    }
+
+    /// Name of the default model, if one is set and still exists
+    pub(crate) fn default_model(&self) -> Option<String> {
+        let name = fs::read_to_string(self.directory.join(DEFAULT_FILE)).ok()?;
+        let name = name.trim();
+        self.model_directory(name).ok().map(|_| name.to_string())
+    }
+
+    /// Directory holding the named model's save files
+    pub(crate) fn model_directory(&self, name: &str) -> Result<PathBuf, ModelStoreError> {
+        check_name(name)?;
+        let model_dir = self.directory.join(name);
+        if model_dir.is_dir() {
+            Ok(model_dir)
+        } else {
+            Err(ModelStoreError::NotFound)
+        }
+    }
+
+    fn read_index(&self) -> Option<Vec<ModelEntry>> {
+        let index = fs::read_to_string(self.directory.join(INDEX_FILE)).ok()?;
+        let mut entries = Vec::new();
+        for line in index.lines().filter(|line| !line.is_empty()) {
+            let mut fields = line.split('\t');
+            let entry = ModelEntry {
+                name: fields.next()?.to_string(),
+                saved: fields.next()?.parse().ok()?,
+                iteration: fields.next()?.parse().ok()?,
+            };
+            if fields.next().is_some() || check_name(&entry.name).is_err() {
+                return None;
+            }
+            entries.push(entry);
+        }
+        Some(entries)
+    }
+
+    fn write_index(&self, entries: &[ModelEntry]) -> Result<(), ModelStoreError> {
+        let mut index = String::new();
+        for entry in entries {
+            index.push_str(&format!("{}\t{}\t{}\n", entry.name, entry.saved, entry.iteration));
+        }
+        fs::create_dir_all(&self.directory)?;
+        fs::write(self.directory.join(INDEX_FILE), index)?;
+        Ok(())
+    }
+
+    /// Build the entries from the model directories themselves
+    fn scan(&self) -> Vec<ModelEntry> {
+        let mut entries = Vec::new();
+        let dir_entries = match fs::read_dir(&self.directory) {
+            Ok(dir_entries) => dir_entries,
+            Err(_) => return entries,
+        };
+        for dir_entry in dir_entries.flatten() {
+            let path = dir_entry.path();
+            let name = match path.file_name().and_then(|n| n.to_str()) {
+                Some(name) if path.is_dir() && check_name(name).is_ok() => name.to_string(),
+                _ => continue,
+            };
+            let saved = fs::metadata(&path).and_then(|m| m.modified()).ok()
+                .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            entries.push(ModelEntry { name, saved, iteration: read_iteration(&path) });
+        }
+        entries
+    }
+}
+
+/// Model names are used as directory names, so are restricted to a safe set of characters
+pub(crate) fn check_name(name: &str) -> Result<(), ModelStoreError> {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        Ok(())
+    } else {
+        Err(ModelStoreError::InvalidName)
+    }
+}
+
+/// Training iteration of the X player in a model directory, 0 if it can't be read
+fn read_iteration(model_dir: &Path) -> u32 {
+    Player::load_expecting(model_dir.join(PLAYER_FILES[0]), PlayerPiece::X)
+        .map(|p| p.get_iteration())
+        .unwrap_or(0)
+}
+
+/// Format seconds since the unix epoch as a UTC date, e.g. 2024-09-30
+pub(crate) fn format_date(seconds: u64) -> String {
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tictacrs::agents::annealing::AnnealingSchedule;
+
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("tictacrs_models_{}", name));
+        _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    /// A directory holding a freshly trained (well, created) pair of players
+    fn training_output(directory: &Path, iteration: u32) -> PathBuf {
+        let output = directory.join("output");
+        fs::create_dir_all(&output).unwrap();
+        for (piece, file) in [(PlayerPiece::X, PLAYER_FILES[0]), (PlayerPiece::O, PLAYER_FILES[1])] {
+            let mut player = Player::new(piece, 0.5, 0.1, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+            player.update_iteration(iteration);
+            player.save_player_state(output.join(file)).unwrap();
+        }
+        output
+    }
+
+    #[test]
+    fn test_save_list_delete() {
+        let directory = test_directory("save");
+        let store = ModelStore::new(directory.join("models"));
+        assert!(store.list().is_empty());
+        let output = training_output(&directory, 41);
+        assert_eq!(store.save("../escape", &output), Err(ModelStoreError::InvalidName));
+        assert_eq!(store.save("empty", &directory.join("nothing")), Err(ModelStoreError::MissingPlayers));
+        store.save("second", &output).unwrap();
+        let first = store.save("first", &output).unwrap();
+        assert_eq!(first.iteration, 41);
+        let names: Vec<String> = store.list().into_iter().map(|e| e.name).collect();
+        assert_eq!(names, vec!["first", "second"]);
+        assert!(store.model_directory("first").unwrap().join(PLAYER_FILES[1]).is_file());
+        // Saving again under the same name replaces the entry
+        store.save("first", &output).unwrap();
+        assert_eq!(store.list().len(), 2);
+        store.set_default("first").unwrap();
+        assert_eq!(store.default_model(), Some("first".to_string()));
+        assert_eq!(store.set_default("missing"), Err(ModelStoreError::NotFound));
+        store.delete("first").unwrap();
+        assert_eq!(store.default_model(), None);
+        assert_eq!(store.delete("first"), Err(ModelStoreError::NotFound));
+        let names: Vec<String> = store.list().into_iter().map(|e| e.name).collect();
+        assert_eq!(names, vec!["second"]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_missing_and_corrupt_index() {
+        let directory = test_directory("index");
+        let store = ModelStore::new(directory.join("models"));
+        let output = training_output(&directory, 7);
+        store.save("kept", &output).unwrap();
+        let index = directory.join("models").join(INDEX_FILE);
+        for contents in ["kept\tnot a time\t7\n", "kept\n", "../oops\t1\t2\n"] {
+            fs::write(&index, contents).unwrap();
+            let entries = store.list();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].name, "kept");
+            assert_eq!(entries[0].iteration, 7);
+        }
+        fs::remove_file(&index).unwrap();
+        assert_eq!(store.list().len(), 1);
+        // The index is rewritten on the next change
+        store.save("another", &output).unwrap();
+        assert_eq!(fs::read_to_string(&index).unwrap().lines().count(), 2);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951782400), "2000-02-29");
+        assert_eq!(format_date(1727740799), "2024-09-30");
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+use std::io::{self, Write};
+
+/// Where the non-interactive commands write to. Output meant for other programs goes to the
+/// machine stream (stdout), progress and messages for people to the human stream (stderr), so
+/// piping a command's output never picks up its chatter.
+pub(crate) struct OutputContext {
+    human: Box<dyn Write>,
+    machine: Box<dyn Write>,
+    /// Drop everything written to the human stream except errors
+    quiet: bool,
+}
+
+impl OutputContext {
+    pub(crate) fn new(human: Box<dyn Write>, machine: Box<dyn Write>, quiet: bool) -> OutputContext {
+        OutputContext { human, machine, quiet }
+    }
+
+    /// Human output to stderr, machine output to stdout
+    pub(crate) fn stdio(quiet: bool) -> OutputContext {
+        Self::new(Box::new(io::stderr()), Box::new(io::stdout()), quiet)
+    }
+
+    pub(crate) fn is_quiet(&self) -> bool {
+        self.quiet
+    }
+
+    /// Write a line of progress or information, unless quiet
+    pub(crate) fn human(&mut self, line: impl fmt::Display) {
+        if !self.quiet {
+            // Nowhere left to report a failure to write messages to
+            _ = writeln!(self.human, "{}", line);
+        }
+    }
+
+    /// Write a line of an error to the human stream, even when quiet
+    pub(crate) fn error(&mut self, line: impl fmt::Display) {
+        _ = writeln!(self.human, "{}", line);
+    }
+
+    /// Write a line of the command's output
+    pub(crate) fn machine(&mut self, line: impl fmt::Display) {
+        _ = writeln!(self.machine, "{}", line);
+    }
+}
+
+/// A writer whose contents can still be read after it's boxed into an [`OutputContext`]
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+#[cfg(test)]
+impl SharedBuffer {
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+#[cfg(test)]
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streams() {
+        for quiet in [false, true] {
+            let (human, machine) = (SharedBuffer::default(), SharedBuffer::default());
+            let mut out = OutputContext::new(Box::new(human.clone()), Box::new(machine.clone()), quiet);
+            out.human("Working");
+            out.machine(format_args!("{}\t{}", "name", 3));
+            out.error("Failed");
+            assert_eq!(machine.contents(), "name\t3\n");
+            let expected = if quiet { "Failed\n" } else { "Working\nFailed\n" };
+            assert_eq!(human.contents(), expected);
+        }
+    }
+}
@@ -0,0 +1,17 @@
+//! Re-exports of the types most users of the crate need, so they can be brought into
+//! scope with a single `use tictacrs::prelude::*;`
+pub use crate::agents::agent::{Agent, AgentError};
+pub use crate::agents::annealing::AnnealingSchedule;
+pub use crate::agents::players::{
+    Knowledge, LearnScope, Learning, LearningViolations, MoveDecision, MoveExplanation, MoveReason,
+    Player, PlayerBuilder, PlayerConfigError, PlayerError, PlyExploration, TieBreak, UpdateMode,
+};
+#[cfg(feature = "train")]
+pub use crate::agents::trainer::{
+    EvalReport, GameRecord, GameSummary, MergePolicy, ParallelOptions, PhaseLearners, PhaseReport, PlayerSummary, Trainer,
+    TrainerError, TrainingOptions, TrainingPhase, TrainingReport, Transition, TRAINING_REPORT_FILE,
+};
+pub use crate::game::board::{Board, BoardError, MoveLegality, Piece, PlayerPiece};
+pub use crate::game::coords::Coord;
+pub use crate::game::result::{GameOutcomeFor, GameResult};
+pub use crate::game::rules::GameState;
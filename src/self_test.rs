@@ -0,0 +1,222 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tictacrs::agents::annealing::{EXPLORATION_SCHEDULE, INITIAL_EXPLORATION_RATE, INITIAL_LEARNING_RATE,
+                                  LEARNING_SCHEDULE};
+use tictacrs::agents::evaluate;
+use tictacrs::agents::minimax::MinimaxAgent;
+use tictacrs::agents::players::{Learning, Player};
+use tictacrs::agents::random::RandomAgent;
+use tictacrs::agents::trainer::{Trainer, TrainingReport};
+use tictacrs::formatting::{count, duration};
+use tictacrs::game::board::{Board, Piece, PlayerPiece};
+use tictacrs::game::coords::Coord;
+use tictacrs::game::result::GameResult;
+use tictacrs::game::threats::LINES;
+use tictacrs::meta::crate_version;
+use crate::tutorial;
+
+/// Games the players are trained for
+const TRAINING_GAMES: u32 = 500;
+/// Games the trained X player plays against random moves, and against perfect play
+const EVALUATION_GAMES: u32 = 50;
+/// Fraction of the evaluation games X has to win. A few hundred games don't teach it much,
+/// so this only catches a player which is badly broken.
+const MIN_WIN_RATE: f64 = 0.5;
+/// How long the whole self-test should take
+const TIME_BUDGET: Duration = Duration::from_secs(10);
+
+/// How one stage of the self-test went
+pub(crate) struct StageReport {
+    pub(crate) name: &'static str,
+    /// What was checked if the stage passed, what went wrong if it failed
+    pub(crate) outcome: Result<String, String>,
+    pub(crate) elapsed: Duration,
+}
+
+/// The stages of the self-test, in the order they ran
+pub(crate) struct SelfTestReport {
+    pub(crate) stages: Vec<StageReport>,
+    pub(crate) elapsed: Duration,
+}
+
+impl SelfTestReport {
+    /// Whether every stage passed within the time budget
+    pub(crate) fn passed(&self) -> bool {
+        self.stages.iter().all(|stage| stage.outcome.is_ok()) && self.elapsed <= TIME_BUDGET
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tictacrs {} self-test", crate_version())?;
+        for stage in &self.stages {
+            let (status, detail) = match &stage.outcome {
+                Ok(detail) => { ("PASS", detail) }
+                Err(detail) => { ("FAIL", detail) }
+            };
+            writeln!(f, "{} {:<16} {:>6}  {}", status, stage.name, duration(stage.elapsed), detail)?;
+        }
+        let failed = self.stages.iter().filter(|stage| stage.outcome.is_err()).count();
+        if failed > 0 {
+            write!(f, "FAIL {} of {} stages failed", failed, self.stages.len())
+        } else if self.elapsed > TIME_BUDGET {
+            write!(f, "FAIL took {}, over the {} budget", duration(self.elapsed), duration(TIME_BUDGET))
+        } else {
+            write!(f, "PASS all {} stages in {}", self.stages.len(), duration(self.elapsed))
+        }
+    }
+}
+
+/// Run every stage of the self-test, working in a fresh temporary directory which is removed
+/// afterwards
+pub(crate) fn run_self_test() -> SelfTestReport {
+    let started = Instant::now();
+    let directory = std::env::temp_dir().join(format!("tictacrs_self_test_{}", std::process::id()));
+    let mut stages = Vec::new();
+    let mut players = None;
+    stages.push(stage("training", || {
+        let (mut player_x, mut player_o) = (seeded_player(PlayerPiece::X, 1), seeded_player(PlayerPiece::O, 2));
+        fs::create_dir_all(&directory).map_err(|e| format!("couldn't create {}: {}", directory.display(), e))?;
+        let saved = Trainer::train(&mut player_x, &mut player_o, TRAINING_GAMES, &directory, false)
+            .and_then(TrainingReport::into_paths)
+            .map_err(|e| format!("training failed: {}", e))?;
+        let detail = format!("{} games, X learned {} positions, O {}", count(TRAINING_GAMES as u64),
+                             count(player_x.state_count() as u64), count(player_o.state_count() as u64));
+        players = Some((player_x, player_o, saved));
+        Ok(detail)
+    }));
+    match players {
+        Some((mut player_x, player_o, (x_path, o_path))) => {
+            stages.push(stage("save and reload", || {
+                check_reload(&player_x, &x_path)?;
+                check_reload(&player_o, &o_path)?;
+                Ok("both players read back with the same tables".to_string())
+            }));
+            stages.push(stage("evaluation", || evaluate(&mut player_x)));
+        }
+        None => {
+            for name in ["save and reload", "evaluation"] {
+                stages.push(StageReport { name, outcome: Err("skipped, training failed".to_string()),
+                                          elapsed: Duration::ZERO });
+            }
+        }
+    }
+    stages.push(stage("rules", check_rules));
+    stages.push(stage("interactive game", play_tutorial));
+    _ = fs::remove_dir_all(&directory);
+    SelfTestReport { stages, elapsed: started.elapsed() }
+}
+
+/// Run a stage, timing it
+fn stage<F: FnOnce() -> Result<String, String>>(name: &'static str, run: F) -> StageReport {
+    let started = Instant::now();
+    let outcome = run();
+    StageReport { name, outcome, elapsed: started.elapsed() }
+}
+
+/// A player on the training schedule, seeded so the self-test is reproducible
+fn seeded_player(piece: PlayerPiece, seed: u64) -> Player {
+    Player::builder(piece)
+        .learning_rate(INITIAL_LEARNING_RATE)
+        .exploration_rate(INITIAL_EXPLORATION_RATE)
+        .learning_schedule(LEARNING_SCHEDULE)
+        .exploration_schedule(EXPLORATION_SCHEDULE)
+        .seed(seed)
+        .build()
+        .expect("The training schedule is a valid configuration")
+}
+
+/// Read a saved player back, checking its table and iteration match the original
+fn check_reload(player: &Player, path: &Path) -> Result<(), String> {
+    let read = Player::load_expecting(path, player.piece())
+        .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+    if read.state_table_hash() != player.state_table_hash() {
+        return Err(format!("player {} read back with a different table", player.get_player_piece()));
+    }
+    if read.get_iteration() != player.get_iteration() {
+        return Err(format!("player {} read back at iteration {}, expected {}", player.get_player_piece(),
+                           read.get_iteration(), player.get_iteration()));
+    }
+    Ok(())
+}
+
+/// Play the trained X player greedily against random moves, and against perfect play as a
+/// baseline. Only the games against random moves have to go well, a few hundred games of
+/// training don't teach it to hold perfect play to a draw.
+fn evaluate(player_x: &mut Player) -> Result<String, String> {
+    player_x.set_learning(Learning::Frozen);
+    let random = evaluate::evaluate(player_x, &mut RandomAgent::new(PlayerPiece::O, 3), EVALUATION_GAMES)
+        .map_err(|e| e.to_string())?;
+    let perfect = evaluate::evaluate(player_x, &mut MinimaxAgent::new(PlayerPiece::O), EVALUATION_GAMES)
+        .map_err(|e| e.to_string())?;
+    let detail = format!("X won {}, drew {}, lost {} of {} against random moves, drew {} against perfect play",
+                         random.wins, random.draws, random.losses, EVALUATION_GAMES, perfect.draws);
+    if (random.wins as f64) < MIN_WIN_RATE * EVALUATION_GAMES as f64 {
+        return Err(detail);
+    }
+    Ok(detail)
+}
+
+/// Check the winner and result of every reachable position against the winning lines
+fn check_rules() -> Result<String, String> {
+    let mut seen = HashSet::new();
+    let mut unvisited = vec![Board::new()];
+    while let Some(board) = unvisited.pop() {
+        let state = board.get_compact_state();
+        if !seen.insert(state) {
+            continue;
+        }
+        let winner = LINES.iter()
+            .find(|line| state[line[0]] != Piece::Empty && line.iter().all(|square| state[*square] == state[line[0]]))
+            .map(|line| state[line[0]]);
+        let full = !state.contains(&Piece::Empty);
+        let expected = match (winner, full) {
+            (Some(piece), _) => { Some(GameResult::Win(PlayerPiece::try_from(piece).unwrap())) }
+            (None, true) => { Some(GameResult::Draw) }
+            (None, false) => { None }
+        };
+        if board.check_winner() != winner || board.result() != expected {
+            return Err(format!("wrong result for position\n{}", board));
+        }
+        if let Some(piece) = board.turn() {
+            for coord in Coord::all().filter(|coord| state[coord.to_index()] == Piece::Empty) {
+                let next = board.try_apply_all(&[(coord.row(), coord.col(), piece.into())])
+                    .map_err(|(_, e)| format!("couldn't play {} at {}: {}", piece, coord, e))?;
+                unvisited.push(next);
+            }
+        }
+    }
+    Ok(format!("{} reachable positions checked", count(seen.len() as u64)))
+}
+
+/// Play through the tutorial with scripted input
+fn play_tutorial() -> Result<String, String> {
+    let mut input = "a1\nb2\nc1\na1\nc3\n".as_bytes();
+    let mut output = Vec::new();
+    tutorial::run_tutorial(&mut input, &mut output).map_err(|e| e.to_string())?;
+    let output = String::from_utf8_lossy(&output);
+    if !output.contains("You won!") {
+        return Err("the scripted game didn't finish".to_string());
+    }
+    Ok(format!("scripted game finished in {} lines of output", output.lines().count()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test_passes() {
+        let report = run_self_test();
+        let names: Vec<&str> = report.stages.iter().map(|stage| stage.name).collect();
+        assert_eq!(names, ["training", "save and reload", "evaluation", "rules", "interactive game"]);
+        for stage in &report.stages {
+            assert!(stage.outcome.is_ok(), "{}", report);
+        }
+        let text = report.to_string();
+        assert!(text.lines().skip(1).all(|line| line.starts_with("PASS ")), "{}", text);
+    }
+}
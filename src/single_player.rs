@@ -1,158 +1,805 @@
-use std::path::PathBuf;
-use std::io;
-use tictacrs::agents::players::Player;
-use tictacrs::game::board::{Board, Piece};
-use crate::annealing;
-
-pub(crate) fn single_player(trained_player_dir: Option<PathBuf>) -> bool {
-    let trained_player_dir = trained_player_dir.unwrap_or_else(|| { std::env::current_dir().unwrap() });
-    let mut play_board = Board::new();
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tictacrs::agents::agent::Agent;
+use tictacrs::agents::annealing;
+use tictacrs::agents::minimax::MinimaxAgent;
+use tictacrs::agents::players::{LearnScope, Learning, Player, PlayerError};
+use tictacrs::agents::session::{ComputerGame, SinglePlayerOutcome};
+use tictacrs::formatting::{count, percent, rate};
+use tictacrs::game::board::{Board, Piece, PlayerPiece, RenderStyle};
+use tictacrs::game::coords::{Coord, DigitLayout};
+use tictacrs::game::result::GameResult;
+use crate::autosave::{newer_autosave, AutosaveSettings, Autosaver};
+use crate::input;
+use crate::storage::{data_directory, resolve_locations};
+
+/// Options for one player games
+pub(crate) struct SinglePlayerOptions {
+    /// Directory containing the trained players
+    pub(crate) trained_player_dir: Option<PathBuf>,
+    /// Pieces placed on the board before each game starts
+    pub(crate) handicap: Vec<(usize, usize, Piece)>,
+    /// Profile whose overlay the computer learns into, instead of its trained table
+    pub(crate) profile: Option<String>,
+    /// How the computer player is autosaved during play
+    pub(crate) autosave: AutosaveSettings,
+    /// Whether to explain each of the computer's moves
+    pub(crate) teach: bool,
+    /// Whether to show the computer's iteration and current rates with each prompt
+    pub(crate) show_agent_stats: bool,
+    /// Whether the computer learns from the games, and how much it explores
+    pub(crate) learning: Learning,
+    /// Whether to show a recap of the moves after each game
+    pub(crate) recap: bool,
+    /// Which states the computer may add to its table and update
+    pub(crate) learn_scope: LearnScope,
+    /// How single digit moves map onto the board
+    pub(crate) digit_layout: DigitLayout,
+    /// Whether to show the digit for each empty square on the board
+    pub(crate) show_numbers: bool,
+    /// How to print a shareable summary after each game, if at all
+    pub(crate) share: Option<ShareOptions>,
+    /// Whether to describe the board and announce moves in plain sentences, for screen readers
+    pub(crate) accessible: bool,
+    /// Never write to the trained player directory, even if it's writable
+    pub(crate) read_only_models: bool,
+    /// The piece the computer plays in every game, or None to ask which piece the human wants
+    pub(crate) computer_piece: Option<PlayerPiece>,
+    /// How the computer chooses its moves
+    pub(crate) difficulty: Difficulty,
+}
+
+/// How the computer chooses its moves in one player games
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Difficulty {
+    /// With the trained player, which learns from the game
+    #[default]
+    Learned,
+    /// Perfectly, so it can't be beaten, learning nothing
+    Impossible,
+}
+
+impl SinglePlayerOptions {
+    /// How the board is shown
+    pub(crate) fn render_style(&self) -> RenderStyle {
+        if self.accessible {
+            RenderStyle::Spoken
+        } else {
+            RenderStyle::Grid(self.show_numbers.then_some(self.digit_layout))
+        }
+    }
+}
+
+/// Options for the shareable summary of a game, see [`format_share`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct ShareOptions {
+    /// Whether to add the computer's confidence after each of its moves
+    pub(crate) annotations: bool,
+    /// Whether to use only ASCII characters, for chats which mangle unicode
+    pub(crate) ascii: bool,
+}
+
+/// Width the move list of a shareable summary is wrapped to
+const SHARE_WIDTH: usize = 32;
+
+/// One move of a game, for the recap shown after the game ends
+pub(crate) struct RecapMove {
+    piece: PlayerPiece,
+    coord: Coord,
+    /// The computer's learned value of the position after its move, None for human moves
+    computer_value: Option<f64>,
+}
+
+impl RecapMove {
+    pub(crate) fn human(piece: PlayerPiece, coord: Coord) -> RecapMove {
+        RecapMove { piece, coord, computer_value: None }
+    }
+
+    fn computer(piece: PlayerPiece, chosen: [u8; 2], value: f64) -> RecapMove {
+        RecapMove { computer_value: Some(value), ..RecapMove::perfect(piece, chosen) }
+    }
+
+    /// A move the computer played perfectly rather than with its learned values, so it has no
+    /// value to show
+    fn perfect(piece: PlayerPiece, chosen: [u8; 2]) -> RecapMove {
+        RecapMove {
+            piece,
+            coord: Coord::new(chosen[0], chosen[1]).expect("The computer only chooses squares on the board"),
+            computer_value: None,
+        }
+    }
+}
+
+/// Ask which piece the human wants to play, returning the computer's piece, or None if they
+/// quit or the input ends
+fn ask_computer_piece() -> Option<PlayerPiece> {
+    println!("Would you like to play as X or O? (X/O)");
+    // Piece selection loop
+    loop {
+        let buffer = input::stdin_line()?;
+        match buffer.trim() {
+            "X" | "x" => { return Some(PlayerPiece::O) }
+            "O" | "o" => { return Some(PlayerPiece::X) }
+            "Q" | "q" => { return None }
+            _ => { println!("Sorry, couldn't understand choice, try again") }
+        }
+    }
+}
+
+pub(crate) fn single_player(options: &SinglePlayerOptions) -> bool {
+    let trained_player_dir = options.trained_player_dir.clone()
+        .unwrap_or_else(|| { std::env::current_dir().unwrap() });
+    let profile = options.profile.as_deref();
+    let locations = resolve_locations(&trained_player_dir, options.read_only_models, profile.is_some(),
+                                      data_directory());
+    if let Some(notice) = &locations.notice {
+        println!("{}", notice);
+    }
+    // Where the computer's save, autosaves and overlays go, None if nothing is saved
+    // Nothing is learned against a perfect computer, so there's nothing to save
+    let write_dir = if options.difficulty == Difficulty::Impossible { None } else { locations.writes };
+    let mut autosaver = Autosaver::new(options.autosave);
+    let style = options.render_style();
     // Start the game loop
     loop {
-        play_board.clear_board();
-        println!("Would you like to play as X or O? (X/O)");
-        // Piece selection loop
-        let computer_piece: Piece;
-        let human_piece: Piece;
-        let mut computer_piece_str: String = String::new();
-        let mut human_piece_str: String = String::new();
-        loop {
-            let mut buffer = String::new();
-            io::stdin().read_line(&mut buffer).expect("Failed to read line");
-            let choice = buffer.trim();
-             human_piece = match choice {
-                "X" | "x" => {
-                    human_piece_str.push_str("X");
-                    computer_piece_str.push_str("O");
-                    computer_piece = Piece::O;
-                    Piece::X
-                },
-                "O" | "o" => {
-                    human_piece_str.push_str("O");
-                    computer_piece_str.push_str("X");
-                    computer_piece=Piece::X;
-                    Piece::O
-                },
-                "Q" | "q" => {
-                    return false;
-                }
-                _ => {
-                    println!("Sorry, couldn't understand choice, try again");
-                    continue;
+        // The handicap has already been validated, so this can't fail
+        let play_board = Board::with_handicap(&options.handicap).expect("Invalid handicap");
+        if play_board.is_handicapped() {
+            println!("This is a handicap game, some pieces start on the board.");
+        }
+        let computer_piece = match options.computer_piece {
+            Some(piece) => {
+                println!("You're playing {}, the computer is playing {}.", piece.opponent(), piece);
+                piece
+            }
+            None => {
+                match ask_computer_piece() {
+                    Some(piece) => { piece }
+                    None => { return false; }
                 }
-            };
-            break;
+            }
         };
         // Now try to read in a trained opponent, if not possible create a new opponent
-        let trained_player_file = match computer_piece {
-            Piece::X => trained_player_dir.join(PathBuf::from("player_x_save.ttr")),
-            Piece::O => trained_player_dir.join(PathBuf::from("player_o_save.ttr")),
-            _=>{panic!("Impossible Automated Player Piece")}
-        };
-        let mut computer_player:Player = match Player::new_from_file(
-            trained_player_file,
-            annealing::learning_rate_function,
-            annealing::exploration_rate_function,
-        ){
-          Ok(p)=>p,
-            Err(_)=>{
-                println!("Couldn't find trained automatic player, creating a new one");
-                Player::new(
-                    computer_piece,
-                    annealing::INITIAL_LEARNING_RATE,
-                    annealing::INITIAL_EXPLORATION_RATE,
-                    annealing::learning_rate_function,
-                    annealing::exploration_rate_function,
-                )
+        let (mut computer_player, opponent) = resolve_opponent(&trained_player_dir, computer_piece);
+        match opponent {
+            _ if options.difficulty == Difficulty::Impossible => {
+                println!("The computer plays perfectly, the best you can do is a draw.");
             }
+            Opponent::Trained(_) => {}
+            Opponent::Untrained(ref path) => {
+                println!("No trained computer player found at {}.", path.display());
+                println!("The computer hasn't learned anything yet, so it will play almost randomly. \
+                          Run `tictacrs train -i 50000` to train one, then pass its output directory \
+                          with -t.");
+            }
+            Opponent::WrongPiece(ref path, found) => {
+                println!("The trained computer player {} plays {}, not {}.", path.display(), found, computer_piece);
+                println!("Playing against an untrained computer instead, which will play almost \
+                          randomly. Check the save files haven't been renamed or swapped.");
+            }
+            Opponent::Unreadable(ref path, ref e) => {
+                println!("Couldn't read the trained computer player {}: {}.", path.display(), e);
+                println!("Playing against an untrained computer instead, which will play almost \
+                          randomly. Run `tictacrs train -i 50000` to train a new one.");
+            }
+        }
+        // Saving the stand-in for a save that couldn't be used would overwrite that save
+        let write_dir = if opponent.can_save_over() {
+            write_dir.clone()
+        } else {
+            if write_dir.is_some() {
+                println!("The computer won't be saved after this game, so that save is left as it is.");
+            }
+            None
         };
-        let mut computer_move:String;
-        let mut human_move:String;
-        // If the computer goes first, get its move
-        if computer_piece == Piece::X {
-            println!("{}", play_board);
-            computer_move = Player::to_human_move(&computer_player.make_move(
-                &play_board.get_compact_state())
-            );
-            // This can't fail, since the board must be empty
-            // Also the computer player should never make an invalid move
-            _=play_board.player_move(&computer_move, &computer_piece_str).expect("Computer failed to make possible move");
-        }
-        // Store a copy of the board state right after the computer plays
-        // in order to show it that as a losing position
-        let mut prev_board: [Piece; 9] =
-            [
-                Piece::Empty, Piece::Empty, Piece::Empty,
-                Piece::Empty, Piece::Empty, Piece::Empty,
-                Piece::Empty, Piece::Empty, Piece::Empty,
-            ];
-        // Start the game itself
-        loop {
-            println!("{}", play_board);
-            // Start with the human player
-            human_move = get_move_selection();
-            if human_move=="q" || human_move=="Q"{
-                return false;
+        // With a profile, learning against this human is kept in a separate overlay
+        if let Some(name) = profile {
+            computer_player = computer_player.with_overlay();
+            let overlay_file = overlay_file_path(write_dir.as_deref().unwrap_or(&trained_player_dir),
+                                                 name, computer_piece);
+            if overlay_file.exists() {
+                if let Err(e) = computer_player.load_overlay(&overlay_file) {
+                    println!("Couldn't read the overlay for profile {} ({}), starting a new one", name, e);
+                }
             }
-            match play_board.player_move(&human_move, &human_piece_str) {
-                Ok(_)=>{
-                    println!("{}", play_board);
-                },
-                Err(_)=>{
-                    println!("Sorry, invalid move, try again");
-                    continue;
+        }
+        // Offer to restore an autosave left behind by an interrupted session
+        let (save_file, autosave_stem) = save_location(write_dir.as_deref().unwrap_or(&trained_player_dir),
+                                                       profile, computer_piece);
+        let newer = write_dir.as_deref().and_then(|dir| newer_autosave(dir, &autosave_stem, &save_file));
+        if let Some(autosave) = newer {
+            println!("Found an autosave newer than the saved computer player, restore it? (y/n)");
+            let buffer = input::stdin_line().unwrap_or_default();
+            if matches!(buffer.trim(), "y" | "Y" | "yes" | "Yes") {
+                let restored = if profile.is_some() {
+                    computer_player.load_overlay(&autosave)
+                } else {
+                    Player::load_expecting(&autosave, computer_piece)
+                        .map(|p| computer_player = p)
+                };
+                if let Err(e) = restored {
+                    println!("Couldn't restore the autosave {}: {}", autosave.display(), e);
                 }
             }
-            // Check if the player won
-            if let Some(_) = play_board.check_winner() {
-                // If there is a winner, it has to be due to the most recent move
-                // in this case the players
-                println!("{}", play_board);
-                println!("Congratulations Player! You Win!");
-                // Show the computer the losing state so it can update
-                computer_player.show_loosing_state(&prev_board);
-                break;
+        }
+        if computer_player.terminal_states_dropped() > 0 {
+            println!("Dropped {} finished positions stored by an older version, they'll be left \
+                      out when the computer is next saved", count(computer_player.terminal_states_dropped() as u64));
+        }
+        computer_player.set_learning(options.learning);
+        computer_player.set_learn_scope(options.learn_scope);
+        let mut game = ComputerGame::new(play_board, &mut computer_player);
+        let states_before = computer_player.state_count();
+        let mut recap: Vec<RecapMove> = Vec::new();
+        // The trained player for the human's side, loaded the first time the sides are swapped
+        let mut swapped_player: Option<Player> = None;
+        // Plays the human's side instead when there's no trained player for it
+        let mut swapped_perfect: Option<MinimaxAgent> = None;
+        // Plays the computer's side instead of the trained player on the impossible difficulty
+        let mut perfect = (options.difficulty == Difficulty::Impossible)
+            .then(|| MinimaxAgent::with_random_ties(computer_piece, rand::random()));
+        if game.is_computer_turn() {
+            println!("{}", game.session().board().render_as(style));
+        }
+        // Start the game itself
+        while let Some(turn) = game.session().turn() {
+            if game.is_computer_turn() {
+                let mover = if turn == computer_piece {
+                    &mut computer_player
+                } else {
+                    swapped_player.as_mut().expect("The other side's player is loaded when swapping")
+                };
+                let before = game.session().board().get_compact_state();
+                let perfect_mover = if turn == computer_piece { perfect.as_mut() } else { swapped_perfect.as_mut() };
+                // Only the learned player's own moves can be explained from its table
+                let learned = perfect_mover.is_none();
+                let agent: &mut dyn Agent = match perfect_mover {
+                    Some(perfect) => { perfect }
+                    None => { &mut *mover }
+                };
+                let chosen = match game.session_mut().play_agent(agent) {
+                    Ok(m) => { m }
+                    Err(e) => {
+                        println!("Computer was unable to find a move ({}), ending game", e);
+                        break;
+                    }
+                };
+                if learned {
+                    let explanation = mover.explain_move(&before, chosen);
+                    if options.teach {
+                        println!("{}", explanation);
+                    }
+                    recap.push(RecapMove::computer(turn, chosen, explanation.value));
+                } else {
+                    if options.teach {
+                        println!("The computer played {} perfectly", Player::to_human_move(&chosen));
+                    }
+                    recap.push(RecapMove::perfect(turn, chosen));
+                }
+                if options.accessible {
+                    println!("{}", announce_move("Computer", turn, &Player::to_human_move(&chosen)));
+                }
+                if game.session().turn().is_some() {
+                    if let (true, Some(dir)) = (autosaver.is_due(), &write_dir) {
+                        autosave(&mut autosaver, &computer_player, dir, profile, &autosave_stem);
+                    }
+                }
+            } else {
+                println!("{}", game.session().board().render_as(style));
+                if game.session_mut().offer_dead_draw() && offer_dead_draw() {
+                    println!("It's a tie.");
+                    game.session_mut().end_as_draw();
+                    break;
+                }
+                if options.show_agent_stats || options.teach {
+                    println!("{}", format_agent_stats(computer_player.get_iteration(),
+                                                      computer_player.get_exploration_rate(),
+                                                      computer_player.get_learning_rate()));
+                }
+                let mut human_move = get_move_selection();
+                if human_move=="q" || human_move=="Q"{
+                    return false;
+                }
+                if let Some(share) = parse_share_command(&human_move) {
+                    print!("{}", format_share(&recap, game.session().board(), None, share));
+                    continue;
+                }
+                if human_move == ":swap" {
+                    if swapped_player.is_none() {
+                        let (mut player, other) = resolve_opponent(&trained_player_dir, computer_piece.opponent());
+                        swapped_perfect = swap_fallback(&other, computer_piece.opponent());
+                        if swapped_perfect.is_some() {
+                            println!("No trained computer player for {}, a perfect one will play it instead.",
+                                     computer_piece.opponent());
+                        }
+                        player.set_learning(Learning::Frozen);
+                        swapped_player = Some(player);
+                    }
+                    game.swap(&mut computer_player);
+                    println!("Swapped sides, you're now playing {} and the computer {}. The computer \
+                              won't learn from this game.", game.sides().human(), game.sides().computer());
+                    continue;
+                }
+                // Moves given as a digit or a square's name are passed on in the usual notation
+                if let Some(coord) = parse_move(&human_move, options.digit_layout) {
+                    human_move = coord.to_string();
+                }
+                match game.session_mut().play(&human_move) {
+                    Ok(_)=>{
+                        println!("{}", game.session().board().render_as(style));
+                        if let Ok(coord) = human_move.parse::<Coord>() {
+                            recap.push(RecapMove::human(turn, coord));
+                        }
+                    },
+                    Err(e)=>{
+                        println!("Sorry, {}, try again", e);
+                        continue;
+                    }
+                }
             }
-            // Check if the board is full
-            if play_board.is_full(){
-                println!("{}", play_board);
-                println!("Sorry, it's a tie.");
-                break;
+        }
+        // The computer learns from the result, unless the sides were swapped
+        let outcome = game.finish(&mut computer_player);
+        if game.session().board().result().is_some() {
+            println!("{}", game.session().board().render_as(style));
+            match outcome {
+                Some(SinglePlayerOutcome::HumanWon) => {
+                    println!("Congratulations Player! You Win!");
+                }
+                Some(SinglePlayerOutcome::ComputerWon) => {
+                    if options.accessible {
+                        println!("The computer won.");
+                    } else {
+                        println!("Oh No! You have been defeated by a computer! :-(");
+                    }
+                }
+                Some(SinglePlayerOutcome::Draw) => { println!("Sorry, it's a tie.") }
+                Some(SinglePlayerOutcome::Swapped(result)) => {
+                    println!("{}, but the sides were swapped so it doesn't count for either of you.",
+                             describe_result(result));
+                }
+                None => {}
             }
-            // Now allow the computer to move
-            computer_move = Player::to_human_move(&computer_player.make_move(&play_board.get_compact_state()));
-            _=play_board.player_move(&computer_move, &computer_piece_str).expect("Computer failed to make possible move");
-            if let Some(_) = play_board.check_winner(){
-                println!("{}", play_board);
-                println!("Oh No! You have been defeated by a computer! :-(");
-                break;
+        }
+        if options.recap {
+            print!("{}", format_recap(&recap, game.session().result()));
+            if game.sides().swapped() {
+                println!("Sides were swapped during the game");
             }
-            if play_board.is_full(){
-                println!("{}", play_board);
-                println!("Sorry, it's a tie.");
-                break;
+            println!("Opponent: {}", opponent);
+            println!("Games played against people: {}", count(computer_player.get_games_played() as u64));
+            println!("{}", format_state_growth(computer_player.state_count().saturating_sub(states_before),
+                                               options.learn_scope));
+        }
+        if let Some(share) = options.share {
+            print!("{}", format_share(&recap, game.session().board(), game.session().result(), share));
+        }
+        // Now that the game has been played, autosave and save the automated player (or just
+        // its overlay when playing with a profile), unless nothing can be written
+        if let Some(dir) = &write_dir {
+            autosave(&mut autosaver, &computer_player, dir, profile, &autosave_stem);
+            if let Err(e) = save_computer_player(&computer_player, &save_file, profile) {
+                println!("Couldn't save automated player state: {}", e);
             }
-            prev_board = play_board.get_compact_state();
-        }
-        computer_player.update_iteration(computer_player.get_iteration());
-        // Now that the game has been played, save the automated player
-        let trained_player_file = match computer_piece {
-            Piece::X => trained_player_dir.join(PathBuf::from("player_x_save.ttr")),
-            Piece::O => trained_player_dir.join(PathBuf::from("player_o_save.ttr")),
-            _=>{panic!("Impossible Automated Player Piece")}
-        };
-        match computer_player.save_player_state(trained_player_file){
-            Ok(_)=>{},
-            Err(_)=>{
-                println!("Couldn't save automated player state.");
+        }
+    }
+}
+
+/// A result in a sentence, e.g. "X wins"
+fn describe_result(result: GameResult) -> String {
+    match result {
+        GameResult::Win(piece) => { format!("{} wins", piece) }
+        GameResult::Draw => { "It's a draw".to_string() }
+    }
+}
+
+/// Which computer player a one player game was played against, so results against an untrained
+/// computer can be told apart
+#[derive(Debug, PartialEq)]
+pub(crate) enum Opponent {
+    /// A trained player, read from the given save file
+    Trained(PathBuf),
+    /// A new, untrained player, since there was no save file at the given path
+    Untrained(PathBuf),
+    /// A new, untrained player, since the save file at the given path couldn't be read
+    Unreadable(PathBuf, PlayerError),
+    /// A new, untrained player, since the save file at the given path plays the given piece
+    /// rather than the computer's
+    WrongPiece(PathBuf, PlayerPiece),
+}
+
+impl fmt::Display for Opponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Opponent::Trained(path) => { write!(f, "trained computer ({})", path.display()) }
+            Opponent::Untrained(_) | Opponent::Unreadable(..) | Opponent::WrongPiece(..) => {
+                write!(f, "untrained computer")
             }
-        };
+        }
+    }
+}
+
+impl Opponent {
+    /// Whether the computer can be saved back to its save file, which isn't so when the file
+    /// holds a player that couldn't be used, since saving would overwrite it
+    pub(crate) fn can_save_over(&self) -> bool {
+        matches!(self, Opponent::Trained(_) | Opponent::Untrained(_))
+    }
+}
+
+/// Read the computer player for the given piece from the trained player directory, falling
+/// back to a new, untrained player, and report which it picked
+fn resolve_opponent(trained_player_dir: &Path, computer_piece: PlayerPiece) -> (Player, Opponent) {
+    let trained_player_file = match computer_piece {
+        PlayerPiece::X => trained_player_dir.join(PathBuf::from("player_x_save.ttr")),
+        PlayerPiece::O => trained_player_dir.join(PathBuf::from("player_o_save.ttr")),
+    };
+    let opponent = match Player::load_expecting(&trained_player_file, computer_piece) {
+        Ok(p) => { return (p, Opponent::Trained(trained_player_file)) }
+        Err(PlayerError::InvalidFile { .. }) => { Opponent::Untrained(trained_player_file) }
+        Err(PlayerError::PieceMismatch { found, .. }) => { Opponent::WrongPiece(trained_player_file, found) }
+        Err(e) => { Opponent::Unreadable(trained_player_file, e) }
+    };
+    (annealing::scheduled_player(computer_piece), opponent)
+}
+
+/// The perfect player which takes over the human's side after a `:swap` when there's no
+/// trained player for it, rather than an untrained one playing almost randomly
+fn swap_fallback(opponent: &Opponent, piece: PlayerPiece) -> Option<MinimaxAgent> {
+    match opponent {
+        Opponent::Trained(_) => { None }
+        _ => { Some(MinimaxAgent::with_random_ties(piece, rand::random())) }
+    }
+}
+
+/// Path of the main save file for the computer player, and the stem used for its autosaves
+fn save_location(trained_player_dir: &Path, profile: Option<&str>, computer_piece: PlayerPiece) -> (PathBuf, String) {
+    let piece_name = match computer_piece {
+        PlayerPiece::X => "x",
+        PlayerPiece::O => "o",
+    };
+    match profile {
+        Some(name) => (overlay_file_path(trained_player_dir, name, computer_piece),
+                       format!("overlay_{}_{}", name, piece_name)),
+        None => (trained_player_dir.join(format!("player_{}_save.ttr", piece_name)),
+                 format!("player_{}", piece_name)),
+    }
+}
+
+/// Save the computer player, or just its overlay when playing with a profile
+fn save_computer_player(computer_player: &Player, path: &Path, profile: Option<&str>) -> Result<(), PlayerError> {
+    match profile {
+        Some(_) => computer_player.save_overlay(path),
+        None => computer_player.save_player_state(path),
+    }
+}
+
+/// Write an autosave of the computer player, reporting (but otherwise ignoring) failures
+fn autosave(autosaver: &mut Autosaver, computer_player: &Player, trained_player_dir: &Path,
+            profile: Option<&str>, autosave_stem: &str) {
+    let saved = autosaver.autosave(trained_player_dir, autosave_stem, |path| {
+        save_computer_player(computer_player, path, profile)
+    });
+    if let Err(e) = saved {
+        println!("Couldn't autosave automated player state: {}", e);
+    }
+}
+
+/// Path of the overlay file for a given profile and computer piece
+pub(crate) fn overlay_file_path(trained_player_dir: &Path, profile: &str, computer_piece: PlayerPiece) -> PathBuf {
+    match computer_piece {
+        PlayerPiece::X => trained_player_dir.join(format!("overlay_{}_x.ttr", profile)),
+        PlayerPiece::O => trained_player_dir.join(format!("overlay_{}_o.ttr", profile)),
+    }
+}
+
+/// Status line showing how far the computer player has annealed, e.g.
+/// `[iter 412 | explore 3.1% | learn 0.42]`
+fn format_agent_stats(iteration: u32, exploration_rate: f64, learning_rate: f64) -> String {
+    let explore = if exploration_rate > 0. && exploration_rate < 0.001 {
+        "<0.1%".to_string()
+    } else {
+        percent(exploration_rate)
+    };
+    format!("[iter {} | explore {} | learn {}]", count(iteration as u64), explore, rate(learning_rate))
+}
+
+/// Recap of a finished game: every move in human notation, with the computer's confidence
+/// (its learned value of the position) after each of its moves, and the result
+fn format_recap(moves: &[RecapMove], result: Option<GameResult>) -> String {
+    let mut recap = String::from("Game recap:\n");
+    for (number, recap_move) in moves.iter().enumerate() {
+        recap.push_str(&format!("{:>3}. {} {}", number + 1, recap_move.piece, recap_move.coord));
+        if let Some(value) = recap_move.computer_value {
+            recap.push_str(&format!("  (computer confidence {:.2})", value));
+        }
+        recap.push('\n');
+    }
+    match result {
+        Some(GameResult::Win(piece)) => { recap.push_str(&format!("Result: {} wins\n", piece)) }
+        Some(GameResult::Draw) => { recap.push_str("Result: draw\n") }
+        None => { recap.push_str("Result: unfinished\n") }
+    }
+    recap
+}
+
+/// Short summary of a game to paste into a chat: the result, a compact diagram of the
+/// board, and the numbered moves wrapped to a narrow width
+pub(crate) fn format_share(moves: &[RecapMove], board: &Board, result: Option<GameResult>,
+                           options: ShareOptions) -> String {
+    let (vertical, horizontal, empty) = if options.ascii {
+        ("|", "---+---+---", ".")
+    } else {
+        ("\u{2502}", "\u{2500}\u{2500}\u{2500}\u{253c}\u{2500}\u{2500}\u{2500}\u{253c}\u{2500}\u{2500}\u{2500}", "\u{00b7}")
+    };
+    let mut share = match result {
+        Some(GameResult::Win(piece)) => { format!("TicTacRs: {} wins\n", piece) }
+        Some(GameResult::Draw) => { "TicTacRs: draw\n".to_string() }
+        None => { "TicTacRs: unfinished\n".to_string() }
+    };
+    let state = board.get_compact_state();
+    for row in 0..3 {
+        let cells: Vec<String> = state[row * 3..row * 3 + 3].iter()
+            .map(|piece| match piece {
+                Piece::Empty => { format!(" {} ", empty) }
+                piece => { format!(" {} ", piece) }
+            })
+            .collect();
+        share.push_str(cells.join(vertical).trim_end());
+        share.push('\n');
+        if row < 2 {
+            share.push_str(horizontal);
+            share.push('\n');
+        }
+    }
+    // Moves are never split across lines
+    let mut line = String::new();
+    for (number, recap_move) in moves.iter().enumerate() {
+        let mut entry = format!("{}.{} {}", number + 1, recap_move.piece, recap_move.coord);
+        if let (true, Some(value)) = (options.annotations, recap_move.computer_value) {
+            entry.push_str(&format!(" ({:.2})", value));
+        }
+        if !line.is_empty() && line.len() + 2 + entry.len() > SHARE_WIDTH {
+            share.push_str(&line);
+            share.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push_str("  ");
+        }
+        line.push_str(&entry);
     }
+    if !line.is_empty() {
+        share.push_str(&line);
+        share.push('\n');
+    }
+    share
+}
+
+/// Options of a `:share` command typed at the move prompt, optionally followed by `ascii`
+/// and/or `plain` (no annotations), or None if the input isn't one
+pub(crate) fn parse_share_command(input: &str) -> Option<ShareOptions> {
+    let mut words = input.split_whitespace();
+    if words.next() != Some(":share") {
+        return None;
+    }
+    let mut options = ShareOptions { annotations: true, ascii: false };
+    for word in words {
+        match word {
+            "ascii" => { options.ascii = true }
+            "plain" => { options.annotations = false }
+            _ => { return None }
+        }
+    }
+    Some(options)
+}
+
+/// How many states the computer added to its table during a game, and under which scope
+fn format_state_growth(added: usize, learn_scope: LearnScope) -> String {
+    let states = if added == 1 { "state" } else { "states" };
+    format!("The computer learned {} new {} (learn scope {})", count(added as u64), states, learn_scope)
+}
+
+/// A move typed at the prompt, in the usual notation, as a digit in the given layout, or as
+/// the name of a square such as "top left"
+pub(crate) fn parse_move(input: &str, digit_layout: DigitLayout) -> Option<Coord> {
+    Coord::parse_with_layout(input, digit_layout).ok().or_else(|| Coord::from_name(input))
+}
+
+/// Announcement of a move for accessible output, e.g. "Computer placed O at b3"
+pub(crate) fn announce_move(mover: &str, piece: PlayerPiece, square: &str) -> String {
+    format!("{} placed {} at {}", mover, piece, square)
+}
+
+/// Tell the players nobody can win any more, and ask whether to end the game as a draw
+pub(crate) fn offer_dead_draw() -> bool {
+    println!("Nobody can win from here, end the game as a draw? (y/n)");
+    let buffer = input::stdin_line().unwrap_or_default();
+    matches!(buffer.trim(), "y" | "Y" | "yes" | "Yes")
 }
 
 fn get_move_selection()->String{
-    println!("Please select your move (q to quit):");
-    let mut buffer = String::new();
-    io::stdin().read_line(&mut buffer).expect("Failed to read line");
-    buffer.trim().to_string()
-}
\ No newline at end of file
+    println!("Please select your move (q to quit, :share for a summary of the game so far, :swap to \
+              trade sides with the computer):");
+    // The end of the input quits
+    match input::stdin_line() {
+        Some(line) => { line.trim().to_string() }
+        None => { "q".to_string() }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A drawn game against the computer, with the board it ends on
+    fn drawn_game() -> (Vec<RecapMove>, Board) {
+        let moves = vec![
+            RecapMove::human(PlayerPiece::X, Coord::new(1, 1).unwrap()),
+            RecapMove::computer(PlayerPiece::O, [0, 0], 0.5),
+            RecapMove::human(PlayerPiece::X, Coord::new(0, 2).unwrap()),
+            RecapMove::computer(PlayerPiece::O, [2, 0], 0.125),
+            RecapMove::human(PlayerPiece::X, Coord::new(1, 0).unwrap()),
+            RecapMove::computer(PlayerPiece::O, [1, 2], 0.31),
+            RecapMove::human(PlayerPiece::X, Coord::new(0, 1).unwrap()),
+            RecapMove::computer(PlayerPiece::O, [2, 1], 0.),
+            RecapMove::human(PlayerPiece::X, Coord::new(2, 2).unwrap()),
+        ];
+        let mut board = Board::new();
+        for recap_move in &moves {
+            board.player_move(&recap_move.coord.to_string(), &recap_move.piece.to_string()).unwrap();
+        }
+        (moves, board)
+    }
+
+    #[test]
+    fn test_format_share() {
+        let (moves, board) = drawn_game();
+        let annotated = ShareOptions { annotations: true, ascii: false };
+        let share = format_share(&moves, &board, board.result(), annotated);
+        assert_eq!(share, "\
+TicTacRs: draw
+ O \u{2502} X \u{2502} X
+\u{2500}\u{2500}\u{2500}\u{253c}\u{2500}\u{2500}\u{2500}\u{253c}\u{2500}\u{2500}\u{2500}
+ X \u{2502} X \u{2502} O
+\u{2500}\u{2500}\u{2500}\u{253c}\u{2500}\u{2500}\u{2500}\u{253c}\u{2500}\u{2500}\u{2500}
+ O \u{2502} O \u{2502} X
+1.X b2  2.O a1 (0.50)  3.X a3
+4.O c1 (0.12)  5.X b1
+6.O b3 (0.31)  7.X a2
+8.O c2 (0.00)  9.X c3
+");
+        assert!(share.lines().count() <= 15);
+        let ascii = ShareOptions { annotations: true, ascii: true };
+        let share = format_share(&moves[..3], &Board::new(), None, ascii);
+        assert_eq!(share, "\
+TicTacRs: unfinished
+ . | . | .
+---+---+---
+ . | . | .
+---+---+---
+ . | . | .
+1.X b2  2.O a1 (0.50)  3.X a3
+");
+        assert!(share.is_ascii());
+        let plain = format_share(&moves, &board, board.result(), ShareOptions::default());
+        assert!(plain.ends_with("1.X b2  2.O a1  3.X a3  4.O c1\n5.X b1  6.O b3  7.X a2  8.O c2\n9.X c3\n"));
+    }
+
+    #[test]
+    fn test_accessible_output() {
+        assert_eq!(announce_move("Computer", PlayerPiece::O, "b3"), "Computer placed O at b3");
+        let (moves, _) = drawn_game();
+        let mut board = Board::new();
+        for recap_move in &moves[..5] {
+            board.player_move(&recap_move.coord.to_string(), &recap_move.piece.to_string()).unwrap();
+        }
+        assert_eq!(board.render_as(RenderStyle::Spoken),
+                   "Row a: O, empty, X. Row b: X, X, empty. Row c: O, empty, empty.");
+        assert_eq!(parse_move("top right", DigitLayout::Reading), "a3".parse().ok());
+        assert_eq!(parse_move("7", DigitLayout::Numpad), "a1".parse().ok());
+        assert_eq!(parse_move("b2", DigitLayout::Reading), "b2".parse().ok());
+        assert_eq!(parse_move("upper left", DigitLayout::Reading), None);
+    }
+
+    #[test]
+    fn test_parse_share_command() {
+        assert_eq!(parse_share_command(":share"), Some(ShareOptions { annotations: true, ascii: false }));
+        assert_eq!(parse_share_command(":share ascii plain"), Some(ShareOptions { annotations: false, ascii: true }));
+        assert_eq!(parse_share_command(":share colour"), None);
+        assert_eq!(parse_share_command("b2"), None);
+    }
+
+    #[test]
+    fn test_format_recap() {
+        let moves = vec![
+            RecapMove { piece: PlayerPiece::X, coord: Coord::new(1, 1).unwrap(), computer_value: None },
+            RecapMove::computer(PlayerPiece::O, [0, 0], 0.5),
+            RecapMove { piece: PlayerPiece::X, coord: Coord::new(0, 2).unwrap(), computer_value: None },
+            RecapMove::computer(PlayerPiece::O, [2, 0], 0.125),
+            RecapMove { piece: PlayerPiece::X, coord: Coord::new(1, 0).unwrap(), computer_value: None },
+            RecapMove::computer(PlayerPiece::O, [1, 2], 0.31),
+            RecapMove { piece: PlayerPiece::X, coord: Coord::new(0, 1).unwrap(), computer_value: None },
+            RecapMove::computer(PlayerPiece::O, [2, 1], 0.),
+            RecapMove { piece: PlayerPiece::X, coord: Coord::new(2, 2).unwrap(), computer_value: None },
+        ];
+        assert_eq!(format_recap(&moves, Some(GameResult::Draw)), "\
+Game recap:
+  1. X b2
+  2. O a1  (computer confidence 0.50)
+  3. X a3
+  4. O c1  (computer confidence 0.12)
+  5. X b1
+  6. O b3  (computer confidence 0.31)
+  7. X a2
+  8. O c2  (computer confidence 0.00)
+  9. X c3
+Result: draw
+");
+        assert!(format_recap(&moves[..3], None).ends_with("  3. X a3\nResult: unfinished\n"));
+        // Perfect moves weren't chosen with learned values, so show no confidence
+        assert_eq!(format_recap(&[RecapMove::perfect(PlayerPiece::O, [0, 0])], None),
+                   "Game recap:\n  1. O a1\nResult: unfinished\n");
+    }
+
+    #[test]
+    fn test_format_state_growth() {
+        assert_eq!(format_state_growth(16, LearnScope::All),
+                   "The computer learned 16 new states (learn scope all)");
+        assert_eq!(format_state_growth(1, LearnScope::TerminalOnly),
+                   "The computer learned 1 new state (learn scope terminal-only)");
+    }
+
+    #[test]
+    fn test_resolve_opponent() {
+        let directory = std::env::temp_dir().join("tictacrs_resolve_opponent");
+        std::fs::create_dir_all(&directory).unwrap();
+        let x_file = directory.join("player_x_save.ttr");
+        let o_file = directory.join("player_o_save.ttr");
+        _ = std::fs::remove_file(&x_file);
+        _ = std::fs::remove_file(&o_file);
+        // Nothing saved yet
+        let (player, opponent) = resolve_opponent(&directory, PlayerPiece::X);
+        assert_eq!(opponent, Opponent::Untrained(x_file.clone()));
+        assert_eq!(player.get_player_piece(), PlayerPiece::X);
+        assert_eq!(opponent.to_string(), "untrained computer");
+        // A saved player is used
+        player.save_player_state(&x_file).unwrap();
+        let (player, opponent) = resolve_opponent(&directory, PlayerPiece::X);
+        assert_eq!(opponent, Opponent::Trained(x_file.clone()));
+        assert_eq!(player.get_player_piece(), PlayerPiece::X);
+        assert!(opponent.to_string().starts_with("trained computer"));
+        // A save for the other piece, or one which can't be read, isn't
+        player.save_player_state(&o_file).unwrap();
+        let (player, opponent) = resolve_opponent(&directory, PlayerPiece::O);
+        assert_eq!(opponent, Opponent::WrongPiece(o_file.clone(), PlayerPiece::X));
+        assert_eq!(player.get_player_piece(), PlayerPiece::O);
+        assert!(!opponent.can_save_over());
+        std::fs::write(&x_file, b"not a player").unwrap();
+        let opponent = resolve_opponent(&directory, PlayerPiece::X).1;
+        assert_eq!(opponent, Opponent::Unreadable(x_file.clone(), PlayerError::BadMagic));
+        assert!(!opponent.can_save_over());
+        // Only a player which replaced a trained or missing save can be saved over it
+        assert!(Opponent::Trained(x_file.clone()).can_save_over());
+        assert!(Opponent::Untrained(x_file.clone()).can_save_over());
+        // Swapping onto a side without a trained player brings in a perfect one
+        assert!(swap_fallback(&Opponent::Trained(x_file.clone()), PlayerPiece::X).is_none());
+        let fallback = swap_fallback(&Opponent::Untrained(x_file), PlayerPiece::X).unwrap();
+        assert_eq!(fallback.piece(), PlayerPiece::X);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_describe_result() {
+        assert_eq!(describe_result(GameResult::Win(PlayerPiece::X)), "X wins");
+        assert_eq!(describe_result(GameResult::Draw), "It's a draw");
+    }
+
+    #[test]
+    fn test_format_agent_stats() {
+        assert_eq!(format_agent_stats(412, 0.0314, 0.4249), "[iter 412 | explore 3.1% | learn 0.42]");
+        assert_eq!(format_agent_stats(0, 0.2, 0.75), "[iter 0 | explore 20.0% | learn 0.75]");
+        assert_eq!(format_agent_stats(9000, 0.00004, 0.001), "[iter 9,000 | explore <0.1% | learn 0.0010]");
+        assert_eq!(format_agent_stats(9000, 0., 0.), "[iter 9,000 | explore 0.0% | learn 0]");
+    }
+
+}
@@ -0,0 +1,159 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a one player game writes what the computer learns: its save, autosaves and profile
+/// overlays
+#[derive(Debug, PartialEq)]
+pub(crate) struct StorageLocations {
+    /// Directory written to, None if nothing is saved at all
+    pub(crate) writes: Option<PathBuf>,
+    /// Why writes don't go to the trained player directory, shown once at startup
+    pub(crate) notice: Option<String>,
+}
+
+/// The user's data directory: `$TICTACRS_DATA_DIR` if set, otherwise
+/// `$XDG_DATA_HOME/tictacrs` or `~/.local/share/tictacrs`
+pub(crate) fn data_directory() -> Option<PathBuf> {
+    data_directory_from(std::env::var_os("TICTACRS_DATA_DIR"), std::env::var_os("XDG_DATA_HOME"),
+                        std::env::var_os("HOME"))
+}
+
+/// [`data_directory`] from the given environment variables, None if none of them are set
+fn data_directory_from(data_dir: Option<OsString>, xdg_data_home: Option<OsString>,
+                       home: Option<OsString>) -> Option<PathBuf> {
+    match (data_dir, xdg_data_home, home) {
+        (Some(dir), _, _) => { Some(PathBuf::from(dir)) }
+        (None, Some(dir), _) => { Some(PathBuf::from(dir).join("tictacrs")) }
+        (None, None, Some(home)) => { Some(PathBuf::from(home).join(".local/share/tictacrs")) }
+        (None, None, None) => { None }
+    }
+}
+
+/// Whether files can be created in the directory, checked by creating (and removing) one
+pub(crate) fn is_writable(directory: &Path) -> bool {
+    let probe = directory.join(format!(".tictacrs_write_probe_{}", std::process::id()));
+    match fs::write(&probe, b"") {
+        Ok(_) => {
+            _ = fs::remove_file(&probe);
+            true
+        }
+        Err(_) => { false }
+    }
+}
+
+/// Decide where a one player game writes to. Normally that's the trained player directory,
+/// but when it's read only (asked for, or found not to be writable) nothing is written there:
+/// a profile's overlays go to the user's data directory instead, kept apart for each trained
+/// player directory, and without a profile nothing is saved.
+pub(crate) fn resolve_locations(trained_player_dir: &Path, read_only: bool, profile: bool,
+                                user_data: Option<PathBuf>) -> StorageLocations {
+    let reason = if read_only {
+        "Trained players are read only"
+    } else if !is_writable(trained_player_dir) {
+        "Trained players are read only, since their directory isn't writable"
+    } else {
+        return StorageLocations { writes: Some(trained_player_dir.to_path_buf()), notice: None };
+    };
+    let overlays = user_data.map(|dir| dir.join("overlays").join(overlay_namespace(trained_player_dir)))
+        .filter(|dir| fs::create_dir_all(dir).is_ok() && is_writable(dir));
+    let (writes, consequence) = match (profile, overlays) {
+        (true, Some(dir)) => {
+            let consequence = format!("the profile's learning is saved in {}", dir.display());
+            (Some(dir), consequence)
+        }
+        (true, None) => {
+            (None, "there's no writable data directory for the profile, so nothing will be saved".to_string())
+        }
+        (false, _) => {
+            (None, "what the computer learns won't be saved, use --profile to keep it".to_string())
+        }
+    };
+    StorageLocations { writes, notice: Some(format!("{} ({}), {}.", reason, trained_player_dir.display(), consequence)) }
+}
+
+/// Name of the directory holding the overlays learned on top of the players in a trained
+/// player directory, so overlays of the same profile for different players don't overwrite
+/// each other: the directory's name and a hash of its full path, e.g. `trained-00c3a1f2e5d7b940`
+fn overlay_namespace(trained_player_dir: &Path) -> String {
+    let canonical = fs::canonicalize(trained_player_dir).unwrap_or_else(|_| trained_player_dir.to_path_buf());
+    // FNV-1a, which unlike the standard library's hasher is the same in every build
+    let hash = canonical.as_os_str().as_encoded_bytes().iter()
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3));
+    let name = canonical.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    format!("{}-{:016x}", name, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tictacrs::agents::players::Player;
+    use tictacrs::game::board::PlayerPiece;
+    use crate::single_player::overlay_file_path;
+
+    #[test]
+    fn test_data_directory_from() {
+        let var = |value: &str| Some(OsString::from(value));
+        assert_eq!(data_directory_from(var("/data"), var("/xdg"), var("/home/a")), Some(PathBuf::from("/data")));
+        assert_eq!(data_directory_from(None, var("/xdg"), var("/home/a")), Some(PathBuf::from("/xdg/tictacrs")));
+        assert_eq!(data_directory_from(None, None, var("/home/a")),
+                   Some(PathBuf::from("/home/a/.local/share/tictacrs")));
+        // No home directory at all
+        assert_eq!(data_directory_from(None, None, None), None);
+    }
+
+    #[test]
+    fn test_resolve_locations() {
+        let directory = std::env::temp_dir().join("tictacrs_storage_test");
+        _ = fs::remove_dir_all(&directory);
+        let models = directory.join("models");
+        fs::create_dir_all(&models).unwrap();
+        let user_data = directory.join("data");
+        // A writable directory is written to as usual
+        assert!(is_writable(&models));
+        assert_eq!(resolve_locations(&models, false, true, Some(user_data.clone())),
+                   StorageLocations { writes: Some(models.clone()), notice: None });
+        assert_eq!(fs::read_dir(&models).unwrap().count(), 0);
+        // Read only, a profile's overlays move to the data directory
+        let locations = resolve_locations(&models, true, true, Some(user_data.clone()));
+        assert_eq!(locations.writes, Some(user_data.join("overlays").join(overlay_namespace(&models))));
+        assert!(locations.notice.unwrap().starts_with("Trained players are read only ("));
+        // Without a profile, or without a data directory, nothing is written
+        let locations = resolve_locations(&models, true, false, Some(user_data.clone()));
+        assert_eq!(locations.writes, None);
+        assert!(locations.notice.unwrap().contains("use --profile"));
+        assert_eq!(resolve_locations(&models, true, true, None).writes, None);
+        // A directory which can't be written to is detected
+        let missing = directory.join("missing");
+        assert!(!is_writable(&missing));
+        let locations = resolve_locations(&missing, false, true, Some(user_data.clone()));
+        assert_eq!(locations.writes, Some(user_data.join("overlays").join(overlay_namespace(&missing))));
+        assert!(locations.notice.unwrap().contains("isn't writable"));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_read_only_overlays_kept_apart() {
+        let directory = std::env::temp_dir().join("tictacrs_storage_overlays");
+        _ = fs::remove_dir_all(&directory);
+        let (first, second) = (directory.join("first"), directory.join("second"));
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        let user_data = directory.join("data");
+        let first_writes = resolve_locations(&first, true, true, Some(user_data.clone())).writes.unwrap();
+        let second_writes = resolve_locations(&second, true, true, Some(user_data.clone())).writes.unwrap();
+        assert_ne!(first_writes, second_writes);
+        assert!(first_writes.file_name().unwrap().to_string_lossy().starts_with("first-"));
+        // The same directory, however it's named, keeps its overlays
+        assert_eq!(resolve_locations(&first.join("../first"), true, true, Some(user_data)).writes,
+                   Some(first_writes.clone()));
+        // Learning with the same profile on top of each directory's players writes two overlays
+        let player = Player::builder(PlayerPiece::O).seed(1).build().unwrap().with_overlay();
+        player.save_overlay(overlay_file_path(&first_writes, "alice", PlayerPiece::O)).unwrap();
+        assert!(!overlay_file_path(&second_writes, "alice", PlayerPiece::O).exists());
+        player.save_overlay(overlay_file_path(&second_writes, "alice", PlayerPiece::O)).unwrap();
+        assert_eq!(fs::read_dir(&first_writes).unwrap().count(), 1);
+        assert_eq!(fs::read_dir(&second_writes).unwrap().count(), 1);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
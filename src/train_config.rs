@@ -0,0 +1,481 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tictacrs::agents::health::HealthThresholds;
+use tictacrs::agents::players::PlyExploration;
+use tictacrs::agents::snapshots::Snapshots;
+use tictacrs::agents::trainer::{HealthCheck, PhaseLearners, TrainingOptions, TrainingPhase};
+use tictacrs::formatting::{count, percent};
+use tictacrs::agents::annealing::{scheduled_player, INITIAL_EXPLORATION_RATE, INITIAL_LEARNING_RATE};
+use tictacrs::agents::players::Player;
+use tictacrs::game::board::PlayerPiece;
+
+/// Fully resolved and validated settings for a training run
+#[derive(Debug)]
+pub(crate) struct TrainConfig {
+    pub(crate) iterations: u32,
+    pub(crate) phases: Vec<TrainingPhase>,
+    pub(crate) symmetric_propagation: Option<f64>,
+    pub(crate) output_directory: PathBuf,
+    pub(crate) compact: bool,
+    /// Factors scaling both players' exploration rates by how far into the game they are
+    pub(crate) ply_exploration: PlyExploration,
+    pub(crate) options: TrainingOptions,
+    /// Games the trained players play against each other afterwards, see
+    /// [`Trainer::evaluate`](tictacrs::agents::trainer::Trainer::evaluate)
+    pub(crate) evaluation_games: Option<u32>,
+    /// Carry on training the players saved in the output directory, see [`TrainConfig::players`]
+    pub(crate) resume: bool,
+    /// Number of threads to train on, 0 for one per core (see
+    /// [`Trainer::train_parallel`](tictacrs::agents::trainer::Trainer::train_parallel)), None
+    /// to train on the current thread
+    pub(crate) threads: Option<usize>,
+    pub(crate) initial_learning_rate: f64,
+    pub(crate) initial_exploration_rate: f64,
+}
+
+impl fmt::Display for TrainConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Training configuration:")?;
+        writeln!(f, "  iterations: {}", count(self.iterations as u64))?;
+        let phases: Vec<String> = self.phases.iter().map(|phase| phase.to_string()).collect();
+        writeln!(f, "  phases: {}", phases.join(","))?;
+        match self.symmetric_propagation {
+            Some(fraction) => writeln!(f, "  symmetric propagation: {}", fraction)?,
+            None => writeln!(f, "  symmetric propagation: off")?,
+        }
+        writeln!(f, "  output directory: {}", self.output_directory.display())?;
+        writeln!(f, "  resume from saves: {}", self.resume)?;
+        match self.threads {
+            Some(0) => writeln!(f, "  threads: one per core")?,
+            Some(threads) => writeln!(f, "  threads: {}", threads)?,
+            None => writeln!(f, "  threads: 1")?,
+        }
+        writeln!(f, "  progress bar: {}", self.options.progress_bar)?;
+        writeln!(f, "  compact before saving: {}", self.compact)?;
+        writeln!(f, "  end dead draws early: {}", self.options.early_draws)?;
+        match self.options.health_check {
+            Some(check) => writeln!(f, "  health check: every {} games, flagging over {} of values \
+                                        within {} of 0, 0.5 or 1", count(check.every as u64),
+                                    percent(check.thresholds.max_fraction), check.thresholds.epsilon)?,
+            None => writeln!(f, "  health check: off")?,
+        }
+        match self.options.snapshots {
+            Some(ref snapshots) => writeln!(f, "  snapshots: every {} games in {}",
+                                            count(snapshots.every as u64), snapshots.directory.display())?,
+            None => writeln!(f, "  snapshots: off")?,
+        }
+        match self.options.checkpoint_every {
+            Some(every) => writeln!(f, "  checkpoints: every {} games", count(every as u64))?,
+            None => writeln!(f, "  checkpoints: off")?,
+        }
+        match self.evaluation_games {
+            Some(games) => writeln!(f, "  final evaluation: {} games", count(games as u64))?,
+            None => writeln!(f, "  final evaluation: off")?,
+        }
+        writeln!(f, "  initial learning rate: {}", self.initial_learning_rate)?;
+        writeln!(f, "  ply exploration factors: {}", self.ply_exploration)?;
+        write!(f, "  initial exploration rate: {}", self.initial_exploration_rate)
+    }
+}
+
+impl TrainConfig {
+    /// Resolve the train subcommand's options into a configuration, filling in defaults. All
+    /// problems found are returned together, rather than stopping at the first.
+    pub(crate) fn resolve(iterations: Option<u32>, phases: Option<&str>,
+                          symmetric_propagation: Option<f64>,
+                          output_directory: Option<PathBuf>, compact: bool,
+                          options: TrainingOptions,
+                          evaluation_games: Option<u32>) -> Result<TrainConfig, Vec<String>> {
+        let mut errors: Vec<String> = Vec::new();
+        let phases = match phases {
+            Some(phases) => match TrainingPhase::parse_list(phases) {
+                Ok(phases) => phases,
+                Err(_) => {
+                    errors.push(format!("invalid phases \"{}\", expected a list like \
+                                         \"both:5000,x-only:5000,o-only:5000\"", phases));
+                    Vec::new()
+                }
+            },
+            None => {
+                let iterations = iterations.unwrap_or(10000);
+                if iterations == 0 {
+                    errors.push("iterations must be greater than 0".to_string());
+                }
+                vec![TrainingPhase { learners: PhaseLearners::Both, iterations }]
+            }
+        };
+        let iterations = match phases.iter().try_fold(0u32, |total, phase| total.checked_add(phase.iterations)) {
+            Some(iterations) => { iterations }
+            None => {
+                errors.push(format!("the phases add up to more than {} games", count(u32::MAX as u64)));
+                0
+            }
+        };
+        if let Some(fraction) = symmetric_propagation {
+            if !(0. ..=1.).contains(&fraction) {
+                errors.push("symmetric propagation must be between 0 and 1".to_string());
+            }
+        }
+        if let Some(check) = options.health_check {
+            if check.every == 0 {
+                errors.push("health checks must be at least 1 game apart".to_string());
+            }
+            // Wider than this and the bands around 0, 0.5 and 1 overlap
+            if !(check.thresholds.epsilon > 0. && check.thresholds.epsilon < 0.25) {
+                errors.push("health check epsilon must be between 0 and 0.25".to_string());
+            }
+            if !(check.thresholds.max_fraction > 0. && check.thresholds.max_fraction <= 1.) {
+                errors.push("health check fraction must be between 0 and 1".to_string());
+            }
+        }
+        if let Some(ref snapshots) = options.snapshots {
+            if snapshots.every == 0 {
+                errors.push("snapshots must be at least 1 game apart".to_string());
+            }
+            // A missing directory is created when the first snapshot is taken
+            if snapshots.directory.exists() && !snapshots.directory.is_dir() {
+                errors.push(format!("snapshot directory {} isn't a directory", snapshots.directory.display()));
+            }
+        }
+        if options.checkpoint_every == Some(0) {
+            errors.push("checkpoints must be at least 1 game apart".to_string());
+        }
+        if evaluation_games == Some(0) {
+            errors.push("the final evaluation must play at least 1 game".to_string());
+        }
+        let output_directory = match output_directory {
+            Some(out) => out,
+            None => std::env::current_dir().unwrap(),
+        };
+        if let Err(e) = check_writable_directory(&output_directory) {
+            errors.push(e);
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(TrainConfig {
+            iterations,
+            phases,
+            symmetric_propagation,
+            output_directory,
+            compact,
+            ply_exploration: PlyExploration::default(),
+            options,
+            evaluation_games,
+            resume: false,
+            threads: None,
+            initial_learning_rate: INITIAL_LEARNING_RATE,
+            initial_exploration_rate: INITIAL_EXPLORATION_RATE,
+        })
+    }
+
+    /// Carry on training the players saved in the output directory, rather than starting afresh
+    pub(crate) fn resuming(self, resume: bool) -> TrainConfig {
+        TrainConfig { resume, ..self }
+    }
+
+    /// Train on several threads at once, see [`TrainConfig::threads`]
+    pub(crate) fn threaded(self, threads: Option<usize>) -> TrainConfig {
+        TrainConfig { threads, ..self }
+    }
+
+    /// Scale the players' exploration rates by how far into the game they are, see
+    /// [`Player::set_ply_exploration`](tictacrs::agents::players::Player::set_ply_exploration)
+    pub(crate) fn exploring_by_ply(self, ply_exploration: PlyExploration) -> TrainConfig {
+        TrainConfig { ply_exploration, ..self }
+    }
+
+    /// The players to train, X then O. When resuming, a player saved in the output directory is
+    /// loaded, keeping its table and iteration, and one which isn't saved there starts fresh.
+    pub(crate) fn players(&self) -> Result<(Player, Player), String> {
+        let mut players = Vec::with_capacity(2);
+        for (piece, file) in [(PlayerPiece::X, "player_x_save.ttr"), (PlayerPiece::O, "player_o_save.ttr")] {
+            let path = self.output_directory.join(file);
+            let mut player = if self.resume && path.exists() {
+                match Player::load_expecting(&path, piece) {
+                    Ok(player) => { player }
+                    Err(e) => { return Err(format!("couldn't resume from {}: {}", path.display(), e)) }
+                }
+            } else {
+                scheduled_player(piece)
+            };
+            // The fraction was checked while resolving, but a resumed player may store
+            // symmetric states as one, which propagation can't be used with
+            if player.set_symmetric_propagation(self.symmetric_propagation).is_err() {
+                return Err(format!("can't use symmetric propagation when resuming from {}, which \
+                                    stores symmetric positions as one", path.display()));
+            }
+            player.set_ply_exploration(self.ply_exploration);
+            players.push(player);
+        }
+        let player_o = players.pop().expect("Both players were made");
+        let player_x = players.pop().expect("Both players were made");
+        Ok((player_x, player_o))
+    }
+}
+
+/// The trainer's options from those of the train subcommand, a health check is only made if
+/// health_every is given, and snapshots only taken if both snapshot options are. Checkpoints
+/// are left off, to be set by the caller
+pub(crate) fn training_options(progress_bar: bool, early_draw: bool, health_every: Option<u32>,
+                               health_epsilon: f64, health_max_fraction: f64,
+                               snapshot_every: Option<u32>, snapshot_dir: Option<PathBuf>) -> TrainingOptions {
+    TrainingOptions {
+        progress_bar,
+        early_draws: early_draw,
+        health_check: health_every.map(|every| HealthCheck {
+            every,
+            thresholds: HealthThresholds { epsilon: health_epsilon, max_fraction: health_max_fraction },
+        }),
+        snapshots: snapshot_every.zip(snapshot_dir).map(|(every, directory)| Snapshots { every, directory }),
+        checkpoint_every: None,
+    }
+}
+
+/// Check that a path is an existing directory which files can be written to
+fn check_writable_directory(directory: &Path) -> Result<(), String> {
+    if !directory.is_dir() {
+        return Err(format!("output directory {} doesn't exist", directory.display()));
+    }
+    let probe = directory.join(".tictacrs_write_test");
+    match fs::write(&probe, b"") {
+        Ok(_) => {
+            _ = fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(e) => Err(format!("output directory {} isn't writable: {}", directory.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use crate::{Cli, Commands};
+
+    fn resolve_cli(args: &[&str]) -> Result<TrainConfig, Vec<String>> {
+        match Cli::parse_from(args).command {
+            Some(Commands::Train {
+                     iterations, phases, symmetric_propagation, output_directory, progress_bar,
+                     compact, early_draw, health_every, health_epsilon, health_max_fraction,
+                     snapshot_every, snapshot_dir, checkpoint_every, threads, evaluate, resume,
+                     ply_exploration, ..
+                 }) => {
+                let options = TrainingOptions {
+                    checkpoint_every,
+                    ..training_options(progress_bar, early_draw, health_every, health_epsilon,
+                                       health_max_fraction, snapshot_every, snapshot_dir)
+                };
+                TrainConfig::resolve(iterations, phases.as_deref(), symmetric_propagation,
+                                     output_directory, compact, options, evaluate)
+                    .map(|config| config.resuming(resume).threaded(threads)
+                        .exploring_by_ply(ply_exploration.unwrap_or_default()))
+            }
+            _ => panic!("Expected the train subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_valid() {
+        let directory = std::env::temp_dir();
+        let config = resolve_cli(&["tictacrs", "train", "-i", "50", "-o",
+            directory.to_str().unwrap(), "--compact"]).unwrap();
+        let early = resolve_cli(&["tictacrs", "train", "-o", directory.to_str().unwrap(),
+            "--early-draw"]).unwrap();
+        assert!(early.options.early_draws);
+        assert!(format!("{}", early).contains("end dead draws early: true"));
+        assert_eq!(config.iterations, 50);
+        assert_eq!(config.output_directory, directory);
+        assert!(config.compact);
+        assert!(!config.options.progress_bar);
+        assert!(!config.options.early_draws);
+        assert_eq!(config.options.health_check, None);
+        assert!(format!("{}", config).contains("health check: off"));
+        assert_eq!(config.symmetric_propagation, None);
+        assert!(format!("{}", config).contains("iterations: 50"));
+        assert_eq!(config.evaluation_games, None);
+        assert!(format!("{}", config).contains("final evaluation: off"));
+    }
+
+    #[test]
+    fn test_resolve_evaluation() {
+        let directory = std::env::temp_dir();
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory.to_str().unwrap(), "--evaluate",
+            "2000"]).unwrap();
+        assert_eq!(config.evaluation_games, Some(2000));
+        assert!(format!("{}", config).contains("final evaluation: 2,000 games"));
+        let errors = resolve_cli(&["tictacrs", "train", "-o", directory.to_str().unwrap(), "--evaluate",
+            "0"]).unwrap_err();
+        assert_eq!(errors, vec!["the final evaluation must play at least 1 game".to_string()]);
+    }
+
+    #[test]
+    fn test_resolve_ply_exploration() {
+        let directory = std::env::temp_dir();
+        let directory = directory.to_str().unwrap();
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory]).unwrap();
+        assert_eq!(config.ply_exploration, PlyExploration::default());
+        assert!(format!("{}", config).contains("ply exploration factors: 1,1,1,1,1,1,1,1,1"));
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory, "--ply-exploration",
+            "1.0,1.0,0.8,0.8,0.5,0.5,0.2,0.2,0.0"]).unwrap();
+        assert!(format!("{}", config).contains("ply exploration factors: 1,1,0.8,0.8,0.5,0.5,0.2,0.2,0"));
+        assert_eq!(config.ply_exploration.factor(2), 0.8);
+        for factors in ["1,1,1", "1,1,1,1,1,1,1,1,1,1", "1,1,1,1,1,1,1,1,2", "1,1,1,1,1,1,1,1,-0.5", "a,b"] {
+            assert!(Cli::try_parse_from(["tictacrs", "train", "--ply-exploration", factors]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_resolve_reports_all_errors() {
+        let errors = resolve_cli(&["tictacrs", "train", "-i", "0", "-o",
+            "/tictacrs/does/not/exist", "--dry-run"]).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("iterations"));
+        assert!(errors[1].contains("/tictacrs/does/not/exist"));
+    }
+
+    #[test]
+    fn test_resolve_phases() {
+        let directory = std::env::temp_dir();
+        let config = resolve_cli(&["tictacrs", "train", "--phases", "both:50,o-only:25", "-o",
+            directory.to_str().unwrap()]).unwrap();
+        assert_eq!(config.iterations, 75);
+        assert_eq!(config.phases, vec![
+            TrainingPhase { learners: PhaseLearners::Both, iterations: 50 },
+            TrainingPhase { learners: PhaseLearners::OOnly, iterations: 25 },
+        ]);
+        assert!(format!("{}", config).contains("phases: both:50,o-only:25"));
+        let errors = resolve_cli(&["tictacrs", "train", "--phases", "both:50,neither:25", "-o",
+            directory.to_str().unwrap()]).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("neither:25"));
+        let errors = resolve_cli(&["tictacrs", "train", "--phases", "both:4000000000,x-only:4000000000", "-o",
+            directory.to_str().unwrap()]).unwrap_err();
+        assert_eq!(errors, vec![format!("the phases add up to more than {} games", count(u32::MAX as u64))]);
+        assert!(Cli::try_parse_from(["tictacrs", "train", "-i", "5", "--phases", "both:5"]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_health_check() {
+        let directory = std::env::temp_dir();
+        let directory = directory.to_str().unwrap();
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory, "--health-every",
+            "1000"]).unwrap();
+        assert_eq!(config.options.health_check, Some(HealthCheck {
+            every: 1000,
+            thresholds: HealthThresholds::default(),
+        }));
+        assert!(format!("{}", config).contains("health check: every 1,000 games, flagging over \
+                                                 90.0% of values within 0.01 of 0, 0.5 or 1"));
+        let errors = resolve_cli(&["tictacrs", "train", "-o", directory, "--health-every", "0",
+            "--health-epsilon", "0.3", "--health-max-fraction", "0"]).unwrap_err();
+        assert_eq!(errors.len(), 3);
+        // The thresholds only apply to a health check
+        assert!(Cli::try_parse_from(["tictacrs", "train", "--health-epsilon", "0.1"]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_snapshots() {
+        let directory = std::env::temp_dir();
+        let snapshots = directory.join("tictacrs_snapshots_config");
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory.to_str().unwrap(),
+            "--snapshot-every", "100", "--snapshot-dir", snapshots.to_str().unwrap()]).unwrap();
+        assert_eq!(config.options.snapshots, Some(Snapshots { every: 100, directory: snapshots.clone() }));
+        assert!(format!("{}", config).contains("snapshots: every 100 games in"));
+        let errors = resolve_cli(&["tictacrs", "train", "-o", directory.to_str().unwrap(),
+            "--snapshot-every", "0", "--snapshot-dir", snapshots.to_str().unwrap()]).unwrap_err();
+        assert_eq!(errors, vec!["snapshots must be at least 1 game apart".to_string()]);
+        // Both options are needed
+        assert!(Cli::try_parse_from(["tictacrs", "train", "--snapshot-every", "100"]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_checkpoints() {
+        let directory = std::env::temp_dir();
+        let directory = directory.to_str().unwrap();
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory]).unwrap();
+        assert_eq!(config.options.checkpoint_every, None);
+        assert!(format!("{}", config).contains("checkpoints: off"));
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory, "--checkpoint-every", "5000"]).unwrap();
+        assert_eq!(config.options.checkpoint_every, Some(5000));
+        assert!(format!("{}", config).contains("checkpoints: every 5,000 games"));
+        let errors = resolve_cli(&["tictacrs", "train", "-o", directory, "--checkpoint-every", "0"]).unwrap_err();
+        assert_eq!(errors, vec!["checkpoints must be at least 1 game apart".to_string()]);
+    }
+
+    #[test]
+    fn test_resolve_threads() {
+        let directory = std::env::temp_dir();
+        let directory = directory.to_str().unwrap();
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory]).unwrap();
+        assert_eq!(config.threads, None);
+        assert!(format!("{}", config).contains("threads: 1"));
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory, "--threads", "8"]).unwrap();
+        assert_eq!(config.threads, Some(8));
+        assert!(format!("{}", config).contains("threads: 8"));
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory, "--threads", "0"]).unwrap();
+        assert!(format!("{}", config).contains("threads: one per core"));
+        // The options only followed during serial training can't be given
+        assert!(Cli::try_parse_from(["tictacrs", "train", "--threads", "4", "--phases", "both:5"]).is_err());
+        assert!(Cli::try_parse_from(["tictacrs", "train", "--threads", "4", "--checkpoint-every", "5"]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_symmetric_propagation() {
+        let directory = std::env::temp_dir();
+        let directory = directory.to_str().unwrap();
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory,
+            "--symmetric-propagation"]).unwrap();
+        assert_eq!(config.symmetric_propagation, Some(0.5));
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory,
+            "--symmetric-propagation", "0.2"]).unwrap();
+        assert_eq!(config.symmetric_propagation, Some(0.2));
+        let errors = resolve_cli(&["tictacrs", "train", "-o", directory,
+            "--symmetric-propagation", "2"]).unwrap_err();
+        assert!(errors[0].contains("symmetric propagation"));
+    }
+
+    #[test]
+    fn test_resume() {
+        let directory = std::env::temp_dir().join("tictacrs_train_config_resume");
+        _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory.to_str().unwrap(), "--resume"]).unwrap();
+        assert!(config.resume);
+        assert!(format!("{}", config).contains("resume from saves: true"));
+        // Nothing saved yet, so both start fresh
+        let (player_x, player_o) = config.players().unwrap();
+        assert_eq!((player_x.get_iteration(), player_o.get_iteration()), (0, 0));
+        let mut trained = Player::builder(PlayerPiece::X).seed(1).build().unwrap();
+        trained.update_iteration(40);
+        trained.save_player_state(directory.join("player_x_save.ttr")).unwrap();
+        let (player_x, player_o) = config.players().unwrap();
+        assert_eq!(player_x.get_iteration(), 40);
+        assert_eq!(player_o.get_iteration(), 0);
+        // Without the flag the saves are ignored
+        let fresh = resolve_cli(&["tictacrs", "train", "-o", directory.to_str().unwrap()]).unwrap();
+        assert_eq!(fresh.players().unwrap().0.get_iteration(), 0);
+        // A save for the wrong piece is an error rather than being overwritten
+        fs::copy(directory.join("player_x_save.ttr"), directory.join("player_o_save.ttr")).unwrap();
+        assert!(matches!(config.players(), Err(e) if e.contains("player_o_save.ttr")));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_resume_symmetric() {
+        let directory = std::env::temp_dir().join("tictacrs_train_config_resume_symmetric");
+        _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let symmetric = Player::builder(PlayerPiece::O).symmetric(true).build().unwrap();
+        symmetric.save_player_state(directory.join("player_o_save.ttr")).unwrap();
+        // Without propagation the symmetric save resumes as usual
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory.to_str().unwrap(), "--resume"]).unwrap();
+        assert!(config.players().unwrap().1.is_symmetric());
+        let config = resolve_cli(&["tictacrs", "train", "-o", directory.to_str().unwrap(), "--resume",
+            "--symmetric-propagation"]).unwrap();
+        assert!(matches!(config.players(), Err(e) if e.contains("symmetric propagation")
+                                                     && e.contains("player_o_save.ttr")));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+use std::io::{self, BufRead, Write};
+use tictacrs::game::board::{Board, Piece, PlayerPiece};
+use tictacrs::game::coords::Coord;
+use tictacrs::game::threats::{describe_line, lines_completed, winning_squares};
+use crate::input::{InputError, InputMode, LineReader};
+
+/// One step of the tutorial: what to explain, which moves the player may make, and how the
+/// scripted opponent replies
+pub(crate) struct TutorialStep {
+    /// Explanation shown before asking for the move
+    pub(crate) text: &'static str,
+    /// Moves accepted at this step, any empty square if there are none
+    pub(crate) accepted: &'static [&'static str],
+    /// Shown instead of making the move when any other one is entered
+    pub(crate) hint: &'static str,
+    /// Where the scripted opponent plays after the move, if it plays at all
+    pub(crate) reply: Option<&'static str>,
+}
+
+/// The guided first game. The player is X, and each step's move and reply are chosen so the
+/// next lesson's position comes up.
+pub(crate) const TUTORIAL_STEPS: &[TutorialStep] = &[
+    TutorialStep {
+        text: "Squares are named by their row letter and column number, so the centre is b2. \
+               Type b2 to play the centre, it's part of four different lines.",
+        accepted: &["b2"],
+        hint: "For this first move, type b2 to play the centre.",
+        reply: Some("a2"),
+    },
+    TutorialStep {
+        text: "Three in a row wins, along a row, a column or a diagonal. Play c1 to line up two \
+               with the centre, threatening to win at a3.",
+        accepted: &["c1"],
+        hint: "Type c1, the bottom left corner.",
+        reply: Some("a3"),
+    },
+    TutorialStep {
+        text: "Your opponent blocked you, and now has two in row a. If you don't block it, \
+               they'll win next move.",
+        accepted: &["a1"],
+        hint: "Block your opponent's line by playing a1.",
+        reply: Some("b1"),
+    },
+    TutorialStep {
+        text: "Blocking at a1 made two threats at once, a fork. Your opponent could only block \
+               one of them, so finish the other.",
+        accepted: &["c3"],
+        hint: "Complete the diagonal a1-c3 by playing c3.",
+        reply: None,
+    },
+];
+
+/// Shown once the tutorial is complete
+const TUTORIAL_END: &str = "\
+You won! Ready for a real game? Run `tictacrs play` to play the trained computer.
+  - Add --teach to have it explain each of its moves
+  - Add --freeze for a computer that always plays its best move, or --explore 0.2 for one
+    that often tries something new
+  - Add --handicap b2:X to start with a piece already on the board";
+
+/// What happened after the player's input at a tutorial step
+#[derive(Debug, PartialEq)]
+pub(crate) enum TutorialResponse {
+    /// The input wasn't accepted, with a hint to try again
+    Retry(String),
+    /// The move was made, with the scripted opponent's reply if any
+    Next(String),
+    /// The move was made and the tutorial is over
+    Finished(String),
+}
+
+/// A tutorial game played through a sequence of [`TutorialStep`]s
+pub(crate) struct TutorialSession {
+    board: Board,
+    steps: &'static [TutorialStep],
+    step: usize,
+}
+
+impl TutorialSession {
+    pub(crate) fn new(steps: &'static [TutorialStep]) -> TutorialSession {
+        TutorialSession { board: Board::new(), steps, step: 0 }
+    }
+
+    /// The board, any immediate threats, and the current step's explanation
+    pub(crate) fn prompt(&self) -> String {
+        let mut prompt = self.board.render(None);
+        let state = self.board.get_compact_state();
+        let threats = [(Piece::O, "Your opponent threatens to win"), (Piece::X, "You can win")];
+        for (piece, whose) in threats {
+            for square in winning_squares(&state, piece) {
+                let lines: Vec<String> = lines_completed(&state, square, piece).iter()
+                    .map(describe_line).collect();
+                let coord = Coord::from_index(square).unwrap();
+                prompt.push_str(&format!("{} at {}, completing {}\n", whose, coord,
+                                         lines.join(" and ")));
+            }
+        }
+        prompt.push_str(self.steps[self.step].text);
+        prompt
+    }
+
+    /// Make the player's move if the current step accepts it, then the scripted reply
+    pub(crate) fn respond(&mut self, input: &str) -> TutorialResponse {
+        let step = &self.steps[self.step];
+        let coord = match input.trim().parse::<Coord>() {
+            Ok(coord) => { coord }
+            Err(_) => { return TutorialResponse::Retry(step.hint.to_string()) }
+        };
+        let accepted = step.accepted.is_empty()
+            || step.accepted.iter().any(|square| square.parse() == Ok(coord));
+        if !accepted || self.board.player_move(&coord.to_string(), "X").is_err() {
+            return TutorialResponse::Retry(step.hint.to_string());
+        }
+        self.step += 1;
+        if self.board.result().is_some() || self.step == self.steps.len() {
+            return TutorialResponse::Finished(TUTORIAL_END.to_string());
+        }
+        match step.reply {
+            Some(reply) => {
+                self.board.player_move(reply, &PlayerPiece::O.to_string())
+                    .expect("Scripted reply must be to an empty square");
+                TutorialResponse::Next(format!("Your opponent plays {}.", reply))
+            }
+            None => { TutorialResponse::Next(String::new()) }
+        }
+    }
+}
+
+/// Play through the tutorial, reading moves from input and writing to output, until it's
+/// complete, the player quits, or the input ends
+pub(crate) fn run_tutorial(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<()> {
+    let mut session = TutorialSession::new(TUTORIAL_STEPS);
+    let mut lines = LineReader::new(input, InputMode::Interactive);
+    writeln!(output, "Welcome to the tutorial! You're X, type q at any time to quit.")?;
+    writeln!(output, "{}", session.prompt())?;
+    loop {
+        let buffer = match lines.read_line(output) {
+            Ok(line) => { line }
+            Err(InputError::Eof) => { return Ok(()) }
+            Err(InputError::Io(e)) => { return Err(e) }
+            Err(e) => { return Err(io::Error::new(io::ErrorKind::InvalidData, e.to_string())) }
+        };
+        if matches!(buffer.trim(), "q" | "Q") {
+            return Ok(());
+        }
+        match session.respond(&buffer) {
+            TutorialResponse::Retry(hint) => { writeln!(output, "{}", hint)? }
+            TutorialResponse::Next(reply) => {
+                if !reply.is_empty() {
+                    writeln!(output, "{}", reply)?;
+                }
+                writeln!(output, "{}", session.prompt())?;
+            }
+            TutorialResponse::Finished(end) => {
+                writeln!(output, "{}", session.board.render(None))?;
+                writeln!(output, "{}", end)?;
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_tutorial() {
+        let mut input = "b2\nc1\na1\nc3\n".as_bytes();
+        let mut output = Vec::new();
+        run_tutorial(&mut input, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Your opponent plays a3."));
+        assert!(output.contains("Your opponent threatens to win at a1, completing row a"));
+        assert!(output.contains("Your opponent plays b1."));
+        assert!(output.contains("You can win at c3, completing the diagonal a1-c3"));
+        assert!(output.ends_with(&format!("{}\n", TUTORIAL_END)));
+    }
+
+    #[test]
+    fn test_wrong_input_reprompts() {
+        let mut session = TutorialSession::new(TUTORIAL_STEPS);
+        let hint = TutorialResponse::Retry(TUTORIAL_STEPS[0].hint.to_string());
+        assert_eq!(session.respond("a1"), hint);
+        assert_eq!(session.respond("centre"), hint);
+        assert_eq!(session.respond("B2"), TutorialResponse::Next("Your opponent plays a2.".to_string()));
+        // An occupied square is never accepted
+        assert_eq!(session.respond("b2"), TutorialResponse::Retry(TUTORIAL_STEPS[1].hint.to_string()));
+        let mut input = "a1\nb2\nq\n".as_bytes();
+        let mut output = Vec::new();
+        run_tutorial(&mut input, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(&format!("{}\nYour opponent plays a2.", TUTORIAL_STEPS[0].hint)));
+    }
+}
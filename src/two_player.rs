@@ -1,58 +1,95 @@
-use std::io;
+use tictacrs::agents::session::Session;
 use tictacrs::game;
-use tictacrs::game::board::Piece;
+use tictacrs::game::board::{Piece, RenderStyle};
+use tictacrs::game::coords::{Coord, DigitLayout};
+use tictacrs::game::result::GameResult;
+use crate::input;
+use crate::single_player::{announce_move, format_share, offer_dead_draw, parse_move, parse_share_command,
+                           RecapMove, ShareOptions};
 
-/// Function to two_player Tic-Tac-Toe, returns true if another game is desired
-pub fn two_player() ->bool{
-    let mut game_board = game::board::Board::new();
-    let mut current_player = Piece::X;
+/// Function to two_player Tic-Tac-Toe, returns true if another game is desired. Single digit
+/// moves are read in the given layout, and the board is shown in the given style, moves being
+/// announced if it's spoken. A shareable summary is printed after the game if share is set.
+pub fn two_player(handicap: &[(usize, usize, Piece)], digit_layout: DigitLayout, style: RenderStyle,
+                  share: Option<ShareOptions>) ->bool{
+    // The handicap has already been validated, so this can't fail
+    let game_board = game::board::Board::with_handicap(handicap).expect("Invalid handicap");
+    if game_board.is_handicapped() {
+        println!("This is a handicap game, some pieces start on the board.");
+    }
+    let mut session = Session::new(game_board);
+    let mut moves: Vec<RecapMove> = Vec::new();
 
-    loop {
-        println!("Player {} Please Enter Your Move (q to quit)", current_player);
-        println!("{}", game_board);
+    while let Some(current_player) = session.turn() {
+        if session.offer_dead_draw() {
+            println!("{}", session.board().render_as(style));
+            if offer_dead_draw() {
+                println!("No Winner!");
+                session.end_as_draw();
+                break;
+            }
+        }
+        println!("Player {} Please Enter Your Move (q to quit, :share for a summary)", current_player);
+        println!("{}", session.board().render_as(style));
         // Get player input
-        let mut buffer = String::new();
-        io::stdin().read_line(&mut buffer).expect("Failed to read line");
+        let buffer = match input::stdin_line() {
+            Some(line) => { line }
+            None => { return false; }
+        };
         let pmove = buffer.trim();
         match pmove {
             "Q"|"q"|"Quit"|"quit"=>{return false;}
             _=>{}
         }
-        match game_board.player_move(pmove, &format!("{}",current_player)){
-            Ok(_) => {}
-            Err(game::board::BoardError::InvalidMove) => {
-                println!("Sorry, invalid move");
+        if let Some(share) = parse_share_command(pmove) {
+            print!("{}", format_share(&moves, session.board(), None, share));
+            continue;
+        }
+        // Moves given as a digit or a square's name are passed on in the usual notation
+        let pmove = match parse_move(pmove, digit_layout) {
+            Some(coord) => { coord.to_string() }
+            None => { pmove.to_string() }
+        };
+        match session.play(&pmove) {
+            Ok(_) => {
+                if style == RenderStyle::Spoken {
+                    println!("{}", announce_move(&format!("Player {}", current_player), current_player, &pmove));
+                }
+                if let Ok(coord) = pmove.parse::<Coord>() {
+                    moves.push(RecapMove::human(current_player, coord));
+                }
+            }
+            Err(e @ game::board::BoardError::InvalidMove(_)) => {
+                println!("Sorry, {}", e);
                 continue;
             }
             Err(game::board::BoardError::NotEmpty) => {
                 println!("Sorry, that space is occupied");
                 continue;
             }
-            Err(_)=>{
-                println!("Sorry, an unknown error occurred, please try again");
+            Err(e)=>{
+                println!("Sorry, {}, please try again", e);
                 continue;
             }
         }
-        match game_board.check_winner() {
-            None => {}
-            Some(piece) => {
-                println!("Congratulations Player {}, You Win!", piece);
-                break;
-            }
+    }
+    match session.board().result() {
+        None => {}
+        Some(GameResult::Win(piece)) => {
+            println!("Congratulations Player {}, You Win!", piece);
         }
-        if game_board.is_full(){
+        Some(GameResult::Draw) => {
             println!("No Winner!");
-            break;
-        }
-        current_player = match current_player{
-            Piece::X => {Piece::O}
-            Piece::O => {Piece::X}
-            Piece::Empty => {panic!("Current Player Error!")}
         }
     }
+    if let Some(share) = share {
+        print!("{}", format_share(&moves, session.board(), session.result(), share));
+    }
     println!("Would you like to two_player again? [y/n]");
-    let mut buffer = String::new();
-    io::stdin().read_line(&mut buffer).expect("Failed to read line");
+    let buffer = match input::stdin_line() {
+        Some(line) => { line }
+        None => { return false; }
+    };
     match buffer.trim() {
         "y"|"Y"|"yes"|"Yes" => {return true},
         "n"|"N"|"no"|"No" => {return false},
@@ -0,0 +1,40 @@
+//! Exercises the public API through the prelude, so that accidentally removing or
+//! renaming a re-exported item breaks the build
+use tictacrs::prelude::*;
+
+#[test]
+fn test_prelude_board() {
+    let mut board = Board::default();
+    board.player_move("a1", "X").unwrap();
+    board.player_move("b1", "O").unwrap();
+    let err: BoardError = board.player_move("a1", "O").unwrap_err();
+    assert_eq!(err, BoardError::NotEmpty);
+    assert_eq!(board.get_compact_state()[0], Piece::X);
+    assert!(!board.is_full());
+    assert_eq!(board.check_winner(), None);
+    board.clear_board();
+    assert_eq!(board.get_compact_state(), [Piece::Empty; 9]);
+}
+
+#[test]
+fn test_prelude_player() {
+    let mut player = Player::new(PlayerPiece::X, 0.75, 0.2, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+    assert_eq!(player.get_player_piece(), PlayerPiece::X);
+    let chosen = player.make_move(&Board::new().get_compact_state()).unwrap();
+    assert_eq!(Player::to_human_move(&chosen).len(), 2);
+    let err: PlayerError = player.make_move(&[Piece::X; 9]).unwrap_err();
+    assert_eq!(err, PlayerError::GameAlreadyOver);
+}
+
+#[cfg(feature = "train")]
+#[test]
+fn test_prelude_trainer() {
+    let out_directory = std::env::temp_dir().join("tictacrs_api_test");
+    std::fs::create_dir_all(&out_directory).unwrap();
+    let mut player_x = Player::new(PlayerPiece::X, 0.75, 0.2, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+    let mut player_o = Player::new(PlayerPiece::O, 0.75, 0.2, AnnealingSchedule::Constant, AnnealingSchedule::Constant);
+    let res: Result<_, TrainerError> = Trainer::train(&mut player_x, &mut player_o, 5,
+                                                      &out_directory, false);
+    assert!(res.is_ok());
+    std::fs::remove_dir_all(&out_directory).unwrap();
+}
@@ -0,0 +1,58 @@
+//! Every historical save format in tests/fixtures migrates to the same bytes as the current
+//! format fixture
+#![cfg(feature = "persistence")]
+use std::path::PathBuf;
+use tictacrs::agents::migrate::{migrate_bytes, migrate_file, CURRENT_SAVE_REVISION};
+use tictacrs::prelude::*;
+
+/// Saves of the same player at each revision, oldest first, all written in save format 1
+const FIXTURES: [&str; 2] = ["player_o_rev1.ttr", "player_o_rev2.ttr"];
+/// The same player saved in the current format
+const CURRENT: &str = "player_o_current.ttr";
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+}
+
+#[test]
+fn test_fixtures_migrate_to_current() {
+    let current = std::fs::read(fixture(CURRENT)).unwrap();
+    for (revision, name) in (1..).zip(FIXTURES) {
+        let (migrated, report) = migrate_bytes(&std::fs::read(fixture(name)).unwrap()).unwrap();
+        assert_eq!(report.from_revision, revision, "{}", name);
+        assert_eq!(report.is_noop(), revision == CURRENT_SAVE_REVISION, "{}", name);
+        assert_eq!(migrated, current, "{}", name);
+        // Migrating the result again changes nothing
+        let (again, report) = migrate_bytes(&migrated).unwrap();
+        assert!(report.is_noop());
+        assert_eq!(again, current);
+    }
+    // Reading an old save applies the same migrations in memory
+    let player = Player::load(fixture(FIXTURES[0])).unwrap();
+    assert_eq!(player.terminal_states_dropped(), 2);
+    assert_eq!(player.to_bytes().unwrap(), current);
+}
+
+#[test]
+fn test_migrate_file() {
+    let directory = std::env::temp_dir().join("tictacrs_migrate_test");
+    _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("player_o.ttr");
+    std::fs::copy(fixture(FIXTURES[0]), &path).unwrap();
+    let report = migrate_file(&path, &path).unwrap();
+    assert_eq!(report.changes.len(), 1);
+    assert_eq!(std::fs::read(&path).unwrap(), std::fs::read(fixture(CURRENT)).unwrap());
+    assert!(migrate_file(&path, &path).unwrap().is_noop());
+    // Only the migrated save is left behind
+    assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+    let missing = directory.join("missing.ttr");
+    assert_eq!(migrate_file(&missing, &path),
+               Err(PlayerError::InvalidFile { path: missing, source: std::io::ErrorKind::NotFound.into() }));
+    // An output which can't be written is reported like a failed player save
+    let unwritable = directory.join("missing").join("player_o.ttr");
+    std::fs::copy(fixture(FIXTURES[0]), &path).unwrap();
+    assert!(matches!(migrate_file(&path, &unwritable), Err(PlayerError::InvalidFile { path, .. }) if path == unwritable));
+    assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,17 @@
+//! Checks that the library still builds, and its own tests pass, with none of the optional
+//! features enabled (just the board, rules and player logic)
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn test_no_default_features() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // A separate target directory, so this doesn't wait on the build running the test
+    let status = Command::new(env!("CARGO"))
+        .current_dir(manifest_dir)
+        .args(["test", "--quiet", "--lib", "--no-default-features", "--target-dir"])
+        .arg(manifest_dir.join("target").join("no-default-features"))
+        .status()
+        .expect("Couldn't run cargo");
+    assert!(status.success());
+}